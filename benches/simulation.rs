@@ -0,0 +1,121 @@
+use boids::boid::Boid;
+use boids::geometry::{draw_line, Circle, Color, Rectangle};
+use boids::node::{QuadTree, RenderNode, Vertice};
+use boids::{World, HEIGHT, QUAD_TREE_CAPACITY, SIZE, WIDTH};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::Rng;
+
+const POPULATIONS: [u16; 3] = [500, 2000, 5000];
+
+fn boundary() -> Rectangle {
+    Rectangle::new(
+        WIDTH as f32 / 2.0,
+        HEIGHT as f32 / 2.0,
+        WIDTH as f32 / 2.0,
+        HEIGHT as f32 / 2.0,
+    )
+}
+
+fn random_boids(count: u16) -> Vec<Boid> {
+    let mut rng = rand::thread_rng();
+    (0..count)
+        .map(|_| {
+            let mut vertice = Vertice::new();
+            vertice.x = rng.gen_range(0..WIDTH - SIZE as u16) as i16;
+            vertice.y = rng.gen_range(0..HEIGHT - SIZE as u16) as i16;
+            Boid::new(
+                vertice,
+                SIZE,
+                rng.gen_range(-5..=5),
+                rng.gen_range(-5..=5),
+                Color::Green,
+            )
+        })
+        .collect()
+}
+
+fn quad_tree_insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("quad_tree_insert");
+    for population in POPULATIONS {
+        let boids = random_boids(population);
+        group.bench_with_input(BenchmarkId::from_parameter(population), &boids, |b, boids| {
+            b.iter(|| {
+                let mut quad_tree = QuadTree::new(QUAD_TREE_CAPACITY, boundary());
+                for boid in boids {
+                    let _ = quad_tree.insert(boid);
+                }
+                quad_tree
+            });
+        });
+    }
+    group.finish();
+}
+
+fn quad_tree_query(c: &mut Criterion) {
+    let mut group = c.benchmark_group("quad_tree_query");
+    for population in POPULATIONS {
+        let boids = random_boids(population);
+        let mut quad_tree = QuadTree::new(QUAD_TREE_CAPACITY, boundary());
+        for boid in &boids {
+            let _ = quad_tree.insert(boid);
+        }
+        group.bench_with_input(BenchmarkId::from_parameter(population), &boids, |b, boids| {
+            b.iter(|| {
+                let mut found: Vec<Boid> = vec![];
+                for boid in boids {
+                    found.clear();
+                    quad_tree.query(&mut found, boid, black_box(30.0));
+                }
+            });
+        });
+    }
+    group.finish();
+}
+
+fn world_update(c: &mut Criterion) {
+    let mut group = c.benchmark_group("world_update");
+    group.sample_size(20);
+    for population in POPULATIONS {
+        let mut world = World::builder().boids(population).predators(3).build();
+        group.bench_function(BenchmarkId::from_parameter(population), |b| {
+            b.iter(|| world.update());
+        });
+    }
+    group.finish();
+}
+
+fn rasterizer(c: &mut Criterion) {
+    let mut frame = vec![0u8; WIDTH as usize * HEIGHT as usize * 4];
+    let mut group = c.benchmark_group("rasterizer");
+
+    for population in POPULATIONS {
+        let mut world = World::builder().boids(population).predators(3).build();
+        group.bench_function(BenchmarkId::new("world_draw", population), |b| {
+            b.iter(|| world.draw(&mut frame));
+        });
+    }
+
+    let mut start = Vertice::new();
+    start.x = 10;
+    start.y = 10;
+    let mut end = Vertice::new();
+    end.x = WIDTH as i16 - 10;
+    end.y = HEIGHT as i16 - 10;
+    group.bench_function("draw_line", |b| {
+        b.iter(|| draw_line(black_box(&start), black_box(&end), &mut frame, WIDTH, HEIGHT, 1.0));
+    });
+
+    let circle = Circle::new(WIDTH as f32 / 2.0, HEIGHT as f32 / 2.0, 100.0, 1.0, Color::Blue);
+    group.bench_function("circle", |b| {
+        b.iter(|| circle.draw(&mut frame, WIDTH, HEIGHT));
+    });
+
+    let rectangle = boundary();
+    group.bench_function("rectangle", |b| {
+        b.iter(|| rectangle.draw(&mut frame, WIDTH, HEIGHT));
+    });
+    group.finish();
+}
+
+criterion_group!(benches, quad_tree_insert, quad_tree_query, world_update, rasterizer);
+criterion_main!(benches);
@@ -1,16 +1,126 @@
-use crate::{geometry::Color, node::RenderNode};
+use std::{path::Path, sync::Arc};
 
+use serde::{Deserialize, Serialize};
+
+use crate::{error::Result, geometry::Color, node::RenderNode, palette::Theme, WorldOption};
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BackgroundStyle {
+    Solid,
+    Gradient,
+    Grid,
+    Image,
+}
+
+impl BackgroundStyle {
+    pub const ALL: [BackgroundStyle; 4] = [
+        BackgroundStyle::Solid,
+        BackgroundStyle::Gradient,
+        BackgroundStyle::Grid,
+        BackgroundStyle::Image,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BackgroundStyle::Solid => "Solid",
+            BackgroundStyle::Gradient => "Gradient",
+            BackgroundStyle::Grid => "Grid",
+            BackgroundStyle::Image => "Image",
+        }
+    }
+}
+
+struct BackgroundImage {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+#[derive(Clone)]
 pub struct Background {
     color: Color,
+    // Shared so cloning the world for the update thread stays cheap
+    image: Option<Arc<BackgroundImage>>,
 }
 
 impl Background {
     pub fn new(color: Color) -> Self {
-        Self { color }
+        Self { color, image: None }
+    }
+
+    pub fn load_image(&mut self, path: &Path) -> Result<()> {
+        let image = image::open(path)?.to_rgba8();
+        self.image = Some(Arc::new(BackgroundImage {
+            width: image.width(),
+            height: image.height(),
+            pixels: image.into_raw(),
+        }));
+        Ok(())
+    }
+
+    fn pixel_color(&self, x: usize, y: usize, width: u16, height: u16, world_option: &WorldOption, theme: &Theme) -> [u8; 4] {
+        match world_option.background_style {
+            BackgroundStyle::Solid => theme.background.to_color_array(),
+            BackgroundStyle::Gradient => {
+                let [top_r, top_g, top_b] = world_option.gradient_top;
+                let [bottom_r, bottom_g, bottom_b] = world_option.gradient_bottom;
+                Color::gradient(
+                    &Color::Rgba(top_r, top_g, top_b, 255),
+                    &Color::Rgba(bottom_r, bottom_g, bottom_b, 255),
+                    y as f32 / height as f32,
+                )
+                .to_color_array()
+            }
+            BackgroundStyle::Grid => {
+                let spacing = world_option.grid_spacing.max(1) as usize;
+                if x.is_multiple_of(spacing) || y.is_multiple_of(spacing) {
+                    theme.grid.to_color_array()
+                } else {
+                    theme.background.to_color_array()
+                }
+            }
+            BackgroundStyle::Image => match &self.image {
+                Some(image) => {
+                    // Nearest neighbour scaling to the frame size
+                    let image_x = x * image.width as usize / width as usize;
+                    let image_y = y * image.height as usize / height as usize;
+                    let start = (image_y * image.width as usize + image_x) * 4;
+                    let mut color = [0; 4];
+                    color.copy_from_slice(&image.pixels[start..start + 4]);
+                    color
+                }
+                None => theme.background.to_color_array(),
+            },
+        }
     }
 }
 
 impl RenderNode for Background {
+    fn draw_with_option(&self, frame: &mut [u8], width: u16, height: u16, world_option: &WorldOption) {
+        let theme = world_option.palette.theme();
+        if world_option.background_style == BackgroundStyle::Solid && !world_option.show_trails {
+            let color = theme.background.to_color_array();
+            for pixel in frame.chunks_exact_mut(4) {
+                pixel.copy_from_slice(&color);
+            }
+            return;
+        }
+        let decay = if world_option.show_trails {
+            world_option.trail_decay
+        } else {
+            0.0
+        };
+        for (index, pixel) in frame.chunks_exact_mut(4).enumerate() {
+            let x = index % width as usize;
+            let y = index / width as usize;
+            let color = self.pixel_color(x, y, width, height, world_option, &theme);
+            // Fade the previous frame towards the background instead of clearing it
+            for (channel, target) in pixel.iter_mut().zip(color) {
+                *channel = (target as f32 + (*channel as f32 - target as f32) * decay) as u8;
+            }
+        }
+    }
+
     fn draw(&self, _frame: &mut [u8], _width: u16, _height: u16) {
         let color = self.color.to_color_array();
         for pixel in _frame.chunks_exact_mut(4) {
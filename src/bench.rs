@@ -0,0 +1,123 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use boids::{World, WorldOption};
+use instant::Instant;
+
+use crate::cli::BenchArgs;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// The system allocator, counting the allocations going through it for `boids bench`.
+pub struct CountingAllocator;
+
+impl CountingAllocator {
+    fn allocated(size: usize) {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(size, Ordering::Relaxed);
+        let live = LIVE_BYTES.fetch_add(size, Ordering::Relaxed) + size;
+        PEAK_BYTES.fetch_max(live, Ordering::Relaxed);
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let pointer = System.alloc(layout);
+        if !pointer.is_null() {
+            Self::allocated(layout.size());
+        }
+        pointer
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let pointer = System.alloc_zeroed(layout);
+        if !pointer.is_null() {
+            Self::allocated(layout.size());
+        }
+        pointer
+    }
+
+    unsafe fn realloc(&self, pointer: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let moved = System.realloc(pointer, layout, new_size);
+        if !moved.is_null() {
+            LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+            Self::allocated(new_size);
+        }
+        moved
+    }
+
+    unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+        System.dealloc(pointer, layout);
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+/// Run updates without drawing and print the tick time percentiles and what they allocated.
+pub fn run(args: &BenchArgs) {
+    let mut option = WorldOption::new();
+    option.index_backend = args.backend.into();
+    let mut world = World::builder()
+        .option(option)
+        .boids(args.boids)
+        .predators(args.predators)
+        .seed(args.seed)
+        .build();
+    println!(
+        "{} boids, {} predators, {} index, seed {}",
+        world.boids_count(),
+        world.predators_count(),
+        world.option.index_backend.name(),
+        world.seed()
+    );
+
+    let mut tick_times = Vec::with_capacity(args.ticks as usize);
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let allocated_bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    PEAK_BYTES.store(LIVE_BYTES.load(Ordering::Relaxed), Ordering::Relaxed);
+    let start_time = Instant::now();
+    for _ in 0..args.ticks {
+        let tick_start = Instant::now();
+        world.update();
+        tick_times.push(tick_start.elapsed());
+    }
+    let elapsed = start_time.elapsed();
+    let ticks = args.ticks.max(1) as f64;
+    let allocations = (ALLOCATIONS.load(Ordering::Relaxed) - allocations) as f64 / ticks;
+    let allocated_bytes = (ALLOCATED_BYTES.load(Ordering::Relaxed) - allocated_bytes) as f64 / ticks;
+
+    println!("{} ticks in {elapsed:.2?}", args.ticks);
+    tick_times.sort_unstable();
+    if !tick_times.is_empty() {
+        println!(
+            "Tick time: mean {}, p50 {}, p90 {}, p99 {}, max {}",
+            milliseconds(elapsed / args.ticks),
+            milliseconds(percentile(&tick_times, 0.5)),
+            milliseconds(percentile(&tick_times, 0.9)),
+            milliseconds(percentile(&tick_times, 0.99)),
+            milliseconds(tick_times[tick_times.len() - 1]),
+        );
+    }
+    println!(
+        "Allocations: {allocations:.0} per tick, {} per tick, peak {} in use",
+        megabytes(allocated_bytes),
+        megabytes(PEAK_BYTES.load(Ordering::Relaxed) as f64)
+    );
+}
+
+// Nearest rank of the sorted `times`
+fn percentile(times: &[Duration], fraction: f64) -> Duration {
+    let rank = (fraction * times.len() as f64).ceil() as usize;
+    times[rank.clamp(1, times.len()) - 1]
+}
+
+fn milliseconds(duration: Duration) -> String {
+    format!("{:.2} ms", duration.as_secs_f64() * 1000.0)
+}
+
+fn megabytes(bytes: f64) -> String {
+    format!("{:.2} MB", bytes / 1_000_000.0)
+}
@@ -1,16 +1,37 @@
-use crate::{geometry::{change_pixel, draw_line, Circle, Color}, node::{self, Vertice}, WorldOption};
+use crate::{geometry::{blend_pixel, draw_colored_line, draw_colored_line_anti_aliased, draw_line, draw_line_anti_aliased, Arc, Circle, Color, Rectangle}, node::{self, Vertice}, obstacle::{Attractor, Obstacle}, preset::Species, profile::Profile, WorldOption};
 use node::{MovableNode, RenderNode};
 use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::{PI, TAU};
 use uuid::Uuid;
 
-#[derive(Clone)]
+/// One member of a flock, boid or predator alike.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Boid {
     id: Uuid,
+    // Told apart in queries of a spatial index holding several species
+    #[serde(default)]
+    species: Species,
+    // Rounded `position_x` and `position_y`, used for neighbor queries
     pub vertice: Vertice,
+    position_x: f32,
+    position_y: f32,
     size: i16,
-    velocity_x: i16,
-    velocity_y: i16,
+    // Random scale on top of the species size, see `vary_size`
+    size_factor: f32,
+    velocity_x: f32,
+    velocity_y: f32,
+    // Direction the body is drawn pointing to in radians, following the velocity smoothly so
+    // it does not flicker when the velocity swings from one tick to the next
+    #[serde(default)]
+    heading: f32,
     color: Color,
+    // Set from a box selection: frozen boids stay put, a fixed color ignores the color mode
+    frozen: bool,
+    fixed_color: bool,
+    // Settings it follows over those of the world, boxed as few boids have one
+    #[serde(default)]
+    profile: Option<Box<Profile>>,
 }
 
 impl Boid {
@@ -23,27 +44,95 @@ impl Boid {
     ) -> Self {
         Self {
             id: Uuid::new_v4(),
+            species: Species::Boid,
+            position_x: vertice.x as f32,
+            position_y: vertice.y as f32,
             vertice,
             size,
-            velocity_x,
-            velocity_y,
+            size_factor: 1.0,
+            velocity_x: velocity_x as f32,
+            velocity_y: velocity_y as f32,
+            heading: (velocity_y as f32).atan2(velocity_x as f32),
             color,
+            frozen: false,
+            fixed_color: false,
+            profile: None,
         }
     }
 
+    /// Position in world coordinates.
+    pub fn position(&self) -> (f32, f32) {
+        (self.position_x, self.position_y)
+    }
+
+    pub fn set_position(&mut self, x: f32, y: f32) {
+        self.position_x = x;
+        self.position_y = y;
+        self.vertice.x = x.round() as i16;
+        self.vertice.y = y.round() as i16;
+    }
+
+    /// Stays the same for the boid's whole life, even across undo and redo.
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn size(&self) -> i16 {
+        self.size
+    }
+
+    pub fn species(&self) -> Species {
+        self.species
+    }
+
+    pub fn set_species(&mut self, species: Species) {
+        self.species = species;
+    }
+
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_deref()
+    }
+
+    /// Follow `profile` over the settings of the world, an empty one drops it.
+    pub fn set_profile(&mut self, profile: Profile) {
+        self.profile = (!profile.is_empty()).then(|| Box::new(profile));
+    }
+
+    pub fn color(&self) -> &Color {
+        &self.color
+    }
+
+    /// Scale the boid by a random factor within `1 ± variation`, kept across `set_base_size`.
+    pub fn vary_size(&mut self, variation: f32, rng: &mut (impl Rng + ?Sized)) {
+        let variation = variation.clamp(0.0, 1.0);
+        if variation > 0.0 {
+            let base_size = self.size as f32 / self.size_factor;
+            self.size_factor = rng.gen_range(1.0 - variation..=1.0 + variation);
+            self.set_base_size(base_size.round() as i16);
+        }
+    }
+
+    /// Apply the species size, e.g. after it was changed in the settings.
+    pub fn set_base_size(&mut self, base_size: i16) {
+        self.size = ((base_size as f32 * self.size_factor).round() as i16).max(1);
+    }
+
+    /// Steer away from boids inside the safe radius. Like `align` and `cohesion`, the ids
+    /// of the boids that passed the distance and view angle tests are pushed to `accepted`.
     pub fn separate(
         &mut self,
-        boids: &Vec<Boid>,
+        boids: &[Boid],
         avoid_factor: f32,
         safe_radius: f32,
         view_angle: f32,
+        mut accepted: Option<&mut Vec<Uuid>>,
     ) {
         let mut close_dx: f32 = 0.0;
         let mut close_dy: f32 = 0.0;
 
         let mut new_vertice = Vertice::new();
-        new_vertice.x = self.velocity_x + self.vertice.x;
-        new_vertice.y = self.velocity_y + self.vertice.y;
+        new_vertice.x = self.velocity_x.round() as i16 + self.vertice.x;
+        new_vertice.y = self.velocity_y.round() as i16 + self.vertice.y;
         let facing_angle: f32 = Self::angle(&self.vertice, &new_vertice);
 
         for other_boid in boids {
@@ -53,20 +142,24 @@ impl Boid {
 
             let dx = (self.vertice.x - other_boid.vertice.x) as f32;
             let dy = (self.vertice.y - other_boid.vertice.y) as f32;
-            let d = (dx * dx + dy * dy).sqrt();
+            // Keep the safe radius between the bodies rather than their corners
+            let d = (dx * dx + dy * dy).sqrt() - (self.size + other_boid.size) as f32 / 2.0;
             let object_angle = Self::angle(&self.vertice, &other_boid.vertice);
             if d <= safe_radius && Self::is_within_sight(facing_angle, view_angle, object_angle) {
                 close_dx += dx;
                 close_dy += dy;
+                if let Some(accepted) = accepted.as_deref_mut() {
+                    accepted.push(other_boid.id);
+                }
             }
         }
-        self.velocity_x += (close_dx * avoid_factor) as i16;
-        self.velocity_y += (close_dy * avoid_factor) as i16;
+        self.velocity_x += close_dx * avoid_factor;
+        self.velocity_y += close_dy * avoid_factor;
     }
 
     pub fn fear(
         &mut self,
-        predators: &Vec<Boid>,
+        predators: &[Boid],
         fear_factor: f32,
         fear_radius: f32,
     ) {
@@ -76,10 +169,6 @@ impl Boid {
         let mut close_dx: f32 = 0.0;
         let mut close_dy: f32 = 0.0;
 
-        let mut new_vertice = Vertice::new();
-        new_vertice.x = self.velocity_x + self.vertice.x;
-        new_vertice.y = self.velocity_y + self.vertice.y;
-
         for predator in predators {
             if self == predator {
                 continue;
@@ -96,25 +185,26 @@ impl Boid {
         if close_dx == 0.0 && close_dy == 0.0 {
             return;
         }
-        self.velocity_x = (close_dx * fear_factor) as i16;
-        self.velocity_y = (close_dy * fear_factor) as i16;
+        self.velocity_x = close_dx * fear_factor;
+        self.velocity_y = close_dy * fear_factor;
     }
 
 
     pub fn align(
         &mut self,
-        boids: &Vec<Boid>,
+        boids: &[Boid],
         matching_factor: f32,
         vision_radius: f32,
         view_angle: f32,
+        mut accepted: Option<&mut Vec<Uuid>>,
     ) {
         let mut neighboring_boids: u16 = 0;
         let mut vx_avg: f32 = 0.0;
         let mut vy_avg: f32 = 0.0;
 
         let mut new_vertice = Vertice::new();
-        new_vertice.x = self.velocity_x + self.vertice.x;
-        new_vertice.y = self.velocity_y + self.vertice.y;
+        new_vertice.x = self.velocity_x.round() as i16 + self.vertice.x;
+        new_vertice.y = self.velocity_y.round() as i16 + self.vertice.y;
         let facing_angle: f32 = Self::angle(&self.vertice, &new_vertice);
 
         for other_boid in boids {
@@ -126,33 +216,37 @@ impl Boid {
             let d = (dx * dx + dy * dy).sqrt();
             let object_angle = Self::angle(&self.vertice, &other_boid.vertice);
             if d <= vision_radius && Self::is_within_sight(facing_angle, view_angle, object_angle) {
-                vx_avg += other_boid.velocity_x as f32;
-                vy_avg += other_boid.velocity_y as f32;
+                vx_avg += other_boid.velocity_x;
+                vy_avg += other_boid.velocity_y;
                 neighboring_boids += 1;
+                if let Some(accepted) = accepted.as_deref_mut() {
+                    accepted.push(other_boid.id);
+                }
             }
         }
         if neighboring_boids > 0 {
             vx_avg /= neighboring_boids as f32;
             vy_avg /= neighboring_boids as f32;
-            self.velocity_x += (vx_avg * matching_factor) as i16;
-            self.velocity_y += (vy_avg * matching_factor) as i16;
+            self.velocity_x += vx_avg * matching_factor;
+            self.velocity_y += vy_avg * matching_factor;
         }
     }
 
     pub fn cohesion(
         &mut self,
-        boids: &Vec<Boid>,
+        boids: &[Boid],
         centering_factor: f32,
         vision_radius: f32,
         view_angle: f32,
+        mut accepted: Option<&mut Vec<Uuid>>,
     ) {
         let mut neighboring_boids: u16 = 0;
         let mut x_avg: f32 = 0.0;
         let mut y_avg: f32 = 0.0;
 
         let mut new_vertice = Vertice::new();
-        new_vertice.x = self.velocity_x + self.vertice.x;
-        new_vertice.y = self.velocity_y + self.vertice.y;
+        new_vertice.x = self.velocity_x.round() as i16 + self.vertice.x;
+        new_vertice.y = self.velocity_y.round() as i16 + self.vertice.y;
         let facing_angle: f32 = Self::angle(&self.vertice, &new_vertice);
 
         for other_boid in boids {
@@ -164,117 +258,330 @@ impl Boid {
             let d = (dx * dx + dy * dy).sqrt();
             let object_angle = Self::angle(&self.vertice, &other_boid.vertice);
             if d <= vision_radius && Self::is_within_sight(facing_angle, view_angle, object_angle) {
-                x_avg += other_boid.vertice.x as f32;
-                y_avg += other_boid.vertice.y as f32;
+                x_avg += other_boid.position_x;
+                y_avg += other_boid.position_y;
                 neighboring_boids += 1;
+                if let Some(accepted) = accepted.as_deref_mut() {
+                    accepted.push(other_boid.id);
+                }
             }
         }
         if neighboring_boids > 0 {
             x_avg /= neighboring_boids as f32;
             y_avg /= neighboring_boids as f32;
-            self.velocity_x += ((x_avg - self.vertice.x as f32) * centering_factor) as i16;
-            self.velocity_y += ((y_avg - self.vertice.y as f32) * centering_factor) as i16;
+            self.velocity_x += (x_avg - self.position_x) * centering_factor;
+            self.velocity_y += (y_avg - self.position_y) * centering_factor;
         }
     }
     
+    /// Move by the velocity, without wrapping around any edge.
+    pub fn advance(&mut self) {
+        self.set_position(self.position_x + self.velocity_x, self.position_y + self.velocity_y);
+    }
+
     pub fn avoid_border(&mut self, turn_factor: i16, margin: u16, width: u16, height: u16) {
-        if self.vertice.x < margin as i16 {
+        let turn_factor = turn_factor as f32;
+        if self.position_x < margin as f32 {
             self.velocity_x += turn_factor;
         }
-        if self.vertice.x > width as i16 - margin as i16 {
+        if self.position_x > width as f32 - margin as f32 {
             self.velocity_x -= turn_factor;
         }
-        if self.vertice.y < margin as i16 {
+        if self.position_y < margin as f32 {
             self.velocity_y += turn_factor;
         }
-        if self.vertice.y > height as i16 - margin as i16 {
+        if self.position_y > height as f32 - margin as f32 {
             self.velocity_y -= turn_factor;
         }
     }
 
-    pub fn speed_limit(&mut self, max_speed: i16, min_speed: i16) {
-        let x = self.velocity_x.wrapping_mul(self.velocity_x);
-        let y = self.velocity_y.wrapping_mul(self.velocity_y);
-        let speed = ((x.wrapping_add(y)) as f32).sqrt();
+    /// Push away from the obstacles, harder the deeper the boid is inside their margin.
+    pub fn avoid_obstacles(&mut self, obstacles: &[Obstacle], turn_factor: i16, margin: u16) {
+        for obstacle in obstacles {
+            let dx = self.position_x - obstacle.x;
+            let dy = self.position_y - obstacle.y;
+            let d = (dx * dx + dy * dy).sqrt().max(f32::EPSILON);
+            let reach = obstacle.radius + margin as f32;
+            if d < reach {
+                let push = (reach - d) / reach * turn_factor as f32;
+                self.velocity_x += dx / d * push;
+                self.velocity_y += dy / d * push;
+            }
+        }
+    }
+
+    /// Steer out of the areas from `margin` away, towards the edge closest to the boid.
+    pub fn avoid_areas(&mut self, areas: &[Rectangle], turn_factor: i16, margin: u16) {
+        for area in areas {
+            let dx = self.position_x - area.center_x;
+            let dy = self.position_y - area.center_y;
+            // How deep inside the area and its margin, from the closest sides
+            let depth_x = area.half_width + margin as f32 - dx.abs();
+            let depth_y = area.half_height + margin as f32 - dy.abs();
+            if depth_x <= 0.0 || depth_y <= 0.0 {
+                continue;
+            }
+            if depth_x < depth_y {
+                self.velocity_x += dx.signum() * turn_factor as f32;
+            } else {
+                self.velocity_y += dy.signum() * turn_factor as f32;
+            }
+        }
+    }
+
+    pub fn seek_attractors(&mut self, attractors: &[Attractor]) {
+        for attractor in attractors {
+            let dx = attractor.x - self.position_x;
+            let dy = attractor.y - self.position_y;
+            if (dx * dx + dy * dy).sqrt() <= attractor.radius {
+                self.velocity_x += dx * attractor.strength;
+                self.velocity_y += dy * attractor.strength;
+            }
+        }
+    }
+
+    pub fn speed_limit(&mut self, max_speed: i16, min_speed: i16, rng: &mut (impl Rng + ?Sized)) {
+        let speed = self.speed();
         if speed == 0.0 {
-            let mut rng = rand::thread_rng();
             let velocity_x = rng.gen_range(-min_speed..=min_speed);
             let range: [i16; 2] = [-1, 1];
             let velocity_y = ((min_speed.pow(2) - velocity_x.pow(2)) as f32).sqrt() as i16
                 * range[rng.gen_range(0..=1)];
 
-            self.velocity_x = velocity_x;
-            self.velocity_y = velocity_y;
+            self.velocity_x = velocity_x as f32;
+            self.velocity_y = velocity_y as f32;
             return;
         }
-        if (speed as i16) > max_speed {
-            self.velocity_x = ((self.velocity_x as f32 / speed) * max_speed as f32) as i16;
-            self.velocity_y = ((self.velocity_y as f32 / speed) * max_speed as f32) as i16;
+        if speed > max_speed as f32 {
+            self.velocity_x = (self.velocity_x / speed) * max_speed as f32;
+            self.velocity_y = (self.velocity_y / speed) * max_speed as f32;
         }
-        if (speed as i16) < min_speed {
-            self.velocity_x = ((self.velocity_x as f32 / speed) * min_speed as f32) as i16;
-            self.velocity_y = ((self.velocity_y as f32 / speed) * min_speed as f32) as i16;
+        if speed < min_speed as f32 {
+            self.velocity_x = (self.velocity_x / speed) * min_speed as f32;
+            self.velocity_y = (self.velocity_y / speed) * min_speed as f32;
+        }
+    }
+
+    pub fn velocity(&self) -> (f32, f32) {
+        (self.velocity_x, self.velocity_y)
+    }
+
+    pub fn set_velocity(&mut self, x: f32, y: f32) {
+        self.velocity_x = x;
+        self.velocity_y = y;
+    }
+
+    /// Take the position, velocity and heading of `other`, keeping everything else.
+    pub(crate) fn move_like(&mut self, other: &Boid) {
+        self.set_position(other.position_x, other.position_y);
+        self.set_velocity(other.velocity_x, other.velocity_y);
+        self.heading = other.heading;
+    }
+
+    /// Direction the body is drawn pointing to, in radians from the right towards +y.
+    pub fn heading(&self) -> f32 {
+        self.heading
+    }
+
+    /// Turn the drawn heading towards the velocity, keeping `smoothing` of the way left, from 0
+    /// to follow it right away to near 1 to barely move. Standing still keeps the heading.
+    pub fn smooth_heading(&mut self, smoothing: f32) {
+        if self.velocity_x == 0.0 && self.velocity_y == 0.0 {
+            return;
         }
+        let target = self.velocity_y.atan2(self.velocity_x);
+        // The short way around, from -PI to PI
+        let turn = (target - self.heading + PI).rem_euclid(TAU) - PI;
+        self.heading = (self.heading + turn * (1.0 - smoothing.clamp(0.0, 1.0)) + PI).rem_euclid(TAU) - PI;
     }
 
-    pub fn noise(&mut self, on: bool) {
+    pub fn speed(&self) -> f32 {
+        (self.velocity_x * self.velocity_x + self.velocity_y * self.velocity_y).sqrt()
+    }
+
+    pub fn set_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    /// Keep `color` regardless of the color mode.
+    pub fn set_fixed_color(&mut self, color: Color) {
+        self.color = color;
+        self.fixed_color = true;
+    }
+
+    pub fn has_fixed_color(&self) -> bool {
+        self.fixed_color
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    pub fn set_frozen(&mut self, frozen: bool) {
+        self.frozen = frozen;
+    }
+
+    /// Color the boid from `slow` at `min_speed` to `fast` at `max_speed`
+    pub fn update_color(&mut self, max_speed: i16, min_speed: i16, slow: &Color, fast: &Color) {
+        let range = (max_speed - min_speed) as f32;
+        let t = if range > 0.0 {
+            (self.speed() - min_speed as f32) / range
+        } else {
+            1.0
+        };
+        self.color = Color::gradient(slow, fast, t);
+    }
+
+    /// Color the boid by the direction it is drawn heading, right is red
+    pub fn update_heading_color(&mut self) {
+        let heading = self.heading.to_degrees();
+        self.color = Color::from_hsv(heading, 1.0, 1.0);
+    }
+
+    pub fn noise(&mut self, on: bool, rng: &mut (impl Rng + ?Sized)) {
         if !on {
             return;
         }
-        let mut rng = rand::thread_rng();
         let val = rng.gen_range(0.0..2.0);
         let x_val = if rng.gen_bool(0.5) {
             1.0 * val
         } else {
-            -1.0 * val
+            -val
         };
         let y_val = if rng.gen_bool(0.5) {
             1.0 * val
         } else {
-            -1.0 * val
+            -val
         };
-        self.velocity_x += x_val as i16;
-        self.velocity_y += y_val as i16;
+        self.velocity_x += x_val;
+        self.velocity_y += y_val;
+    }
+
+    /// Direction from `origin` to `other` in degrees from 0 to 360, right is 0 and angles grow
+    /// towards +y like the headings drawn by the debug overlays.
+    pub fn angle(origin: &Vertice, other: &Vertice) -> f32 {
+        let dx = (other.x - origin.x) as f32;
+        let dy = (other.y - origin.y) as f32;
+        dy.atan2(dx).to_degrees().rem_euclid(360.0)
+    }
+
+    /// Whether something in the direction `object_angle` lies in a view cone `view_angle` wide,
+    /// centered on `facing_angle`. All in degrees.
+    pub fn is_within_sight(facing_angle: f32, view_angle: f32, object_angle: f32) -> bool {
+        // From -180 to 180, so the cone can straddle 0
+        let difference = (object_angle - facing_angle + 180.0).rem_euclid(360.0) - 180.0;
+        difference.abs() <= view_angle / 2.0
     }
 
-    // right is 0 degree
-    fn angle(origin: &Vertice, other: &Vertice) -> f32 {
-        let dx = (origin.x - other.x) as f32;
-        let dy = (origin.y - other.y) as f32;
-        let radions = (dy / dx).atan() * 180.0;
-        if dx >= 0.0 && dy >= 0.0 {
-            return radions;
+    /// Spread the body over every pixel it overlaps, weighted by the covered area, so slow
+    /// boids glide between pixels instead of jumping from one to the next.
+    fn splat(&self, frame: &mut [u8], width: u16, height: u16) {
+        let color = self.color.to_color_array();
+        let size = self.size as f32;
+        let (left, top) = (self.position_x, self.position_y);
+        let (right, bottom) = (left + size, top + size);
+        // Length of [start, end) inside the pixel starting at `pixel`
+        let overlap = |pixel: f32, start: f32, end: f32| (end.min(pixel + 1.0) - start.max(pixel)).max(0.0);
+        for y in top.floor() as i32..bottom.ceil() as i32 {
+            let coverage_y = overlap(y as f32, top, bottom);
+            for x in left.floor() as i32..right.ceil() as i32 {
+                let coverage = overlap(x as f32, left, right) * coverage_y;
+                blend_pixel(frame, x, y, width, height, color, coverage);
+            }
         }
-        if dx < 0.0 && dy >= 0.0 {
-            return 180.0 - radions;
+    }
+
+    fn draw_circle(&self, frame: &mut [u8], width: u16, height: u16, radius: f32, color: Color, world_option: &WorldOption) {
+        let circle = Circle::new(self.vertice.x as f32, self.vertice.y as f32, radius, world_option.debug_line_width, color);
+        circle.draw_with_option(frame, width, height, world_option);
+    }
+
+    /// Field of view wedge: an arc at the vision radius and its two edges
+    fn draw_vision_cone(&self, frame: &mut [u8], width: u16, height: u16, world_option: &WorldOption) {
+        let radius = world_option.boid_vision_radius;
+        let view_angle = world_option.boid_view_angle;
+        let heading = self.velocity_y.atan2(self.velocity_x).to_degrees();
+        let color = world_option.palette.theme().vision;
+        let arc = Arc::new(
+            self.vertice.x as f32,
+            self.vertice.y as f32,
+            radius,
+            heading,
+            view_angle,
+            world_option.debug_line_width,
+            color.clone(),
+        );
+        arc.draw_with_option(frame, width, height, world_option);
+        if view_angle >= 360.0 {
+            return;
         }
-        if dx < 0.0 && dy < 0.0 {
-            return 180.0 + radions;
+        for edge_angle in [heading - view_angle / 2.0, heading + view_angle / 2.0] {
+            let mut end = Vertice::new();
+            end.x = self.vertice.x + (radius * edge_angle.to_radians().cos()).round() as i16;
+            end.y = self.vertice.y + (radius * edge_angle.to_radians().sin()).round() as i16;
+            if world_option.high_quality_rendering {
+                draw_colored_line_anti_aliased(&self.vertice, &end, frame, width, height, color.to_color_array(), world_option.debug_line_width);
+            } else {
+                draw_colored_line(&self.vertice, &end, frame, width, height, color.to_color_array(), world_option.debug_line_width);
+            }
         }
-        360.0 - radions
     }
 
-    fn is_within_sight(facing_angle: f32, view_angle: f32, object_angle: f32) -> bool {
-        let lower_angle: f32 = facing_angle - (view_angle / 2.0);
-        let upper_angle: f32 = facing_angle + (view_angle / 2.0);
-        let lower_to_object: f32 = (object_angle - lower_angle) % 360.0;
-        let lower_to_upper: f32 = (upper_angle - lower_angle) % 360.0;
-        if lower_to_object <= lower_to_upper {
-            return true;
+    fn draw_facing_direction_with_speed(&self, frame: &mut [u8], width: u16, height: u16, world_option: &WorldOption) {
+        let mut end = Vertice::new();
+        end.x = self.vertice.x + self.velocity_x.round() as i16;
+        end.y = self.vertice.y + self.velocity_y.round() as i16;
+        if world_option.high_quality_rendering {
+            draw_line_anti_aliased(&self.vertice, &end, frame, width, height, world_option.debug_line_width);
+        } else {
+            draw_line(&self.vertice, &end, frame, width, height, world_option.debug_line_width);
         }
-        false
     }
+}
 
-    fn draw_circle(&self, frame: &mut [u8], width: u16, height: u16, radius: f32, color: Color) {
-        let circle = Circle::new(self.vertice.x as f32, self.vertice.y as f32, radius, color);
-        circle.draw(frame, width, height);
+/// Read-only look at a boid or predator, see `World::boids` and `World::predators`.
+#[derive(Clone, Copy)]
+pub struct BoidView<'a> {
+    boid: &'a Boid,
+}
+
+impl<'a> BoidView<'a> {
+    pub(crate) fn new(boid: &'a Boid) -> Self {
+        Self { boid }
     }
 
-    fn draw_facing_direction_with_speed(&self, frame: &mut [u8], width: u16, height: u16) {
-        let mut end = Vertice::new();
-        end.x = self.vertice.x + self.velocity_x;
-        end.y = self.vertice.y + self.velocity_y;
-        draw_line(&self.vertice, &end, frame, width, height);
+    pub fn id(&self) -> Uuid {
+        self.boid.id
+    }
+
+    /// Position in world coordinates.
+    pub fn position(&self) -> (f32, f32) {
+        self.boid.position()
+    }
+
+    pub fn velocity(&self) -> (f32, f32) {
+        self.boid.velocity()
+    }
+
+    /// Direction the body is drawn pointing to, see `Boid::heading`.
+    pub fn heading(&self) -> f32 {
+        self.boid.heading
+    }
+
+    pub fn color(&self) -> &'a Color {
+        &self.boid.color
+    }
+
+    pub fn size(&self) -> i16 {
+        self.boid.size
+    }
+
+    pub fn species(&self) -> Species {
+        self.boid.species
+    }
+
+    pub fn profile(&self) -> Option<&'a Profile> {
+        self.boid.profile.as_deref()
     }
 }
 
@@ -285,44 +592,45 @@ impl PartialEq for Boid {
 }
 
 impl RenderNode for Boid {
-    fn draw_with_option(&self, frame: &mut [u8], width: u16, height: u16, _world_option: &WorldOption) {
-        if _world_option.show_safe_radius {
-            self.draw_circle(frame, width, height, _world_option.safe_radius, Color::Red);
-        }
-        if _world_option.show_vision_radius {
-            self.draw_circle(frame, width, height, _world_option.boid_vision_radius, Color::Blue);
-        }
-        if _world_option.show_facing_direction_with_speed {
-            self.draw_facing_direction_with_speed(frame, width, height);
-        }
-        for i in 0..self.size {
-            for j in 0..self.size {
-                let x = (self.vertice.x + j) as usize;
-                let y = (self.vertice.y + i) as usize;
-                if x >= width as usize || y >= height as usize {
-                    continue;
-                }
-                change_pixel(frame, x, y, width, height, self.color.to_color_array());
-            }
+    fn draw_with_option(&self, frame: &mut [u8], width: u16, height: u16, world_option: &WorldOption) {
+        self.draw_overlays(frame, width, height, world_option);
+        self.splat(frame, width, height);
+    }
+}
+
+impl Boid {
+    /// Debug overlays turned on in `world_option`, without the body itself.
+    pub fn draw_overlays(&self, frame: &mut [u8], width: u16, height: u16, world_option: &WorldOption) {
+        if world_option.show_safe_radius {
+            let radius = world_option.safe_radius + self.size as f32 / 2.0;
+            let color = world_option.palette.theme().safe_radius;
+            self.draw_circle(frame, width, height, radius, color, world_option);
+        }
+        if world_option.show_vision_radius {
+            self.draw_vision_cone(frame, width, height, world_option);
+        }
+        if world_option.show_facing_direction_with_speed {
+            self.draw_facing_direction_with_speed(frame, width, height, world_option);
         }
     }
 }
 
 impl MovableNode for Boid {
     fn update(&mut self, width: u16, height: u16) {
-        self.vertice.x += self.velocity_x;
-        self.vertice.y += self.velocity_y;
-        if self.vertice.x < 0 {
-            self.vertice.x = width as i16;
+        let mut x = self.position_x + self.velocity_x;
+        let mut y = self.position_y + self.velocity_y;
+        if x < 0.0 {
+            x = width as f32;
         }
-        if self.vertice.x > width as i16 {
-            self.vertice.x = 0;
+        if x > width as f32 {
+            x = 0.0;
         }
-        if self.vertice.y < 0 {
-            self.vertice.y = height as i16;
+        if y < 0.0 {
+            y = height as f32;
         }
-        if self.vertice.y > height as i16 {
-            self.vertice.y = 0;
+        if y > height as f32 {
+            y = 0.0;
         }
+        self.set_position(x, y);
     }
 }
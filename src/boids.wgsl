@@ -0,0 +1,51 @@
+// Boid and predator bodies, one instanced triangle each
+
+struct Uniforms {
+    // Left, top, width and height of the visible world area
+    area: vec4<f32>,
+    // Colors come sRGB encoded, an sRGB target expects them linear
+    srgb_target: u32,
+}
+
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) corner: u32,
+    @location(0) center: vec2<f32>,
+    @location(1) heading: vec2<f32>,
+    @location(2) size: f32,
+    @location(3) color: vec4<f32>,
+) -> VertexOutput {
+    // Pointing along +x, in boid sizes
+    var corners = array<vec2<f32>, 3>(
+        vec2<f32>(0.75, 0.0),
+        vec2<f32>(-0.5, 0.5),
+        vec2<f32>(-0.5, -0.5),
+    );
+    let local = corners[corner] * size;
+    let world = center + vec2<f32>(
+        local.x * heading.x - local.y * heading.y,
+        local.x * heading.y + local.y * heading.x,
+    );
+    let screen = (world - uniforms.area.xy) / uniforms.area.zw;
+
+    var out: VertexOutput;
+    out.position = vec4<f32>(screen.x * 2.0 - 1.0, 1.0 - screen.y * 2.0, 0.0, 1.0);
+    out.color = color;
+    if uniforms.srgb_target != 0u {
+        let rgb = color.rgb;
+        out.color = vec4<f32>(select(pow((rgb + 0.055) / 1.055, vec3<f32>(2.4)), rgb / 12.92, rgb <= vec3<f32>(0.04045)), color.a);
+    }
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
@@ -0,0 +1,101 @@
+use crate::random::Random;
+use crate::{World, WorldOption, HEIGHT, WIDTH};
+
+/// Sets up a `World` and spawns its flock, see `World::builder`.
+///
+/// ```
+/// use boids::World;
+///
+/// let world = World::builder().size(320, 240).boids(100).predators(2).seed(42).build();
+/// assert_eq!((world.boids_count(), world.predators_count()), (100, 2));
+/// assert_eq!(world.seed(), 42);
+/// ```
+#[derive(Clone)]
+pub struct WorldBuilder {
+    width: u16,
+    height: u16,
+    option: WorldOption,
+    boids: Option<u16>,
+    predators: Option<u16>,
+    seed: Option<u64>,
+    rng: Option<Box<dyn Random>>,
+}
+
+impl WorldBuilder {
+    pub(crate) fn new() -> Self {
+        Self {
+            width: WIDTH,
+            height: HEIGHT,
+            option: WorldOption::new(),
+            boids: None,
+            predators: None,
+            seed: None,
+            rng: None,
+        }
+    }
+
+    /// Size in pixels, `WIDTH` × `HEIGHT` unless set.
+    pub fn size(mut self, width: u16, height: u16) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Number of boids spawned, `boid_count` of the options unless set.
+    pub fn boids(mut self, count: u16) -> Self {
+        self.boids = Some(count);
+        self
+    }
+
+    /// Number of predators spawned, `predator_count` of the options unless set.
+    pub fn predators(mut self, count: u16) -> Self {
+        self.predators = Some(count);
+        self
+    }
+
+    /// Seed of every random choice, a random one when `None`.
+    pub fn seed(mut self, seed: impl Into<Option<u64>>) -> Self {
+        self.seed = seed.into();
+        self
+    }
+
+    /// Generator of every random choice in place of the seeded default, see `World::set_rng`.
+    pub fn rng(mut self, rng: impl Random + 'static) -> Self {
+        self.rng = Some(Box::new(rng));
+        self
+    }
+
+    /// Every setting at once. Counts given with `boids` and `predators` still win, whatever
+    /// the order they are called in.
+    pub fn option(mut self, option: WorldOption) -> Self {
+        self.option = option;
+        self
+    }
+
+    /// The world with its flock spawned at random positions, nothing to undo yet.
+    pub fn build(self) -> World {
+        let mut world = World::with_size(self.width, self.height);
+        world.option = self.option;
+        if let Some(count) = self.boids {
+            world.option.boid_count = count;
+        }
+        if let Some(count) = self.predators {
+            world.option.predator_count = count;
+        }
+        if let Some(seed) = self.seed {
+            world.set_seed(seed);
+        }
+        if let Some(rng) = self.rng {
+            world.set_boxed_rng(rng);
+        }
+        world.restart();
+        world.forget_history();
+        world
+    }
+}
+
+impl Default for WorldBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,100 @@
+use crate::geometry::Rectangle;
+
+/// Which part of the world is shown on screen.
+///
+/// The screen is the same size as the world, so a zoom of 1 shows everything.
+#[derive(Clone)]
+pub struct Camera {
+    center_x: f32,
+    center_y: f32,
+    zoom: f32,
+}
+
+impl Camera {
+    pub const MIN_ZOOM: f32 = 1.0;
+    pub const MAX_ZOOM: f32 = 8.0;
+
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            center_x: width as f32 / 2.0,
+            center_y: height as f32 / 2.0,
+            zoom: Self::MIN_ZOOM,
+        }
+    }
+
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    pub fn is_zoomed(&self) -> bool {
+        self.zoom > Self::MIN_ZOOM
+    }
+
+    /// Visible area in world coordinates.
+    pub fn viewport(&self, width: u16, height: u16) -> Rectangle {
+        Rectangle::new(
+            self.center_x,
+            self.center_y,
+            width as f32 / 2.0 / self.zoom,
+            height as f32 / 2.0 / self.zoom,
+        )
+    }
+
+    pub fn screen_to_world(&self, x: f32, y: f32, width: u16, height: u16) -> (f32, f32) {
+        (
+            self.center_x + (x - width as f32 / 2.0) / self.zoom,
+            self.center_y + (y - height as f32 / 2.0) / self.zoom,
+        )
+    }
+
+    /// Multiply the zoom by `factor` keeping the world point under (x, y) on screen still.
+    pub fn zoom_at(&mut self, factor: f32, x: f32, y: f32, width: u16, height: u16) {
+        let (world_x, world_y) = self.screen_to_world(x, y, width, height);
+        self.zoom = (self.zoom * factor).clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+        self.center_x = world_x - (x - width as f32 / 2.0) / self.zoom;
+        self.center_y = world_y - (y - height as f32 / 2.0) / self.zoom;
+        self.clamp(width, height);
+    }
+
+    /// Drag the view by a distance in screen pixels.
+    pub fn pan(&mut self, dx: f32, dy: f32, width: u16, height: u16) {
+        self.center_x -= dx / self.zoom;
+        self.center_y -= dy / self.zoom;
+        self.clamp(width, height);
+    }
+
+    /// Center the view on a world position.
+    pub fn look_at(&mut self, x: f32, y: f32, width: u16, height: u16) {
+        self.center_x = x;
+        self.center_y = y;
+        self.clamp(width, height);
+    }
+
+    // Keep the viewport inside the world
+    fn clamp(&mut self, width: u16, height: u16) {
+        let viewport = self.viewport(width, height);
+        self.center_x = self
+            .center_x
+            .clamp(viewport.half_width, width as f32 - viewport.half_width);
+        self.center_y = self
+            .center_y
+            .clamp(viewport.half_height, height as f32 - viewport.half_height);
+    }
+
+    /// Scale the viewport of a world sized `scene` up to fill `frame`.
+    pub fn blit(&self, scene: &[u8], frame: &mut [u8], width: u16, height: u16) {
+        let viewport = self.viewport(width, height);
+        let left = viewport.center_x - viewport.half_width;
+        let top = viewport.center_y - viewport.half_height;
+        let columns: Vec<usize> = (0..width as usize)
+            .map(|x| ((left + x as f32 / self.zoom) as usize).min(width as usize - 1))
+            .collect();
+        for (y, row) in frame.chunks_exact_mut(width as usize * 4).enumerate() {
+            let scene_y = ((top + y as f32 / self.zoom) as usize).min(height as usize - 1);
+            let scene_row = &scene[scene_y * width as usize * 4..(scene_y + 1) * width as usize * 4];
+            for (pixel, &scene_x) in row.chunks_exact_mut(4).zip(&columns) {
+                pixel.copy_from_slice(&scene_row[scene_x * 4..scene_x * 4 + 4]);
+            }
+        }
+    }
+}
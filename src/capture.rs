@@ -0,0 +1,461 @@
+use std::{
+    collections::VecDeque,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::mpsc::{self, SyncSender},
+    thread::{self, JoinHandle},
+};
+
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    Delay, Frame, ImageResult, RgbImage, RgbaImage,
+};
+use instant::{Instant, SystemTime};
+
+pub(crate) fn timestamped_path(directory: &Path, name: &str, extension: &str) -> PathBuf {
+    let timestamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or_default();
+    directory.join(format!("{name}-{timestamp}.{extension}"))
+}
+
+/// Save an RGBA frame buffer as `screenshot-<unix milliseconds>.png` in `directory`.
+///
+/// The alpha channel is dropped since the background is not always opaque.
+pub fn save_screenshot(frame: &[u8], width: u32, height: u32, directory: &Path) -> ImageResult<PathBuf> {
+    let path = timestamped_path(directory, "screenshot", "png");
+    rgb_image(frame, width, height).save(&path)?;
+    Ok(path)
+}
+
+/// Save an RGBA frame buffer as `frame-<index>.png` in `directory`, the index padded to six
+/// digits so the files sort in order and ffmpeg can read them with `-i frame-%06d.png`.
+pub fn save_frame(frame: &[u8], width: u32, height: u32, directory: &Path, index: u32) -> ImageResult<PathBuf> {
+    let path = directory.join(format!("frame-{index:06}.png"));
+    rgb_image(frame, width, height).save(&path)?;
+    Ok(path)
+}
+
+fn rgb_image(frame: &[u8], width: u32, height: u32) -> RgbImage {
+    let pixels = frame
+        .chunks_exact(4)
+        .flat_map(|pixel| [pixel[0], pixel[1], pixel[2]])
+        .collect();
+    // The buffer always matches the frame size so this cannot fail
+    RgbImage::from_raw(width, height, pixels).expect("frame size mismatch")
+}
+
+/// How a GIF recording samples the frame buffer.
+#[derive(Clone, Copy)]
+pub struct GifSettings {
+    /// Keep one pixel out of `scale` in each direction
+    pub scale: u32,
+    /// Keep one frame out of `frame_step`
+    pub frame_step: u32,
+}
+
+impl Default for GifSettings {
+    fn default() -> Self {
+        Self {
+            scale: 2,
+            frame_step: 2,
+        }
+    }
+}
+
+/// Collects frames while recording and writes them out as an animated GIF.
+pub struct GifRecorder {
+    width: u32,
+    height: u32,
+    settings: GifSettings,
+    frames: Vec<Vec<u8>>,
+    frame_counter: u32,
+    start_time: Instant,
+}
+
+impl GifRecorder {
+    // Largest dimension a GIF can describe
+    const MAX_SIZE: u32 = u16::MAX as u32;
+    const PALETTE_LEVELS: u32 = 6;
+
+    /// Start recording a `width` by `height` frame buffer.
+    pub fn new(width: u32, height: u32, settings: GifSettings) -> Self {
+        let settings = GifSettings {
+            scale: settings.scale.max(1),
+            frame_step: settings.frame_step.max(1),
+        };
+        Self {
+            width,
+            height,
+            settings,
+            frames: Vec::new(),
+            frame_counter: 0,
+            start_time: Instant::now(),
+        }
+    }
+
+    fn output_size(width: u32, height: u32, scale: u32) -> (u32, u32) {
+        (
+            width.div_ceil(scale).min(Self::MAX_SIZE),
+            height.div_ceil(scale).min(Self::MAX_SIZE),
+        )
+    }
+
+    /// Add an RGBA frame, frames of another size (e.g. after a resize) are skipped.
+    pub fn capture(&mut self, frame: &[u8], width: u32, height: u32) {
+        let skipped = !self.frame_counter.is_multiple_of(self.settings.frame_step);
+        self.frame_counter += 1;
+        if skipped || width != self.width || height != self.height {
+            return;
+        }
+        self.frames.push(Self::downscale(frame, width, height, self.settings.scale));
+    }
+
+    // Palette indices of one pixel out of `scale` in each direction
+    fn downscale(frame: &[u8], width: u32, height: u32, scale: u32) -> Vec<u8> {
+        let (output_width, output_height) = Self::output_size(width, height, scale);
+        let mut indices = Vec::with_capacity((output_width * output_height) as usize);
+        for y in 0..output_height {
+            for x in 0..output_width {
+                let start = ((y * scale * width + x * scale) * 4) as usize;
+                indices.push(Self::palette_index(&frame[start..start + 3]));
+            }
+        }
+        indices
+    }
+
+    fn palette_index(rgb: &[u8]) -> u8 {
+        let level = |channel: u8| (channel as u32 * (Self::PALETTE_LEVELS - 1) + 127) / 255;
+        (level(rgb[0]) * Self::PALETTE_LEVELS * Self::PALETTE_LEVELS + level(rgb[1]) * Self::PALETTE_LEVELS + level(rgb[2])) as u8
+    }
+
+    /// Write `recording-<unix milliseconds>.gif` to `directory`, looping forever.
+    pub fn finish(self, directory: &Path) -> io::Result<PathBuf> {
+        let path = timestamped_path(directory, "recording", "gif");
+        // Spread the real recording time over the kept frames
+        let delay = gif_delay(self.start_time.elapsed().as_secs_f32() / self.frames.len().max(1) as f32);
+        let (width, height) = Self::output_size(self.width, self.height, self.settings.scale);
+        Self::write(&path, width, height, &self.frames, delay)?;
+        Ok(path)
+    }
+
+    // Color of every palette index, a cube of `PALETTE_LEVELS` levels per channel
+    fn palette() -> Vec<[u8; 4]> {
+        let levels = Self::PALETTE_LEVELS;
+        let scale = |level: u32| (level * 255 / (levels - 1)) as u8;
+        (0..levels * levels * levels)
+            .map(|index| {
+                let (red, green, blue) = (index / (levels * levels), index / levels % levels, index % levels);
+                [scale(red), scale(green), scale(blue), 255]
+            })
+            .collect()
+    }
+
+    // Write `frames` of palette indices as a GIF looping forever, `delay` between them. Frames
+    // have at most 216 colors, which the encoder keeps exactly instead of quantizing them.
+    fn write<'a>(
+        path: &Path,
+        width: u32,
+        height: u32,
+        frames: impl IntoIterator<Item = &'a Vec<u8>>,
+        delay: u16,
+    ) -> io::Result<()> {
+        let mut frames = frames.into_iter().peekable();
+        if frames.peek().is_none() {
+            return Err(io::Error::other("no frames were recorded"));
+        }
+        let palette = Self::palette();
+        let delay = Delay::from_numer_denom_ms(delay as u32 * 10, 1);
+        let mut gif = Vec::new();
+        let mut encoder = GifEncoder::new(&mut gif);
+        encoder.set_repeat(Repeat::Infinite).map_err(io::Error::other)?;
+        for indices in frames {
+            let pixels = indices.iter().flat_map(|index| palette[*index as usize]).collect();
+            // Frames are downscaled to this size so this cannot fail
+            let image = RgbaImage::from_raw(width, height, pixels).expect("frame size mismatch");
+            encoder
+                .encode_frame(Frame::from_parts(image, 0, 0, delay))
+                .map_err(io::Error::other)?;
+        }
+        // The trailer is written when the encoder is dropped
+        drop(encoder);
+        fs::write(path, gif)
+    }
+}
+
+// GIF delay of `seconds` between frames, in 1/100 s and at least the 2 most viewers show
+fn gif_delay(seconds: f32) -> u16 {
+    (seconds * 100.0).round().clamp(2.0, u16::MAX as f32) as u16
+}
+
+/// Keeps the last seconds of the frame buffer, sampled like a GIF recording, so a moment can
+/// be saved after it happened.
+pub struct InstantReplay {
+    width: u32,
+    height: u32,
+    settings: GifSettings,
+    seconds: f32,
+    // Palette indices of the kept frames and when they were captured, oldest first
+    frames: VecDeque<(Instant, Vec<u8>)>,
+    frame_counter: u32,
+}
+
+impl InstantReplay {
+    // Smaller and sparser than a GIF recording, frames are kept all the time
+    const SETTINGS: GifSettings = GifSettings { scale: 3, frame_step: 2 };
+
+    /// Keep the last `seconds` of frames.
+    pub fn new(seconds: f32) -> Self {
+        Self {
+            width: 0,
+            height: 0,
+            settings: Self::SETTINGS,
+            seconds,
+            frames: VecDeque::new(),
+            frame_counter: 0,
+        }
+    }
+
+    pub fn set_seconds(&mut self, seconds: f32) {
+        self.seconds = seconds;
+    }
+
+    /// Whether the next captured frame is kept, the others can skip copying the boids in.
+    pub fn wants_frame(&self) -> bool {
+        self.frame_counter.is_multiple_of(self.settings.frame_step)
+    }
+
+    /// Add an RGBA frame and drop those older than the kept seconds. A frame of another size
+    /// starts over.
+    pub fn capture(&mut self, frame: &[u8], width: u32, height: u32) {
+        let kept = self.wants_frame();
+        self.frame_counter += 1;
+        if !kept {
+            return;
+        }
+        if (width, height) != (self.width, self.height) {
+            (self.width, self.height) = (width, height);
+            self.frames.clear();
+        }
+        let now = Instant::now();
+        while self
+            .frames
+            .front()
+            .is_some_and(|(time, _)| now.duration_since(*time).as_secs_f32() > self.seconds)
+        {
+            self.frames.pop_front();
+        }
+        let indices = GifRecorder::downscale(frame, width, height, self.settings.scale);
+        self.frames.push_back((now, indices));
+    }
+
+    /// Seconds of frames kept so far.
+    pub fn duration(&self) -> f32 {
+        match (self.frames.front(), self.frames.back()) {
+            (Some((first, _)), Some((last, _))) => last.duration_since(*first).as_secs_f32(),
+            _ => 0.0,
+        }
+    }
+
+    /// A copy of the kept frames, to save while more come in.
+    pub fn clip(&self) -> GifClip {
+        let (width, height) = GifRecorder::output_size(self.width, self.height, self.settings.scale);
+        GifClip {
+            width,
+            height,
+            delay: gif_delay(self.duration() / self.frames.len().saturating_sub(1).max(1) as f32),
+            frames: self.frames.iter().map(|(_, indices)| indices.clone()).collect(),
+        }
+    }
+}
+
+/// Frames taken from an `InstantReplay`.
+pub struct GifClip {
+    width: u32,
+    height: u32,
+    delay: u16,
+    frames: Vec<Vec<u8>>,
+}
+
+impl GifClip {
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Write `replay-<unix milliseconds>.gif` to `directory`, looping forever.
+    pub fn save(self, directory: &Path) -> io::Result<PathBuf> {
+        let path = timestamped_path(directory, "replay", "gif");
+        GifRecorder::write(&path, self.width, self.height, &self.frames, self.delay)?;
+        Ok(path)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum VideoFormat {
+    Mp4,
+    WebM,
+}
+
+impl VideoFormat {
+    pub const ALL: [VideoFormat; 2] = [VideoFormat::Mp4, VideoFormat::WebM];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            VideoFormat::Mp4 => "MP4 (H.264)",
+            VideoFormat::WebM => "WebM (VP9)",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            VideoFormat::Mp4 => "mp4",
+            VideoFormat::WebM => "webm",
+        }
+    }
+
+    fn codec_arguments(&self) -> &'static [&'static str] {
+        match self {
+            VideoFormat::Mp4 => &["-c:v", "libx264", "-preset", "veryfast", "-pix_fmt", "yuv420p"],
+            VideoFormat::WebM => &["-c:v", "libvpx-vp9", "-deadline", "realtime", "-pix_fmt", "yuv420p"],
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum VideoResolution {
+    Window,
+    P1080,
+    P720,
+    P480,
+}
+
+impl VideoResolution {
+    pub const ALL: [VideoResolution; 4] = [
+        VideoResolution::Window,
+        VideoResolution::P1080,
+        VideoResolution::P720,
+        VideoResolution::P480,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            VideoResolution::Window => "Window size",
+            VideoResolution::P1080 => "1920 x 1080",
+            VideoResolution::P720 => "1280 x 720",
+            VideoResolution::P480 => "854 x 480",
+        }
+    }
+
+    /// Output size for a `width` by `height` frame, rounded down to even numbers for yuv420p
+    fn size(&self, width: u32, height: u32) -> (u32, u32) {
+        let (width, height) = match self {
+            VideoResolution::Window => (width, height),
+            VideoResolution::P1080 => (1920, 1080),
+            VideoResolution::P720 => (1280, 720),
+            VideoResolution::P480 => (854, 480),
+        };
+        ((width & !1).max(2), (height & !1).max(2))
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct VideoSettings {
+    pub format: VideoFormat,
+    pub resolution: VideoResolution,
+    pub frame_rate: u32,
+}
+
+impl Default for VideoSettings {
+    fn default() -> Self {
+        Self {
+            format: VideoFormat::Mp4,
+            resolution: VideoResolution::Window,
+            frame_rate: 30,
+        }
+    }
+}
+
+/// Streams raw frames to an `ffmpeg` process which encodes the video.
+pub struct VideoRecorder {
+    width: u32,
+    height: u32,
+    frame_rate: u32,
+    path: PathBuf,
+    start_time: Instant,
+    frames_sent: u64,
+    sender: SyncSender<Vec<u8>>,
+    writer: JoinHandle<io::Result<()>>,
+}
+
+impl VideoRecorder {
+    // Frames waiting for ffmpeg before capturing blocks
+    const QUEUE_SIZE: usize = 4;
+
+    /// Start `ffmpeg` for a `width` by `height` frame buffer, fails if it is not installed.
+    pub fn new(width: u32, height: u32, settings: VideoSettings, directory: &Path) -> io::Result<Self> {
+        let frame_rate = settings.frame_rate.max(1);
+        let (output_width, output_height) = settings.resolution.size(width, height);
+        let path = timestamped_path(directory, "recording", settings.format.extension());
+        let mut child = Command::new("ffmpeg")
+            .args(["-y", "-loglevel", "error", "-f", "rawvideo", "-pix_fmt", "rgba"])
+            .args(["-s", &format!("{width}x{height}"), "-r", &frame_rate.to_string(), "-i", "-"])
+            .args(["-vf", &format!("scale={output_width}:{output_height}")])
+            .args(settings.format.codec_arguments())
+            .arg(&path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()?;
+        let mut stdin = child.stdin.take().expect("ffmpeg stdin is piped");
+        let (sender, receiver) = mpsc::sync_channel::<Vec<u8>>(Self::QUEUE_SIZE);
+        let writer = thread::Builder::new()
+            .name("video-encoder".to_string())
+            .spawn(move || {
+                let written = receiver.iter().try_for_each(|frame| stdin.write_all(&frame));
+                // Closing stdin lets ffmpeg finish the file
+                drop(stdin);
+                let status = child.wait()?;
+                written?;
+                if !status.success() {
+                    return Err(io::Error::other(format!("ffmpeg exited with {status}")));
+                }
+                Ok(())
+            })?;
+        Ok(Self {
+            width,
+            height,
+            frame_rate,
+            path,
+            start_time: Instant::now(),
+            frames_sent: 0,
+            sender,
+            writer,
+        })
+    }
+
+    /// Add an RGBA frame, repeated or dropped so the video plays back in real time.
+    pub fn capture(&mut self, frame: &[u8], width: u32, height: u32) {
+        if width != self.width || height != self.height {
+            return;
+        }
+        let frames_due = (self.start_time.elapsed().as_secs_f64() * self.frame_rate as f64) as u64 + 1;
+        while self.frames_sent < frames_due {
+            if self.sender.send(frame.to_vec()).is_err() {
+                // The encoder stopped, the error is reported by `finish`
+                return;
+            }
+            self.frames_sent += 1;
+        }
+    }
+
+    /// Wait for `ffmpeg` to write the file and return its path.
+    pub fn finish(self) -> io::Result<PathBuf> {
+        drop(self.sender);
+        self.writer
+            .join()
+            .map_err(|_| io::Error::other("video encoder thread panicked"))??;
+        Ok(self.path)
+    }
+}
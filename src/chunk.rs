@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+
+use crate::boid::Boid;
+use crate::geometry::Rectangle;
+use crate::preset::Species;
+
+/// Boids of an unbounded world left too far from the frame and the flock to be simulated,
+/// parked in the square chunk of space they were in until one of them comes back.
+///
+/// The frame, the part of the plane in world coordinates, moves along with the flock so
+/// coordinates stay small however far it migrates. Parked boids are kept relative to the
+/// corner of their chunk, they do not move or lose precision meanwhile.
+#[derive(Clone, Default)]
+pub struct Chunks {
+    // Position on the plane of the top left corner of the frame
+    origin: (i64, i64),
+    parked: HashMap<(i64, i64), Parked>,
+}
+
+#[derive(Clone, Default)]
+struct Parked {
+    boids: Vec<Boid>,
+    predators: Vec<Boid>,
+}
+
+impl Parked {
+    fn of(&mut self, species: Species) -> &mut Vec<Boid> {
+        match species {
+            Species::Boid => &mut self.boids,
+            Species::Predator => &mut self.predators,
+        }
+    }
+}
+
+impl Chunks {
+    /// Side of a chunk in pixels.
+    pub const SIZE: f64 = 512.0;
+    /// Farthest the loaded chunks reach from the frame, neighbor queries round positions to `i16`.
+    pub const REACH: f64 = 16384.0;
+
+    /// Position on the plane of the top left corner of the frame.
+    pub fn origin(&self) -> (i64, i64) {
+        self.origin
+    }
+
+    /// Move the frame by `dx`, `dy` on the plane, parked boids stay where they are.
+    pub fn shift(&mut self, dx: i64, dy: i64) {
+        self.origin.0 += dx;
+        self.origin.1 += dy;
+    }
+
+    /// Number of parked boids or predators.
+    pub fn count(&self, species: Species) -> usize {
+        self.parked
+            .values()
+            .map(|parked| match species {
+                Species::Boid => parked.boids.len(),
+                Species::Predator => parked.predators.len(),
+            })
+            .sum()
+    }
+
+    /// Number of chunks with parked boids.
+    pub fn len(&self) -> usize {
+        self.parked.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parked.is_empty()
+    }
+
+    /// Park `boid`, given in world coordinates, in the chunk under it.
+    pub fn park(&mut self, mut boid: Boid, species: Species) {
+        let (x, y) = self.on_plane(boid.position());
+        let chunk = ((x / Self::SIZE).floor() as i64, (y / Self::SIZE).floor() as i64);
+        let (left, top) = Self::corner(chunk);
+        boid.set_position((x - left) as f32, (y - top) as f32);
+        self.parked.entry(chunk).or_default().of(species).push(boid);
+    }
+
+    /// Take back the boids and predators of the chunks inside `area`, in world coordinates.
+    pub fn unpark_within(&mut self, area: &Rectangle) -> (Vec<Boid>, Vec<Boid>) {
+        let (min_x, min_y) = self.on_plane((area.center_x - area.half_width, area.center_y - area.half_height));
+        let (max_x, max_y) = self.on_plane((area.center_x + area.half_width, area.center_y + area.half_height));
+        let inside = |(column, row): (i64, i64)| {
+            let (left, top) = Self::corner((column, row));
+            left >= min_x && top >= min_y && left + Self::SIZE <= max_x && top + Self::SIZE <= max_y
+        };
+        let chunks: Vec<(i64, i64)> = self.parked.keys().copied().filter(|chunk| inside(*chunk)).collect();
+        self.unpark(chunks)
+    }
+
+    /// Take back every parked boid and predator, in world coordinates.
+    pub fn unpark_all(&mut self) -> (Vec<Boid>, Vec<Boid>) {
+        let chunks: Vec<(i64, i64)> = self.parked.keys().copied().collect();
+        self.unpark(chunks)
+    }
+
+    fn unpark(&mut self, chunks: Vec<(i64, i64)>) -> (Vec<Boid>, Vec<Boid>) {
+        let (mut boids, mut predators) = (vec![], vec![]);
+        for chunk in chunks {
+            let Some(parked) = self.parked.remove(&chunk) else {
+                continue;
+            };
+            let mut in_world = self.in_world(chunk);
+            boids.extend(parked.boids.into_iter().map(&mut in_world));
+            predators.extend(parked.predators.into_iter().map(&mut in_world));
+        }
+        (boids, predators)
+    }
+
+    /// Take back the parked boids and predators `pick` chooses, in world coordinates.
+    pub fn take(&mut self, mut pick: impl FnMut(&Boid) -> bool) -> Vec<Boid> {
+        let mut taken = vec![];
+        let chunks: Vec<(i64, i64)> = self.parked.keys().copied().collect();
+        for chunk in chunks {
+            let mut in_world = self.in_world(chunk);
+            let Some(parked) = self.parked.get_mut(&chunk) else {
+                continue;
+            };
+            for boids in [&mut parked.boids, &mut parked.predators] {
+                let (picked, kept) = std::mem::take(boids).into_iter().partition(|boid| pick(boid));
+                *boids = kept;
+                taken.extend(picked.into_iter().map(&mut in_world));
+            }
+        }
+        self.parked.retain(|_, parked| !parked.boids.is_empty() || !parked.predators.is_empty());
+        taken
+    }
+
+    /// Copies of the parked boids or predators, in world coordinates.
+    pub fn parked(&self, species: Species) -> Vec<Boid> {
+        let mut found = vec![];
+        for (chunk, parked) in &self.parked {
+            let boids = match species {
+                Species::Boid => &parked.boids,
+                Species::Predator => &parked.predators,
+            };
+            found.extend(boids.iter().cloned().map(self.in_world(*chunk)));
+        }
+        found
+    }
+
+    /// Positions of the parked boids or predators in world coordinates, with the precision
+    /// of far away ones.
+    pub fn positions(&self, species: Species) -> Vec<(f64, f64)> {
+        let mut positions = vec![];
+        for (chunk, parked) in &self.parked {
+            let (left, top) = Self::corner(*chunk);
+            let boids = match species {
+                Species::Boid => &parked.boids,
+                Species::Predator => &parked.predators,
+            };
+            positions.extend(boids.iter().map(|boid| {
+                let (x, y) = boid.position();
+                (left + x as f64 - self.origin.0 as f64, top + y as f64 - self.origin.1 as f64)
+            }));
+        }
+        positions
+    }
+
+    /// `area`, in world coordinates, grown to the edges of the chunks it touches.
+    pub fn snap(&self, area: &Rectangle) -> Rectangle {
+        let snap = |value: f64, origin: i64, round: fn(f64) -> f64| {
+            (round((value + origin as f64) / Self::SIZE) * Self::SIZE - origin as f64) as f32
+        };
+        let (min_x, min_y) = ((area.center_x - area.half_width) as f64, (area.center_y - area.half_height) as f64);
+        let (max_x, max_y) = ((area.center_x + area.half_width) as f64, (area.center_y + area.half_height) as f64);
+        Rectangle::from_corners(
+            snap(min_x, self.origin.0, f64::floor),
+            snap(min_y, self.origin.1, f64::floor),
+            snap(max_x, self.origin.0, f64::ceil),
+            snap(max_y, self.origin.1, f64::ceil),
+        )
+    }
+
+    fn on_plane(&self, (x, y): (f32, f32)) -> (f64, f64) {
+        (x as f64 + self.origin.0 as f64, y as f64 + self.origin.1 as f64)
+    }
+
+    fn corner((column, row): (i64, i64)) -> (f64, f64) {
+        (column as f64 * Self::SIZE, row as f64 * Self::SIZE)
+    }
+
+    // Moves a boid parked in `chunk` back to world coordinates
+    fn in_world(&self, chunk: (i64, i64)) -> impl FnMut(Boid) -> Boid {
+        let (left, top) = Self::corner(chunk);
+        let (left, top) = (left - self.origin.0 as f64, top - self.origin.1 as f64);
+        move |mut boid| {
+            let (x, y) = boid.position();
+            boid.set_position((left + x as f64) as f32, (top + y as f64) as f32);
+            boid
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use std::path::PathBuf;
+
+use boids::lfo::Parameter;
+use boids::node::IndexBackend;
+use boids::preset::load_preset;
+use boids::replay::Replay;
+use boids::scenario::ScenarioFile;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::config::Config;
+
+/// What `--record` starts recording at launch.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum RecordFormat {
+    Gif,
+    Mp4,
+    Webm,
+}
+
+/// Spatial index picked on the command line.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Backend {
+    QuadTree,
+    Grid,
+}
+
+impl From<Backend> for IndexBackend {
+    fn from(backend: Backend) -> Self {
+        match backend {
+            Backend::QuadTree => IndexBackend::QuadTree,
+            Backend::Grid => IndexBackend::Grid,
+        }
+    }
+}
+
+/// Ways to run other than the window.
+#[derive(Subcommand)]
+pub enum Mode {
+    /// Run updates without drawing from the default settings and print tick time percentiles
+    /// and allocations, to compare setups reproducibly
+    Bench(BenchArgs),
+    /// Run one setting over evenly spaced values with several seeds each, without drawing,
+    /// and write the order parameters and flock counts each run ends with to a CSV table
+    Sweep(SweepArgs),
+}
+
+#[derive(Args)]
+pub struct BenchArgs {
+    /// Boids spawned at the start
+    #[arg(long, default_value_t = 5000)]
+    pub boids: u16,
+    /// Predators spawned at the start
+    #[arg(long, default_value_t = 0)]
+    pub predators: u16,
+    /// Updates timed
+    #[arg(long, default_value_t = 1000)]
+    pub ticks: u32,
+    /// Spatial index the neighbors are found with
+    #[arg(long, value_enum, default_value_t = Backend::QuadTree)]
+    pub backend: Backend,
+    /// Seed of the random numbers, the same seed replays the same run
+    #[arg(long)]
+    pub seed: Option<u64>,
+}
+
+#[derive(Args)]
+pub struct SweepArgs {
+    /// Setting swept, by its OSC name like cohesion, alignment, separation or vision_radius
+    #[arg(long, value_parser = parse_parameter)]
+    pub parameter: Parameter,
+    /// First value
+    #[arg(long)]
+    pub from: f32,
+    /// Last value
+    #[arg(long)]
+    pub to: f32,
+    /// Values run, evenly spaced from `--from` to `--to`
+    #[arg(long, default_value_t = 10)]
+    pub steps: u32,
+    /// Seeds run for each value
+    #[arg(long, default_value_t = 5)]
+    pub seeds: u32,
+    /// First seed, the others count up from it
+    #[arg(long, default_value_t = 0)]
+    pub seed: u64,
+    /// Updates in each run
+    #[arg(long, default_value_t = 1000)]
+    pub ticks: u32,
+    /// Boids spawned at the start of each run
+    #[arg(long, default_value_t = 300)]
+    pub boids: u16,
+    /// Predators spawned at the start of each run
+    #[arg(long, default_value_t = 0)]
+    pub predators: u16,
+    /// Start every run from a preset saved from the Presets window instead of the default
+    /// settings
+    #[arg(long, value_name = "FILE")]
+    pub preset: Option<PathBuf>,
+    /// Directory the table is written to
+    #[arg(long, value_name = "DIRECTORY", default_value = ".")]
+    pub output: PathBuf,
+}
+
+fn parse_parameter(key: &str) -> Result<Parameter, String> {
+    Parameter::from_key(key).ok_or_else(|| {
+        let keys: Vec<_> = Parameter::ALL.iter().map(|parameter| parameter.key()).collect();
+        format!("expected one of {}", keys.join(", "))
+    })
+}
+
+/// Simulation of flocking behavior.
+///
+/// Options override the settings saved in `boids.ron` by the last run.
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub mode: Option<Mode>,
+    /// Window width in physical pixels
+    #[arg(long, requires = "height")]
+    pub width: Option<u32>,
+    /// Window height in physical pixels
+    #[arg(long, requires = "width")]
+    pub height: Option<u32>,
+    /// Boids spawned at launch
+    #[arg(long)]
+    pub boids: Option<u16>,
+    /// Predators spawned at launch
+    #[arg(long)]
+    pub predators: Option<u16>,
+    /// Seed of the random numbers, the same seed replays the same run
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Start from a preset saved from the Presets window
+    #[arg(long, value_name = "FILE")]
+    pub preset: Option<PathBuf>,
+    /// Start from a scenario file bundling the settings, the scenery and groups of boids and
+    /// predators, saved from the Presets window or written by hand
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["tui", "replay", "spectate"])]
+    pub scenario: Option<PathBuf>,
+    /// Run the simulation without a window and print how it went, `bench` always does
+    #[arg(long, global = true)]
+    pub headless: bool,
+    /// Draw the flock as characters in the terminal instead of a window, needs the `tui` feature
+    #[arg(long, conflicts_with_all = ["headless", "record", "replay"])]
+    pub tui: bool,
+    /// Take JSON commands over WebSocket on this address, like 127.0.0.1:9001, needs the `remote` feature
+    #[arg(long, value_name = "ADDRESS", conflicts_with_all = ["headless", "tui"])]
+    pub remote: Option<String>,
+    /// Take OSC messages like `/boids/cohesion 0.5` over UDP on this port
+    #[arg(long, value_name = "PORT", conflicts_with_all = ["headless", "tui"])]
+    pub osc: Option<u16>,
+    /// Send the flock to spectators over UDP on this address, like 0.0.0.0:9002
+    #[arg(long, value_name = "ADDRESS", conflicts_with_all = ["headless", "tui"])]
+    pub spectator_server: Option<String>,
+    /// Let spectators spawn, place obstacles and attractors and move settings, as a shared sandbox
+    #[arg(long, requires = "spectator_server")]
+    pub sandbox: bool,
+    /// Watch the flock of a `--spectator-server` on this address instead of simulating one
+    #[arg(long, value_name = "ADDRESS", conflicts_with_all = ["headless", "tui", "replay", "spectator_server"])]
+    pub spectate: Option<String>,
+    /// Draw every update without a window to numbered PNGs in this directory, at the size of
+    /// `--width` and `--height`
+    #[arg(long, value_name = "DIRECTORY", conflicts_with_all = ["record", "replay", "tui"])]
+    pub render_frames: Option<PathBuf>,
+    /// Updates run in headless mode or rendered with `--render-frames`
+    #[arg(long, default_value_t = 600)]
+    pub ticks: u32,
+    /// Write where every boid and predator is at every tick to Parquet files in this directory,
+    /// in the window, in headless mode or with `--render-frames`
+    #[arg(long, value_name = "DIRECTORY", conflicts_with_all = ["tui", "spectate"])]
+    pub trajectories: Option<PathBuf>,
+    /// Size in megabytes past which `--trajectories` starts the next file
+    #[arg(long, value_name = "MB", default_value_t = 256, requires = "trajectories")]
+    pub trajectory_file_size: u64,
+    /// Start recording at launch, saved when stopped from the Recording window or on exit
+    #[arg(long, value_enum, value_name = "FORMAT", conflicts_with = "headless")]
+    pub record: Option<RecordFormat>,
+    /// Play a replay recorded from the Recording window, to the end in headless mode
+    #[arg(long, value_name = "FILE")]
+    pub replay: Option<PathBuf>,
+    /// Show the statistics and the inspector in a second window, for another monitor
+    #[arg(long, conflicts_with_all = ["headless", "tui", "render_frames"])]
+    pub stats_window: bool,
+}
+
+impl Cli {
+    /// Apply the options over the saved `config`, fails if the preset cannot be loaded.
+    pub fn apply(&self, config: &mut Config) -> Result<(), String> {
+        if let Some(path) = &self.preset {
+            config.option = load_preset(path).map_err(|error| format!("Cannot load {}: {error}", path.display()))?;
+        }
+        if let (Some(width), Some(height)) = (self.width, self.height) {
+            config.window_size = Some((width, height));
+        }
+        if let Some(boids) = self.boids {
+            config.option.boid_count = boids;
+        }
+        if let Some(predators) = self.predators {
+            config.option.predator_count = predators;
+        }
+        if self.stats_window {
+            config.windows.stats_window = true;
+        }
+        Ok(())
+    }
+
+    /// The scenario given with `--scenario`, fails if it cannot be loaded.
+    pub fn load_scenario(&self) -> Result<Option<ScenarioFile>, String> {
+        let Some(path) = &self.scenario else {
+            return Ok(None);
+        };
+        ScenarioFile::load(path)
+            .map(Some)
+            .map_err(|error| format!("Cannot load {}: {error}", path.display()))
+    }
+
+    /// The replay given with `--replay`, fails if it cannot be loaded.
+    pub fn load_replay(&self) -> Result<Option<Replay>, String> {
+        let Some(path) = &self.replay else {
+            return Ok(None);
+        };
+        Replay::load(path)
+            .map(Some)
+            .map_err(|error| format!("Cannot load {}: {error}", path.display()))
+    }
+}
@@ -0,0 +1,184 @@
+use boids::preset::Scenario;
+
+use crate::gui::Tool;
+use crate::shortcut::Action;
+
+/// Windows the command palette can open.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Panel {
+    Flocks,
+    Debug,
+    Appearance,
+    Recording,
+    Statistics,
+    Presets,
+    SpatialIndex,
+    Animation,
+    Timeline,
+    Obstacles,
+    Midi,
+    Compare,
+    Help,
+}
+
+impl Panel {
+    pub const ALL: [Panel; 13] = [
+        Panel::Flocks,
+        Panel::Debug,
+        Panel::Appearance,
+        Panel::Recording,
+        Panel::Statistics,
+        Panel::Presets,
+        Panel::SpatialIndex,
+        Panel::Animation,
+        Panel::Timeline,
+        Panel::Obstacles,
+        Panel::Midi,
+        Panel::Compare,
+        Panel::Help,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Panel::Flocks => "Flocks",
+            Panel::Debug => "Debug",
+            Panel::Appearance => "Appearance",
+            Panel::Recording => "Recording",
+            Panel::Statistics => "Statistics",
+            Panel::Presets => "Presets",
+            Panel::SpatialIndex => "Spatial index",
+            Panel::Animation => "Animation",
+            Panel::Timeline => "Timeline",
+            Panel::Obstacles => "Obstacles",
+            Panel::Midi => "MIDI",
+            Panel::Compare => "A/B comparison",
+            Panel::Help => "Keyboard shortcuts",
+        }
+    }
+}
+
+/// Something the command palette can run.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Action(Action),
+    Tool(Tool),
+    Open(Panel),
+    Scenario(Scenario),
+    SurpriseMe,
+    ResetAll,
+    ResetCamera,
+    ExportSvg,
+    Step,
+    SavePreset,
+    LoadPreset,
+    SaveSnapshot,
+    LoadSnapshot,
+    OpenConfig,
+    ToggleGifRecording,
+    ToggleVideoRecording,
+    ToggleInputRecording,
+    PlayReplay,
+    ToggleStatsWindow,
+}
+
+impl Command {
+    /// Every command, in the order listed before anything is typed.
+    pub fn all() -> Vec<Command> {
+        let mut commands: Vec<Command> = Action::ALL
+            .into_iter()
+            .filter(|action| *action != Action::CommandPalette)
+            .map(Command::Action)
+            .collect();
+        commands.push(Command::Step);
+        commands.extend(Tool::ALL.map(Command::Tool));
+        commands.extend(Panel::ALL.map(Command::Open));
+        commands.extend(Scenario::ALL.map(Command::Scenario));
+        commands.extend([
+            Command::SurpriseMe,
+            Command::ResetAll,
+            Command::ResetCamera,
+            Command::ExportSvg,
+            Command::SavePreset,
+            Command::LoadPreset,
+            Command::SaveSnapshot,
+            Command::LoadSnapshot,
+            Command::OpenConfig,
+            Command::ToggleGifRecording,
+            Command::ToggleVideoRecording,
+            Command::ToggleInputRecording,
+            Command::PlayReplay,
+            Command::ToggleStatsWindow,
+        ]);
+        commands
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Command::Action(action) => action.description().to_string(),
+            Command::Tool(tool) => format!("Use the {} tool", tool.name().to_lowercase()),
+            Command::Open(panel) => format!("Open {}", panel.name()),
+            Command::Scenario(scenario) => format!("Scenario: {}", scenario.name()),
+            Command::SurpriseMe => "Surprise me".to_string(),
+            Command::ResetAll => "Reset all settings".to_string(),
+            Command::ResetCamera => "Reset camera".to_string(),
+            Command::ExportSvg => "Export the world as an SVG figure".to_string(),
+            Command::Step => "Step one tick".to_string(),
+            Command::SavePreset => "Save preset".to_string(),
+            Command::LoadPreset => "Load preset".to_string(),
+            Command::SaveSnapshot => "Save snapshot of the world".to_string(),
+            Command::LoadSnapshot => "Load snapshot of the world".to_string(),
+            Command::OpenConfig => "Open config (boids.toml)".to_string(),
+            Command::ToggleGifRecording => "Start or stop recording a GIF".to_string(),
+            Command::ToggleVideoRecording => "Start or stop recording a video".to_string(),
+            Command::ToggleInputRecording => "Start or stop recording inputs for a replay".to_string(),
+            Command::PlayReplay => "Play replay".to_string(),
+            Command::ToggleStatsWindow => "Open or close the stats window".to_string(),
+        }
+    }
+
+    /// Keys running the same thing, shown next to the label.
+    pub fn keys(&self) -> Option<&'static str> {
+        match self {
+            Command::Action(action) => Some(action.keys()),
+            _ => None,
+        }
+    }
+}
+
+/// How well `text` matches `query`, typed letters have to appear in order but not next to
+/// each other. Higher is better, None when some letter is missing.
+fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    // Where the search for the next letter starts
+    let mut position = 0;
+    let mut last_match: Option<usize> = None;
+    for wanted in query.to_lowercase().chars().filter(|letter| !letter.is_whitespace()) {
+        let found = position + text[position..].iter().position(|letter| *letter == wanted)?;
+        score += 1;
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            // Initials like "sm" for "Surprise me" rank high
+            score += 3;
+        }
+        match last_match {
+            Some(last) if last + 1 == found => score += 5,
+            // Skipping letters costs a little, not so much that initials lose to a run of letters
+            Some(_) => score -= (found - position).min(3) as i32,
+            None => (),
+        }
+        last_match = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
+
+/// Commands matching `query`, best first.
+pub fn search(query: &str) -> Vec<Command> {
+    let mut scored: Vec<(i32, Command)> = Command::all()
+        .into_iter()
+        .filter_map(|command| fuzzy_score(query, &command.label()).map(|score| (score, command)))
+        .collect();
+    // Stable, so equal matches keep the listing order
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    scored.into_iter().map(|(_, command)| command).collect()
+}
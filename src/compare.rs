@@ -0,0 +1,69 @@
+use crate::{World, WorldOption};
+
+// Divider between the two halves
+const DIVIDER: [u8; 4] = [0x80, 0x80, 0x80, 0xff];
+
+/// A second world started from the same state and random numbers as the main one, run with
+/// its own options next to it so a parameter change can be seen side by side.
+pub struct Comparison {
+    pub world: World,
+    left: Vec<u8>,
+    right: Vec<u8>,
+}
+
+impl Comparison {
+    /// Compare against `main`, both running its options at first.
+    pub fn new(main: &World) -> Self {
+        let mut world = World::with_size(main.width(), main.height());
+        world.option = main.option.clone();
+        let mut comparison = Self {
+            world,
+            left: vec![],
+            right: vec![],
+        };
+        comparison.sync(main);
+        comparison
+    }
+
+    /// Start over from where `main` is now, keeping the compared options.
+    pub fn sync(&mut self, main: &World) {
+        let option = self.world.option.clone();
+        self.world.restore(main.snapshot());
+        self.world.option = option;
+    }
+
+    /// Give the compared world the options of `main` again.
+    pub fn copy_options(&mut self, option: &WorldOption) {
+        self.world.option = option.clone();
+    }
+
+    /// Run as many updates as `main` did since the last call. A restart, rewind or jump of
+    /// `main` starts over from its new state.
+    pub fn follow(&mut self, main: &World) {
+        let behind = main.tick().checked_sub(self.world.tick());
+        match behind {
+            Some(ticks) if ticks <= WorldOption::MAX_SIMULATION_SPEED as u64 => {
+                for _ in 0..ticks {
+                    self.world.update();
+                }
+            }
+            _ => self.sync(main),
+        }
+    }
+
+    /// Draw `main` into the left half of `frame` and the compared world into the right half.
+    /// `frame` is as wide as both worlds together.
+    pub fn draw(&mut self, main: &mut World, frame: &mut [u8]) {
+        let (left_width, right_width) = (main.width() as usize * 4, self.world.width() as usize * 4);
+        self.left.resize(left_width * main.height() as usize, 0);
+        self.right.resize(right_width * self.world.height() as usize, 0);
+        main.draw(&mut self.left);
+        self.world.draw(&mut self.right);
+        let rows = frame.chunks_exact_mut(left_width + right_width);
+        for ((row, left), right) in rows.zip(self.left.chunks_exact(left_width)).zip(self.right.chunks_exact(right_width)) {
+            row[..left_width].copy_from_slice(left);
+            row[left_width..].copy_from_slice(right);
+            row[left_width..left_width + 4].copy_from_slice(&DIVIDER);
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[cfg(feature = "midi")]
+use boids::midi::MidiBinding;
+use boids::preset::{read_ron, write_ron};
+use boids::Error;
+use boids::WorldOption;
+use serde::{Deserialize, Serialize};
+use winit::dpi::PhysicalSize;
+use winit::window::{Fullscreen, Window};
+
+use crate::gui::OpenWindows;
+
+// Kept in the working directory next to screenshots and presets
+const CONFIG_PATH: &str = "boids.ron";
+
+/// Settings and GUI state kept between runs.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub option: WorldOption,
+    pub windows: OpenWindows,
+    // Inner size of the window in physical pixels
+    pub window_size: Option<(u32, u32)>,
+    #[cfg(feature = "midi")]
+    pub midi: Vec<MidiBinding>,
+}
+
+impl Config {
+    /// The config saved by the last run, or the defaults if there is none.
+    pub fn load() -> Self {
+        match read_ron(Path::new(CONFIG_PATH)) {
+            Ok(config) => config,
+            Err(Error::Io(error))
+                if matches!(error.kind(), io::ErrorKind::NotFound | io::ErrorKind::Unsupported) =>
+            {
+                Self::default()
+            }
+            Err(error) => {
+                eprintln!("Ignoring {CONFIG_PATH}: {error}");
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        if let Err(error) = write_ron(self, Path::new(CONFIG_PATH)) {
+            eprintln!("Cannot save {CONFIG_PATH}: {error}");
+        }
+    }
+}
+
+/// Window part of `boids.toml`.
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowSettings {
+    // Inner size in physical pixels, both or neither
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub fullscreen: bool,
+}
+
+/// Settings written by hand in `boids.toml`, applied over the ones saved by the last run.
+///
+/// `[simulation]` takes every `WorldOption` field, those left out get their default.
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    pub window: WindowSettings,
+    pub simulation: Option<WorldOption>,
+}
+
+impl ConfigFile {
+    pub const PATH: &'static str = "boids.toml";
+
+    /// Read `boids.toml` from the working directory, None if there is none.
+    pub fn load() -> Result<Option<Self>, String> {
+        let text = match fs::read_to_string(Self::PATH) {
+            Ok(text) => text,
+            // The browser build has no file system
+            Err(error) if matches!(error.kind(), io::ErrorKind::NotFound | io::ErrorKind::Unsupported) => return Ok(None),
+            Err(error) => return Err(format!("Cannot read {}: {error}", Self::PATH)),
+        };
+        let file: Self = toml::from_str(&text).map_err(|error| format!("Invalid {}: {error}", Self::PATH))?;
+        Self::check_setting_names(&text)?;
+        if file.window.width.is_some() != file.window.height.is_some() {
+            return Err(format!("Invalid {}: [window] needs both width and height", Self::PATH));
+        }
+        if let Some(option) = &file.simulation {
+            check_values(option).map_err(|problems| format!("Invalid {}:\n{problems}", Self::PATH))?;
+        }
+        Ok(Some(file))
+    }
+
+    // Unknown settings are ignored by the parser, most likely they are typos
+    fn check_setting_names(text: &str) -> Result<(), String> {
+        let table: toml::Table = toml::from_str(text).map_err(|error| error.to_string())?;
+        let Some(simulation) = table.get("simulation").and_then(|value| value.as_table()) else {
+            return Ok(());
+        };
+        let known = toml::Table::try_from(WorldOption::new()).map_err(|error| error.to_string())?;
+        match simulation.keys().find(|name| !known.contains_key(*name)) {
+            Some(name) => Err(format!("Invalid {}: unknown setting `{name}` in [simulation]", Self::PATH)),
+            None => Ok(()),
+        }
+    }
+
+    /// Apply over the config saved by the last run, before the window is created.
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(option) = &self.simulation {
+            config.option = option.clone();
+        }
+        if let (Some(width), Some(height)) = (self.window.width, self.window.height) {
+            config.window_size = Some((width, height));
+        }
+    }
+
+    pub fn apply_to_window(&self, window: &Window) {
+        if let (Some(width), Some(height)) = (self.window.width, self.window.height) {
+            window.set_inner_size(PhysicalSize::new(width, height));
+        }
+        let fullscreen = self.window.fullscreen.then_some(Fullscreen::Borderless(None));
+        window.set_fullscreen(fullscreen);
+    }
+}
+
+// Values that parse but make no sense, all of them listed one per line
+pub fn check_values(option: &WorldOption) -> Result<(), String> {
+    let mut problems = vec![];
+    let factors = [
+        ("avoid_factor", option.avoid_factor),
+        ("matching_factor", option.matching_factor),
+        ("centering_factor", option.centering_factor),
+        ("fear_factor", option.fear_factor),
+        ("trail_decay", option.trail_decay),
+    ];
+    for (name, value) in factors {
+        if !(0.0..=1.0).contains(&value) {
+            problems.push(format!("{name} = {value} must be between 0 and 1"));
+        }
+    }
+    let radii = [
+        ("safe_radius", option.safe_radius),
+        ("boid_vision_radius", option.boid_vision_radius),
+        ("fear_radius", option.fear_radius),
+        ("predator_vision_radius", option.predator_vision_radius),
+        ("catch_radius", option.catch_radius),
+        ("size_variation", option.size_variation),
+    ];
+    for (name, value) in radii {
+        if value < 0.0 {
+            problems.push(format!("{name} = {value} must not be negative"));
+        }
+    }
+    if option.grid_cell_size < 1.0 {
+        problems.push(format!("grid_cell_size = {} must be at least 1", option.grid_cell_size));
+    }
+    for (name, value) in [("boid_size", option.boid_size), ("predator_size", option.predator_size)] {
+        if value < 1 {
+            problems.push(format!("{name} = {value} must be at least 1"));
+        }
+    }
+    let speeds = [
+        ("boid", option.boid_min_speed, option.boid_max_speed),
+        ("predator", option.predator_min_speed, option.predator_max_speed),
+    ];
+    for (species, min, max) in speeds {
+        if min < 0 || min > max {
+            problems.push(format!("{species}_min_speed = {min} must be between 0 and {species}_max_speed = {max}"));
+        }
+    }
+    if !(1..=WorldOption::MAX_SIMULATION_SPEED).contains(&option.simulation_speed) {
+        problems.push(format!(
+            "simulation_speed = {} must be between 1 and {}",
+            option.simulation_speed,
+            WorldOption::MAX_SIMULATION_SPEED
+        ));
+    }
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems.join("\n"))
+    }
+}
@@ -0,0 +1,49 @@
+use std::{fmt, io};
+
+use image::ImageError;
+
+/// What can go wrong in the library, none of it fatal to the simulation.
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// A preset, snapshot or replay that is not valid RON for what it holds
+    Format(String),
+    Image(ImageError),
+    /// A boid outside the area of the spatial index, left out of it
+    OutOfBounds { x: f32, y: f32 },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(error) => write!(f, "{error}"),
+            Error::Format(error) => write!(f, "Invalid file: {error}"),
+            Error::Image(error) => write!(f, "{error}"),
+            Error::OutOfBounds { x, y } => write!(f, "Boid at {x:.1}, {y:.1} is outside the world"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(error) => Some(error),
+            Error::Image(error) => Some(error),
+            Error::Format(_) | Error::OutOfBounds { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<ImageError> for Error {
+    fn from(error: ImageError) -> Self {
+        Error::Image(error)
+    }
+}
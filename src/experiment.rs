@@ -0,0 +1,72 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use boids::preset::load_preset;
+use boids::sweep::{export_csv, Sweep, SweepResult};
+use boids::{WorldOption, HEIGHT, WIDTH};
+use instant::Instant;
+
+use crate::cli::SweepArgs;
+
+/// Run the sweep of `boids sweep`, printing each run as it ends and the mean of every value,
+/// and write the table of every run.
+pub fn run(args: &SweepArgs) {
+    let mut option = match &args.preset {
+        Some(path) => load_preset(path).unwrap_or_else(|error| {
+            eprintln!("Cannot load {}: {error}", path.display());
+            std::process::exit(1);
+        }),
+        None => WorldOption::new(),
+    };
+    option.boid_count = args.boids;
+    option.predator_count = args.predators;
+    let sweep = Sweep {
+        parameter: args.parameter,
+        from: args.from,
+        to: args.to,
+        steps: args.steps,
+        seeds: args.seeds,
+        first_seed: args.seed,
+        ticks: args.ticks,
+        option,
+        size: (WIDTH, HEIGHT),
+    };
+    let name = args.parameter.key();
+    let total = sweep.values().len() * args.seeds as usize;
+    println!(
+        "Sweeping {name} from {} to {} in {} steps, {} seeds each, {} ticks per run",
+        args.from, args.to, args.steps, args.seeds, args.ticks
+    );
+
+    let finished = AtomicUsize::new(0);
+    let start_time = Instant::now();
+    let results = sweep.run(|result| {
+        let finished = finished.fetch_add(1, Ordering::Relaxed) + 1;
+        println!(
+            "[{finished}/{total}] {name} {} seed {}: polarization {:.3}, milling {:.3}, {} flocks",
+            result.value, result.seed, result.order.polarization, result.order.milling, result.order.flocks
+        );
+    });
+    println!("{total} runs in {:.2?}", start_time.elapsed());
+
+    println!("{name:>12} {:>12} {:>12} {:>12}", "polarization", "milling", "flocks");
+    for value in sweep.values() {
+        let runs: Vec<&SweepResult> = results.iter().filter(|result| result.value == value).collect();
+        let mean = |measure: fn(&SweepResult) -> f32| {
+            runs.iter().map(|result| measure(result)).sum::<f32>() / runs.len().max(1) as f32
+        };
+        println!(
+            "{value:>12.4} {:>12.3} {:>12.3} {:>12.1}",
+            mean(|result| result.order.polarization),
+            mean(|result| result.order.milling),
+            mean(|result| result.order.flocks as f32)
+        );
+    }
+
+    match export_csv(args.parameter, &results, &args.output) {
+        Ok(path) => println!("Saved results to {}", path.display()),
+        Err(error) => {
+            eprintln!("Cannot save results to {}: {error}", args.output.display());
+            std::process::exit(1);
+        }
+    }
+}
@@ -1,8 +1,18 @@
-use std::{f32::consts::PI, fmt::Display, mem::swap};
+use std::{
+    f32::consts::SQRT_2,
+    fmt::Display,
+    mem::swap,
+};
 
-use crate::node::{RenderNode, Vertice};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone)]
+use crate::{
+    node::{RenderNode, Vertice},
+    WorldOption,
+};
+
+/// Axis aligned rectangle given by its center and half extents.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct Rectangle {
     pub center_x: f32,
     pub center_y: f32,
@@ -20,6 +30,16 @@ impl Rectangle {
         }
     }
 
+    /// Rectangle spanned by two opposite corners in any order.
+    pub fn from_corners(x1: f32, y1: f32, x2: f32, y2: f32) -> Self {
+        Self::new(
+            (x1 + x2) / 2.0,
+            (y1 + y2) / 2.0,
+            (x1 - x2).abs() / 2.0,
+            (y1 - y2).abs() / 2.0,
+        )
+    }
+
     pub fn contains_point(&self, x: f32, y: f32) -> bool {
         let min_x = self.center_x - self.half_width;
         let max_x = self.center_x + self.half_width;
@@ -28,16 +48,48 @@ impl Rectangle {
         x >= min_x && x <= max_x && y >= min_y && y <= max_y
     }
 
+    pub fn overlaps(&self, other: &Rectangle) -> bool {
+        (self.center_x - other.center_x).abs() <= self.half_width + other.half_width
+            && (self.center_y - other.center_y).abs() <= self.half_height + other.half_height
+    }
+
+    /// Whether the circle of `vision_radius` around `vertice` reaches into the rectangle.
     pub fn intersects(&self, vertice: &Vertice, vision_radius: f32) -> bool {
-        let dx = self.center_x - vertice.x as f32;
-        let dy = self.center_y - vertice.y as f32;
-        let d = (dx * dx + dy * dy).sqrt();
-        d <= vision_radius
+        // From the closest point of the rectangle, zero inside it
+        let dx = ((vertice.x as f32 - self.center_x).abs() - self.half_width).max(0.0);
+        let dy = ((vertice.y as f32 - self.center_y).abs() - self.half_height).max(0.0);
+        dx * dx + dy * dy <= vision_radius * vision_radius
     }
 }
 
-impl RenderNode for Rectangle {
-    fn draw(&self, frame: &mut [u8], width: u16, height: u16) {
+impl Rectangle {
+    /// Outline in `color` with a `stroke_width` pixels wide line.
+    pub fn draw_stroke(
+        &self,
+        frame: &mut [u8],
+        width: u16,
+        height: u16,
+        stroke_width: f32,
+        color: &Color,
+        world_option: &WorldOption,
+    ) {
+        let color = color.to_color_array();
+        if world_option.high_quality_rendering {
+            self.draw_outline(frame, width, height, stroke_width, color, draw_colored_line_anti_aliased);
+        } else {
+            self.draw_outline(frame, width, height, stroke_width, color, draw_colored_line);
+        }
+    }
+
+    fn draw_outline(
+        &self,
+        frame: &mut [u8],
+        width: u16,
+        height: u16,
+        stroke_width: f32,
+        color: [u8; 4],
+        line: ColoredLine,
+    ) {
         let mut a = Vertice::new();
         let mut b = Vertice::new();
         let mut c = Vertice::new();
@@ -55,10 +107,35 @@ impl RenderNode for Rectangle {
         d.x = self.center_x as i16 + self.half_width as i16;
         d.y = self.center_y as i16 - self.half_height as i16;
 
-        draw_line(&a, &b, frame, width, height);
-        draw_line(&b, &c, frame, width, height);
-        draw_line(&c, &d, frame, width, height);
-        draw_line(&d, &a, frame, width, height);
+        line(&a, &b, frame, width, height, color, stroke_width);
+        line(&b, &c, frame, width, height, color, stroke_width);
+        line(&c, &d, frame, width, height, color, stroke_width);
+        line(&d, &a, frame, width, height, color, stroke_width);
+    }
+}
+
+impl Rectangle {
+    /// Blend `color` over the whole area by `coverage` between 0 and 1.
+    pub fn fill(&self, frame: &mut [u8], width: u16, height: u16, color: [u8; 4], coverage: f32) {
+        let min_x = (self.center_x - self.half_width).round() as i32;
+        let max_x = (self.center_x + self.half_width).round() as i32;
+        let min_y = (self.center_y - self.half_height).round() as i32;
+        let max_y = (self.center_y + self.half_height).round() as i32;
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                blend_pixel(frame, x, y, width, height, color, coverage);
+            }
+        }
+    }
+}
+
+impl RenderNode for Rectangle {
+    fn draw_with_option(&self, frame: &mut [u8], width: u16, height: u16, world_option: &WorldOption) {
+        self.draw_stroke(frame, width, height, 1.0, &Color::White, world_option);
+    }
+
+    fn draw(&self, frame: &mut [u8], width: u16, height: u16) {
+        self.draw_outline(frame, width, height, 1.0, Color::White.to_color_array(), draw_colored_line);
     }
 }
 
@@ -72,39 +149,139 @@ impl Display for Rectangle {
     }
 }
 
-pub fn draw_line(start: &Vertice, end: &Vertice, frame: &mut [u8], width: u16, height: u16) {
-    let color = Color::White.to_color_array();
-    let mut start_x = start.x;
-    let mut start_y = start.y;
-    let mut end_x = end.x;
-    let mut end_y = end.y;
-    sort_two_value(&mut start_x, &mut end_x);
-    sort_two_value(&mut start_y, &mut end_y);
-    match start.slope(end) {
-        Some(slope) => {
-            if slope == 0.0 {
-                for x in start_x..=end_x {
-                    change_pixel(frame, x as usize, start.y as usize, width, height, color);
-                }
+pub fn draw_line(start: &Vertice, end: &Vertice, frame: &mut [u8], width: u16, height: u16, stroke_width: f32) {
+    draw_colored_line(start, end, frame, width, height, Color::White.to_color_array(), stroke_width);
+}
+
+pub fn draw_colored_line(
+    start: &Vertice,
+    end: &Vertice,
+    frame: &mut [u8],
+    width: u16,
+    height: u16,
+    color: [u8; 4],
+    stroke_width: f32,
+) {
+    // Integer Bresenham, handles every octant
+    let (mut x, mut y) = (start.x as i32, start.y as i32);
+    let (end_x, end_y) = (end.x as i32, end.y as i32);
+    let dx = (end_x - x).abs();
+    let dy = -(end_y - y).abs();
+    let step_x = if x < end_x { 1 } else { -1 };
+    let step_y = if y < end_y { 1 } else { -1 };
+    let mut error = dx + dy;
+    // Thick lines repeat each pixel across the minor axis
+    let steep = -dy > dx;
+    let span = (stroke_width * span_factor(dx as f32, -dy as f32)).round().max(1.0) as i32;
+    let first_offset = -(span - 1) / 2;
+    loop {
+        for offset in first_offset..first_offset + span {
+            if steep {
+                plot_pixel(frame, x + offset, y, width, height, color);
             } else {
-                let c = start.y as f32 - slope * start.x as f32; 
-                for x in start_x..=end_x {
-                    let y = (slope * x as f32 + c) as usize;
-                    change_pixel(frame, x as usize, y, width, height, color);
-                }
+                plot_pixel(frame, x, y + offset, width, height, color);
             }
         }
-        None => {
-            for y in start_y..=end_y {
-                change_pixel(frame, start.x as usize, y as usize, width, height, color);
-            }
+        if x == end_x && y == end_y {
+            break;
+        }
+        let double_error = 2 * error;
+        if double_error >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if double_error <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+}
+
+/// Xiaolin Wu anti-aliased line
+pub fn draw_line_anti_aliased(
+    start: &Vertice,
+    end: &Vertice,
+    frame: &mut [u8],
+    width: u16,
+    height: u16,
+    stroke_width: f32,
+) {
+    draw_colored_line_anti_aliased(start, end, frame, width, height, Color::White.to_color_array(), stroke_width);
+}
+
+pub fn draw_colored_line_anti_aliased(
+    start: &Vertice,
+    end: &Vertice,
+    frame: &mut [u8],
+    width: u16,
+    height: u16,
+    color: [u8; 4],
+    stroke_width: f32,
+) {
+    let (mut start_x, mut start_y) = (start.x as f32, start.y as f32);
+    let (mut end_x, mut end_y) = (end.x as f32, end.y as f32);
+    let steep = (end_y - start_y).abs() > (end_x - start_x).abs();
+    if steep {
+        swap(&mut start_x, &mut start_y);
+        swap(&mut end_x, &mut end_y);
+    }
+    if start_x > end_x {
+        swap(&mut start_x, &mut end_x);
+        swap(&mut start_y, &mut end_y);
+    }
+    let dx = end_x - start_x;
+    let gradient = if dx == 0.0 { 1.0 } else { (end_y - start_y) / dx };
+    let half_span = stroke_width.max(1.0) * span_factor(dx, (end_y - start_y).abs()) / 2.0;
+
+    let mut plot = |x: i32, y: i32, coverage: f32| {
+        if steep {
+            blend_pixel(frame, y, x, width, height, color, coverage);
+        } else {
+            blend_pixel(frame, x, y, width, height, color, coverage);
         }
     };
+    // Endpoints are integer so only the span in between needs coverage
+    let mut intersect_y = start_y;
+    for x in start_x as i32..=end_x as i32 {
+        // Each column covers [top, bottom) centred on the ideal line
+        let top = intersect_y + 0.5 - half_span;
+        let bottom = intersect_y + 0.5 + half_span;
+        for y in top.floor() as i32..bottom.ceil() as i32 {
+            let coverage = bottom.min(y as f32 + 1.0) - top.max(y as f32);
+            plot(x, y, coverage);
+        }
+        intersect_y += gradient;
+    }
 }
 
-pub fn sort_two_value(val_a: &mut i16, val_b: &mut i16) {
-    if val_a > val_b {
-        swap(val_a, val_b);
+// Length of a stroke measured along the minor axis for the same perpendicular width
+fn span_factor(dx: f32, dy: f32) -> f32 {
+    let major = dx.max(dy);
+    if major == 0.0 {
+        return 1.0;
+    }
+    (dx * dx + dy * dy).sqrt() / major
+}
+
+/// Mix `color` into the pixel at (x, y) by `coverage` between 0 and 1.
+pub fn blend_pixel(
+    frame: &mut [u8],
+    x: i32,
+    y: i32,
+    width: u16,
+    height: u16,
+    color: [u8; 4],
+    coverage: f32,
+) {
+    if x < 0 || y < 0 || x >= width as i32 || y >= height as i32 || coverage <= 0.0 {
+        return;
+    }
+    let start = (y as usize * width as usize + x as usize) * 4;
+    let Some(pixel) = frame.get_mut(start..start + 4) else {
+        return;
+    };
+    for (channel, value) in pixel.iter_mut().zip(color) {
+        *channel = (*channel as f32 + (value as f32 - *channel as f32) * coverage.min(1.0)) as u8;
     }
 }
 
@@ -136,37 +313,513 @@ pub struct Circle {
     x: f32,
     y: f32,
     radius: f32,
+    stroke_width: f32,
     color: Color,
 }
 
 impl Circle {
+    pub fn new(x: f32, y: f32, radius: f32, stroke_width: f32, color: Color) -> Self {
+        Self {
+            x,
+            y,
+            radius,
+            stroke_width,
+            color,
+        }
+    }
+}
+
+impl RenderNode for Circle {
+    fn draw_with_option(&self, frame: &mut [u8], width: u16, height: u16, world_option: &WorldOption) {
+        let color = self.color.to_color_array();
+        let (center_x, center_y) = (self.x.round() as i32, self.y.round() as i32);
+        if world_option.high_quality_rendering {
+            anti_aliased_outline(self.radius, self.stroke_width, |dx, dy, coverage| {
+                blend_pixel(frame, center_x + dx, center_y + dy, width, height, color, coverage);
+            });
+        } else {
+            self.draw(frame, width, height);
+        }
+    }
+
+    fn draw(&self, frame: &mut [u8], width: u16, height: u16) {
+        let color = self.color.to_color_array();
+        let (center_x, center_y) = (self.x.round() as i32, self.y.round() as i32);
+        outline(self.radius, self.stroke_width, |dx, dy| {
+            plot_pixel(frame, center_x + dx, center_y + dy, width, height, color);
+        });
+    }
+}
+
+/// Circular arc of `sweep` degrees centred on `direction`, right is 0 degree and
+/// angles grow clockwise on screen.
+pub struct Arc {
+    x: f32,
+    y: f32,
+    radius: f32,
+    direction: f32,
+    sweep: f32,
+    stroke_width: f32,
+    color: Color,
+}
+
+impl Arc {
+    pub fn new(x: f32, y: f32, radius: f32, direction: f32, sweep: f32, stroke_width: f32, color: Color) -> Self {
+        Self {
+            x,
+            y,
+            radius,
+            direction,
+            sweep,
+            stroke_width,
+            color,
+        }
+    }
+
+    fn contains_offset(&self, dx: i32, dy: i32) -> bool {
+        if self.sweep >= 360.0 {
+            return true;
+        }
+        let angle = (dy as f32).atan2(dx as f32).to_degrees();
+        // Signed difference to the direction in -180..180
+        let difference = (angle - self.direction + 180.0).rem_euclid(360.0) - 180.0;
+        difference.abs() <= self.sweep / 2.0
+    }
+}
+
+impl RenderNode for Arc {
+    fn draw_with_option(&self, frame: &mut [u8], width: u16, height: u16, world_option: &WorldOption) {
+        if world_option.high_quality_rendering {
+            let color = self.color.to_color_array();
+            let (center_x, center_y) = (self.x.round() as i32, self.y.round() as i32);
+            anti_aliased_outline(self.radius, self.stroke_width, |dx, dy, coverage| {
+                if self.contains_offset(dx, dy) {
+                    blend_pixel(frame, center_x + dx, center_y + dy, width, height, color, coverage);
+                }
+            });
+        } else {
+            self.draw(frame, width, height);
+        }
+    }
+
+    fn draw(&self, frame: &mut [u8], width: u16, height: u16) {
+        let color = self.color.to_color_array();
+        let (center_x, center_y) = (self.x.round() as i32, self.y.round() as i32);
+        outline(self.radius, self.stroke_width, |dx, dy| {
+            if self.contains_offset(dx, dy) {
+                plot_pixel(frame, center_x + dx, center_y + dy, width, height, color);
+            }
+        });
+    }
+}
+
+/// Circle outline `stroke_width` pixels wide, `plot` receives offsets from the centre
+fn outline(radius: f32, stroke_width: f32, mut plot: impl FnMut(i32, i32)) {
+    if stroke_width <= 1.0 {
+        midpoint_circle(radius, plot);
+    } else {
+        ring(radius, stroke_width, |dx, dy, coverage| {
+            if coverage >= 0.5 {
+                plot(dx, dy);
+            }
+        });
+    }
+}
+
+/// Anti-aliased `outline`, `plot` also receives the pixel coverage
+fn anti_aliased_outline(radius: f32, stroke_width: f32, plot: impl FnMut(i32, i32, f32)) {
+    if stroke_width <= 1.0 {
+        wu_circle(radius, plot);
+    } else {
+        ring(radius, stroke_width, plot);
+    }
+}
+
+/// Coverage of every pixel within half a stroke of the circle
+fn ring(radius: f32, stroke_width: f32, mut plot: impl FnMut(i32, i32, f32)) {
+    let half_stroke = stroke_width / 2.0;
+    let outer = (radius + half_stroke).ceil() as i32 + 1;
+    for dy in -outer..=outer {
+        for dx in -outer..=outer {
+            let distance = ((dx * dx + dy * dy) as f32).sqrt();
+            let coverage = (half_stroke + 0.5 - (distance - radius).abs()).clamp(0.0, 1.0);
+            if coverage > 0.0 {
+                plot(dx, dy, coverage);
+            }
+        }
+    }
+}
+
+/// Midpoint circle outline, `plot` receives offsets from the centre
+fn midpoint_circle(radius: f32, mut plot: impl FnMut(i32, i32)) {
+    if radius == 0.0 {
+        return;
+    }
+    let mut x = radius.round() as i32;
+    let mut y = 0;
+    let mut decision = 1 - x;
+    while x >= y {
+        for (dx, dy) in [
+            (x, y),
+            (-x, y),
+            (x, -y),
+            (-x, -y),
+            (y, x),
+            (-y, x),
+            (y, -x),
+            (-y, -x),
+        ] {
+            plot(dx, dy);
+        }
+        y += 1;
+        if decision < 0 {
+            decision += 2 * y + 1;
+        } else {
+            x -= 1;
+            decision += 2 * (y - x) + 1;
+        }
+    }
+}
+
+/// Xiaolin Wu anti-aliased circle outline, `plot` receives offsets and coverage
+fn wu_circle(radius: f32, mut plot: impl FnMut(i32, i32, f32)) {
+    if radius == 0.0 {
+        return;
+    }
+    // Walk one octant and mirror it to the other seven
+    let octant_end = (radius / SQRT_2).ceil() as i32;
+    for offset in 0..=octant_end {
+        let edge = (radius * radius - (offset * offset) as f32).max(0.0).sqrt();
+        let inner = edge.floor();
+        let fraction = edge - inner;
+        for (distance, coverage) in [(inner as i32, 1.0 - fraction), (inner as i32 + 1, fraction)] {
+            for (dx, dy) in [
+                (offset, distance),
+                (-offset, distance),
+                (offset, -distance),
+                (-offset, -distance),
+                (distance, offset),
+                (-distance, offset),
+                (distance, -offset),
+                (-distance, -offset),
+            ] {
+                plot(dx, dy, coverage);
+            }
+        }
+    }
+}
+
+/// Filled circle
+pub struct Disc {
+    x: f32,
+    y: f32,
+    radius: f32,
+    color: Color,
+}
+
+impl Disc {
     pub fn new(x: f32, y: f32, radius: f32, color: Color) -> Self {
         Self { x, y, radius, color }
     }
 }
 
-impl RenderNode for Circle {
-    fn draw(&self, _frame: &mut [u8], _width: u16, _height: u16) {
-        if self.radius == 0.0 {
+impl RenderNode for Disc {
+    fn draw(&self, frame: &mut [u8], width: u16, height: u16) {
+        if self.radius <= 0.0 {
             return;
         }
         let color = self.color.to_color_array();
-        for angle in 0..=365 {
-            let angle = angle as f32;
-            let x = self.radius * (angle * PI / 180.0).cos() + self.x;
-            let y = self.radius * (angle * PI / 180.0).sin() + self.y;
-            change_pixel(_frame, x as usize, y as usize, _width, _height, color);
+        let (center_x, center_y) = (self.x.round() as i32, self.y.round() as i32);
+        let radius = self.radius.round() as i32;
+        for dy in -radius..=radius {
+            let half_span = ((radius * radius - dy * dy) as f32).sqrt() as i32;
+            for dx in -half_span..=half_span {
+                plot_pixel(frame, center_x + dx, center_y + dy, width, height, color);
+            }
         }
     }
 }
 
-#[derive(Clone)]
+// Signature shared by `draw_colored_line` and `draw_colored_line_anti_aliased`
+type ColoredLine = fn(&Vertice, &Vertice, &mut [u8], u16, u16, [u8; 4], f32);
+
+/// Connected line segments fading from black at the first point to `color` at the last.
+pub struct Polyline {
+    points: Vec<Vertice>,
+    stroke_width: f32,
+    color: Color,
+}
+
+impl Polyline {
+    pub fn new(points: Vec<Vertice>, stroke_width: f32, color: Color) -> Self {
+        Self {
+            points,
+            stroke_width,
+            color,
+        }
+    }
+
+    fn draw_segments(
+        &self,
+        frame: &mut [u8],
+        width: u16,
+        height: u16,
+        line: ColoredLine,
+    ) {
+        let segments = self.points.len().saturating_sub(1).max(1) as f32;
+        for (index, pair) in self.points.windows(2).enumerate() {
+            let color = Color::gradient(&Color::Rgba(0, 0, 0, 255), &self.color, (index + 1) as f32 / segments);
+            line(&pair[0], &pair[1], frame, width, height, color.to_color_array(), self.stroke_width);
+        }
+    }
+}
+
+impl RenderNode for Polyline {
+    fn draw_with_option(&self, frame: &mut [u8], width: u16, height: u16, world_option: &WorldOption) {
+        if world_option.high_quality_rendering {
+            self.draw_segments(frame, width, height, draw_colored_line_anti_aliased);
+        } else {
+            self.draw(frame, width, height);
+        }
+    }
+
+    fn draw(&self, frame: &mut [u8], width: u16, height: u16) {
+        self.draw_segments(frame, width, height, draw_colored_line);
+    }
+}
+
+/// Smallest convex polygon holding every point, going around without repeating the first
+/// point. Fewer than three points that are not on one line come back as the ends only.
+pub fn convex_hull(points: &[(f32, f32)]) -> Vec<(f32, f32)> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted;
+    }
+    // Above zero when `o`, `a`, `b` turn one way, below zero the other, zero on a line
+    let cross = |o: (f32, f32), a: (f32, f32), b: (f32, f32)| (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0);
+    let mut hull: Vec<(f32, f32)> = Vec::with_capacity(sorted.len() * 2);
+    // Lower half left to right, then upper half back, each dropping the points turning inwards
+    for pass in [sorted.clone(), sorted.into_iter().rev().collect()] {
+        let start = hull.len();
+        for point in pass {
+            while hull.len() >= start + 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], point) <= 0.0 {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        // The last point starts the other half
+        hull.pop();
+    }
+    hull
+}
+
+/// Polygon filled with `color` blended by `opacity`, outlined in full `color`.
+pub struct Polygon {
+    points: Vec<(f32, f32)>,
+    color: Color,
+    opacity: f32,
+    stroke_width: f32,
+}
+
+impl Polygon {
+    pub fn new(points: Vec<(f32, f32)>, color: Color, opacity: f32, stroke_width: f32) -> Self {
+        Self {
+            points,
+            color,
+            opacity,
+            stroke_width,
+        }
+    }
+
+    // Rows through pixel centers, filled between each pair of edge crossings
+    fn fill(&self, frame: &mut [u8], width: u16, height: u16) {
+        let color = self.color.to_color_array();
+        let top = self.points.iter().map(|point| point.1).fold(f32::INFINITY, f32::min).floor().max(0.0) as i32;
+        let bottom = self.points.iter().map(|point| point.1).fold(f32::NEG_INFINITY, f32::max).ceil() as i32;
+        let bottom = bottom.min(height as i32 - 1);
+        let mut crossings = vec![];
+        for y in top..=bottom {
+            let row = y as f32 + 0.5;
+            crossings.clear();
+            for (index, &(x1, y1)) in self.points.iter().enumerate() {
+                let (x2, y2) = self.points[(index + 1) % self.points.len()];
+                if (y1 <= row) != (y2 <= row) {
+                    crossings.push(x1 + (row - y1) / (y2 - y1) * (x2 - x1));
+                }
+            }
+            crossings.sort_by(f32::total_cmp);
+            for pair in crossings.chunks_exact(2) {
+                let (start, end) = (pair[0].round() as i32, pair[1].round() as i32);
+                for x in start.max(0)..end.min(width as i32) {
+                    blend_pixel(frame, x, y, width, height, color, self.opacity);
+                }
+            }
+        }
+    }
+
+    fn draw_shape(&self, frame: &mut [u8], width: u16, height: u16, line: ColoredLine) {
+        if self.points.len() < 2 {
+            return;
+        }
+        self.fill(frame, width, height);
+        self.draw_outline(frame, width, height, line);
+    }
+
+    fn draw_outline(&self, frame: &mut [u8], width: u16, height: u16, line: ColoredLine) {
+        let color = self.color.to_color_array();
+        let vertice = |(x, y): (f32, f32)| Vertice {
+            x: x.round() as i16,
+            y: y.round() as i16,
+        };
+        for (index, &point) in self.points.iter().enumerate() {
+            let next = self.points[(index + 1) % self.points.len()];
+            line(&vertice(point), &vertice(next), frame, width, height, color, self.stroke_width);
+        }
+    }
+}
+
+impl RenderNode for Polygon {
+    fn draw_with_option(&self, frame: &mut [u8], width: u16, height: u16, world_option: &WorldOption) {
+        if world_option.high_quality_rendering {
+            self.draw_shape(frame, width, height, draw_colored_line_anti_aliased);
+        } else {
+            self.draw(frame, width, height);
+        }
+    }
+
+    fn draw(&self, frame: &mut [u8], width: u16, height: u16) {
+        self.draw_shape(frame, width, height, draw_colored_line);
+    }
+}
+
+/// Single line of text in a built-in 3 by 5 pixel font, scaled by `scale`.
+///
+/// Letters are drawn in upper case and unknown characters as `?`.
+pub struct Text {
+    x: i32,
+    y: i32,
+    scale: i32,
+    content: String,
+    color: Color,
+}
+
+impl Text {
+    const GLYPH_WIDTH: i32 = 3;
+    const GLYPH_HEIGHT: i32 = 5;
+
+    pub fn new(x: i32, y: i32, scale: i32, content: String, color: Color) -> Self {
+        Self {
+            x,
+            y,
+            scale: scale.max(1),
+            content,
+            color,
+        }
+    }
+
+    /// Size in pixels of `content` drawn at `scale`, with one column between glyphs.
+    pub fn size(content: &str, scale: i32) -> (i32, i32) {
+        let glyphs = content.chars().count() as i32;
+        let width = (glyphs * (Self::GLYPH_WIDTH + 1) - 1).max(0) * scale;
+        (width, Self::GLYPH_HEIGHT * scale)
+    }
+
+    // Rows from the top, the left column is the highest of the three bits
+    fn glyph(character: char) -> [u8; 5] {
+        match character.to_ascii_uppercase() {
+            '0' => [7, 5, 5, 5, 7],
+            '1' => [2, 6, 2, 2, 7],
+            '2' => [7, 1, 7, 4, 7],
+            '3' => [7, 1, 7, 1, 7],
+            '4' => [5, 5, 7, 1, 1],
+            '5' => [7, 4, 7, 1, 7],
+            '6' => [7, 4, 7, 5, 7],
+            '7' => [7, 1, 1, 1, 1],
+            '8' => [7, 5, 7, 5, 7],
+            '9' => [7, 5, 7, 1, 7],
+            'A' => [2, 5, 7, 5, 5],
+            'B' => [6, 5, 6, 5, 6],
+            'C' => [3, 4, 4, 4, 3],
+            'D' => [6, 5, 5, 5, 6],
+            'E' => [7, 4, 6, 4, 7],
+            'F' => [7, 4, 6, 4, 4],
+            'G' => [3, 4, 5, 5, 3],
+            'H' => [5, 5, 7, 5, 5],
+            'I' => [7, 2, 2, 2, 7],
+            'J' => [1, 1, 1, 5, 2],
+            'K' => [5, 5, 6, 5, 5],
+            'L' => [4, 4, 4, 4, 7],
+            'M' => [5, 7, 7, 5, 5],
+            'N' => [6, 5, 5, 5, 5],
+            'O' => [2, 5, 5, 5, 2],
+            'P' => [6, 5, 6, 4, 4],
+            'Q' => [2, 5, 5, 6, 3],
+            'R' => [6, 5, 6, 5, 5],
+            'S' => [3, 4, 2, 1, 6],
+            'T' => [7, 2, 2, 2, 2],
+            'U' => [5, 5, 5, 5, 7],
+            'V' => [5, 5, 5, 5, 2],
+            'W' => [5, 5, 7, 7, 5],
+            'X' => [5, 5, 2, 5, 5],
+            'Y' => [5, 5, 2, 2, 2],
+            'Z' => [7, 1, 2, 4, 7],
+            ' ' => [0, 0, 0, 0, 0],
+            '.' => [0, 0, 0, 0, 2],
+            ',' => [0, 0, 0, 2, 4],
+            ':' => [0, 2, 0, 2, 0],
+            '-' => [0, 0, 7, 0, 0],
+            '+' => [0, 2, 7, 2, 0],
+            '/' => [1, 1, 2, 4, 4],
+            '%' => [5, 1, 2, 4, 5],
+            '(' => [1, 2, 2, 2, 1],
+            ')' => [4, 2, 2, 2, 4],
+            _ => [7, 1, 2, 0, 2],
+        }
+    }
+}
+
+impl RenderNode for Text {
+    fn draw(&self, frame: &mut [u8], width: u16, height: u16) {
+        let color = self.color.to_color_array();
+        for (index, character) in self.content.chars().enumerate() {
+            let glyph_x = self.x + index as i32 * (Self::GLYPH_WIDTH + 1) * self.scale;
+            for (row, bits) in Self::glyph(character).into_iter().enumerate() {
+                for column in 0..Self::GLYPH_WIDTH {
+                    if bits & (1 << (Self::GLYPH_WIDTH - 1 - column)) == 0 {
+                        continue;
+                    }
+                    let pixel_x = glyph_x + column * self.scale;
+                    let pixel_y = self.y + row as i32 * self.scale;
+                    for dy in 0..self.scale {
+                        for dx in 0..self.scale {
+                            plot_pixel(frame, pixel_x + dx, pixel_y + dy, width, height, color);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// `change_pixel` for signed coordinates, skipping anything off the frame
+fn plot_pixel(frame: &mut [u8], x: i32, y: i32, width: u16, height: u16, color: [u8; 4]) {
+    if x < 0 || y < 0 || x >= width as i32 || y >= height as i32 {
+        return;
+    }
+    change_pixel(frame, x as usize, y as usize, width, height, color);
+}
+
+/// Color of a boid or shape, `Rgba` for anything off the named ones.
+#[derive(Clone, Serialize, Deserialize)]
 pub enum Color {
     Black,
     White,
     Red,
     Green,
     Blue,
+    Rgba(u8, u8, u8, u8),
 }
 
 impl Color {
@@ -177,6 +830,34 @@ impl Color {
             Color::Red => [255, 0, 0, 255],
             Color::Green => [0, 255, 0, 255],
             Color::Blue => [0, 0, 255, 255],
+            Color::Rgba(r, g, b, a) => [*r, *g, *b, *a],
         }
     }
+
+    /// Hue in degrees, saturation and value between 0 and 1
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Color {
+        let hue = hue.rem_euclid(360.0) / 60.0;
+        let chroma = value * saturation;
+        let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+        let (r, g, b) = match hue as u8 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let m = value - chroma;
+        let channel = |c: f32| ((c + m) * 255.0).round() as u8;
+        Color::Rgba(channel(r), channel(g), channel(b), 255)
+    }
+
+    /// Linear blend from `from` (t = 0) to `to` (t = 1)
+    pub fn gradient(from: &Color, to: &Color, t: f32) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let from = from.to_color_array();
+        let to = to.to_color_array();
+        let mix = |i: usize| (from[i] as f32 + (to[i] as f32 - from[i] as f32) * t) as u8;
+        Color::Rgba(mix(0), mix(1), mix(2), mix(3))
+    }
 }
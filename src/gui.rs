@@ -1,13 +1,45 @@
-use std::cmp::min;
+use std::collections::VecDeque;
+use std::path::Path;
 
-use egui::{Align, Button, Checkbox, ClippedPrimitive, Context, Layout, Slider, TexturesDelta};
+use egui::{
+    pos2, vec2, Align, Align2, Button, Checkbox, ClippedPrimitive, Color32, Context, DragValue, Key, Layout, Rect,
+    Sense, Shape, Slider, Stroke, TextEdit, TexturesDelta,
+};
 use egui_wgpu::renderer::{Renderer, ScreenDescriptor};
 use egui_winit::EventResponse;
 use pixels::{wgpu, PixelsContext};
+use serde::{Deserialize, Serialize};
+use winit::dpi::{PhysicalPosition, PhysicalSize};
 use winit::event_loop::EventLoopWindowTarget;
-use winit::window::Window;
+use winit::window::{Fullscreen, Window};
 
-use crate::{World, WIDTH};
+use crate::command::{search, Command, Panel};
+use crate::config::ConfigFile;
+use crate::shortcut::Action;
+
+use boids::capture::{GifSettings, VideoFormat, VideoResolution, VideoSettings};
+use boids::compare::Comparison;
+use boids::geometry::Color;
+use boids::hunt::HuntStats;
+use boids::preset::{load_preset, save_preset, Movement, RuleTransition, Rules, Scenario, Species};
+use boids::profile::Profile;
+use boids::lfo::{Lfo, Parameter, Waveform};
+#[cfg(feature = "midi")]
+use boids::midi::{MidiBinding, MidiControls};
+use boids::node::IndexBackend;
+use boids::obstacle::{Attractor, Obstacle};
+use boids::profiler::Stage;
+use boids::replay::Replay;
+use boids::rewind::Rewind;
+use boids::rule::default_rules;
+use boids::scenario::ScenarioFile;
+#[cfg(feature = "scripting")]
+use boids::script::ScriptWatcher;
+use boids::snapshot::Snapshot;
+use boids::statistics::{Statistics, StatisticsSample, MAX_OVERLAPS_PER_BOID};
+use boids::svg::save_svg;
+use boids::tuner::{AvoidTuner, CapacityTuner};
+use boids::{BackgroundStyle, ColorMode, ForceArrows, Palette, World, WorldOption};
 
 /// Manages all state required for rendering egui over `Pixels`.
 pub struct Framework {
@@ -23,12 +55,662 @@ pub struct Framework {
     gui: Gui,
 }
 
+/// What a left click in the simulation does.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Tool {
+    SpawnBoid,
+    SpawnPredator,
+    Obstacle,
+    Attractor,
+    Select,
+    Erase,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 6] = [
+        Tool::SpawnBoid,
+        Tool::SpawnPredator,
+        Tool::Obstacle,
+        Tool::Attractor,
+        Tool::Select,
+        Tool::Erase,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tool::SpawnBoid => "Boid",
+            Tool::SpawnPredator => "Predator",
+            Tool::Obstacle => "Obstacle",
+            Tool::Attractor => "Attractor",
+            Tool::Select => "Select",
+            Tool::Erase => "Erase",
+        }
+    }
+}
+
+/// Which windows are open, kept between runs.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OpenWindows {
+    pub flocks: bool,
+    pub debug: bool,
+    pub appearance: bool,
+    pub recording: bool,
+    pub statistics: bool,
+    pub preset: bool,
+    pub help: bool,
+    pub index: bool,
+    pub stats_window: bool,
+}
+
+impl Default for OpenWindows {
+    fn default() -> Self {
+        Self {
+            flocks: false,
+            debug: true,
+            appearance: false,
+            recording: false,
+            statistics: false,
+            preset: false,
+            help: false,
+            index: false,
+            stats_window: false,
+        }
+    }
+}
+
 /// Example application state. A real application will need a lot more state than this.
 struct Gui {
     /// Only show the egui window when true.
-    open_boid_window: bool,
-    open_predator_window: bool,
+    open_flocks_window: bool,
+    // None shows the overview
+    flock_tab: Option<Species>,
     open_debug_window: bool,
+    open_appearance_window: bool,
+    background_image_path: String,
+    background_image_error: Option<String>,
+    screenshot_requested: bool,
+    open_recording_window: bool,
+    recording_gif: bool,
+    gif_settings: GifSettings,
+    // Keeps the last seconds of frames to save after the fact
+    instant_replay: bool,
+    instant_replay_seconds: u32,
+    instant_replay_requested: bool,
+    recording_video: bool,
+    video_settings: VideoSettings,
+    video_error: Option<String>,
+    replay_path: String,
+    // What the last input recording or replay did, or why it failed
+    replay_status: Option<Result<String, String>>,
+    fps_history: FpsHistory,
+    open_statistics_window: bool,
+    statistics: Statistics,
+    // Path of the last CSV export or why it failed
+    statistics_export: Option<Result<String, String>>,
+    // Statistics and the inspector moved to an OS window of their own
+    stats_window: bool,
+    open_preset_window: bool,
+    preset_path: String,
+    // What the last save or load did, or why it failed
+    preset_status: Option<Result<String, String>>,
+    snapshot_path: String,
+    scenario_path: String,
+    tool: Tool,
+    // Boids per second spawned while dragging with a spawn tool
+    spray_rate: f32,
+    erase_radius: f32,
+    selection_color: [u8; 3],
+    open_help_window: bool,
+    open_index_window: bool,
+    open_animation_window: bool,
+    open_timeline_window: bool,
+    open_obstacle_window: bool,
+    open_midi_window: bool,
+    open_compare_window: bool,
+    // Second world drawn on the right half of the window
+    comparison: Option<Comparison>,
+    // Where the panels and windows were drawn this frame, in points
+    areas: Vec<Rect>,
+    #[cfg(feature = "midi")]
+    midi: MidiControls,
+    // Why the MIDI inputs could not be opened
+    #[cfg(feature = "midi")]
+    midi_error: Option<String>,
+    rewind: Rewind,
+    #[cfg(feature = "scripting")]
+    script_path: String,
+    // Script rule kept in the world and reloaded when its file changes
+    #[cfg(feature = "scripting")]
+    script: Option<ScriptWatcher>,
+    animate_surprise: bool,
+    // Rules easing towards a "Surprise me" pick
+    rule_transition: Option<RuleTransition>,
+    // Raising the avoid factor until boids stop overlapping, from the Flocks window
+    avoid_tuner: Option<AvoidTuner>,
+    // Hidden with the H key, the simulation keeps running
+    hidden: bool,
+    command_palette: Option<CommandPalette>,
+    seed_text: String,
+    seed_error: Option<String>,
+    // Failure the app went on after, shown until dismissed
+    error: Option<String>,
+}
+
+/// Search field of the Ctrl+P palette.
+struct CommandPalette {
+    query: String,
+    // Highlighted among the matches, run by Enter
+    selected: usize,
+}
+
+impl CommandPalette {
+    // Matches listed below the search field
+    const MAX_SHOWN: usize = 12;
+}
+
+/// Update and draw FPS over the last few seconds.
+struct FpsHistory {
+    // Seconds since start, update FPS and draw FPS
+    samples: VecDeque<(f64, f32, f32)>,
+}
+
+impl FpsHistory {
+    const SECONDS: f64 = 10.0;
+    const UPDATE_COLOR: Color32 = Color32::LIGHT_BLUE;
+    const DRAW_COLOR: Color32 = Color32::YELLOW;
+
+    fn new() -> Self {
+        Self {
+            samples: VecDeque::new(),
+        }
+    }
+
+    fn record(&mut self, time: f64, update_fps: f32, draw_fps: f32) {
+        // Both can be infinite before the first frame is timed
+        let clean = |fps: f32| if fps.is_finite() { fps } else { 0.0 };
+        self.samples.push_back((time, clean(update_fps), clean(draw_fps)));
+        while self
+            .samples
+            .front()
+            .is_some_and(|(sample_time, _, _)| time - sample_time > Self::SECONDS)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    fn ui(&self, ui: &mut egui::Ui) {
+        let (update_fps, draw_fps) = self
+            .samples
+            .back()
+            .map(|(_, update_fps, draw_fps)| (*update_fps, *draw_fps))
+            .unwrap_or_default();
+        ui.horizontal(|ui| {
+            ui.colored_label(Self::UPDATE_COLOR, format!("Update FPS: {:.0}", update_fps));
+            ui.colored_label(Self::DRAW_COLOR, format!("Draw FPS: {:.0}", draw_fps));
+        });
+        let Some(&(latest, _, _)) = self.samples.back() else {
+            return;
+        };
+        let update_line = self.samples.iter().map(|(time, fps, _)| (*time, *fps)).collect();
+        let draw_line = self.samples.iter().map(|(time, _, fps)| (*time, *fps)).collect();
+        plot_lines(
+            ui,
+            80.0,
+            (latest - Self::SECONDS, latest),
+            60.0,
+            &[(Self::UPDATE_COLOR, update_line), (Self::DRAW_COLOR, draw_line)],
+        );
+    }
+}
+
+/// Population plots with CSV export, `export` keeps what the last export did.
+pub fn statistics_ui(
+    ui: &mut egui::Ui,
+    statistics: &mut Statistics,
+    export: &mut Option<Result<String, String>>,
+    world: &World,
+) {
+    let samples = statistics.samples();
+    let time_range = match (samples.first(), samples.last()) {
+        (Some(first), Some(last)) => (first.time, last.time),
+        _ => (0.0, 0.0),
+    };
+    let series = [
+        ("Boids", Color32::GREEN, samples.iter().map(|sample| (sample.time, sample.boids as f32)).collect()),
+        ("Predators", Color32::RED, samples.iter().map(|sample| (sample.time, sample.predators as f32)).collect()),
+        ("Catches", Color32::YELLOW, samples.iter().map(|sample| (sample.time, sample.catches as f32)).collect::<Vec<_>>()),
+    ];
+    for (name, color, points) in series {
+        let latest = points.last().map(|(_, value)| *value).unwrap_or_default();
+        ui.colored_label(color, format!("{name}: {latest:.0}"));
+        plot_lines(ui, 50.0, time_range, 1.0, &[(color, points)]);
+    }
+    // Gaps while no boid sees another are left out of the lines
+    let distances = |value: fn(&StatisticsSample) -> Option<f32>| -> Vec<(f64, f32)> {
+        samples
+            .iter()
+            .filter_map(|sample| Some((sample.time, value(sample)?)))
+            .collect()
+    };
+    let (mean_color, min_color) = (Color32::LIGHT_BLUE, Color32::from_rgb(80, 120, 255));
+    ui.horizontal(|ui| {
+        ui.label("Nearest neighbor:");
+        match samples.last().and_then(|sample| sample.nearest_mean.zip(sample.nearest_min)) {
+            Some((mean, min)) => {
+                ui.colored_label(mean_color, format!("mean {mean:.1} px"));
+                ui.colored_label(min_color, format!("closest {min:.1} px"));
+            }
+            None => {
+                ui.weak("no boid sees another");
+            }
+        }
+    });
+    plot_lines(
+        ui,
+        50.0,
+        time_range,
+        1.0,
+        &[
+            (mean_color, distances(|sample| sample.nearest_mean)),
+            (min_color, distances(|sample| sample.nearest_min)),
+        ],
+    );
+    crowding_warning(ui, world);
+    ui.with_layout(Layout::left_to_right(Align::TOP), |ui| {
+        if ui.add(Button::new("Export CSV")).clicked() {
+            *export = Some(
+                statistics
+                    .export_csv(Path::new("."))
+                    .map(|path| path.display().to_string())
+                    .map_err(|error| error.to_string()),
+            );
+        }
+        if ui.add(Button::new("Reset")).clicked() {
+            statistics.clear();
+        }
+    });
+    match export {
+        Some(Ok(path)) => {
+            ui.label(format!("Saved to {path}"));
+        }
+        Some(Err(error)) => {
+            ui.colored_label(Color32::RED, error.as_str());
+        }
+        None => {}
+    }
+}
+
+/// Tell when boids sit deep inside the safe radius on average, see `NeighborDistances::is_crowded`.
+fn crowding_warning(ui: &mut egui::Ui, world: &World) {
+    let Some(distances) = world.neighbor_distances() else {
+        return;
+    };
+    if distances.is_crowded(&world.option) {
+        ui.colored_label(
+            Color32::YELLOW,
+            format!(
+                "⚠ Boids average {:.1} px to their nearest neighbor, within half the safe radius of {:.1} px. \
+                 Raise the avoid factor or lower cohesion",
+                distances.mean, world.option.safe_radius
+            ),
+        );
+    }
+}
+
+fn overlaps_per_boid(world: &World) -> f32 {
+    world.overlaps() as f32 / world.boids_count().max(1) as f32
+}
+
+/// Boid pairs closer than the safe radius, with a warning once separation is too weak and a
+/// button raising the avoid factor until they are few enough.
+fn overlap_ui(ui: &mut egui::Ui, world: &World, tuner: &mut Option<AvoidTuner>) {
+    let per_boid = overlaps_per_boid(world);
+    ui.label(format!(
+        "Pairs within safe radius: {} ({per_boid:.1} per boid)",
+        world.overlaps()
+    ));
+    if per_boid > MAX_OVERLAPS_PER_BOID {
+        ui.colored_label(
+            Color32::YELLOW,
+            format!(
+                "⚠ Separation too weak, over {MAX_OVERLAPS_PER_BOID:.0} pairs per boid within the safe radius. \
+                 Raise the avoid factor"
+            ),
+        );
+    }
+    ui.horizontal(|ui| match tuner {
+        Some(_) => {
+            ui.spinner();
+            ui.label(format!("Avoid factor {:.2}", world.option.avoid_factor));
+            if ui.add(Button::new("Stop tuning")).clicked() {
+                *tuner = None;
+            }
+        }
+        None => {
+            if ui
+                .add(Button::new("Auto tune avoid factor"))
+                .on_hover_text(format!(
+                    "Raise the avoid factor a little at a time until at most {MAX_OVERLAPS_PER_BOID:.0} pairs per boid \
+                     are within the safe radius"
+                ))
+                .clicked()
+            {
+                *tuner = Some(AvoidTuner::default());
+            }
+        }
+    });
+}
+
+/// Where the boid picked with a right click is and how it moves, with the settings it follows
+/// over those of the world.
+pub fn inspector_ui(ui: &mut egui::Ui, world: &mut World) {
+    let Some(id) = world.selected() else {
+        ui.label("Right click a boid to inspect it");
+        return;
+    };
+    let found = world
+        .boids()
+        .map(|boid| ("Boid", boid))
+        .chain(world.predators().map(|boid| ("Predator", boid)))
+        .find(|(_, boid)| boid.id() == id);
+    let Some((kind, boid)) = found else {
+        ui.label("The selected boid is gone");
+        return;
+    };
+    let species = boid.species();
+    let mut profile = boid.profile().copied().unwrap_or_default();
+    let (x, y) = boid.position();
+    let (velocity_x, velocity_y) = boid.velocity();
+    // Degrees clockwise from the right, y points down
+    let heading = velocity_y.atan2(velocity_x).to_degrees().rem_euclid(360.0);
+    egui::Grid::new("inspector").num_columns(2).show(ui, |ui| {
+        ui.label(kind);
+        ui.label(id.to_string());
+        ui.end_row();
+        ui.label("Position");
+        ui.label(format!("{x:.1}, {y:.1}"));
+        ui.end_row();
+        ui.label("Velocity");
+        ui.label(format!("{velocity_x:.2}, {velocity_y:.2}"));
+        ui.end_row();
+        ui.label("Speed");
+        ui.label(format!("{:.2}", velocity_x.hypot(velocity_y)));
+        ui.end_row();
+        ui.label("Heading");
+        ui.label(format!("{heading:.0}°"));
+        ui.end_row();
+        ui.label("Size");
+        ui.label(boid.size().to_string());
+        ui.end_row();
+        if let Some(hunt) = world.hunt(id) {
+            ui.label("Chases");
+            ui.label(hunt.chases.to_string());
+            ui.end_row();
+            ui.label("Catches");
+            ui.label(hunt.catches.to_string());
+            ui.end_row();
+            ui.label("Mean chase");
+            ui.label(mean_chase(hunt));
+            ui.end_row();
+            ui.label("Distance");
+            ui.label(format!("{:.0} px", hunt.distance));
+            ui.end_row();
+        }
+    });
+    if species == Species::Boid {
+        ui.separator();
+        if profile_ui(ui, &mut profile, &world.option) {
+            world.set_profile(id, profile);
+        }
+    }
+}
+
+/// Settings of one boid over those of the world, true when `profile` changed.
+fn profile_ui(ui: &mut egui::Ui, profile: &mut Profile, option: &WorldOption) -> bool {
+    let before = *profile;
+    ui.horizontal(|ui| {
+        ui.label("Make it");
+        if ui.add(Button::new("Faster")).on_hover_text("Twice the speed of the flock").clicked() {
+            profile.max_speed = Some(option.boid_max_speed.saturating_mul(2));
+            profile.min_speed = Some(option.boid_min_speed.saturating_mul(2));
+        }
+        if ui.add(Button::new("Blind")).on_hover_text("Sees no other boid").clicked() {
+            profile.vision_radius = Some(0.0);
+        }
+        if ui.add(Button::new("Fearless")).on_hover_text("Ignores predators").clicked() {
+            profile.fear_factor = Some(0.0);
+        }
+        if ui.add_enabled(!profile.is_empty(), Button::new("Like the others")).clicked() {
+            *profile = Profile::default();
+        }
+    });
+    profile_row(ui, "Avoid factor", &mut profile.avoid_factor, option.avoid_factor, 0.0..=1.0);
+    profile_row(ui, "Matching factor", &mut profile.matching_factor, option.matching_factor, 0.0..=1.0);
+    profile_row(ui, "Centering factor", &mut profile.centering_factor, option.centering_factor, 0.0..=1.0);
+    profile_row(ui, "Vision radius", &mut profile.vision_radius, option.boid_vision_radius, 0.0..=200.0);
+    profile_row(ui, "Max speed", &mut profile.max_speed, option.boid_max_speed, 0..=100);
+    profile_row(ui, "Min speed", &mut profile.min_speed, option.boid_min_speed, 0..=100);
+    profile_row(ui, "Fear factor", &mut profile.fear_factor, option.fear_factor, 0.0..=1.0);
+    *profile != before
+}
+
+// A setting of the world taken over for one boid when ticked, starting from the world's value
+fn profile_row<T: egui::emath::Numeric + std::fmt::Display>(
+    ui: &mut egui::Ui,
+    name: &str,
+    value: &mut Option<T>,
+    world_value: T,
+    range: std::ops::RangeInclusive<T>,
+) {
+    ui.horizontal(|ui| {
+        let mut own = value.is_some();
+        if ui.add(Checkbox::new(&mut own, name)).on_hover_text("Its own instead of the flock's").changed() {
+            *value = own.then_some(world_value);
+        }
+        match value {
+            Some(value) => {
+                ui.add(Slider::new(value, range));
+            }
+            None => {
+                ui.weak(world_value.to_string());
+            }
+        }
+    });
+}
+
+/// How every predator hunted, the best hunters first.
+fn hunting_ui(ui: &mut egui::Ui, world: &World) {
+    let mut hunts: Vec<_> = world
+        .predators()
+        .filter_map(|predator| Some((predator.id(), world.hunt(predator.id())?)))
+        .collect();
+    if hunts.is_empty() {
+        ui.label("No predator has hunted yet");
+        return;
+    }
+    hunts.sort_by(|(_, a), (_, b)| b.catches.cmp(&a.catches).then(b.chases.cmp(&a.chases)));
+    egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+        egui::Grid::new("hunting").striped(true).show(ui, |ui| {
+            for title in ["Predator", "Chases", "Catches", "Mean chase", "Distance"] {
+                ui.label(title);
+            }
+            ui.end_row();
+            for (id, hunt) in hunts {
+                let mut name = id.to_string();
+                name.truncate(8);
+                if hunt.is_chasing() {
+                    name.push_str(" (chasing)");
+                }
+                ui.label(name).on_hover_text(id.to_string());
+                ui.label(hunt.chases.to_string());
+                ui.label(hunt.catches.to_string());
+                ui.label(mean_chase(hunt));
+                ui.label(format!("{:.0} px", hunt.distance));
+                ui.end_row();
+            }
+        });
+    });
+}
+
+/// Every obstacle and attractor with its exact place and size, for laying out a scenario by
+/// numbers. All changes of a frame go to the world as one edit.
+fn scenery_ui(ui: &mut egui::Ui, world: &mut World) {
+    let mut obstacles = world.obstacles().to_vec();
+    let mut attractors = world.attractors().to_vec();
+    // Copies are moved down and right so they do not hide the original
+    const DUPLICATE_OFFSET: f32 = 20.0;
+    let view = world.viewport();
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        if ui.button("Add obstacle").on_hover_text("In the middle of the view").clicked() {
+            obstacles.push(Obstacle::new(view.center_x, view.center_y));
+            changed = true;
+        }
+        if ui.button("Add attractor").on_hover_text("In the middle of the view").clicked() {
+            attractors.push(Attractor::new(view.center_x, view.center_y));
+            changed = true;
+        }
+    });
+    ui.separator();
+    ui.label(format!("Obstacles ({})", obstacles.len()));
+    let fields = |ui: &mut egui::Ui, obstacle: &mut Obstacle| {
+        let mut changed = ui.add(DragValue::new(&mut obstacle.x)).changed();
+        changed |= ui.add(DragValue::new(&mut obstacle.y)).changed();
+        changed |= ui
+            .add(DragValue::new(&mut obstacle.radius).speed(0.5).clamp_range(1.0..=1000.0))
+            .changed();
+        changed
+    };
+    let duplicate = |obstacle: &Obstacle| Obstacle {
+        x: obstacle.x + DUPLICATE_OFFSET,
+        y: obstacle.y + DUPLICATE_OFFSET,
+        ..obstacle.clone()
+    };
+    changed |= scenery_rows(ui, "obstacles", &["X", "Y", "Radius"], &mut obstacles, fields, duplicate);
+    ui.separator();
+    ui.label(format!("Attractors ({})", attractors.len()));
+    let fields = |ui: &mut egui::Ui, attractor: &mut Attractor| {
+        let mut changed = ui.add(DragValue::new(&mut attractor.x)).changed();
+        changed |= ui.add(DragValue::new(&mut attractor.y)).changed();
+        changed |= ui
+            .add(DragValue::new(&mut attractor.radius).speed(0.5).clamp_range(1.0..=2000.0))
+            .changed();
+        changed |= ui
+            .add(DragValue::new(&mut attractor.strength).speed(0.001).clamp_range(0.0..=0.2))
+            .changed();
+        changed
+    };
+    let duplicate = |attractor: &Attractor| Attractor {
+        x: attractor.x + DUPLICATE_OFFSET,
+        y: attractor.y + DUPLICATE_OFFSET,
+        ..attractor.clone()
+    };
+    let titles = ["X", "Y", "Radius", "Strength"];
+    changed |= scenery_rows(ui, "attractors", &titles, &mut attractors, fields, duplicate);
+    if changed {
+        world.edit_scenery(obstacles, attractors);
+    }
+}
+
+/// One row of fields per item with duplicate and delete buttons, true once anything changed.
+/// Copies made by `duplicate` are put at the end of the list.
+fn scenery_rows<T>(
+    ui: &mut egui::Ui,
+    id: &str,
+    titles: &[&str],
+    items: &mut Vec<T>,
+    mut fields: impl FnMut(&mut egui::Ui, &mut T) -> bool,
+    duplicate: impl Fn(&T) -> T,
+) -> bool {
+    if items.is_empty() {
+        ui.weak("None yet");
+        return false;
+    }
+    let mut changed = false;
+    let (mut duplicated, mut deleted) = (None, None);
+    egui::ScrollArea::vertical().id_source(id).max_height(200.0).show(ui, |ui| {
+        egui::Grid::new(id).striped(true).show(ui, |ui| {
+            ui.label("");
+            for title in titles {
+                ui.label(*title);
+            }
+            ui.end_row();
+            for (index, item) in items.iter_mut().enumerate() {
+                ui.label((index + 1).to_string());
+                changed |= fields(ui, item);
+                if ui.small_button("Duplicate").clicked() {
+                    duplicated = Some(index);
+                }
+                if ui.small_button("Delete").clicked() {
+                    deleted = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+    });
+    if let Some(index) = duplicated {
+        items.push(duplicate(&items[index]));
+        changed = true;
+    }
+    if let Some(index) = deleted {
+        items.remove(index);
+        changed = true;
+    }
+    changed
+}
+
+fn mean_chase(hunt: &HuntStats) -> String {
+    hunt.mean_chase_ticks()
+        .map_or_else(|| "-".to_string(), |ticks| format!("{ticks:.0} ticks"))
+}
+
+/// Small button restoring `value` to `default`, only enabled once it was changed.
+fn reset_button<T: PartialEq>(ui: &mut egui::Ui, value: &mut T, default: T) {
+    let changed = *value != default;
+    if ui
+        .add_enabled(changed, Button::new("⟲").small())
+        .on_hover_text("Reset to default")
+        .clicked()
+    {
+        *value = default;
+    }
+}
+
+/// Paint (time, value) lines over `time_range`, scaled so the largest value (at least
+/// `minimum_top`) reaches the top.
+fn plot_lines(
+    ui: &mut egui::Ui,
+    height: f32,
+    time_range: (f64, f64),
+    minimum_top: f32,
+    lines: &[(Color32, Vec<(f64, f32)>)],
+) {
+    let (response, painter) = ui.allocate_painter(vec2(ui.available_width(), height), Sense::hover());
+    let rect = response.rect;
+    painter.rect_filled(rect, 2.0, Color32::from_black_alpha(120));
+    let highest = lines
+        .iter()
+        .flat_map(|(_, points)| points.iter().map(|(_, value)| *value))
+        .fold(minimum_top, f32::max);
+    painter.text(
+        rect.left_top() + vec2(4.0, 2.0),
+        egui::Align2::LEFT_TOP,
+        format!("{:.0}", highest),
+        egui::FontId::monospace(10.0),
+        Color32::GRAY,
+    );
+    let (start, end) = time_range;
+    let duration = (end - start).max(f64::EPSILON);
+    let to_screen = |time: f64, value: f32| {
+        let x = rect.left() + ((time - start) / duration) as f32 * rect.width();
+        let y = rect.bottom() - value / highest * rect.height();
+        pos2(x, y)
+    };
+    for (color, points) in lines {
+        let points = points.iter().map(|(time, value)| to_screen(*time, *value)).collect();
+        painter.add(Shape::line(points, Stroke::new(1.5, *color)));
+    }
 }
 
 impl Framework {
@@ -65,6 +747,20 @@ impl Framework {
         }
     }
 
+    /// Recreate the renderer for a new `Pixels` instance, keeping the GUI state.
+    pub fn rebuild<T>(&mut self, event_loop: &EventLoopWindowTarget<T>, pixels: &pixels::Pixels) {
+        let gui = std::mem::replace(&mut self.gui, Gui::new());
+        let [width, height] = self.screen_descriptor.size_in_pixels;
+        *self = Framework::new(
+            event_loop,
+            width,
+            height,
+            self.screen_descriptor.pixels_per_point,
+            pixels,
+        );
+        self.gui = gui;
+    }
+
     /// Handle input events from the window manager.
     pub fn handle_event(&mut self, event: &winit::event::WindowEvent) -> EventResponse {
         self.egui_state.on_event(&self.egui_ctx, event)
@@ -87,9 +783,36 @@ impl Framework {
         // Run the egui frame and create all paint jobs to prepare for rendering.
         let raw_input = self.egui_state.take_egui_input(window);
         let output = self.egui_ctx.run(raw_input, |egui_ctx| {
+            self.gui.animate(egui_ctx, world);
+            // Kept even with the GUI hidden, to look back at what just happened
+            self.gui.rewind.record(egui_ctx.input(|input| input.time), world);
+            // Also plotted in the stats window, which stays up with the GUI hidden
+            self.gui.statistics.record(egui_ctx.input(|input| input.time), world);
+            #[cfg(feature = "scripting")]
+            if let Some(script) = &mut self.gui.script {
+                script.poll(world);
+            }
+            #[cfg(feature = "midi")]
+            self.gui.midi.poll(&mut world.option);
+            self.gui.areas.clear();
             // Draw the demo application.
-            self.gui.ui(egui_ctx, world);
+            if !self.gui.hidden {
+                self.gui.ui(egui_ctx, world, window);
+            }
+            // Shown even with the GUI hidden, it was asked for with Ctrl+P
+            self.gui.command_palette(egui_ctx, world, window);
+            if let Some(error) = world.take_error() {
+                self.gui.error = Some(error.to_string());
+            }
+            self.gui.error_notice(egui_ctx);
         });
+        // A slider drag, a spray, a rule transition or a tuning is one edit, closed once it is over
+        if !self.egui_ctx.input(|input| input.pointer.any_down())
+            && self.gui.rule_transition.is_none()
+            && self.gui.avoid_tuner.is_none()
+        {
+            world.commit_edit();
+        }
 
         self.textures.append(output.textures_delta);
         self.egui_state
@@ -97,6 +820,146 @@ impl Framework {
         self.paint_jobs = self.egui_ctx.tessellate(output.shapes);
     }
 
+    /// Do what a keyboard shortcut is bound to.
+    pub fn perform(&mut self, action: Action, world: &mut World, window: &Window) {
+        self.gui.perform(action, world, window);
+    }
+
+    pub fn open_windows(&self) -> OpenWindows {
+        OpenWindows {
+            flocks: self.gui.open_flocks_window,
+            debug: self.gui.open_debug_window,
+            appearance: self.gui.open_appearance_window,
+            recording: self.gui.open_recording_window,
+            statistics: self.gui.open_statistics_window,
+            preset: self.gui.open_preset_window,
+            help: self.gui.open_help_window,
+            index: self.gui.open_index_window,
+            stats_window: self.gui.stats_window,
+        }
+    }
+
+    pub fn set_open_windows(&mut self, windows: OpenWindows) {
+        self.gui.open_flocks_window = windows.flocks;
+        self.gui.open_debug_window = windows.debug;
+        self.gui.open_appearance_window = windows.appearance;
+        self.gui.open_recording_window = windows.recording;
+        self.gui.open_statistics_window = windows.statistics;
+        self.gui.open_preset_window = windows.preset;
+        self.gui.open_help_window = windows.help;
+        self.gui.open_index_window = windows.index;
+        self.gui.stats_window = windows.stats_window;
+    }
+
+    /// Control changes bound to parameters, kept between runs.
+    #[cfg(feature = "midi")]
+    pub fn midi_bindings(&self) -> Vec<MidiBinding> {
+        self.gui.midi.bindings().to_vec()
+    }
+
+    /// Bind the controls saved by the last run and listen to the MIDI inputs plugged in.
+    #[cfg(feature = "midi")]
+    pub fn start_midi(&mut self, bindings: Vec<MidiBinding>) {
+        self.gui.midi.set_bindings(bindings);
+        self.gui.midi_error = self.gui.midi.connect().err();
+    }
+
+    /// Tool picked in the toolbar.
+    pub fn tool(&self) -> Tool {
+        self.gui.tool
+    }
+
+    /// Boids per second to spawn while dragging with a spawn tool.
+    pub fn spray_rate(&self) -> f32 {
+        self.gui.spray_rate
+    }
+
+    /// Radius around the cursor cleared by the eraser.
+    pub fn erase_radius(&self) -> f32 {
+        self.gui.erase_radius
+    }
+
+    /// Corners of the panels and windows drawn this frame, in physical pixels.
+    pub fn areas(&self) -> Vec<(PhysicalPosition<f64>, PhysicalPosition<f64>)> {
+        let scale = self.screen_descriptor.pixels_per_point as f64;
+        let corner = |point: egui::Pos2| PhysicalPosition::new(point.x as f64 * scale, point.y as f64 * scale);
+        self.gui.areas.iter().map(|area| (corner(area.min), corner(area.max))).collect()
+    }
+
+    /// Whether the statistics and the inspector are asked for in a window of their own.
+    pub fn wants_stats_window(&self) -> bool {
+        self.gui.stats_window
+    }
+
+    /// Bring the statistics back to the main window, once the stats window was closed.
+    pub fn close_stats_window(&mut self) {
+        self.gui.stats_window = false;
+    }
+
+    /// Population samples plotted in the Statistics window.
+    pub fn statistics_mut(&mut self) -> &mut Statistics {
+        &mut self.gui.statistics
+    }
+
+    /// Whether the window is split between the main world and a compared one.
+    pub fn is_comparing(&self) -> bool {
+        self.gui.comparison.is_some()
+    }
+
+    /// World run next to the main one with other options, while comparing.
+    pub fn comparison_mut(&mut self) -> Option<&mut Comparison> {
+        self.gui.comparison.as_mut()
+    }
+
+    /// Whether a screenshot was requested from the GUI since the last call.
+    pub fn take_screenshot_request(&mut self) -> bool {
+        std::mem::take(&mut self.gui.screenshot_requested)
+    }
+
+    /// Settings of the GIF recording while one is requested from the GUI.
+    pub fn gif_recording(&self) -> Option<GifSettings> {
+        self.gui.recording_gif.then_some(self.gui.gif_settings)
+    }
+
+    /// Seconds of frames to keep for an instant replay while it is on.
+    pub fn instant_replay(&self) -> Option<f32> {
+        self.gui
+            .instant_replay
+            .then_some(self.gui.instant_replay_seconds as f32)
+    }
+
+    /// Whether saving the instant replay was requested since the last call.
+    pub fn take_instant_replay_request(&mut self) -> bool {
+        std::mem::take(&mut self.gui.instant_replay_requested)
+    }
+
+    /// Settings of the video recording while one is requested from the GUI.
+    pub fn video_recording(&self) -> Option<VideoSettings> {
+        self.gui.recording_video.then_some(self.gui.video_settings)
+    }
+
+    /// Start a GIF recording as if from the Recording window.
+    pub fn start_gif_recording(&mut self) {
+        self.gui.recording_gif = true;
+    }
+
+    /// Start a video recording in `format` as if from the Recording window.
+    pub fn start_video_recording(&mut self, format: VideoFormat) {
+        self.gui.video_settings.format = format;
+        self.gui.recording_video = true;
+    }
+
+    /// Stop the requested video recording and show why it failed.
+    pub fn set_video_error(&mut self, error: String) {
+        self.gui.recording_video = false;
+        self.gui.video_error = Some(error);
+    }
+
+    /// Show a failure the app could go on after, like a surface that did not resize.
+    pub fn report_error(&mut self, error: String) {
+        self.gui.error = Some(error);
+    }
+
     /// Render egui.
     pub fn render(
         &mut self,
@@ -104,113 +967,824 @@ impl Framework {
         render_target: &wgpu::TextureView,
         context: &PixelsContext,
     ) {
-        // Upload all resources to the GPU.
-        for (id, image_delta) in &self.textures.set {
-            self.renderer
-                .update_texture(&context.device, &context.queue, *id, image_delta);
-        }
-        self.renderer.update_buffers(
-            &context.device,
-            &context.queue,
-            encoder,
+        render_egui(
+            &mut self.renderer,
+            &mut self.textures,
             &self.paint_jobs,
             &self.screen_descriptor,
+            encoder,
+            render_target,
+            context,
         );
+    }
+}
 
-        // Render egui with WGPU
-        {
-            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("egui"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: render_target,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Load,
-                        store: true,
-                    },
-                })],
-                depth_stencil_attachment: None,
-            });
+/// Upload the textures and draw the paint jobs of an egui frame over `render_target`.
+pub fn render_egui(
+    renderer: &mut Renderer,
+    textures: &mut TexturesDelta,
+    paint_jobs: &[ClippedPrimitive],
+    screen_descriptor: &ScreenDescriptor,
+    encoder: &mut wgpu::CommandEncoder,
+    render_target: &wgpu::TextureView,
+    context: &PixelsContext,
+) {
+    // Upload all resources to the GPU.
+    for (id, image_delta) in &textures.set {
+        renderer.update_texture(&context.device, &context.queue, *id, image_delta);
+    }
+    renderer.update_buffers(&context.device, &context.queue, encoder, paint_jobs, screen_descriptor);
 
-            self.renderer
-                .render(&mut rpass, &self.paint_jobs, &self.screen_descriptor);
-        }
+    // Render egui with WGPU
+    {
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("egui"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: render_target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
 
-        // Cleanup
-        let textures = std::mem::take(&mut self.textures);
-        for id in &textures.free {
-            self.renderer.free_texture(id);
-        }
+        renderer.render(&mut rpass, paint_jobs, screen_descriptor);
+    }
+
+    // Cleanup
+    let textures = std::mem::take(textures);
+    for id in &textures.free {
+        renderer.free_texture(id);
+    }
+}
+
+/// Switch the window between windowed and borderless fullscreen.
+pub fn toggle_fullscreen(window: &Window) {
+    if window.fullscreen().is_some() {
+        window.set_fullscreen(None);
+    } else {
+        window.set_fullscreen(Some(Fullscreen::Borderless(None)));
     }
 }
 
 impl Gui {
+    // Seconds of history the Timeline window starts with
+    const REWIND_LENGTH: f64 = 10.0;
+
     /// Create a `Gui`.
     fn new() -> Self {
         Self { 
-            open_boid_window: false,
-            open_predator_window: false,
+            open_flocks_window: false,
+            flock_tab: None,
             open_debug_window: true,
+            open_appearance_window: false,
+            background_image_path: String::new(),
+            background_image_error: None,
+            screenshot_requested: false,
+            open_recording_window: false,
+            recording_gif: false,
+            gif_settings: GifSettings::default(),
+            instant_replay: true,
+            instant_replay_seconds: 10,
+            instant_replay_requested: false,
+            recording_video: false,
+            video_settings: VideoSettings::default(),
+            video_error: None,
+            replay_path: "replay.ron".to_string(),
+            replay_status: None,
+            fps_history: FpsHistory::new(),
+            open_statistics_window: false,
+            statistics: Statistics::new(0.5),
+            statistics_export: None,
+            stats_window: false,
+            open_preset_window: false,
+            preset_path: "preset.ron".to_string(),
+            preset_status: None,
+            snapshot_path: "snapshot.ron".to_string(),
+            scenario_path: "scenario.ron".to_string(),
+            tool: Tool::SpawnBoid,
+            spray_rate: 30.0,
+            erase_radius: 15.0,
+            selection_color: [255, 255, 255],
+            open_help_window: false,
+            open_index_window: false,
+            open_animation_window: false,
+            open_timeline_window: false,
+            open_obstacle_window: false,
+            open_midi_window: false,
+            open_compare_window: false,
+            comparison: None,
+            areas: vec![],
+            #[cfg(feature = "midi")]
+            midi: MidiControls::new(),
+            #[cfg(feature = "midi")]
+            midi_error: None,
+            rewind: Rewind::new(Self::REWIND_LENGTH, 0.1),
+            #[cfg(feature = "scripting")]
+            script_path: "rule.rhai".to_string(),
+            #[cfg(feature = "scripting")]
+            script: None,
+            animate_surprise: true,
+            rule_transition: None,
+            avoid_tuner: None,
+            hidden: false,
+            command_palette: None,
+            seed_text: String::new(),
+            seed_error: None,
+            error: None,
+        }
+    }
+
+    /// Move the rules along a running transition and the avoid factor along its tuning, even
+    /// while the GUI is hidden.
+    fn animate(&mut self, ctx: &Context, world: &mut World) {
+        if let Some(tuner) = &mut self.avoid_tuner {
+            let overlaps = overlaps_per_boid(world);
+            if !tuner.record(world.tick(), overlaps, MAX_OVERLAPS_PER_BOID, &mut world.option.avoid_factor) {
+                self.avoid_tuner = None;
+            }
+        }
+        let Some(transition) = &self.rule_transition else {
+            return;
+        };
+        if !transition.apply_at(&mut world.option, ctx.input(|input| input.time)) {
+            self.rule_transition = None;
+        }
+        ctx.request_repaint();
+    }
+
+    /// Do what a keyboard shortcut is bound to, from the keyboard or the command palette.
+    fn perform(&mut self, action: Action, world: &mut World, window: &Window) {
+        match action {
+            Action::TogglePause => world.toggle_pause(),
+            Action::Restart => world.restart(),
+            Action::Clear => world.clear_all(),
+            Action::ToggleGui => self.hidden = !self.hidden,
+            Action::ToggleDebugOverlays => {
+                world.option.show_debug_overlays = !world.option.show_debug_overlays;
+            }
+            Action::SpeedUp => world.speed_up(),
+            Action::SlowDown => world.slow_down(),
+            Action::Fullscreen => toggle_fullscreen(window),
+            Action::Screenshot => self.screenshot_requested = true,
+            Action::SaveInstantReplay => self.instant_replay_requested = self.instant_replay,
+            Action::Undo => world.undo(),
+            Action::Redo => world.redo(),
+            Action::CommandPalette => {
+                self.command_palette = Some(CommandPalette {
+                    query: String::new(),
+                    selected: 0,
+                });
+            }
+        }
+    }
+
+    fn run(&mut self, command: Command, world: &mut World, window: &Window, time: f64) {
+        match command {
+            Command::Action(action) => self.perform(action, world, window),
+            Command::Tool(tool) => self.tool = tool,
+            Command::Open(panel) => *self.window_open(panel) = true,
+            Command::Scenario(scenario) => self.apply_scenario(scenario, world),
+            Command::SurpriseMe => self.surprise(world, time),
+            Command::ResetAll => self.reset_all(world),
+            Command::ResetCamera => world.reset_camera(),
+            Command::Step => world.step_once(),
+            Command::SavePreset => self.preset_status = Some(save_preset_file(&self.preset_path, world)),
+            Command::LoadPreset => {
+                let status = load_preset_file(&self.preset_path, world);
+                if status.is_ok() {
+                    self.rule_transition = None;
+                }
+                self.preset_status = Some(status);
+            }
+            Command::ExportSvg => self.export_svg(world),
+            Command::SaveSnapshot => self.save_snapshot(world),
+            Command::LoadSnapshot => self.load_snapshot(world),
+            Command::OpenConfig => self.open_config(world, window),
+            Command::ToggleGifRecording => self.recording_gif = !self.recording_gif,
+            Command::ToggleVideoRecording => {
+                self.recording_video = !self.recording_video;
+                self.video_error = None;
+            }
+            Command::ToggleInputRecording => {
+                self.replay_status = Some(toggle_input_recording(&self.replay_path, world));
+                self.open_recording_window = true;
+            }
+            Command::PlayReplay => {
+                self.replay_status = Some(play_replay_file(&self.replay_path, world, window));
+                self.open_recording_window = true;
+            }
+            Command::ToggleStatsWindow => self.stats_window = !self.stats_window,
+        }
+    }
+
+    /// Save the whole world, the outcome is shown in the Presets window.
+    fn export_svg(&mut self, world: &World) {
+        match save_svg(world, Path::new(".")) {
+            Ok(path) => println!("Saved SVG to {}", path.display()),
+            Err(error) => self.error = Some(format!("Cannot save SVG: {error}")),
+        }
+    }
+
+    fn save_snapshot(&mut self, world: &World) {
+        self.preset_status = Some(save_snapshot_file(&self.snapshot_path, world));
+        self.open_preset_window = true;
+    }
+
+    fn load_snapshot(&mut self, world: &mut World) {
+        let status = load_snapshot_file(&self.snapshot_path, world);
+        if status.is_ok() {
+            self.rule_transition = None;
+        }
+        self.preset_status = Some(status);
+        self.open_preset_window = true;
+    }
+
+    fn load_scenario(&mut self, world: &mut World) {
+        let status = load_scenario_file(&self.scenario_path, world);
+        if status.is_ok() {
+            self.rule_transition = None;
+        }
+        self.preset_status = Some(status);
+        self.open_preset_window = true;
+    }
+
+    /// Apply `boids.toml`, the outcome is shown in the Presets window.
+    fn open_config(&mut self, world: &mut World, window: &Window) {
+        let status = open_config_file(world, window);
+        if status.is_ok() {
+            self.rule_transition = None;
+        }
+        self.preset_status = Some(status);
+        self.open_preset_window = true;
+    }
+
+    // Remembers where a panel or window was drawn, for the flock to keep out of it
+    fn note_area(&mut self, area: Option<Rect>) {
+        self.areas.extend(area);
+    }
+
+    fn window_open(&mut self, panel: Panel) -> &mut bool {
+        match panel {
+            Panel::Flocks => &mut self.open_flocks_window,
+            Panel::Debug => &mut self.open_debug_window,
+            Panel::Appearance => &mut self.open_appearance_window,
+            Panel::Recording => &mut self.open_recording_window,
+            Panel::Statistics => &mut self.open_statistics_window,
+            Panel::Presets => &mut self.open_preset_window,
+            Panel::SpatialIndex => &mut self.open_index_window,
+            Panel::Animation => &mut self.open_animation_window,
+            Panel::Timeline => &mut self.open_timeline_window,
+            Panel::Obstacles => &mut self.open_obstacle_window,
+            Panel::Midi => &mut self.open_midi_window,
+            Panel::Compare => &mut self.open_compare_window,
+            Panel::Help => &mut self.open_help_window,
+        }
+    }
+
+    fn apply_scenario(&mut self, scenario: Scenario, world: &mut World) {
+        self.rule_transition = None;
+        scenario.apply(&mut world.option);
+        world.restart();
+    }
+
+    /// Pick random rules, eased into when animated changes are on.
+    fn surprise(&mut self, world: &mut World, time: f64) {
+        let rules = Rules::random(&mut rand::thread_rng());
+        if self.animate_surprise {
+            let from = Rules::of(&world.option);
+            self.rule_transition = Some(RuleTransition::new(from, rules, time));
+        } else {
+            rules.apply(&mut world.option);
+        }
+    }
+
+    fn reset_all(&mut self, world: &mut World) {
+        self.rule_transition = None;
+        world.option = WorldOption::new();
+    }
+
+    /// The last failure reported, shown even with the GUI hidden until dismissed.
+    fn error_notice(&mut self, ctx: &Context) {
+        let Some(error) = &self.error else {
+            return;
+        };
+        let mut dismissed = false;
+        egui::Window::new("Error")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_BOTTOM, vec2(0.0, -40.0))
+            .show(ctx, |ui| {
+                ui.colored_label(Color32::RED, error);
+                dismissed = ui.button("Dismiss").clicked();
+            });
+        if dismissed {
+            self.error = None;
+        }
+    }
+
+    /// Search field listing the commands matching what is typed, closed by Escape.
+    fn command_palette(&mut self, ctx: &Context, world: &mut World, window: &Window) {
+        let Some(palette) = &mut self.command_palette else {
+            return;
+        };
+        let (up, down, enter, escape) = ctx.input(|input| {
+            (
+                input.key_pressed(Key::ArrowUp),
+                input.key_pressed(Key::ArrowDown),
+                input.key_pressed(Key::Enter),
+                input.key_pressed(Key::Escape),
+            )
+        });
+        let mut chosen = None;
+        egui::Window::new("Command palette")
+            .title_bar(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_TOP, vec2(0.0, 40.0))
+            .fixed_size(vec2(360.0, 0.0))
+            .show(ctx, |ui| {
+                let response = ui.add(
+                    TextEdit::singleline(&mut palette.query)
+                        .hint_text("Type a command")
+                        .desired_width(f32::INFINITY),
+                );
+                response.request_focus();
+                if response.changed() {
+                    palette.selected = 0;
+                }
+                let matches = search(&palette.query);
+                let shown = matches.len().min(CommandPalette::MAX_SHOWN);
+                if down && palette.selected + 1 < shown {
+                    palette.selected += 1;
+                }
+                if up {
+                    palette.selected = palette.selected.saturating_sub(1);
+                }
+                ui.separator();
+                if matches.is_empty() {
+                    ui.weak("No matching command");
+                }
+                for (index, command) in matches.iter().take(shown).enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui.selectable_label(index == palette.selected, command.label()).clicked() {
+                            chosen = Some(*command);
+                        }
+                        if let Some(keys) = command.keys() {
+                            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                                ui.weak(keys);
+                            });
+                        }
+                    });
+                }
+                if enter {
+                    chosen = chosen.or(matches.get(palette.selected).copied());
+                }
+            });
+        if escape || chosen.is_some() {
+            self.command_palette = None;
+        }
+        if let Some(command) = chosen {
+            self.run(command, world, window, ctx.input(|input| input.time));
         }
     }
 
     /// Create the UI using egui.
-    fn ui(&mut self, ctx: &Context, world: &mut World) {
-        egui::TopBottomPanel::top("menubar_container").show(ctx, |ui| {
+    fn ui(&mut self, ctx: &Context, world: &mut World, window: &Window) {
+        let max_radius = world.width() as f32;
+        let defaults = WorldOption::new();
+        let time = ctx.input(|input| input.time);
+        self.fps_history.record(time, world.update_fps, world.draw_fps);
+
+        let shown = egui::TopBottomPanel::top("menubar_container").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 ui.menu_button("Setting", |ui| {
-                    if ui.button("Boid").clicked() {
-                        self.open_boid_window = true;
-                        ui.close_menu();
-                    } else if ui.button("Predator").clicked() {
-                        self.open_predator_window = true;
+                    if ui.button("Flocks").clicked() {
+                        self.open_flocks_window = true;
                         ui.close_menu();
                     } else if ui.button("Debug").clicked() {
                         self.open_debug_window = true;
                         ui.close_menu();
+                    } else if ui.button("Appearance").clicked() {
+                        self.open_appearance_window = true;
+                        ui.close_menu();
+                    } else if ui.button("Recording").clicked() {
+                        self.open_recording_window = true;
+                        ui.close_menu();
+                    } else if ui.button("Statistics").clicked() {
+                        self.open_statistics_window = true;
+                        ui.close_menu();
+                    } else if ui.button("Presets").clicked() {
+                        self.open_preset_window = true;
+                        ui.close_menu();
+                    } else if ui.button("Spatial index").clicked() {
+                        self.open_index_window = true;
+                        ui.close_menu();
+                    } else if ui.button("Animation").clicked() {
+                        self.open_animation_window = true;
+                        ui.close_menu();
+                    } else if ui.button("Timeline").clicked() {
+                        self.open_timeline_window = true;
+                        ui.close_menu();
+                    } else if ui.button("Obstacles").clicked() {
+                        self.open_obstacle_window = true;
+                        ui.close_menu();
+                    } else if ui.button("MIDI").clicked() {
+                        self.open_midi_window = true;
+                        ui.close_menu();
+                    } else if ui.button("A/B comparison").clicked() {
+                        self.open_compare_window = true;
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button("Reset all").on_hover_text("Restore every setting to its default").clicked() {
+                        self.reset_all(world);
+                        ui.close_menu();
+                    }
+                    if ui.button("Open config").on_hover_text(ConfigFile::PATH).clicked() {
+                        self.open_config(world, window);
+                        ui.close_menu();
+                    }
+                });
+                ui.menu_button("Edit", |ui| {
+                    if ui.add_enabled(world.can_undo(), Button::new("Undo (Ctrl+Z)")).clicked() {
+                        world.undo();
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(world.can_redo(), Button::new("Redo (Ctrl+Y)")).clicked() {
+                        world.redo();
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button("Save snapshot").on_hover_text(&self.snapshot_path).clicked() {
+                        self.save_snapshot(world);
+                        ui.close_menu();
+                    }
+                    if ui.button("Load snapshot").on_hover_text(&self.snapshot_path).clicked() {
+                        self.load_snapshot(world);
+                        ui.close_menu();
+                    }
+                    if ui.button("Load scenario").on_hover_text(&self.scenario_path).clicked() {
+                        self.load_scenario(world);
+                        ui.close_menu();
+                    }
+                });
+                ui.menu_button("View", |ui| {
+                    if ui.button("Fullscreen (F11)").clicked() {
+                        toggle_fullscreen(window);
+                        ui.close_menu();
+                    } else if ui.button("Borderless").clicked() {
+                        window.set_decorations(!window.is_decorated());
+                        ui.close_menu();
+                    } else if ui.button("Reset camera").clicked() {
+                        world.reset_camera();
+                        ui.close_menu();
+                    } else if ui.button("Screenshot (F12)").clicked() {
+                        self.screenshot_requested = true;
+                        ui.close_menu();
+                    } else if ui.button("Export SVG").clicked() {
+                        self.export_svg(world);
+                        ui.close_menu();
                     }
-                })
+                    ui.separator();
+                    ui.checkbox(&mut self.stats_window, "Stats window")
+                        .on_hover_text("Statistics and the inspector in a window of their own");
+                });
+                ui.menu_button("Scenarios", |ui| {
+                    for scenario in Scenario::ALL {
+                        if ui.button(scenario.name()).clicked() {
+                            self.apply_scenario(scenario, world);
+                            ui.close_menu();
+                        }
+                    }
+                    ui.separator();
+                    if ui.button("Surprise me").on_hover_text("Random rule weights and radii").clicked() {
+                        self.surprise(world, time);
+                        ui.close_menu();
+                    }
+                    ui.checkbox(&mut self.animate_surprise, "Animate changes");
+                });
+                ui.menu_button("Help", |ui| {
+                    if ui.button("Keyboard shortcuts").clicked() {
+                        self.open_help_window = true;
+                        ui.close_menu();
+                    } else if ui.button("Command palette (Ctrl+P)").clicked() {
+                        self.perform(Action::CommandPalette, world, window);
+                        ui.close_menu();
+                    }
+                });
+                ui.separator();
+                let label = if world.is_paused() { "Resume (Space)" } else { "Pause (Space)" };
+                if ui.button(label).clicked() {
+                    world.toggle_pause();
+                }
+                if ui.add_enabled(world.is_paused(), Button::new("Step")).clicked() {
+                    world.step_once();
+                }
+                ui.add(
+                    Slider::new(&mut world.option.simulation_speed, 1..=WorldOption::MAX_SIMULATION_SPEED)
+                        .text("Speed (+/-)"),
+                );
             });
         });
+        self.note_area(Some(shown.response.rect));
 
-        egui::Window::new("Boid")
-            .open(&mut self.open_boid_window)
+        let shown = egui::Window::new("Help")
+            .open(&mut self.open_help_window)
             .show(ctx, |ui| {
-                ui.add(Slider::new(&mut world.option.avoid_factor, 0.0..=1.0).text("Avoid factor"));
-                ui.add(Slider::new(&mut world.option.matching_factor, 0.0..=1.0).text("Matching factor"));
-                ui.add(Slider::new(&mut world.option.centering_factor, 0.0..=1.0).text("Centering factor"));
-                ui.add(Slider::new(&mut world.option.safe_radius, 0.0..=world.option.boid_vision_radius).text("Safe radius"));
-                ui.add(Slider::new(&mut world.option.boid_vision_radius, 0.0..=WIDTH as f32).text("Vision radius"));
-                ui.separator();
-                ui.add(Slider::new(&mut world.option.boid_max_speed, world.option.boid_min_speed..=100).text("Max speed"));
-                ui.add(Slider::new(&mut world.option.boid_min_speed, 0..=world.option.boid_max_speed).text("Min speed"));
-                ui.separator();
-                ui.add(Slider::new(&mut world.option.margin, 0..=500).text("Margin"));
-                ui.add(Slider::new(&mut world.option.turn_factor, 0..=30).text("Turn factor"));
-                ui.separator();
-                ui.add(Slider::new(&mut world.option.boid_view_angle, 0.0..=365.0).text("View angle"));
-                ui.add(Checkbox::new(&mut world.option.noise, "Add Noise"));
+                egui::Grid::new("shortcuts").striped(true).show(ui, |ui| {
+                    for action in Action::ALL {
+                        ui.monospace(action.keys());
+                        ui.label(action.description());
+                        ui.end_row();
+                    }
+                });
+            });
+        self.note_area(shown.map(|shown| shown.response.rect));
+
+        let shown = egui::TopBottomPanel::top("toolbar").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Left click:");
+                for tool in Tool::ALL {
+                    ui.selectable_value(&mut self.tool, tool, tool.name());
+                }
+                if matches!(self.tool, Tool::SpawnBoid | Tool::SpawnPredator) {
+                    ui.separator();
+                    ui.add(Slider::new(&mut self.spray_rate, 1.0..=500.0).logarithmic(true).text("Spray per second"));
+                }
+                if self.tool == Tool::Erase {
+                    ui.separator();
+                    ui.add(Slider::new(&mut self.erase_radius, 1.0..=100.0).text("Eraser radius"));
+                }
+            });
+        });
+        self.note_area(Some(shown.response.rect));
+
+        let selection_count = world.box_selection_count();
+        if selection_count > 0 {
+            let shown = egui::Window::new("Selection").show(ctx, |ui| {
+                ui.label(format!("{selection_count} selected"));
                 ui.with_layout(Layout::left_to_right(Align::TOP), |ui| {
-                    if ui.add(Button::new("Restart")).clicked() {
-                        world.restart();
+                    if ui.add(Button::new("Delete")).clicked() {
+                        world.delete_box_selection();
                     }
-                    if ui.add(Button::new("Clear")).clicked() {
-                        world.clear_all();
+                    if ui.add(Button::new("To predators")).clicked() {
+                        world.convert_box_selection_to_predators();
+                    }
+                });
+                ui.with_layout(Layout::left_to_right(Align::TOP), |ui| {
+                    if ui.add(Button::new("Freeze")).clicked() {
+                        world.freeze_box_selection(true);
+                    }
+                    if ui.add(Button::new("Unfreeze")).clicked() {
+                        world.freeze_box_selection(false);
                     }
                 });
+                ui.horizontal(|ui| {
+                    ui.color_edit_button_srgb(&mut self.selection_color);
+                    if ui.add(Button::new("Recolor")).clicked() {
+                        let [r, g, b] = self.selection_color;
+                        world.recolor_box_selection(Color::Rgba(r, g, b, 255));
+                    }
+                });
+                if ui.add(Button::new("Clear selection")).clicked() {
+                    world.clear_box_selection();
+                }
             });
+            self.note_area(shown.map(|shown| shown.response.rect));
+        }
 
-        egui::Window::new("Predator")
-            .open(&mut self.open_predator_window)
+        let shown = egui::Window::new("Flocks")
+            .open(&mut self.open_flocks_window)
             .show(ctx, |ui| {
-                ui.add(Slider::new(&mut world.option.fear_factor, 0.0..=1.0).text("Fear factor"));
-                ui.add(Slider::new(&mut world.option.fear_radius, 0.0..=WIDTH as f32).text("Fear radius"));
-                ui.separator();
-                ui.add(Slider::new(&mut world.option.predator_max_speed, world.option.predator_min_speed..=100).text("Max speed"));
-                ui.add(Slider::new(&mut world.option.predator_min_speed, 0..=world.option.predator_max_speed).text("Min speed"));
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut self.flock_tab, None, "Overview");
+                    for species in Species::ALL {
+                        ui.selectable_value(&mut self.flock_tab, Some(species), species.name());
+                    }
+                });
                 ui.separator();
-                ui.add(Slider::new(&mut world.option.predator_vision_radius, 0.0..=WIDTH as f32).text("Vision radius"));
-                ui.add(Slider::new(&mut world.option.predator_view_angle, 0.0..=365.0).text("View angle"));
+                if let Some(species) = self.flock_tab {
+                    ui.horizontal(|ui| {
+                        for other in Species::ALL.into_iter().filter(|other| *other != species) {
+                            if ui
+                                .button(format!("Copy from {}", other.name().to_lowercase()))
+                                .on_hover_text("Copy the speeds, vision, view angle and size")
+                                .clicked()
+                            {
+                                Movement::of(&world.option, other).apply(&mut world.option, species);
+                            }
+                        }
+                    });
+                    ui.separator();
+                }
+                match self.flock_tab {
+                    None => {
+                        let theme = world.option.palette.theme();
+                        let flocks = [
+                            (Species::Boid, theme.boid, world.boids_count(), world.option.boid_count),
+                            (Species::Predator, theme.predator, world.predators_count(), world.option.predator_count),
+                        ];
+                        egui::Grid::new("flocks_overview").striped(true).show(ui, |ui| {
+                            ui.label("");
+                            ui.label("Flock");
+                            ui.label("Count");
+                            ui.label("Target");
+                            ui.end_row();
+                            for (species, color, count, target) in flocks {
+                                let [r, g, b, _] = color.to_color_array();
+                                let (rect, _) = ui.allocate_exact_size(vec2(12.0, 12.0), Sense::hover());
+                                ui.painter().rect_filled(rect, 2.0, Color32::from_rgb(r, g, b));
+                                if ui.link(species.name()).clicked() {
+                                    self.flock_tab = Some(species);
+                                }
+                                ui.label(count.to_string());
+                                ui.label(target.to_string());
+                                ui.end_row();
+                            }
+                        });
+                        ui.separator();
+                        ui.collapsing("Boid steering rules", |ui| {
+                            let rules = world.rules_mut();
+                            let mut moved = None;
+                            let mut removed = None;
+                            for (index, rule) in rules.iter().enumerate() {
+                                ui.horizontal(|ui| {
+                                    ui.label(format!("{}. {}", index + 1, rule.name()));
+                                    if ui.add_enabled(index > 0, Button::new("⏶").small()).on_hover_text("Run earlier").clicked() {
+                                        moved = Some((index, index - 1));
+                                    }
+                                    if ui
+                                        .add_enabled(index + 1 < rules.len(), Button::new("⏷").small())
+                                        .on_hover_text("Run later")
+                                        .clicked()
+                                    {
+                                        moved = Some((index, index + 1));
+                                    }
+                                    if ui.add(Button::new("✖").small()).on_hover_text("Remove").clicked() {
+                                        removed = Some(index);
+                                    }
+                                });
+                            }
+                            if let Some((from, to)) = moved {
+                                rules.swap(from, to);
+                            }
+                            if let Some(index) = removed {
+                                rules.remove(index);
+                            }
+                            if ui.button("Restore built-in rules").clicked() {
+                                *rules = default_rules();
+                            }
+                            #[cfg(feature = "scripting")]
+                            {
+                                ui.separator();
+                                ui.label("Rhai script with a steer(boid, neighbors) function, reloaded on save");
+                                ui.text_edit_singleline(&mut self.script_path);
+                                ui.horizontal(|ui| {
+                                    if ui.button("Load script").clicked() {
+                                        if let Some(mut script) = self.script.take() {
+                                            script.unload(world);
+                                        }
+                                        let mut script = ScriptWatcher::new(self.script_path.clone().into());
+                                        script.poll(world);
+                                        self.script = Some(script);
+                                    }
+                                    if ui.add_enabled(self.script.is_some(), Button::new("Unload")).clicked() {
+                                        if let Some(mut script) = self.script.take() {
+                                            script.unload(world);
+                                        }
+                                    }
+                                });
+                                if let Some(script) = &self.script {
+                                    match script.error() {
+                                        Some(error) => {
+                                            ui.colored_label(Color32::RED, error);
+                                        }
+                                        None => {
+                                            ui.label(format!("Running {}", script.path().display()));
+                                        }
+                                    }
+                                }
+                            }
+                        });
+                    }
+                    Some(Species::Boid) => {
+                        ui.horizontal(|ui| {
+                            ui.add(Slider::new(&mut world.option.avoid_factor, 0.0..=1.0).text("Avoid factor"));
+                            reset_button(ui, &mut world.option.avoid_factor, defaults.avoid_factor);
+                        });
+                        ui.horizontal(|ui| {
+                            ui.add(Slider::new(&mut world.option.matching_factor, 0.0..=1.0).text("Matching factor"));
+                            reset_button(ui, &mut world.option.matching_factor, defaults.matching_factor);
+                        });
+                        ui.horizontal(|ui| {
+                            ui.add(Slider::new(&mut world.option.centering_factor, 0.0..=1.0).text("Centering factor"));
+                            reset_button(ui, &mut world.option.centering_factor, defaults.centering_factor);
+                        });
+                        ui.horizontal(|ui| {
+                            ui.add(Slider::new(&mut world.option.safe_radius, 0.0..=world.option.boid_vision_radius).text("Safe radius"));
+                            reset_button(ui, &mut world.option.safe_radius, defaults.safe_radius);
+                        });
+                        crowding_warning(ui, world);
+                        overlap_ui(ui, world, &mut self.avoid_tuner);
+                        ui.horizontal(|ui| {
+                            ui.add(Slider::new(&mut world.option.boid_vision_radius, 0.0..=max_radius).text("Vision radius"));
+                            reset_button(ui, &mut world.option.boid_vision_radius, defaults.boid_vision_radius);
+                        });
+                        ui.separator();
+                        ui.horizontal(|ui| {
+                            ui.add(Slider::new(&mut world.option.boid_max_speed, world.option.boid_min_speed..=100).text("Max speed"));
+                            reset_button(ui, &mut world.option.boid_max_speed, defaults.boid_max_speed);
+                        });
+                        ui.horizontal(|ui| {
+                            ui.add(Slider::new(&mut world.option.boid_min_speed, 0..=world.option.boid_max_speed).text("Min speed"));
+                            reset_button(ui, &mut world.option.boid_min_speed, defaults.boid_min_speed);
+                        });
+                        ui.separator();
+                        ui.horizontal(|ui| {
+                            ui.add(Slider::new(&mut world.option.margin, 0..=500).text("Margin"));
+                            reset_button(ui, &mut world.option.margin, defaults.margin);
+                        });
+                        ui.horizontal(|ui| {
+                            ui.add(Slider::new(&mut world.option.turn_factor, 0..=30).text("Turn factor"));
+                            reset_button(ui, &mut world.option.turn_factor, defaults.turn_factor);
+                        });
+                        ui.add(Checkbox::new(&mut world.option.avoid_gui, "Avoid GUI windows"))
+                            .on_hover_text("Flow around the open panels and windows like obstacles");
+                        ui.add(Checkbox::new(&mut world.option.unbounded, "Unbounded world")).on_hover_text(
+                            "No edges, the view follows the flock and stragglers left far behind wait where they are",
+                        );
+                        ui.separator();
+                        ui.horizontal(|ui| {
+                            ui.add(Slider::new(&mut world.option.boid_view_angle, 0.0..=365.0).text("View angle"));
+                            reset_button(ui, &mut world.option.boid_view_angle, defaults.boid_view_angle);
+                        });
+                        ui.add(Checkbox::new(&mut world.option.noise, "Add Noise"));
+                        ui.separator();
+                        ui.horizontal(|ui| {
+                            ui.add(Slider::new(&mut world.option.boid_size, 1..=20).text("Size"));
+                            reset_button(ui, &mut world.option.boid_size, defaults.boid_size);
+                        });
+                        ui.horizontal(|ui| {
+                            ui.add(Slider::new(&mut world.option.size_variation, 0.0..=0.9).text("Size variation"));
+                            reset_button(ui, &mut world.option.size_variation, defaults.size_variation);
+                        });
+                        ui.separator();
+                        ui.add(Checkbox::new(&mut world.option.live_population, "Live population"))
+                            .on_hover_text("Spawn or remove boids and predators while running to reach the counts below");
+                        let label = if world.option.live_population { "Target boids" } else { "Boids on restart" };
+                        ui.horizontal(|ui| {
+                            ui.add(Slider::new(&mut world.option.boid_count, 0..=20000).logarithmic(true).text(label));
+                            reset_button(ui, &mut world.option.boid_count, defaults.boid_count);
+                        });
+                    }
+                    Some(Species::Predator) => {
+                        ui.horizontal(|ui| {
+                            ui.add(Slider::new(&mut world.option.fear_factor, 0.0..=1.0).text("Fear factor"));
+                            reset_button(ui, &mut world.option.fear_factor, defaults.fear_factor);
+                        });
+                        ui.horizontal(|ui| {
+                            ui.add(Slider::new(&mut world.option.fear_radius, 0.0..=max_radius).text("Fear radius"));
+                            reset_button(ui, &mut world.option.fear_radius, defaults.fear_radius);
+                        });
+                        ui.separator();
+                        ui.horizontal(|ui| {
+                            ui.add(Slider::new(&mut world.option.predator_max_speed, world.option.predator_min_speed..=100).text("Max speed"));
+                            reset_button(ui, &mut world.option.predator_max_speed, defaults.predator_max_speed);
+                        });
+                        ui.horizontal(|ui| {
+                            ui.add(Slider::new(&mut world.option.predator_min_speed, 0..=world.option.predator_max_speed).text("Min speed"));
+                            reset_button(ui, &mut world.option.predator_min_speed, defaults.predator_min_speed);
+                        });
+                        ui.separator();
+                        ui.horizontal(|ui| {
+                            ui.add(Slider::new(&mut world.option.predator_vision_radius, 0.0..=max_radius).text("Vision radius"));
+                            reset_button(ui, &mut world.option.predator_vision_radius, defaults.predator_vision_radius);
+                        });
+                        ui.horizontal(|ui| {
+                            ui.add(Slider::new(&mut world.option.predator_view_angle, 0.0..=365.0).text("View angle"));
+                            reset_button(ui, &mut world.option.predator_view_angle, defaults.predator_view_angle);
+                        });
+                        ui.horizontal(|ui| {
+                            ui.add(Slider::new(&mut world.option.predator_size, 1..=40).text("Size"));
+                            reset_button(ui, &mut world.option.predator_size, defaults.predator_size);
+                        });
+                        ui.add(Checkbox::new(&mut world.option.predators_catch, "Catch boids"));
+                        ui.horizontal(|ui| {
+                            ui.add(Slider::new(&mut world.option.catch_radius, 0.0..=50.0).text("Catch radius"));
+                            reset_button(ui, &mut world.option.catch_radius, defaults.catch_radius);
+                        });
+                        ui.separator();
+                        let label = if world.option.live_population { "Target predators" } else { "Predators on restart" };
+                        ui.horizontal(|ui| {
+                            ui.add(Slider::new(&mut world.option.predator_count, 0..=500).logarithmic(true).text(label));
+                            reset_button(ui, &mut world.option.predator_count, defaults.predator_count);
+                        });
+                        ui.separator();
+                        ui.collapsing("Hunting", |ui| hunting_ui(ui, world));
+                    }
+                }
                 ui.separator();
                 ui.with_layout(Layout::left_to_right(Align::TOP), |ui| {
                     if ui.add(Button::new("Restart")).clicked() {
@@ -221,16 +1795,369 @@ impl Gui {
                     }
                 });
             });
+        self.note_area(shown.map(|shown| shown.response.rect));
+
+        let shown = egui::Window::new("Timeline")
+            .open(&mut self.open_timeline_window)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.add(Slider::new(&mut self.rewind.length, 1.0..=Rewind::MAX_LENGTH).text("Seconds kept"));
+                    reset_button(ui, &mut self.rewind.length, Self::REWIND_LENGTH);
+                });
+                if self.rewind.is_empty() {
+                    ui.label("Nothing kept yet, the world has not moved");
+                    return;
+                }
+                let last = self.rewind.len() - 1;
+                let shown = self.rewind.position().unwrap_or(last);
+                let mut index = shown;
+                // Jumping around would throw a replay off its inputs
+                ui.add_enabled_ui(!world.is_replaying(), |ui| {
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(index > 0, Button::new("⏴")).on_hover_text("Step back").clicked() {
+                            index -= 1;
+                        }
+                        ui.add(Slider::new(&mut index, 0..=last).show_value(false));
+                        if ui.add_enabled(index < last, Button::new("⏵")).on_hover_text("Step forward").clicked() {
+                            index += 1;
+                        }
+                    });
+                });
+                if index != shown {
+                    self.rewind.seek(index, world);
+                }
+                let tick = self.rewind.tick_at(index).unwrap_or_default();
+                ui.label(format!("Tick {tick}, {} of {} frames", index + 1, last + 1));
+                if self.rewind.position().is_some() {
+                    ui.horizontal(|ui| {
+                        if ui
+                            .button("Run from here")
+                            .on_hover_text("Later frames are dropped once the world runs on")
+                            .clicked()
+                            && world.is_paused()
+                        {
+                            world.toggle_pause();
+                        }
+                        if ui.button("Step").clicked() {
+                            world.step_once();
+                        }
+                    });
+                }
+            });
+        self.note_area(shown.map(|shown| shown.response.rect));
+
+        let shown = egui::Window::new("Animation")
+            .open(&mut self.open_animation_window)
+            .show(ctx, |ui| {
+                let mut removed = None;
+                // Values before the oscillators below are edited
+                let current = world.option.clone();
+                for (index, lfo) in world.option.lfos.iter_mut().enumerate() {
+                    ui.push_id(index, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.checkbox(&mut lfo.enabled, "");
+                            let parameter = lfo.parameter;
+                            egui::ComboBox::from_id_source("parameter")
+                                .selected_text(lfo.parameter.name())
+                                .show_ui(ui, |ui| {
+                                    for parameter in Parameter::ALL {
+                                        ui.selectable_value(&mut lfo.parameter, parameter, parameter.name());
+                                    }
+                                });
+                            if lfo.parameter != parameter {
+                                (lfo.min, lfo.max) = lfo.parameter.bounds_around(lfo.parameter.get(&current));
+                            }
+                            egui::ComboBox::from_id_source("waveform")
+                                .selected_text(lfo.waveform.name())
+                                .show_ui(ui, |ui| {
+                                    for waveform in Waveform::ALL {
+                                        ui.selectable_value(&mut lfo.waveform, waveform, waveform.name());
+                                    }
+                                });
+                            if ui.button("Remove").clicked() {
+                                removed = Some(index);
+                            }
+                        });
+                        let range = lfo.parameter.range();
+                        ui.add(Slider::new(&mut lfo.min, range.clone()).text("From"));
+                        ui.add(Slider::new(&mut lfo.max, range).text("To"));
+                        ui.add(Slider::new(&mut lfo.period, 1.0..=300.0).logarithmic(true).suffix(" s").text("Period"));
+                    });
+                    ui.separator();
+                }
+                if let Some(index) = removed {
+                    world.option.lfos.remove(index);
+                }
+                if ui.button("Add oscillator").clicked() {
+                    let lfo = Lfo::new(Parameter::BoidVisionRadius, &world.option);
+                    world.option.lfos.push(lfo);
+                }
+            });
+        self.note_area(shown.map(|shown| shown.response.rect));
+
+        let shown = egui::Window::new("MIDI")
+            .open(&mut self.open_midi_window)
+            .show(ctx, |ui| {
+                #[cfg(feature = "midi")]
+                {
+                    ui.horizontal(|ui| {
+                        if ui.button("Connect").on_hover_text("Listen to every MIDI input plugged in now").clicked() {
+                            self.midi_error = self.midi.connect().err();
+                        }
+                        match self.midi.port_names() {
+                            [] => ui.label("No inputs connected"),
+                            names => ui.label(names.join(", ")),
+                        };
+                    });
+                    if let Some(error) = &self.midi_error {
+                        ui.colored_label(Color32::RED, error);
+                    }
+                    let mut learning = self.midi.is_learning();
+                    if ui
+                        .checkbox(&mut learning, "Learn")
+                        .on_hover_text("Move a knob on the controller, then click the slider it should drive")
+                        .changed()
+                    {
+                        self.midi.set_learning(learning);
+                    }
+                    if learning {
+                        match self.midi.last_control() {
+                            Some((channel, controller)) => {
+                                ui.label(format!("Moved CC {controller} on channel {}", channel + 1))
+                            }
+                            None => ui.label("Move a knob on the controller"),
+                        };
+                    }
+                    ui.separator();
+                    for parameter in Parameter::ALL {
+                        ui.horizontal(|ui| {
+                            let mut value = parameter.get(&world.option);
+                            let slider = ui.add(Slider::new(&mut value, parameter.range()).text(parameter.name()));
+                            if slider.changed() {
+                                parameter.set(&mut world.option, value);
+                            }
+                            if learning && (slider.clicked() || slider.drag_started()) {
+                                self.midi.learn(parameter);
+                            }
+                            if let Some(MidiBinding { channel, controller, .. }) = self.midi.binding(parameter).copied() {
+                                ui.label(format!("CC {controller} ch {}", channel + 1));
+                                if ui.add(Button::new("✖").small()).on_hover_text("Unbind").clicked() {
+                                    self.midi.unbind(parameter);
+                                }
+                            }
+                        });
+                    }
+                }
+                #[cfg(not(feature = "midi"))]
+                ui.label("Built without MIDI support, run with `cargo run --features midi`");
+            });
+        self.note_area(shown.map(|shown| shown.response.rect));
+
+        let shown = egui::Window::new("Obstacles")
+            .open(&mut self.open_obstacle_window)
+            .show(ctx, |ui| scenery_ui(ui, world));
+        self.note_area(shown.map(|shown| shown.response.rect));
+
+        let shown = egui::Window::new("A/B comparison")
+            .open(&mut self.open_compare_window)
+            .show(ctx, |ui| {
+                let mut comparing = self.comparison.is_some();
+                if ui
+                    .checkbox(&mut comparing, "Split screen")
+                    .on_hover_text("Run a copy of the world with the settings below on the right half")
+                    .changed()
+                {
+                    self.comparison = comparing.then(|| Comparison::new(world));
+                }
+                let Some(comparison) = &mut self.comparison else {
+                    return;
+                };
+                ui.label("A on the left runs the main settings, B on the right the ones below.");
+                ui.horizontal(|ui| {
+                    if ui.button("Restart both").clicked() {
+                        world.restart();
+                        comparison.sync(world);
+                    }
+                    if ui.button("Copy A to B").clicked() {
+                        comparison.copy_options(&world.option);
+                    }
+                });
+                ui.separator();
+                for parameter in Parameter::ALL {
+                    ui.horizontal(|ui| {
+                        let mut value = parameter.get(&comparison.world.option);
+                        if ui.add(Slider::new(&mut value, parameter.range()).text(parameter.name())).changed() {
+                            parameter.set(&mut comparison.world.option, value);
+                        }
+                        let main = parameter.get(&world.option);
+                        if main != value {
+                            ui.weak(format!("A: {main:.2}"));
+                        }
+                    });
+                }
+            });
+        self.note_area(shown.map(|shown| shown.response.rect));
 
-        egui::Window::new("Debug")
+        let shown = egui::Window::new("Spatial index")
+            .open(&mut self.open_index_window)
+            .show(ctx, |ui| {
+                egui::ComboBox::from_label("Backend")
+                    .selected_text(world.option.index_backend.name())
+                    .show_ui(ui, |ui| {
+                        for backend in IndexBackend::ALL {
+                            ui.selectable_value(&mut world.option.index_backend, backend, backend.name());
+                        }
+                    });
+                match world.option.index_backend {
+                    IndexBackend::QuadTree => {
+                        ui.horizontal(|ui| {
+                            ui.add(Slider::new(&mut world.option.quad_tree_capacity, CapacityTuner::MIN_CAPACITY..=CapacityTuner::MAX_CAPACITY).text("Capacity"));
+                            reset_button(ui, &mut world.option.quad_tree_capacity, defaults.quad_tree_capacity);
+                        });
+                        ui.add(Checkbox::new(&mut world.option.auto_quad_tree_capacity, "Auto tune capacity"));
+                        ui.label(format!("Capacity in use: {}", world.quad_tree_capacity()));
+                        ui.horizontal(|ui| {
+                            ui.add(Slider::new(&mut world.option.quad_tree_max_depth, 1..=24).text("Max depth"));
+                            reset_button(ui, &mut world.option.quad_tree_max_depth, defaults.quad_tree_max_depth);
+                        });
+                    }
+                    IndexBackend::Grid => {
+                        ui.horizontal(|ui| {
+                            ui.add(Slider::new(&mut world.option.grid_cell_size, 5.0..=200.0).text("Cell size"));
+                            reset_button(ui, &mut world.option.grid_cell_size, defaults.grid_cell_size);
+                        });
+                    }
+                }
+                ui.separator();
+                let stats = world.index_stats();
+                egui::Grid::new("index_stats").show(ui, |ui| {
+                    ui.label("Nodes");
+                    ui.label(stats.nodes.to_string());
+                    ui.end_row();
+                    ui.label("Deepest leaf");
+                    ui.label(stats.deepest.to_string());
+                    ui.end_row();
+                    ui.label("Average depth");
+                    ui.label(format!("{:.2}", stats.average_depth));
+                    ui.end_row();
+                    ui.label("Boids per query");
+                    ui.label(format!("{:.1}", world.query_candidates()));
+                    ui.end_row();
+                    ui.label("Rules time per query");
+                    ui.label(format!("{:.2} µs", world.query_cost()));
+                    ui.end_row();
+                    if world.option.unbounded {
+                        let (loaded, parked_chunks) = world.chunk_counts();
+                        let (x, y) = world.origin();
+                        ui.label("Loaded chunks");
+                        ui.label(loaded.to_string());
+                        ui.end_row();
+                        ui.label("Parked boids");
+                        ui.label(format!("{} in {parked_chunks} chunks", world.parked_count()));
+                        ui.end_row();
+                        ui.label("Frame position");
+                        ui.label(format!("{x}, {y}"));
+                        ui.end_row();
+                    }
+                });
+            });
+        self.note_area(shown.map(|shown| shown.response.rect));
+
+        let shown = egui::Window::new("Debug")
             .open(&mut self.open_debug_window)
             .show(ctx, |ui| {
+                ui.add(Checkbox::new(&mut world.option.show_debug_overlays, "Show debug overlays (D)"));
+                ui.separator();
                 ui.add(Checkbox::new(&mut world.option.show_quad_tree, "Show quad tree"));
+                ui.add_enabled_ui(world.option.show_quad_tree, |ui| {
+                    ui.indent("quad_tree_options", |ui| {
+                        ui.add(Checkbox::new(&mut world.option.show_quad_tree_occupancy, "Color by occupancy"));
+                        ui.add(Checkbox::new(&mut world.option.show_quad_tree_counts, "Show counts"));
+                    });
+                });
                 ui.add(Checkbox::new(&mut world.option.show_safe_radius, "Show safe radius"));
-                ui.add(Checkbox::new(&mut world.option.show_vision_radius, "Show vision radius"));
+                ui.add(Checkbox::new(&mut world.option.show_vision_radius, "Show vision cone"));
                 ui.add(Checkbox::new(&mut world.option.show_facing_direction_with_speed, "Show facing direction with speed"));
+                ui.horizontal(|ui| {
+                    ui.add(Slider::new(&mut world.option.debug_line_width, 1.0..=5.0).text("Line width"));
+                    reset_button(ui, &mut world.option.debug_line_width, defaults.debug_line_width);
+                });
+                ui.add(Checkbox::new(&mut world.option.show_timing_overlay, "Show timing overlay"));
+                ui.horizontal(|ui| {
+                    ui.add(Slider::new(&mut world.option.trajectory_length, 2..=500).text("Trajectory length"));
+                    reset_button(ui, &mut world.option.trajectory_length, defaults.trajectory_length);
+                });
+                ui.add(Checkbox::new(&mut world.option.show_selected_neighbors, "Show neighbors of selected boid"))
+                    .on_hover_text("Colored by the palette's separation, alignment and cohesion colors");
+                ui.add(Checkbox::new(&mut world.option.show_flock_hulls, "Show flock outlines"))
+                    .on_hover_text("Boids linked by chains of neighbors within vision radius make one flock");
+                egui::ComboBox::from_label("Rule forces")
+                    .selected_text(world.option.force_arrows.name())
+                    .show_ui(ui, |ui| {
+                        for arrows in ForceArrows::ALL {
+                            ui.selectable_value(&mut world.option.force_arrows, arrows, arrows.name());
+                        }
+                    })
+                    .response
+                    .on_hover_text("Arrows of what separation, alignment, cohesion and fear changed in the velocity this tick, in the palette's colors, fear in the predator color");
+                ui.add_enabled_ui(world.option.force_arrows != ForceArrows::Off, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.add(Slider::new(&mut world.option.force_arrow_scale, 1.0..=20.0).text("Arrow scale"));
+                        reset_button(ui, &mut world.option.force_arrow_scale, defaults.force_arrow_scale);
+                    });
+                });
+                ui.separator();
+                self.fps_history.ui(ui);
+                ui.add(Checkbox::new(&mut world.option.auto_population, "Auto population"));
+                ui.horizontal(|ui| {
+                    ui.add(Slider::new(&mut world.option.target_fps, 10..=240).text("Target FPS"));
+                    reset_button(ui, &mut world.option.target_fps, defaults.target_fps);
+                });
+                ui.label(format!("Boids: {}", world.boids_count()));
+                ui.add(Checkbox::new(&mut world.option.limit_fps, "Limit FPS"));
+                ui.horizontal(|ui| {
+                    ui.add(Slider::new(&mut world.option.max_fps, 1..=240).text("Max FPS"));
+                    reset_button(ui, &mut world.option.max_fps, defaults.max_fps);
+                });
+                ui.add(Checkbox::new(&mut world.option.vsync, "VSync"));
+                ui.add(Checkbox::new(&mut world.option.pause_in_background, "Pause in background"))
+                    .on_hover_text("Stop updating and drawing while the window is unfocused or minimized, to save battery");
+                ui.add(Checkbox::new(&mut world.option.gpu_boids, "Draw boids on the GPU"))
+                    .on_hover_text("Much faster for large flocks. Boids leave no trails this way");
                 ui.separator();
-                ui.label(format!("FPS: {}", min(world.draw_fps as u16, world.update_fps as u16)));
+                ui.horizontal(|ui| {
+                    ui.label(format!("Tick {}", world.tick()));
+                    ui.label(format!("Seed {}", world.seed()));
+                    ui.add(TextEdit::singleline(&mut self.seed_text).hint_text("New seed").desired_width(140.0));
+                    if ui
+                        .button("Restart")
+                        .on_hover_text("Restart from the typed seed, or replay this run if it is empty")
+                        .clicked()
+                    {
+                        let text = self.seed_text.trim();
+                        let seed = if text.is_empty() { Ok(world.seed()) } else { text.parse() };
+                        match seed {
+                            Ok(seed) => {
+                                world.set_seed(seed);
+                                world.restart();
+                                self.seed_error = None;
+                            }
+                            Err(_) => self.seed_error = Some(format!("{text} is not a whole number")),
+                        }
+                    }
+                });
+                if let Some(error) = &self.seed_error {
+                    ui.colored_label(Color32::RED, error);
+                }
+                ui.collapsing("Profiler", |ui| {
+                    egui::Grid::new("profiler_grid").striped(true).show(ui, |ui| {
+                        for stage in Stage::ALL {
+                            ui.label(stage.name());
+                            ui.label(format!("{:.2} ms", world.profiler.milliseconds(stage)));
+                            ui.end_row();
+                        }
+                    });
+                });
                 ui.with_layout(Layout::left_to_right(Align::TOP), |ui| {
                     if ui.add(Button::new("Restart")).clicked() {
                         world.restart();
@@ -240,5 +2167,352 @@ impl Gui {
                     }
                 });
             });
+        self.note_area(shown.map(|shown| shown.response.rect));
+
+        let shown = egui::Window::new("Appearance")
+            .open(&mut self.open_appearance_window)
+            .show(ctx, |ui| {
+                egui::ComboBox::from_label("Palette")
+                    .selected_text(world.option.palette.name())
+                    .show_ui(ui, |ui| {
+                        for palette in Palette::ALL {
+                            ui.selectable_value(&mut world.option.palette, palette, palette.name());
+                        }
+                    });
+                egui::ComboBox::from_label("Background")
+                    .selected_text(world.option.background_style.name())
+                    .show_ui(ui, |ui| {
+                        for style in BackgroundStyle::ALL {
+                            ui.selectable_value(&mut world.option.background_style, style, style.name());
+                        }
+                    });
+                match world.option.background_style {
+                    BackgroundStyle::Solid => {}
+                    BackgroundStyle::Gradient => {
+                        ui.horizontal(|ui| {
+                            ui.color_edit_button_srgb(&mut world.option.gradient_top);
+                            ui.label("Top");
+                            ui.color_edit_button_srgb(&mut world.option.gradient_bottom);
+                            ui.label("Bottom");
+                        });
+                    }
+                    BackgroundStyle::Grid => {
+                        ui.horizontal(|ui| {
+                            ui.add(Slider::new(&mut world.option.grid_spacing, 5..=200).text("Grid spacing"));
+                            reset_button(ui, &mut world.option.grid_spacing, defaults.grid_spacing);
+                        });
+                    }
+                    BackgroundStyle::Image => {
+                        ui.horizontal(|ui| {
+                            ui.text_edit_singleline(&mut self.background_image_path);
+                            if ui.add(Button::new("Load")).clicked() {
+                                self.background_image_error = world
+                                    .load_background_image(Path::new(&self.background_image_path))
+                                    .err()
+                                    .map(|error| error.to_string());
+                            }
+                        });
+                        if let Some(error) = &self.background_image_error {
+                            ui.colored_label(Color32::RED, error);
+                        }
+                    }
+                }
+                ui.separator();
+                ui.add(Checkbox::new(&mut world.option.show_trails, "Show trails"));
+                ui.horizontal(|ui| {
+                    ui.add(Slider::new(&mut world.option.trail_decay, 0.0..=0.99).text("Trail decay"));
+                    reset_button(ui, &mut world.option.trail_decay, defaults.trail_decay);
+                });
+                ui.add(Checkbox::new(&mut world.option.high_quality_rendering, "High quality rendering"));
+                ui.add(Checkbox::new(&mut world.option.show_catch_particles, "Show catch particles"));
+                ui.add(Checkbox::new(&mut world.option.show_minimap, "Show minimap when zoomed in"));
+                egui::ComboBox::from_label("Color mode")
+                    .selected_text(world.option.color_mode.name())
+                    .show_ui(ui, |ui| {
+                        for mode in ColorMode::ALL {
+                            ui.selectable_value(&mut world.option.color_mode, mode, mode.name());
+                        }
+                    });
+                ui.horizontal(|ui| {
+                    ui.add(Slider::new(&mut world.option.heading_smoothing, 0.0..=0.95).text("Heading smoothing"))
+                        .on_hover_text("Keeps bodies from flickering when their velocity swings between ticks");
+                    reset_button(ui, &mut world.option.heading_smoothing, defaults.heading_smoothing);
+                });
+            });
+        self.note_area(shown.map(|shown| shown.response.rect));
+
+        // Shown in the stats window instead while it is open
+        if !self.stats_window {
+            let shown = egui::Window::new("Statistics")
+                .open(&mut self.open_statistics_window)
+                .show(ctx, |ui| {
+                    statistics_ui(ui, &mut self.statistics, &mut self.statistics_export, world);
+                    ui.collapsing("Selected boid", |ui| inspector_ui(ui, world));
+                });
+            self.note_area(shown.map(|shown| shown.response.rect));
+        }
+
+        let shown = egui::Window::new("Presets")
+            .open(&mut self.open_preset_window)
+            .show(ctx, |ui| {
+                ui.label("All settings and spawn counts, as a RON file");
+                ui.text_edit_singleline(&mut self.preset_path);
+                ui.with_layout(Layout::left_to_right(Align::TOP), |ui| {
+                    if ui.add(Button::new("Save")).clicked() {
+                        self.preset_status = Some(save_preset_file(&self.preset_path, world));
+                    }
+                    if ui.add(Button::new("Load")).clicked() {
+                        let status = load_preset_file(&self.preset_path, world);
+                        if status.is_ok() {
+                            self.rule_transition = None;
+                        }
+                        self.preset_status = Some(status);
+                    }
+                });
+                if ui
+                    .button(format!("Open {}", ConfigFile::PATH))
+                    .on_hover_text("Apply the settings and window size written in the config file")
+                    .clicked()
+                {
+                    let status = open_config_file(world, window);
+                    if status.is_ok() {
+                        self.rule_transition = None;
+                    }
+                    self.preset_status = Some(status);
+                }
+                ui.separator();
+                ui.label("Snapshot of the whole world, every boid, the scenery and the settings");
+                ui.text_edit_singleline(&mut self.snapshot_path);
+                ui.with_layout(Layout::left_to_right(Align::TOP), |ui| {
+                    if ui.add(Button::new("Save snapshot")).clicked() {
+                        self.preset_status = Some(save_snapshot_file(&self.snapshot_path, world));
+                    }
+                    if ui.add(Button::new("Load snapshot")).clicked() {
+                        let status = load_snapshot_file(&self.snapshot_path, world);
+                        if status.is_ok() {
+                            self.rule_transition = None;
+                        }
+                        self.preset_status = Some(status);
+                    }
+                });
+                ui.separator();
+                ui.label("Scenario to share a demo, the settings, the scenery and groups to spawn");
+                ui.text_edit_singleline(&mut self.scenario_path);
+                ui.with_layout(Layout::left_to_right(Align::TOP), |ui| {
+                    if ui
+                        .add(Button::new("Save scenario"))
+                        .on_hover_text("The settings and scenery, spawn groups are written by hand")
+                        .clicked()
+                    {
+                        self.preset_status = Some(save_scenario_file(&self.scenario_path, world));
+                    }
+                    if ui.add(Button::new("Load scenario")).clicked() {
+                        let status = load_scenario_file(&self.scenario_path, world);
+                        if status.is_ok() {
+                            self.rule_transition = None;
+                        }
+                        self.preset_status = Some(status);
+                    }
+                });
+                match &self.preset_status {
+                    Some(Ok(message)) => {
+                        ui.label(message);
+                    }
+                    Some(Err(error)) => {
+                        ui.colored_label(Color32::RED, error);
+                    }
+                    None => {}
+                }
+            });
+        self.note_area(shown.map(|shown| shown.response.rect));
+
+        let shown = egui::Window::new("Recording")
+            .open(&mut self.open_recording_window)
+            .show(ctx, |ui| {
+                ui.label("GIF");
+                ui.add_enabled_ui(!self.recording_gif, |ui| {
+                    ui.add(Slider::new(&mut self.gif_settings.scale, 1..=4).text("Downscale"));
+                    ui.add(Slider::new(&mut self.gif_settings.frame_step, 1..=10).text("Keep every n frames"));
+                });
+                let label = if self.recording_gif { "Stop GIF" } else { "Start GIF" };
+                if ui.add(Button::new(label)).clicked() {
+                    self.recording_gif = !self.recording_gif;
+                }
+                ui.separator();
+                ui.checkbox(&mut self.instant_replay, "Instant replay");
+                ui.add_enabled_ui(self.instant_replay, |ui| {
+                    ui.add(Slider::new(&mut self.instant_replay_seconds, 5..=30).text("Seconds kept"));
+                    let label = format!("Save last {} seconds (F10)", self.instant_replay_seconds);
+                    if ui.button(label).clicked() {
+                        self.instant_replay_requested = true;
+                    }
+                });
+                ui.separator();
+                ui.label("Video (requires ffmpeg)");
+                ui.add_enabled_ui(!self.recording_video, |ui| {
+                    egui::ComboBox::from_label("Format")
+                        .selected_text(self.video_settings.format.name())
+                        .show_ui(ui, |ui| {
+                            for format in VideoFormat::ALL {
+                                ui.selectable_value(&mut self.video_settings.format, format, format.name());
+                            }
+                        });
+                    egui::ComboBox::from_label("Resolution")
+                        .selected_text(self.video_settings.resolution.name())
+                        .show_ui(ui, |ui| {
+                            for resolution in VideoResolution::ALL {
+                                ui.selectable_value(&mut self.video_settings.resolution, resolution, resolution.name());
+                            }
+                        });
+                    ui.add(Slider::new(&mut self.video_settings.frame_rate, 10..=60).text("Frame rate"));
+                });
+                let label = if self.recording_video { "Stop video" } else { "Start video" };
+                if ui.add(Button::new(label)).clicked() {
+                    self.recording_video = !self.recording_video;
+                    self.video_error = None;
+                }
+                if let Some(error) = &self.video_error {
+                    ui.colored_label(Color32::RED, error);
+                }
+                ui.separator();
+                ui.label("Inputs, replayed exactly from the world they started in");
+                ui.text_edit_singleline(&mut self.replay_path);
+                ui.with_layout(Layout::left_to_right(Align::TOP), |ui| {
+                    let label = if world.is_recording() { "Stop and save" } else { "Record inputs" };
+                    if ui.add_enabled(!world.is_replaying(), Button::new(label)).clicked() {
+                        self.replay_status = Some(toggle_input_recording(&self.replay_path, world));
+                    }
+                    if world.is_replaying() {
+                        if ui.button("Stop replay").clicked() {
+                            world.stop_replay();
+                            self.replay_status = Some(Ok("Replay stopped".to_string()));
+                        }
+                    } else if ui.add_enabled(!world.is_recording(), Button::new("Play replay")).clicked() {
+                        self.replay_status = Some(play_replay_file(&self.replay_path, world, window));
+                    }
+                });
+                if let Some(inputs) = world.recorded_inputs() {
+                    ui.label(format!("Recording, {inputs} inputs so far"));
+                } else if let Some((played, ticks)) = world.replay_progress() {
+                    ui.add(egui::ProgressBar::new(played as f32 / ticks.max(1) as f32).text(format!("Tick {played} of {ticks}")));
+                }
+                match &self.replay_status {
+                    Some(Ok(message)) => {
+                        ui.label(message);
+                    }
+                    Some(Err(error)) => {
+                        ui.colored_label(Color32::RED, error);
+                    }
+                    None => {}
+                }
+            });
+        self.note_area(shown.map(|shown| shown.response.rect));
+    }
+}
+
+/// Start recording inputs, or stop and save them to `path`, giving what to show in the Recording window.
+fn toggle_input_recording(path: &str, world: &mut World) -> Result<String, String> {
+    let Some(replay) = world.stop_recording() else {
+        if world.is_replaying() {
+            return Err("Cannot record while a replay is playing".to_string());
+        }
+        world.start_recording();
+        return Ok("Recording inputs".to_string());
+    };
+    let path = Path::new(path);
+    replay
+        .save(path)
+        .map(|()| format!("Saved {} ticks to {}", replay.ticks(), path.display()))
+        .map_err(|error| error.to_string())
+}
+
+/// Play the replay saved in `path`, the window takes the size it was recorded at.
+fn play_replay_file(path: &str, world: &mut World, window: &Window) -> Result<String, String> {
+    if world.is_recording() {
+        return Err("Stop recording inputs before playing a replay".to_string());
+    }
+    let path = Path::new(path);
+    let replay = Replay::load(path).map_err(|error| error.to_string())?;
+    let message = format!("Playing {} ticks from {}", replay.ticks(), path.display());
+    let (width, height) = replay.size();
+    window.set_inner_size(PhysicalSize::new(width as u32, height as u32));
+    world.start_replay(replay);
+    Ok(message)
+}
+
+/// Save the settings to `path`, giving what to show in the Presets window.
+fn save_preset_file(path: &str, world: &World) -> Result<String, String> {
+    let path = Path::new(path);
+    save_preset(&world.option, path)
+        .map(|()| format!("Saved to {}", path.display()))
+        .map_err(|error| error.to_string())
+}
+
+/// Load the settings from `path`, giving what to show in the Presets window.
+fn load_preset_file(path: &str, world: &mut World) -> Result<String, String> {
+    let path = Path::new(path);
+    load_preset(path)
+        .map(|option| {
+            world.option = option;
+            format!("Loaded {}", path.display())
+        })
+        .map_err(|error| error.to_string())
+}
+
+/// Save the whole world to `path`, giving what to show in the Presets window.
+fn save_snapshot_file(path: &str, world: &World) -> Result<String, String> {
+    let path = Path::new(path);
+    world
+        .snapshot()
+        .save(path)
+        .map(|()| format!("Saved tick {} to {}", world.tick(), path.display()))
+        .map_err(|error| error.to_string())
+}
+
+/// Resume the world saved in `path`, giving what to show in the Presets window.
+fn load_snapshot_file(path: &str, world: &mut World) -> Result<String, String> {
+    let path = Path::new(path);
+    let snapshot = Snapshot::load(path).map_err(|error| error.to_string())?;
+    let message = format!(
+        "Resumed {} boids at tick {} from {}",
+        snapshot.boids_count(),
+        snapshot.tick(),
+        path.display()
+    );
+    world.restore(snapshot);
+    Ok(message)
+}
+
+fn save_scenario_file(path: &str, world: &World) -> Result<String, String> {
+    let path = Path::new(path);
+    ScenarioFile::of(world)
+        .save(path)
+        .map(|()| format!("Saved the settings and scenery to {}", path.display()))
+        .map_err(|error| error.to_string())
+}
+
+/// Start over from the scenario in `path`, giving what to show in the Presets window.
+fn load_scenario_file(path: &str, world: &mut World) -> Result<String, String> {
+    let path = Path::new(path);
+    let scenario = ScenarioFile::load(path).map_err(|error| error.to_string())?;
+    world.load_scenario(&scenario);
+    let name = match scenario.name.as_str() {
+        "" => path.display().to_string(),
+        name => name.to_string(),
+    };
+    let mut message = format!("Loaded {name}, {} spawned in groups", scenario.spawn_count());
+    if !scenario.description.is_empty() {
+        message = format!("{message}\n{}", scenario.description);
+    }
+    Ok(message)
+}
+
+/// Apply `boids.toml` to the world and the window, giving what to show in the Presets window.
+fn open_config_file(world: &mut World, window: &Window) -> Result<String, String> {
+    let file = ConfigFile::load()?.ok_or_else(|| format!("No {} in the working directory", ConfigFile::PATH))?;
+    if let Some(option) = &file.simulation {
+        world.option = option.clone();
     }
+    file.apply_to_window(window);
+    Ok(format!("Loaded {}", ConfigFile::PATH))
 }
@@ -0,0 +1,132 @@
+use crate::boid::Boid;
+use crate::obstacle::{Attractor, Obstacle};
+use crate::WorldOption;
+
+/// Reverts one change to the world. Applying it gives back the edit redoing the change.
+#[derive(Default)]
+pub(crate) struct Edit {
+    pub option: Option<Box<WorldOption>>,
+    // Boids and predators taken out of the world by id
+    pub take: Vec<Boid>,
+    // Put back into the world as they were
+    pub put: Vec<Boid>,
+    pub scenery: Option<(Vec<Obstacle>, Vec<Attractor>)>,
+}
+
+impl Edit {
+    fn is_empty(&self) -> bool {
+        self.option.is_none() && self.take.is_empty() && self.put.is_empty() && self.scenery.is_none()
+    }
+}
+
+/// Undo and redo stacks of the changes made by the user.
+///
+/// Changes are gathered into a pending edit until `commit`, so a whole drag or a restart
+/// is undone at once.
+pub struct History {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    pending: Edit,
+    // Options as of the last commit, compared against to notice slider changes
+    committed_option: WorldOption,
+}
+
+impl History {
+    // Oldest edits are forgotten past this
+    const MAX_EDITS: usize = 100;
+
+    pub fn new(option: WorldOption) -> Self {
+        Self {
+            undo: vec![],
+            redo: vec![],
+            pending: Edit::default(),
+            committed_option: option,
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty() || !self.pending.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Forget every edit, `option` becomes the starting point.
+    pub fn clear(&mut self, option: WorldOption) {
+        *self = Self::new(option);
+    }
+
+    pub(crate) fn record_added(&mut self, boid: &Boid) {
+        self.pending.take.push(boid.clone());
+    }
+
+    pub(crate) fn record_removed(&mut self, boid: &Boid) {
+        let taken = &mut self.pending.take;
+        // Added and removed within the same edit, there is nothing to undo
+        if let Some(index) = taken.iter().position(|other| other.id() == boid.id()) {
+            taken.swap_remove(index);
+            return;
+        }
+        self.pending.put.push(boid.clone());
+    }
+
+    /// Remember the obstacles and attractors before their first change in this edit.
+    pub(crate) fn record_scenery(&mut self, obstacles: &[Obstacle], attractors: &[Attractor]) {
+        if self.pending.scenery.is_none() {
+            self.pending.scenery = Some((obstacles.to_vec(), attractors.to_vec()));
+        }
+    }
+
+    /// Close the pending edit, adding the option changes made since the last commit.
+    /// Returns false when there was nothing to close.
+    pub(crate) fn commit(&mut self, option: &WorldOption) -> bool {
+        if *option != self.committed_option {
+            let previous = std::mem::replace(&mut self.committed_option, option.clone());
+            self.pending.option.get_or_insert(Box::new(previous));
+        }
+        if self.pending.is_empty() {
+            return false;
+        }
+        let edit = std::mem::take(&mut self.pending);
+        self.push_undo(edit);
+        self.redo.clear();
+        true
+    }
+
+    pub(crate) fn pop_undo(&mut self) -> Option<Edit> {
+        self.undo.pop()
+    }
+
+    pub(crate) fn pop_redo(&mut self) -> Option<Edit> {
+        self.redo.pop()
+    }
+
+    pub(crate) fn push_undo(&mut self, edit: Edit) {
+        if self.undo.len() == Self::MAX_EDITS {
+            self.undo.remove(0);
+        }
+        self.undo.push(edit);
+    }
+
+    pub(crate) fn push_redo(&mut self, edit: Edit) {
+        self.redo.push(edit);
+    }
+
+    /// Make the same change to the committed options, so it is not recorded.
+    pub(crate) fn follow(&mut self, change: impl FnOnce(&mut WorldOption)) {
+        change(&mut self.committed_option);
+    }
+
+    /// Options restored by undo or redo are not a new change.
+    pub(crate) fn set_committed_option(&mut self, option: &WorldOption) {
+        self.committed_option = option.clone();
+    }
+}
+
+// The update thread never edits, so clones start with an empty history
+impl Clone for History {
+    fn clone(&self) -> Self {
+        Self::new(self.committed_option.clone())
+    }
+}
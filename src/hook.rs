@@ -0,0 +1,139 @@
+use std::sync::Arc;
+
+use crate::boid::Boid;
+use crate::preset::Species;
+use crate::World;
+
+type BoidHook = Arc<dyn Fn(&Boid, Species) + Send + Sync>;
+type CatchHook = Arc<dyn Fn(&Boid, &Boid) + Send + Sync>;
+type TickHook = Arc<dyn Fn(&World) + Send + Sync>;
+
+/// Callbacks registered with `World::on_spawn`, `on_despawn`, `on_catch` and
+/// `on_tick_complete`. Shared by the copies of a world, but a copy updated elsewhere holds
+/// its calls back until `World::apply_update` takes the update.
+#[derive(Default)]
+pub(crate) struct Hooks {
+    spawn: Vec<BoidHook>,
+    despawn: Vec<BoidHook>,
+    catch: Vec<CatchHook>,
+    tick_complete: Vec<TickHook>,
+    // Calls held back in order, see `defer`
+    deferred: Option<Vec<Call>>,
+}
+
+pub(crate) enum Call {
+    Spawn(Boid, Species),
+    Despawn(Boid, Species),
+    Catch(Boid, Boid),
+    // The world as the tick ended, or none for the world the update is merged into
+    TickComplete(Option<Box<World>>),
+}
+
+impl Clone for Hooks {
+    // Calls held back stay with the world that made them
+    fn clone(&self) -> Self {
+        Self {
+            spawn: self.spawn.clone(),
+            despawn: self.despawn.clone(),
+            catch: self.catch.clone(),
+            tick_complete: self.tick_complete.clone(),
+            deferred: None,
+        }
+    }
+}
+
+impl Hooks {
+    pub(crate) fn add_spawn(&mut self, hook: BoidHook) {
+        self.spawn.push(hook);
+    }
+
+    pub(crate) fn add_despawn(&mut self, hook: BoidHook) {
+        self.despawn.push(hook);
+    }
+
+    pub(crate) fn add_catch(&mut self, hook: CatchHook) {
+        self.catch.push(hook);
+    }
+
+    pub(crate) fn add_tick_complete(&mut self, hook: TickHook) {
+        self.tick_complete.push(hook);
+    }
+
+    /// Hold every call back from now on, for `replay` once the update is taken.
+    pub(crate) fn defer(&mut self) {
+        self.deferred = Some(vec![]);
+    }
+
+    pub(crate) fn spawned(&mut self, boid: &Boid, species: Species) {
+        match &mut self.deferred {
+            Some(calls) if !self.spawn.is_empty() => calls.push(Call::Spawn(boid.clone(), species)),
+            Some(_) => {}
+            None => self.spawn.iter().for_each(|hook| hook(boid, species)),
+        }
+    }
+
+    pub(crate) fn despawned(&mut self, boid: &Boid, species: Species) {
+        match &mut self.deferred {
+            Some(calls) if !self.despawn.is_empty() => calls.push(Call::Despawn(boid.clone(), species)),
+            Some(_) => {}
+            None => self.despawn.iter().for_each(|hook| hook(boid, species)),
+        }
+    }
+
+    pub(crate) fn caught(&mut self, predator: &Boid, boid: &Boid) {
+        match &mut self.deferred {
+            Some(calls) if !self.catch.is_empty() => calls.push(Call::Catch(predator.clone(), boid.clone())),
+            Some(_) => {}
+            None => self.catch.iter().for_each(|hook| hook(predator, boid)),
+        }
+    }
+
+    pub(crate) fn tick_completed(&self, world: &World) {
+        for hook in &self.tick_complete {
+            hook(world);
+        }
+    }
+
+    /// Hold back the tick hooks of the update that just ended, false if they are not held back.
+    pub(crate) fn defer_tick(&mut self) -> bool {
+        match &mut self.deferred {
+            Some(calls) => {
+                if !self.tick_complete.is_empty() {
+                    calls.push(Call::TickComplete(None));
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether the tick held back last still needs the world it ended with, as another
+    /// update is about to change it.
+    pub(crate) fn needs_world(&self) -> bool {
+        matches!(self.deferred.as_deref(), Some([.., Call::TickComplete(None)]))
+    }
+
+    pub(crate) fn keep_world(&mut self, world: World) {
+        if let Some([.., Call::TickComplete(ended)]) = self.deferred.as_deref_mut() {
+            *ended = Some(Box::new(world));
+        }
+    }
+
+    /// The calls held back so far.
+    pub(crate) fn take_deferred(&mut self) -> Vec<Call> {
+        self.deferred.take().unwrap_or_default()
+    }
+
+    /// Make the calls held back by a copy, in the order it made them. Ticks without a world
+    /// of their own see `world`.
+    pub(crate) fn replay(&self, calls: Vec<Call>, world: &World) {
+        for call in calls {
+            match call {
+                Call::Spawn(boid, species) => self.spawn.iter().for_each(|hook| hook(&boid, species)),
+                Call::Despawn(boid, species) => self.despawn.iter().for_each(|hook| hook(&boid, species)),
+                Call::Catch(predator, boid) => self.catch.iter().for_each(|hook| hook(&predator, &boid)),
+                Call::TickComplete(ended) => self.tick_completed(ended.as_deref().unwrap_or(world)),
+            }
+        }
+    }
+}
@@ -0,0 +1,44 @@
+/// How one predator hunted since it spawned, see `World::hunt`.
+///
+/// A chase starts when the predator sees a boid and lasts as long as it sees one, catches
+/// included.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct HuntStats {
+    pub chases: u32,
+    pub catches: u32,
+    /// Pixels flown
+    pub distance: f32,
+    // Ticks of the chases that ended, and how many ended
+    chase_ticks: u64,
+    chases_ended: u32,
+    // Tick the chase going on started at
+    chasing_since: Option<u64>,
+}
+
+impl HuntStats {
+    pub fn is_chasing(&self) -> bool {
+        self.chasing_since.is_some()
+    }
+
+    /// Mean length in ticks of the chases that ended, `None` before the first one ends.
+    pub fn mean_chase_ticks(&self) -> Option<f32> {
+        (self.chases_ended > 0).then(|| self.chase_ticks as f32 / self.chases_ended as f32)
+    }
+
+    /// Count an update at `tick` where the predator flew `distance`, seeing boids or not.
+    pub fn record(&mut self, tick: u64, chasing: bool, distance: f32) {
+        self.distance += distance;
+        match (self.chasing_since, chasing) {
+            (None, true) => {
+                self.chases += 1;
+                self.chasing_since = Some(tick);
+            }
+            (Some(since), false) => {
+                self.chase_ticks += tick - since;
+                self.chases_ended += 1;
+                self.chasing_since = None;
+            }
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use std::mem::size_of;
+
+use boids::World;
+use bytemuck::{Pod, Zeroable};
+use pixels::{wgpu, Pixels, PixelsContext};
+
+/// One boid or predator as the shader sees it.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct Instance {
+    center: [f32; 2],
+    // Unit vector the body points along
+    heading: [f32; 2],
+    size: f32,
+    color: [u8; 4],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct Uniforms {
+    // Left, top, width and height of the visible world area
+    area: [f32; 4],
+    srgb_target: u32,
+    _padding: [u32; 3],
+}
+
+/// Draws the boid and predator bodies as instanced triangles over the scaled pixel buffer, so
+/// large flocks cost one buffer upload instead of rasterizing every boid on the CPU.
+pub struct BoidRenderer {
+    pipeline: wgpu::RenderPipeline,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    instance_buffer: wgpu::Buffer,
+    // Instances `instance_buffer` has room for
+    capacity: usize,
+    instances: Vec<Instance>,
+    uniforms: Uniforms,
+}
+
+impl BoidRenderer {
+    const INITIAL_CAPACITY: usize = 1024;
+
+    pub fn new(pixels: &Pixels) -> Self {
+        let device = pixels.device();
+        let format = pixels.render_texture_format();
+        let shader = device.create_shader_module(wgpu::include_wgsl!("boids.wgsl"));
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("boids_uniforms"),
+            size: size_of::<Uniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("boids_bind_group_layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("boids_bind_group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("boids_pipeline_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("boids_pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: size_of::<Instance>() as u64,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Float32, 3 => Unorm8x4],
+                }],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            uniform_buffer,
+            bind_group,
+            instance_buffer: Self::create_instance_buffer(device, Self::INITIAL_CAPACITY),
+            capacity: Self::INITIAL_CAPACITY,
+            instances: Vec::new(),
+            uniforms: Uniforms {
+                area: [0.0; 4],
+                srgb_target: format.is_srgb() as u32,
+                _padding: [0; 3],
+            },
+        }
+    }
+
+    fn create_instance_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("boids_instances"),
+            size: (capacity * size_of::<Instance>()) as u64,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Gather the bodies of `world` to draw on the next `render`.
+    pub fn prepare(&mut self, world: &World) {
+        let viewport = world.viewport();
+        self.uniforms.area = [
+            viewport.center_x - viewport.half_width,
+            viewport.center_y - viewport.half_height,
+            viewport.half_width * 2.0,
+            viewport.half_height * 2.0,
+        ];
+        self.instances.clear();
+        // Predators last so they stay on top of the flock
+        for boid in world.boids().chain(world.predators()) {
+            let (x, y) = boid.position();
+            let size = boid.size() as f32;
+            let [r, g, b, _] = boid.color().to_color_array();
+            self.instances.push(Instance {
+                // Bodies are drawn from their position to the right and down
+                center: [x + size / 2.0, y + size / 2.0],
+                heading: [boid.heading().cos(), boid.heading().sin()],
+                size,
+                color: [r, g, b, 255],
+            });
+        }
+    }
+
+    /// Draw nothing on the next `render`, the bodies are in the pixel buffer.
+    pub fn clear(&mut self) {
+        self.instances.clear();
+    }
+
+    /// Draw the gathered bodies on `render_target`, after the pixel buffer and before the GUI.
+    pub fn render(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        render_target: &wgpu::TextureView,
+        context: &PixelsContext,
+    ) {
+        if self.instances.is_empty() {
+            return;
+        }
+        if self.instances.len() > self.capacity {
+            self.capacity = self.instances.len().next_power_of_two();
+            self.instance_buffer = Self::create_instance_buffer(&context.device, self.capacity);
+        }
+        context
+            .queue
+            .write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&self.instances));
+        context
+            .queue
+            .write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&self.uniforms));
+
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("boids"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: render_target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        // Where the scaling renderer put the pixel buffer on the surface
+        let (x, y, width, height) = context.scaling_renderer.clip_rect();
+        rpass.set_viewport(x as f32, y as f32, width as f32, height as f32, 0.0, 1.0);
+        rpass.set_scissor_rect(x, y, width, height);
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, &self.bind_group, &[]);
+        rpass.set_vertex_buffer(0, self.instance_buffer.slice(..));
+        rpass.draw(0..3, 0..self.instances.len() as u32);
+    }
+}
@@ -0,0 +1,237 @@
+use std::f64::consts::TAU;
+use std::ops::RangeInclusive;
+
+use serde::{Deserialize, Serialize};
+
+use crate::WorldOption;
+
+/// Numeric options an oscillator can drive.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Parameter {
+    AvoidFactor,
+    MatchingFactor,
+    CenteringFactor,
+    SafeRadius,
+    BoidVisionRadius,
+    BoidViewAngle,
+    BoidMaxSpeed,
+    BoidMinSpeed,
+    TurnFactor,
+    SizeVariation,
+    FearFactor,
+    FearRadius,
+    PredatorVisionRadius,
+    PredatorViewAngle,
+    TrailDecay,
+}
+
+impl Parameter {
+    pub const ALL: [Parameter; 15] = [
+        Parameter::AvoidFactor,
+        Parameter::MatchingFactor,
+        Parameter::CenteringFactor,
+        Parameter::SafeRadius,
+        Parameter::BoidVisionRadius,
+        Parameter::BoidViewAngle,
+        Parameter::BoidMaxSpeed,
+        Parameter::BoidMinSpeed,
+        Parameter::TurnFactor,
+        Parameter::SizeVariation,
+        Parameter::FearFactor,
+        Parameter::FearRadius,
+        Parameter::PredatorVisionRadius,
+        Parameter::PredatorViewAngle,
+        Parameter::TrailDecay,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Parameter::AvoidFactor => "Avoid factor",
+            Parameter::MatchingFactor => "Matching factor",
+            Parameter::CenteringFactor => "Centering factor",
+            Parameter::SafeRadius => "Safe radius",
+            Parameter::BoidVisionRadius => "Boid vision radius",
+            Parameter::BoidViewAngle => "Boid view angle",
+            Parameter::BoidMaxSpeed => "Boid max speed",
+            Parameter::BoidMinSpeed => "Boid min speed",
+            Parameter::TurnFactor => "Turn factor",
+            Parameter::SizeVariation => "Size variation",
+            Parameter::FearFactor => "Fear factor",
+            Parameter::FearRadius => "Fear radius",
+            Parameter::PredatorVisionRadius => "Predator vision radius",
+            Parameter::PredatorViewAngle => "Predator view angle",
+            Parameter::TrailDecay => "Trail decay",
+        }
+    }
+
+    /// Short lowercase name, used in OSC addresses after `/boids/` and on the command line.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Parameter::AvoidFactor => "separation",
+            Parameter::MatchingFactor => "alignment",
+            Parameter::CenteringFactor => "cohesion",
+            Parameter::SafeRadius => "safe_radius",
+            Parameter::BoidVisionRadius => "vision_radius",
+            Parameter::BoidViewAngle => "view_angle",
+            Parameter::BoidMaxSpeed => "max_speed",
+            Parameter::BoidMinSpeed => "min_speed",
+            Parameter::TurnFactor => "turn_factor",
+            Parameter::SizeVariation => "size_variation",
+            Parameter::FearFactor => "fear",
+            Parameter::FearRadius => "fear_radius",
+            Parameter::PredatorVisionRadius => "predator_vision_radius",
+            Parameter::PredatorViewAngle => "predator_view_angle",
+            Parameter::TrailDecay => "trail_decay",
+        }
+    }
+
+    /// The parameter named `key`, see `key`.
+    pub fn from_key(key: &str) -> Option<Self> {
+        Parameter::ALL.into_iter().find(|parameter| parameter.key() == key)
+    }
+
+    /// Values the GUI offers for the oscillator bounds.
+    pub fn range(&self) -> RangeInclusive<f32> {
+        match self {
+            Parameter::AvoidFactor
+            | Parameter::MatchingFactor
+            | Parameter::CenteringFactor
+            | Parameter::FearFactor => 0.0..=1.0,
+            Parameter::SafeRadius
+            | Parameter::BoidVisionRadius
+            | Parameter::FearRadius
+            | Parameter::PredatorVisionRadius => 0.0..=300.0,
+            Parameter::BoidViewAngle | Parameter::PredatorViewAngle => 0.0..=365.0,
+            Parameter::BoidMaxSpeed | Parameter::BoidMinSpeed => 0.0..=100.0,
+            Parameter::TurnFactor => 0.0..=30.0,
+            Parameter::SizeVariation => 0.0..=0.9,
+            Parameter::TrailDecay => 0.0..=0.99,
+        }
+    }
+
+    /// Half of the range centered on `value`, moved back inside the range if needed.
+    pub fn bounds_around(&self, value: f32) -> (f32, f32) {
+        let range = self.range();
+        let quarter = (range.end() - range.start()) / 4.0;
+        let center = value.clamp(range.start() + quarter, range.end() - quarter);
+        (center - quarter, center + quarter)
+    }
+
+    pub fn get(&self, option: &WorldOption) -> f32 {
+        match self {
+            Parameter::AvoidFactor => option.avoid_factor,
+            Parameter::MatchingFactor => option.matching_factor,
+            Parameter::CenteringFactor => option.centering_factor,
+            Parameter::SafeRadius => option.safe_radius,
+            Parameter::BoidVisionRadius => option.boid_vision_radius,
+            Parameter::BoidViewAngle => option.boid_view_angle,
+            Parameter::BoidMaxSpeed => option.boid_max_speed as f32,
+            Parameter::BoidMinSpeed => option.boid_min_speed as f32,
+            Parameter::TurnFactor => option.turn_factor as f32,
+            Parameter::SizeVariation => option.size_variation,
+            Parameter::FearFactor => option.fear_factor,
+            Parameter::FearRadius => option.fear_radius,
+            Parameter::PredatorVisionRadius => option.predator_vision_radius,
+            Parameter::PredatorViewAngle => option.predator_view_angle,
+            Parameter::TrailDecay => option.trail_decay,
+        }
+    }
+
+    pub fn set(&self, option: &mut WorldOption, value: f32) {
+        match self {
+            Parameter::AvoidFactor => option.avoid_factor = value,
+            Parameter::MatchingFactor => option.matching_factor = value,
+            Parameter::CenteringFactor => option.centering_factor = value,
+            Parameter::SafeRadius => option.safe_radius = value,
+            Parameter::BoidVisionRadius => option.boid_vision_radius = value,
+            Parameter::BoidViewAngle => option.boid_view_angle = value,
+            Parameter::BoidMaxSpeed => option.boid_max_speed = value.round() as i16,
+            Parameter::BoidMinSpeed => option.boid_min_speed = value.round() as i16,
+            Parameter::TurnFactor => option.turn_factor = value.round() as i16,
+            Parameter::SizeVariation => option.size_variation = value,
+            Parameter::FearFactor => option.fear_factor = value,
+            Parameter::FearRadius => option.fear_radius = value,
+            Parameter::PredatorVisionRadius => option.predator_vision_radius = value,
+            Parameter::PredatorViewAngle => option.predator_view_angle = value,
+            Parameter::TrailDecay => option.trail_decay = value,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Waveform {
+    Sine,
+    Triangle,
+    Sawtooth,
+    Square,
+}
+
+impl Waveform {
+    pub const ALL: [Waveform; 4] = [Waveform::Sine, Waveform::Triangle, Waveform::Sawtooth, Waveform::Square];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Waveform::Sine => "Sine",
+            Waveform::Triangle => "Triangle",
+            Waveform::Sawtooth => "Sawtooth",
+            Waveform::Square => "Square",
+        }
+    }
+
+    // Between 0 and 1 at `phase` between 0 and 1, starting at the bottom
+    fn sample(&self, phase: f64) -> f64 {
+        match self {
+            Waveform::Sine => (1.0 - (phase * TAU).cos()) / 2.0,
+            Waveform::Triangle => 1.0 - (2.0 * phase - 1.0).abs(),
+            Waveform::Sawtooth => phase,
+            Waveform::Square => {
+                if phase < 0.5 {
+                    0.0
+                } else {
+                    1.0
+                }
+            }
+        }
+    }
+}
+
+/// Slow oscillator sweeping one parameter between `min` and `max`.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lfo {
+    pub parameter: Parameter,
+    pub waveform: Waveform,
+    pub min: f32,
+    pub max: f32,
+    // Seconds per full cycle
+    pub period: f32,
+    pub enabled: bool,
+}
+
+impl Lfo {
+    /// Oscillator around the current value of `parameter`, over half its range.
+    pub fn new(parameter: Parameter, option: &WorldOption) -> Self {
+        let (min, max) = parameter.bounds_around(parameter.get(option));
+        Self {
+            parameter,
+            waveform: Waveform::Sine,
+            min,
+            max,
+            period: 30.0,
+            enabled: true,
+        }
+    }
+
+    /// Parameter value at `time` seconds.
+    pub fn value_at(&self, time: f64) -> f32 {
+        let phase = (time / self.period.max(0.1) as f64).rem_euclid(1.0);
+        let position = self.waveform.sample(phase) as f32;
+        self.min + (self.max - self.min) * position
+    }
+
+    /// Set the parameter to its value at `time`, if enabled.
+    pub fn apply(&self, option: &mut WorldOption, time: f64) {
+        if self.enabled {
+            self.parameter.set(option, self.value_at(time));
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! Flocking simulation of boids chased by predators, rendered in software.
+//!
+//! The `boids` binary is a winit and pixels app on top of this library, which can be
+//! embedded anywhere a frame buffer is available:
+//!
+//! ```
+//! use boids::World;
+//!
+//! let mut world = World::builder().size(320, 240).boids(100).build();
+//! world.update();
+//! let mut frame = vec![0; 320 * 240 * 4];
+//! world.draw(&mut frame);
+//! assert_eq!(world.boids_count(), 100);
+//! ```
+//!
+//! Settings live in [`WorldOption`], the boids in a [`QuadTree`] spatial index.
+
+pub mod background;
+pub mod boid;
+pub mod builder;
+pub mod camera;
+pub mod capture;
+pub mod chunk;
+pub mod compare;
+pub mod error;
+pub mod geometry;
+pub mod history;
+mod hook;
+pub mod hunt;
+pub mod lfo;
+#[cfg(feature = "midi")]
+pub mod midi;
+pub mod minimap;
+pub mod node;
+pub mod obstacle;
+pub mod palette;
+pub mod particle;
+pub mod pipeline;
+pub mod preset;
+pub mod profile;
+pub mod profiler;
+pub mod random;
+pub mod render;
+pub mod replay;
+pub mod rewind;
+pub mod rule;
+pub mod sandbox;
+pub mod scenario;
+#[cfg(feature = "scripting")]
+pub mod script;
+pub mod snapshot;
+pub mod spectate_wire;
+pub mod statistics;
+pub mod svg;
+pub mod sweep;
+#[cfg(feature = "tui")]
+pub mod terminal;
+pub mod trajectory;
+pub mod tuner;
+pub mod world;
+
+pub use background::BackgroundStyle;
+pub use boid::{Boid, BoidView};
+pub use builder::WorldBuilder;
+pub use error::Error;
+pub use geometry::{Circle, Color, Rectangle};
+pub use node::{QuadTree, Vertice};
+pub use palette::Palette;
+pub use pipeline::UpdatePipeline;
+pub use render::Renderer;
+pub use world::{ColorMode, ForceArrows, RunState, World, WorldOption};
+
+pub const WIDTH: u16 = 1280;
+pub const HEIGHT: u16 = 720;
+pub const SIZE: i16 = 3;
+pub const NUMBER_OF_BOIDS: u16 = 2000;
+pub const NUMBER_OF_PREDATOR: u16 = 3;
+pub const QUAD_TREE_CAPACITY: usize = 4;
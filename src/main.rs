@@ -1,87 +1,525 @@
-mod background;
-mod boid;
+mod bench;
+mod cli;
+mod command;
+mod config;
+mod experiment;
 mod gui;
-mod node;
-mod geometry;
+mod instanced;
+mod osc;
+#[cfg(feature = "remote")]
+mod remote;
+mod shortcut;
+mod spectator;
+mod stats_window;
 
-use std::time::SystemTime;
-
-use background::Background;
-use boid::Boid;
-use geometry::{Color, Rectangle};
-use gui::Framework;
-use node::{MovableNode, QuadTree, RenderNode, Vertice};
-use pixels::{self, Pixels, SurfaceTexture};
-use rand::Rng;
+use boids::capture::{save_frame, save_screenshot, GifRecorder, InstantReplay, VideoFormat, VideoRecorder};
+use boids::profiler::Stage;
+use boids::geometry::Rectangle;
+use boids::replay::Replay;
+use boids::sandbox::Change;
+use boids::scenario::ScenarioFile;
+use boids::trajectory::{TrajectoryRecording, TrajectoryWriter};
+use boids::{UpdatePipeline, World, WorldOption, HEIGHT, WIDTH};
+use clap::Parser;
+use cli::{Cli, Mode, RecordFormat};
+use config::{Config, ConfigFile};
+use gui::{toggle_fullscreen, Framework, Tool};
+use instanced::BoidRenderer;
+use pixels::{self, Pixels, PixelsBuilder, SurfaceTexture};
+use shortcut::Action;
+use stats_window::StatsWindow;
+use instant::Instant;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
 use winit::dpi::PhysicalPosition;
 use winit::{
     self,
     dpi::PhysicalSize,
-    event::{ElementState, Event, MouseButton, WindowEvent},
-    event_loop::EventLoop,
-    window::WindowBuilder,
+    event::{
+        ElementState, Event, KeyboardInput, ModifiersState, MouseButton, MouseScrollDelta,
+        WindowEvent,
+    },
+    event_loop::{EventLoop, EventLoopWindowTarget},
+    window::{Window, WindowBuilder},
 };
 
-const WIDTH: u16 = 1280;
-const HEIGHT: u16 = 720;
-const SIZE: i16 = 3;
-const NUMBER_OF_BOIDS: u16 = 2000;
-const NUMBER_OF_PREDATOR: u16 = 3;
-const QUAD_TREE_CAPACITY: usize = 4;
+// Counts allocations for `boids bench`, otherwise the system allocator
+#[global_allocator]
+static ALLOCATOR: bench::CountingAllocator = bench::CountingAllocator;
+
+const MIN_WIDTH: u16 = 320;
+const MIN_HEIGHT: u16 = 240;
+// Zoom factor for one notch of the mouse wheel
+const ZOOM_STEP: f32 = 1.1;
+// How often the remote and OSC inputs are still read while paused in the background
+const BACKGROUND_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Spawning along the cursor path while a spawn tool is dragged.
+struct Spray {
+    tool: Tool,
+    // Where the last boid was spawned, in window pixels
+    position: PhysicalPosition<f64>,
+    time: Instant,
+    // Fraction of a boid carried over to the next frame
+    pending: f32,
+}
 
 fn main() {
+    #[cfg(target_arch = "wasm32")]
+    {
+        std::panic::set_hook(Box::new(console_error_panic_hook::hook));
+        wasm_bindgen_futures::spawn_local(run());
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    pollster::block_on(run());
+}
+
+// Async because the browser cannot wait for the GPU adapter
+async fn run() {
+    let cli = Cli::parse();
+    match &cli.mode {
+        Some(Mode::Bench(args)) => {
+            bench::run(args);
+            return;
+        }
+        Some(Mode::Sweep(args)) => {
+            experiment::run(args);
+            return;
+        }
+        None => (),
+    }
+    let mut config = Config::load();
+    let config_file = ConfigFile::load().unwrap_or_else(|error| {
+        eprintln!("{error}");
+        std::process::exit(1);
+    });
+    if let Some(file) = &config_file {
+        file.apply(&mut config);
+    }
+    if let Err(error) = cli.apply(&mut config) {
+        eprintln!("{error}");
+        std::process::exit(1);
+    }
+    let replay = cli.load_replay().unwrap_or_else(|error| {
+        eprintln!("{error}");
+        std::process::exit(1);
+    });
+    let scenario = cli.load_scenario().unwrap_or_else(|error| {
+        eprintln!("{error}");
+        std::process::exit(1);
+    });
+    let trajectories = start_trajectories(cli.trajectories.as_deref(), cli.trajectory_file_size).unwrap_or_else(|error| {
+        eprintln!("{error}");
+        std::process::exit(1);
+    });
+    if let Some(directory) = &cli.render_frames {
+        let (width, height) = config.window_size.unwrap_or((WIDTH as u32, HEIGHT as u32));
+        let size = (width as u16, height as u16);
+        let scenario = scenario.as_ref();
+        if let Err(error) = render_frames(config.option, size, cli.seed, cli.ticks, directory, scenario, trajectories) {
+            eprintln!("{error}");
+            std::process::exit(1);
+        }
+        return;
+    }
+    if cli.headless {
+        match replay {
+            Some(replay) => run_replay_headless(replay, trajectories),
+            None => {
+                let (width, height) = config.window_size.unwrap_or((WIDTH as u32, HEIGHT as u32));
+                let size = (width as u16, height as u16);
+                run_headless(config.option, size, cli.seed, cli.ticks, scenario.as_ref(), trajectories);
+            }
+        }
+        return;
+    }
+    if cli.tui {
+        if let Err(error) = run_tui(config.option, cli.seed) {
+            eprintln!("{error}");
+            std::process::exit(1);
+        }
+        return;
+    }
+    if let Some(replay) = &replay {
+        let (width, height) = replay.size();
+        config.window_size = Some((width as u32, height as u32));
+    }
     let event_loop = EventLoop::new();
     let window = {
-        let size = PhysicalSize::new(WIDTH, HEIGHT);
+        let (width, height) = config.window_size.unwrap_or((WIDTH as u32, HEIGHT as u32));
+        let size = PhysicalSize::new(width.max(MIN_WIDTH as u32), height.max(MIN_HEIGHT as u32));
         WindowBuilder::new()
             .with_title("Boids")
             .with_inner_size(size)
-            .with_min_inner_size(size)
-            .with_resizable(false)
+            .with_min_inner_size(PhysicalSize::new(MIN_WIDTH, MIN_HEIGHT))
+            .with_resizable(true)
             .build(&event_loop)
-            .unwrap()
+            .unwrap_or_else(|error| {
+                eprintln!("Cannot open a window: {error}");
+                std::process::exit(1);
+            })
     };
+    #[cfg(target_arch = "wasm32")]
+    {
+        use winit::platform::web::WindowExtWebSys;
+        web_sys::window()
+            .and_then(|page| page.document())
+            .and_then(|document| document.body())
+            .and_then(|body| body.append_child(&window.canvas()).ok())
+            .expect("Cannot add the canvas to the page");
+    }
+    if config_file.is_some_and(|file| file.window.fullscreen) {
+        toggle_fullscreen(&window);
+    }
+
+    let window_size = window.inner_size();
+    let mut world = World::builder()
+        .size(window_size.width as u16, window_size.height as u16)
+        .option(config.option)
+        .seed(cli.seed)
+        .build();
+    if let Some(scenario) = &scenario {
+        world.load_scenario(scenario);
+    }
+    let trajectories = trajectories.map(|writer| writer.record_ticks(&mut world));
 
     let (mut pixels, mut framework) = {
-        let window_size = window.inner_size();
         let scale_factor = window.scale_factor() as f32;
-        let surface_texture = SurfaceTexture::new(window_size.width, window_size.height, &window);
-        let pixels = Pixels::new(WIDTH as u32, HEIGHT as u32, surface_texture).unwrap();
-        let framework = Framework::new(
+        let pixels = create_pixels(&window, world.option.vsync).await.unwrap_or_else(|error| {
+            eprintln!("Cannot set up rendering: {error}");
+            std::process::exit(1);
+        });
+        let mut framework = Framework::new(
             &event_loop,
             window_size.width,
             window_size.height,
             scale_factor,
             &pixels,
         );
+        framework.set_open_windows(config.windows);
+        #[cfg(feature = "midi")]
+        framework.start_midi(config.midi);
+        match cli.record {
+            Some(RecordFormat::Gif) => framework.start_gif_recording(),
+            Some(RecordFormat::Mp4) => framework.start_video_recording(VideoFormat::Mp4),
+            Some(RecordFormat::Webm) => framework.start_video_recording(VideoFormat::WebM),
+            None => (),
+        }
 
         (pixels, framework)
     };
+    let mut boid_renderer = BoidRenderer::new(&pixels);
+    #[cfg(feature = "remote")]
+    let mut remote = start_remote(cli.remote.as_deref()).unwrap_or_else(|error| {
+        eprintln!("{error}");
+        std::process::exit(1);
+    });
+    #[cfg(not(feature = "remote"))]
+    if cli.remote.is_some() {
+        eprintln!("Built without the remote control server, run with `cargo run --features remote -- --remote ADDRESS`");
+        std::process::exit(1);
+    }
+    let mut osc = start_osc(cli.osc).unwrap_or_else(|error| {
+        eprintln!("{error}");
+        std::process::exit(1);
+    });
+    let mut spectator_server = start_spectator_server(cli.spectator_server.as_deref(), cli.sandbox).unwrap_or_else(|error| {
+        eprintln!("{error}");
+        std::process::exit(1);
+    });
+    let mut spectating = start_spectating(cli.spectate.as_deref()).unwrap_or_else(|error| {
+        eprintln!("{error}");
+        std::process::exit(1);
+    });
 
-    let mut world = World::new();
+    let mut pipeline = UpdatePipeline::new();
     let mut mouse_press: bool = false;
+    let mut panning: bool = false;
     let mut mouse_position: PhysicalPosition<f64> = PhysicalPosition::new(0.0, 0.0);
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut vsync = world.option.vsync;
+    let mut next_frame = Instant::now();
+    // Oscillators are timed from launch
+    let start_time = Instant::now();
+    let mut screenshot_requested = false;
+    let mut gif_recorder: Option<GifRecorder> = None;
+    let mut video_recorder: Option<VideoRecorder> = None;
+    let mut instant_replay: Option<InstantReplay> = None;
+    let mut spray: Option<Spray> = None;
+    let mut modifiers = ModifiersState::empty();
+    let mut focused = true;
+    let mut stats_window: Option<StatsWindow> = None;
+    // Erasing follows the cursor until the button is released
+    let mut erasing = false;
+    // World position where the selection box drag started
+    let mut selection_start: Option<(f32, f32)> = None;
 
-    world.spawn_random_boids(NUMBER_OF_BOIDS, NUMBER_OF_PREDATOR);
+    if let Some(replay) = replay {
+        world.start_replay(replay);
+    }
+    // Size of the pixels buffer, following the world
+    let mut buffer_size = (world.width(), world.height());
 
-    event_loop.run(move |event, _, elwt| {
+    event_loop.run(move |event, event_loop_target, elwt| {
         match event {
             Event::MainEventsCleared => {
+                let now = Instant::now();
+                if world.option.limit_fps {
+                    if now < next_frame {
+                        elwt.set_wait_until(next_frame);
+                        return;
+                    }
+                    next_frame = now + Duration::from_secs_f32(1.0 / world.option.max_fps as f32);
+                    elwt.set_wait_until(next_frame);
+                } else {
+                    elwt.set_poll();
+                }
+                #[cfg(feature = "remote")]
+                if let Some(server) = &mut remote {
+                    server.poll(&mut world);
+                }
+                if let Some(receiver) = &mut osc {
+                    receiver.poll(&mut world);
+                }
+                if let Some(server) = &mut spectator_server {
+                    server.poll(&mut world);
+                }
+                if let Some(client) = &mut spectating {
+                    client.poll(&mut world);
+                }
+                // Nothing updates or redraws until the window is back, the last frame stays on screen
+                if world.option.pause_in_background && (!focused || window.is_minimized() == Some(true)) {
+                    elwt.set_wait_until(now + BACKGROUND_INTERVAL);
+                    return;
+                }
                 framework.prepare(&window, &mut world);
-                world.update();
+                if let Some(client) = &mut spectating {
+                    client.send_settings(&world);
+                }
+                if world.option.avoid_gui {
+                    let areas = framework
+                        .areas()
+                        .into_iter()
+                        .map(|(min, max)| {
+                            let (min_x, min_y) = frame_position(&pixels, min);
+                            let (max_x, max_y) = frame_position(&pixels, max);
+                            let (min_x, min_y) = world.screen_to_world(min_x, min_y);
+                            let (max_x, max_y) = world.screen_to_world(max_x, max_y);
+                            Rectangle::from_corners(min_x, min_y, max_x, max_y)
+                        })
+                        .collect();
+                    world.set_avoided_areas(areas);
+                }
+                match (framework.wants_stats_window(), stats_window.is_some()) {
+                    (true, false) => match open_stats_window(event_loop_target) {
+                        Ok(opened) => stats_window = Some(opened),
+                        Err(error) => {
+                            framework.close_stats_window();
+                            framework.report_error(error);
+                        }
+                    },
+                    (false, true) => stats_window = None,
+                    _ => (),
+                }
+                if framework.take_screenshot_request() {
+                    screenshot_requested = true;
+                }
+                match (framework.gif_recording(), gif_recorder.is_some()) {
+                    (Some(settings), false) => {
+                        let (width, height) = (world.width() as u32, world.height() as u32);
+                        gif_recorder = Some(GifRecorder::new(width, height, settings));
+                    }
+                    (None, true) => {
+                        if let Some(recorder) = gif_recorder.take() {
+                            // Encoding takes a while, keep the window responsive
+                            in_background(move || match recorder.finish(Path::new(".")) {
+                                Ok(path) => println!("Saved recording to {}", path.display()),
+                                Err(error) => eprintln!("Cannot save recording: {error}"),
+                            });
+                        }
+                    }
+                    _ => (),
+                }
+                match (framework.instant_replay(), &mut instant_replay) {
+                    (Some(seconds), Some(replay)) => replay.set_seconds(seconds),
+                    (Some(seconds), None) => instant_replay = Some(InstantReplay::new(seconds)),
+                    (None, _) => instant_replay = None,
+                }
+                if framework.take_instant_replay_request() {
+                    let clip = instant_replay.as_ref().map(InstantReplay::clip);
+                    if let Some(clip) = clip.filter(|clip| !clip.is_empty()) {
+                        in_background(move || match clip.save(Path::new(".")) {
+                            Ok(path) => println!("Saved instant replay to {}", path.display()),
+                            Err(error) => eprintln!("Cannot save instant replay: {error}"),
+                        });
+                    }
+                }
+                match (framework.video_recording(), video_recorder.is_some()) {
+                    (Some(settings), false) => {
+                        let (width, height) = (world.width() as u32, world.height() as u32);
+                        match VideoRecorder::new(width, height, settings, Path::new(".")) {
+                            Ok(recorder) => video_recorder = Some(recorder),
+                            Err(error) => framework.set_video_error(format!("Cannot start ffmpeg: {error}")),
+                        }
+                    }
+                    (None, true) => {
+                        if let Some(recorder) = video_recorder.take() {
+                            in_background(move || match recorder.finish() {
+                                Ok(path) => println!("Saved recording to {}", path.display()),
+                                Err(error) => eprintln!("Cannot save recording: {error}"),
+                            });
+                        }
+                    }
+                    _ => (),
+                }
+                // Browsers always sync to the display
+                #[cfg(not(target_arch = "wasm32"))]
+                if world.option.vsync != vsync {
+                    vsync = world.option.vsync;
+                    // The current surface keeps drawing if a new one cannot be made
+                    match pollster::block_on(create_pixels(&window, vsync)) {
+                        Ok(rebuilt) => {
+                            pixels = rebuilt;
+                            framework.rebuild(event_loop_target, &pixels);
+                            boid_renderer = BoidRenderer::new(&pixels);
+                        }
+                        Err(error) => framework.report_error(format!("Cannot change vsync: {error}")),
+                    }
+                }
+                if let Some(spray) = &mut spray {
+                    spray.pending += now.duration_since(spray.time).as_secs_f32() * framework.spray_rate();
+                    spray.time = now;
+                    let count = spray.pending.floor() as u32;
+                    spray.pending -= count as f32;
+                    // Spread this frame's boids evenly between the last spawn and the cursor. They
+                    // do not hold back the update in flight, it takes them on when merged
+                    for step in 1..=count {
+                        let t = step as f64 / count as f64;
+                        let x = spray.position.x + (mouse_position.x - spray.position.x) * t;
+                        let y = spray.position.y + (mouse_position.y - spray.position.y) * t;
+                        let (x, y) = frame_position(&pixels, PhysicalPosition::new(x, y));
+                        let (world_x, world_y) = world.screen_to_world(x, y);
+                        place(&mut world, spectating.as_ref(), spray.tool, world_x, world_y);
+                    }
+                    if count > 0 {
+                        spray.position = mouse_position;
+                    }
+                }
+                // A replay keeps the size it was recorded at, scaled to the window if they differ
+                let size = window.inner_size();
+                // A spectator shows the flock at the size it is simulated at
+                if spectating.is_none() && !world.is_replaying() {
+                    // Compared side by side, each world gets half of the window
+                    let width = if framework.is_comparing() { size.width / 2 } else { size.width };
+                    world.resize(width as u16, size.height as u16);
+                }
+                let frame_width = match framework.comparison_mut() {
+                    Some(comparison) => {
+                        comparison.world.resize(world.width(), world.height());
+                        world.width() + comparison.world.width()
+                    }
+                    None => world.width(),
+                };
+                if buffer_size != (frame_width, world.height()) {
+                    buffer_size = (frame_width, world.height());
+                    if let Err(error) = pixels.resize_buffer(buffer_size.0 as u32, buffer_size.1 as u32) {
+                        framework.report_error(format!("Cannot resize the frame: {error}"));
+                    }
+                }
+                let time = start_time.elapsed().as_secs_f64();
+                world.animate(time);
+                if world.is_replaying() {
+                    // Replayed inputs land between ticks, so updates run here one at a time
+                    world.advance();
+                } else if spectating.is_none() {
+                    // A spectator's flock only moves with the frames of the server
+                    pipeline.step(&mut world);
+                }
+                if let Some(comparison) = framework.comparison_mut() {
+                    comparison.world.animate(time);
+                    comparison.follow(&world);
+                }
+                #[cfg(feature = "remote")]
+                if let Some(server) = &mut remote {
+                    server.publish(&world);
+                }
+                if let Some(server) = &mut spectator_server {
+                    server.broadcast(&world);
+                }
+                if let Some(stats) = &mut stats_window {
+                    stats.prepare(framework.statistics_mut(), &mut world);
+                }
                 window.request_redraw();
             }
+            Event::RedrawRequested(window_id) if stats_window.as_ref().is_some_and(|stats| stats.id() == window_id) => {
+                if let Some(Err(error)) = stats_window.as_mut().map(StatsWindow::render) {
+                    stats_window = None;
+                    framework.close_stats_window();
+                    framework.report_error(format!("Cannot render the stats window: {error}"));
+                }
+            }
             Event::RedrawRequested(_) => {
-                world.draw(pixels.frame_mut());
+                // Screenshots and recordings copy the pixel buffer, so the bodies go in there meanwhile
+                let capturing = screenshot_requested
+                    || gif_recorder.is_some()
+                    || video_recorder.is_some()
+                    || instant_replay.as_ref().is_some_and(InstantReplay::wants_frame);
+                if let Some(comparison) = framework.comparison_mut() {
+                    comparison.draw(&mut world, pixels.frame_mut());
+                    boid_renderer.clear();
+                } else if world.option.gpu_boids && !capturing {
+                    world.draw_scenery(pixels.frame_mut());
+                    boid_renderer.prepare(&world);
+                } else {
+                    world.draw(pixels.frame_mut());
+                    boid_renderer.clear();
+                }
+                if screenshot_requested {
+                    screenshot_requested = false;
+                    // Taken before the GUI is rendered so only the simulation is saved
+                    let (width, height) = (world.width() as u32, world.height() as u32);
+                    match save_screenshot(pixels.frame(), width, height, Path::new(".")) {
+                        Ok(path) => println!("Saved screenshot to {}", path.display()),
+                        Err(error) => eprintln!("Cannot save screenshot: {error}"),
+                    }
+                }
+                if let Some(recorder) = &mut gif_recorder {
+                    recorder.capture(pixels.frame(), world.width() as u32, world.height() as u32);
+                }
+                if let Some(recorder) = &mut video_recorder {
+                    recorder.capture(pixels.frame(), world.width() as u32, world.height() as u32);
+                }
+                if let Some(replay) = &mut instant_replay {
+                    replay.capture(pixels.frame(), world.width() as u32, world.height() as u32);
+                }
                 let render_result = pixels.render_with(|encoder, render_target, context| {
                     context.scaling_renderer.render(encoder, render_target);
-                    framework.render(encoder, render_target, context);
+                    boid_renderer.render(encoder, render_target, context);
+                    world
+                        .profiler
+                        .time(Stage::Gui, || framework.render(encoder, render_target, context));
                     Ok(())
                 });
 
                 if let Err(error) = render_result {
                     elwt.set_exit();
-                    eprint!("{error}");
+                    eprintln!("Cannot render: {error}");
+                }
+            }
+            Event::WindowEvent { window_id, event } if stats_window.as_ref().is_some_and(|stats| stats.id() == window_id) => {
+                match event {
+                    WindowEvent::CloseRequested => {
+                        stats_window = None;
+                        framework.close_stats_window();
+                    }
+                    // The simulation keeps running in the background while the stats are looked at
+                    WindowEvent::Focused(focus) => focused = focus,
+                    _ => {
+                        if let Some(stats) = &mut stats_window {
+                            stats.handle_event(&event);
+                        }
+                    }
                 }
             }
             Event::WindowEvent { event, .. } => {
@@ -91,32 +529,149 @@ fn main() {
                     match event {
                         WindowEvent::CloseRequested => {
                             println!("Close Window");
+                            let size = window.inner_size();
+                            let config = Config {
+                                option: world.option.clone(),
+                                windows: framework.open_windows(),
+                                window_size: Some((size.width, size.height)),
+                                #[cfg(feature = "midi")]
+                                midi: framework.midi_bindings(),
+                            };
+                            config.save();
+                            // Recordings still running are saved before leaving
+                            if let Some(recorder) = gif_recorder.take() {
+                                match recorder.finish(Path::new(".")) {
+                                    Ok(path) => println!("Saved recording to {}", path.display()),
+                                    Err(error) => eprintln!("Cannot save recording: {error}"),
+                                }
+                            }
+                            if let Some(recorder) = video_recorder.take() {
+                                match recorder.finish() {
+                                    Ok(path) => println!("Saved recording to {}", path.display()),
+                                    Err(error) => eprintln!("Cannot save recording: {error}"),
+                                }
+                            }
+                            if let Some(trajectories) = &trajectories {
+                                finish_trajectories(trajectories);
+                            }
                             elwt.set_exit();
                         }
                         WindowEvent::MouseInput { button, state, .. } => {
+                            let (x, y) = frame_position(&pixels, mouse_position);
                             if button == MouseButton::Left
                                 && state == ElementState::Pressed
                                 && !mouse_press
+                                // The compared world on the right only follows the main one
+                                && !(framework.is_comparing() && x >= world.width() as f32)
                             {
                                 mouse_press = true;
-                                println!("{:}, {:}", mouse_position.x, mouse_position.y);
-                                world.spawn_boids(mouse_position.x as i16, mouse_position.y as i16);
+                                if !world.jump_to_minimap(x, y) {
+                                    let (world_x, world_y) = world.screen_to_world(x, y);
+                                    // Shift click spawns predators and control click erases whatever the tool
+                                    let tool = if modifiers.shift() {
+                                        Tool::SpawnPredator
+                                    } else if modifiers.ctrl() {
+                                        Tool::Erase
+                                    } else {
+                                        framework.tool()
+                                    };
+                                    match tool {
+                                        Tool::SpawnBoid | Tool::SpawnPredator => {
+                                            place(&mut world, spectating.as_ref(), tool, world_x, world_y);
+                                            spray = Some(Spray {
+                                                tool,
+                                                position: mouse_position,
+                                                time: Instant::now(),
+                                                pending: 0.0,
+                                            });
+                                        }
+                                        Tool::Obstacle | Tool::Attractor => {
+                                            place(&mut world, spectating.as_ref(), tool, world_x, world_y)
+                                        }
+                                        Tool::Select => selection_start = Some((world_x, world_y)),
+                                        Tool::Erase => {
+                                            world.erase_at(world_x, world_y, framework.erase_radius());
+                                            erasing = true;
+                                        }
+                                    }
+                                }
                             }
                             if button == MouseButton::Left
                                 && state == ElementState::Released
                                 && mouse_press
                             {
                                 mouse_press = false;
+                                spray = None;
+                                erasing = false;
+                                if let Some((start_x, start_y)) = selection_start.take() {
+                                    let (world_x, world_y) = world.screen_to_world(x, y);
+                                    let area = Rectangle::from_corners(start_x, start_y, world_x, world_y);
+                                    // A click without much of a drag picks a single boid
+                                    if area.half_width < 2.0 && area.half_height < 2.0 {
+                                        world.select_at(start_x as i16, start_y as i16);
+                                    } else {
+                                        world.select_in(&area);
+                                    }
+                                    world.set_selection_box(None);
+                                }
+                            }
+                            if button == MouseButton::Right && state == ElementState::Pressed {
+                                let (world_x, world_y) = world.screen_to_world(x, y);
+                                world.select_at(world_x as i16, world_y as i16);
                             }
+                            if button == MouseButton::Middle {
+                                panning = state == ElementState::Pressed;
+                            }
+                        }
+                        WindowEvent::KeyboardInput {
+                            input:
+                                KeyboardInput {
+                                    state: ElementState::Pressed,
+                                    virtual_keycode: Some(key),
+                                    ..
+                                },
+                            ..
+                        } => {
+                            if let Some(action) = Action::for_key(key, modifiers) {
+                                framework.perform(action, &mut world, &window);
+                            }
+                        }
+                        WindowEvent::ModifiersChanged(state) => {
+                            modifiers = state;
+                        }
+                        WindowEvent::Focused(focus) => {
+                            focused = focus;
                         }
                         WindowEvent::CursorMoved { position, .. } => {
+                            let (x, y) = frame_position(&pixels, position);
+                            if panning {
+                                let (last_x, last_y) = frame_position(&pixels, mouse_position);
+                                world.pan(x - last_x, y - last_y);
+                            }
                             mouse_position = position;
+                            let (world_x, world_y) = world.screen_to_world(x, y);
+                            if erasing {
+                                world.erase_at(world_x, world_y, framework.erase_radius());
+                            }
+                            if let Some((start_x, start_y)) = selection_start {
+                                let area = Rectangle::from_corners(start_x, start_y, world_x, world_y);
+                                world.set_selection_box(Some(area));
+                            }
+                        }
+                        WindowEvent::MouseWheel { delta, .. } => {
+                            let steps = match delta {
+                                MouseScrollDelta::LineDelta(_, y) => y,
+                                MouseScrollDelta::PixelDelta(position) => position.y as f32 / 50.0,
+                            };
+                            let (x, y) = frame_position(&pixels, mouse_position);
+                            world.zoom_at(ZOOM_STEP.powf(steps), x, y);
                         }
                         WindowEvent::Resized(new_size) => {
+                            // The buffer and the world follow on the next frame
                             if new_size.width > 0 && new_size.height > 0 {
-                                pixels
-                                    .resize_surface(new_size.width, new_size.height)
-                                    .unwrap();
+                                if let Err(error) = pixels.resize_surface(new_size.width, new_size.height) {
+                                    framework.report_error(format!("Cannot resize the window surface: {error}"));
+                                }
                             }
                             framework.resize(new_size.width, new_size.height);
                         }
@@ -132,242 +687,261 @@ fn main() {
     });
 }
 
-struct WorldOption {
-    // Boid
-    avoid_factor: f32,
-    matching_factor: f32,
-    centering_factor: f32,
-    safe_radius: f32,
-    boid_vision_radius: f32,
-    boid_max_speed: i16,
-    boid_min_speed: i16,
-    margin: u16,
-    turn_factor: i16,
-    boid_view_angle: f32,
-    noise: bool,
-    // Predator
-    fear_factor: f32,
-    fear_radius: f32,
-    predator_vision_radius: f32,
-    predator_max_speed: i16,
-    predator_min_speed: i16,
-    predator_view_angle: f32,
-    // DEBUG
-    show_quad_tree: bool,
-    show_safe_radius: bool,
-    show_vision_radius: bool,
-    show_facing_direction_with_speed: bool,
-}
-
-impl WorldOption {
-    fn new() -> Self {
-        Self {
-            // Boid
-            avoid_factor: 0.27,
-            matching_factor: 0.55,
-            centering_factor: 0.06,
-            safe_radius: 10.0,
-            boid_vision_radius: 30.0,
-            boid_max_speed: 10,
-            boid_min_speed: 5,
-            margin: 20,
-            turn_factor: 30,
-            boid_view_angle: 120.0,
-            noise: false,
-            // Predator
-            fear_factor: 1.0,
-            fear_radius: 30.0,
-            predator_vision_radius: 40.0,
-            predator_max_speed: 8,
-            predator_min_speed: 3,
-            predator_view_angle: 90.0,
-            // DEBUG
-            show_quad_tree: false,
-            show_safe_radius: false,
-            show_vision_radius: false,
-            show_facing_direction_with_speed: false,
-        }
+/// Run `ticks` updates without a window and print the speed, the seed and the population.
+fn run_headless(
+    option: WorldOption,
+    (width, height): (u16, u16),
+    seed: Option<u64>,
+    ticks: u32,
+    scenario: Option<&ScenarioFile>,
+    trajectories: Option<TrajectoryWriter>,
+) {
+    let mut world = World::builder().size(width, height).option(option).seed(seed).build();
+    if let Some(scenario) = scenario {
+        world.load_scenario(scenario);
+    }
+    let trajectories = trajectories.map(|writer| writer.record_ticks(&mut world));
+    let start_time = Instant::now();
+    for _ in 0..ticks {
+        world.update();
+    }
+    let elapsed = start_time.elapsed();
+    println!(
+        "{ticks} ticks in {:.2?} ({:.0} ticks per second)",
+        elapsed,
+        ticks as f64 / elapsed.as_secs_f64()
+    );
+    println!(
+        "Seed {}: {} boids, {} predators, {} caught",
+        world.seed(),
+        world.boids_count(),
+        world.predators_count(),
+        world.catches()
+    );
+    if let Some(trajectories) = &trajectories {
+        finish_trajectories(trajectories);
     }
 }
 
-struct World {
-    background: Background,
-    boundary: Rectangle,
-    boids_quad_tree: QuadTree,
-    predator_quad_tree: QuadTree,
-    update_fps: f32,
-    draw_fps: f32,
+/// Run `ticks` updates without a window, drawing each one to a PNG in `directory`.
+fn render_frames(
     option: WorldOption,
+    (width, height): (u16, u16),
+    seed: Option<u64>,
+    ticks: u32,
+    directory: &Path,
+    scenario: Option<&ScenarioFile>,
+    trajectories: Option<TrajectoryWriter>,
+) -> Result<(), String> {
+    fs::create_dir_all(directory).map_err(|error| format!("Cannot create {}: {error}", directory.display()))?;
+    let mut world = World::builder().size(width, height).option(option).seed(seed).build();
+    if let Some(scenario) = scenario {
+        world.load_scenario(scenario);
+    }
+    let trajectories = trajectories.map(|writer| writer.record_ticks(&mut world));
+    let mut frame = vec![0; width as usize * height as usize * 4];
+    let start_time = Instant::now();
+    for index in 0..ticks {
+        world.update();
+        world.draw(&mut frame);
+        save_frame(&frame, width as u32, height as u32, directory, index)
+            .map_err(|error| format!("Cannot save frame {index}: {error}"))?;
+    }
+    println!(
+        "Rendered {ticks} frames of {width}x{height} to {} in {:.2?}, seed {}",
+        directory.display(),
+        start_time.elapsed(),
+        world.seed()
+    );
+    if let Some(trajectories) = &trajectories {
+        finish_trajectories(trajectories);
+    }
+    Ok(())
 }
 
-impl World {
-    fn new() -> Self {
-        Self {
-            background: Background::new(Color::Black),
-            boundary: Rectangle::new(
-                WIDTH as f32 / 2.0,
-                HEIGHT as f32 / 2.0,
-                WIDTH as f32 / 2.0,
-                HEIGHT as f32 / 2.0,
-            ),
-            boids_quad_tree: QuadTree::new(
-                QUAD_TREE_CAPACITY,
-                Rectangle::new(
-                    WIDTH as f32 / 2.0,
-                    HEIGHT as f32 / 2.0,
-                    WIDTH as f32 / 2.0,
-                    HEIGHT as f32 / 2.0,
-                ),
-            ),
-            predator_quad_tree: QuadTree::new(
-                QUAD_TREE_CAPACITY,
-                Rectangle::new(
-                    WIDTH as f32 / 2.0,
-                    HEIGHT as f32 / 2.0,
-                    WIDTH as f32 / 2.0,
-                    HEIGHT as f32 / 2.0,
-                ),
-            ),
-            update_fps: 0.0,
-            draw_fps: 0.0,
-            option: WorldOption::new(),
-        }
+/// Play `replay` to the end without a window and print where it ended.
+fn run_replay_headless(replay: Replay, trajectories: Option<TrajectoryWriter>) {
+    let (width, height) = replay.size();
+    let ticks = replay.ticks();
+    let mut world = World::with_size(width, height);
+    world.start_replay(replay);
+    let trajectories = trajectories.map(|writer| writer.record_ticks(&mut world));
+    let start_time = Instant::now();
+    while world.is_replaying() {
+        world.update();
     }
+    println!("Replayed {ticks} ticks in {:.2?}", start_time.elapsed());
+    println!(
+        "Tick {}: {} boids, {} predators, {} caught",
+        world.tick(),
+        world.boids_count(),
+        world.predators_count(),
+        world.catches()
+    );
+    if let Some(trajectories) = &trajectories {
+        finish_trajectories(trajectories);
+    }
+}
 
-    fn spawn_random_boids(&mut self, boids_numbers: u16, predators_numbers: u16) {
-        let mut rng = rand::thread_rng();
-        for _ in 0..boids_numbers {
-            let x = rng.gen_range(0..WIDTH - SIZE as u16) as i16;
-            let y = rng.gen_range(0..HEIGHT - SIZE as u16) as i16;
-
-            self.spawn_boids(x, y);
-        }
-        for _ in 0..predators_numbers {
-            let x = rng.gen_range(0..WIDTH - SIZE as u16) as i16;
-            let y = rng.gen_range(0..HEIGHT - SIZE as u16) as i16;
+/// Update and draw the flock in the terminal until Q, Escape or Control C is pressed.
+#[cfg(feature = "tui")]
+fn run_tui(option: WorldOption, seed: Option<u64>) -> std::io::Result<()> {
+    use boids::terminal::TerminalRenderer;
+    use boids::Renderer;
+    use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
 
-            self.spawn_predators(x, y);
+    let mut renderer = TerminalRenderer::new()?;
+    let (width, height) = renderer.size()?;
+    let mut world = World::builder().size(width, height).option(option).seed(seed).build();
+    loop {
+        let frame_end = Instant::now() + Duration::from_secs_f32(1.0 / world.option.max_fps.max(1) as f32);
+        let (width, height) = renderer.size()?;
+        world.resize(width, height);
+        world.advance();
+        renderer.render(&mut world)?;
+        loop {
+            let now = Instant::now();
+            if now >= frame_end || !event::poll(frame_end - now)? {
+                break;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+                KeyCode::Char(' ') => world.toggle_pause(),
+                KeyCode::Char('r') => world.restart(),
+                KeyCode::Char('+') | KeyCode::Char('=') => world.speed_up(),
+                KeyCode::Char('-') => world.slow_down(),
+                _ => (),
+            }
         }
     }
+}
 
-    fn spawn_boids(&mut self, x: i16, y: i16) {
-        let mut rng = rand::thread_rng();
-        let velocity_x = rng.gen_range(-self.option.boid_min_speed..=self.option.boid_min_speed);
-        let velocity_y = rng.gen_range(-self.option.boid_min_speed..=self.option.boid_min_speed);
-        let mut vertice = Vertice::new();
-        vertice.x = x;
-        vertice.y = y;
-        self.boids_quad_tree.insert(&Boid::new(
-            vertice,
-            SIZE,
-            velocity_x,
-            velocity_y,
-            Color::Green,
-        ));
-    }
+#[cfg(not(feature = "tui"))]
+fn run_tui(_: WorldOption, _: Option<u64>) -> Result<(), String> {
+    Err("Built without the terminal renderer, run with `cargo run --features tui -- --tui`".to_string())
+}
 
-    fn spawn_predators(&mut self, x: i16, y: i16) {
-        let mut rng = rand::thread_rng();
-        let velocity_x = rng.gen_range(-self.option.predator_min_speed..=self.option.predator_min_speed);
-        let velocity_y = rng.gen_range(-self.option.predator_min_speed..=self.option.predator_min_speed);
-        let mut vertice = Vertice::new();
-        vertice.x = x;
-        vertice.y = y;
-        self.predator_quad_tree.insert(&Boid::new(
-            vertice,
-            SIZE * 2,
-            velocity_x,
-            velocity_y,
-            Color::Red,
-        ));
+/// Spawn or place what `tool` does at `x`, `y`, in the world of the server when spectating.
+fn place(world: &mut World, spectating: Option<&spectator::SpectatorClient>, tool: Tool, x: f32, y: f32) {
+    let change = match tool {
+        Tool::SpawnPredator => Change::SpawnPredator { x, y },
+        Tool::Obstacle => Change::AddObstacle { x, y },
+        Tool::Attractor => Change::AddAttractor { x, y },
+        _ => Change::SpawnBoid { x, y },
+    };
+    match spectating {
+        Some(client) => client.send(change),
+        None => change.apply(world),
     }
+}
 
-    fn restart(&mut self) {
-        self.clear_all();
-        self.spawn_random_boids(NUMBER_OF_BOIDS, NUMBER_OF_PREDATOR);
-    }
+#[cfg(feature = "remote")]
+fn start_remote(address: Option<&str>) -> Result<Option<remote::RemoteServer>, String> {
+    let Some(address) = address else {
+        return Ok(None);
+    };
+    remote::RemoteServer::start(address)
+        .map(Some)
+        .map_err(|error| format!("Cannot listen on {address}: {error}"))
+}
 
-    fn clear_all(&mut self) {
-        self.boids_quad_tree = QuadTree::new(QUAD_TREE_CAPACITY, self.boundary.clone());
-        self.predator_quad_tree = QuadTree::new(QUAD_TREE_CAPACITY, self.boundary.clone());
-    }
+fn start_osc(port: Option<u16>) -> Result<Option<osc::OscReceiver>, String> {
+    let Some(port) = port else {
+        return Ok(None);
+    };
+    osc::OscReceiver::bind(port)
+        .map(Some)
+        .map_err(|error| format!("Cannot listen for OSC on port {port}: {error}"))
+}
 
-    fn draw(&mut self, frame: &mut [u8]) {
-        let start_time = SystemTime::now();
-        self.background.draw(frame, WIDTH, HEIGHT);
-        self.boids_quad_tree.draw_with_option(frame, WIDTH, HEIGHT, &self.option);
-        self.predator_quad_tree.draw_with_option(frame, WIDTH, HEIGHT, &self.option);
-        let end_time = SystemTime::now();
-        Self::update_fps_count(&mut self.draw_fps, start_time, end_time);
-    }
+fn start_spectator_server(address: Option<&str>, sandbox: bool) -> Result<Option<spectator::SpectatorServer>, String> {
+    let Some(address) = address else {
+        return Ok(None);
+    };
+    spectator::SpectatorServer::bind(address, sandbox)
+        .map(Some)
+        .map_err(|error| format!("Cannot listen for spectators on {address}: {error}"))
+}
 
-    fn update(&mut self) {
-        let start_time = SystemTime::now();
-        let mut new_boids_quard_tree = QuadTree::new(QUAD_TREE_CAPACITY, self.boundary.clone());
-        let mut new_predator_quard_tree = QuadTree::new(QUAD_TREE_CAPACITY, self.boundary.clone());
-        for predator in self.predator_quad_tree.to_vec() {
-            let mut new_predator = predator.clone();
-            let mut found_boids: Vec<Boid> = vec![];
-            self.boids_quad_tree.query(&mut found_boids, &predator, self.option.fear_radius);
-            new_predator.cohesion(
-                &found_boids,
-                1.0,
-                self.option.predator_vision_radius,
-                self.option.predator_view_angle,
-            );
-            new_predator.speed_limit(self.option.predator_max_speed, self.option.predator_min_speed);
-            new_predator.update(WIDTH, HEIGHT);
-            new_predator_quard_tree.insert(&new_predator);
-        }
-        for boid in self.boids_quad_tree.to_vec() {
-            let mut new_boid = boid.clone();
-            let mut found_boids: Vec<Boid> = vec![];
-            let mut found_predators: Vec<Boid> = vec![];
-            self.boids_quad_tree.query(&mut found_boids, &boid, self.option.boid_vision_radius);
-            self.predator_quad_tree.query(&mut found_predators, &boid, self.option.fear_radius);
-            new_boid.separate(
-                &found_boids, 
-                self.option.avoid_factor, 
-                self.option.safe_radius, 
-                self.option.boid_view_angle
-            );
-            new_boid.align(
-                &found_boids,
-                self.option.matching_factor,
-                self.option.boid_vision_radius,
-                self.option.boid_view_angle,
-            );
-            new_boid.cohesion(
-                &found_boids,
-                self.option.centering_factor,
-                self.option.boid_vision_radius,
-                self.option.boid_view_angle,
-            );
-            new_boid.fear(
-                &found_predators, 
-                self.option.fear_factor, 
-                self.option.fear_radius, 
-            );
-            new_boid.noise(self.option.noise);
-            new_boid.speed_limit(self.option.boid_max_speed, self.option.boid_min_speed);
-            new_boid.avoid_border(self.option.turn_factor, self.option.margin, WIDTH, HEIGHT);
-            new_boid.update(WIDTH, HEIGHT);
-            new_boids_quard_tree.insert(&new_boid);
-        }
-        self.boids_quad_tree = new_boids_quard_tree.clone();
-        self.predator_quad_tree = new_predator_quard_tree.clone();
-        let end_time = SystemTime::now();
-        Self::update_fps_count(&mut self.update_fps, start_time, end_time);
-    }
+fn start_spectating(address: Option<&str>) -> Result<Option<spectator::SpectatorClient>, String> {
+    let Some(address) = address else {
+        return Ok(None);
+    };
+    spectator::SpectatorClient::connect(address)
+        .map(Some)
+        .map_err(|error| format!("Cannot spectate {address}: {error}"))
+}
 
-    fn update_fps_count(fps: &mut f32, start_time: SystemTime, end_time: SystemTime) {
-        match end_time.duration_since(start_time) {
-            Ok(duration) => {
-                *fps = 1.0 / duration.as_secs_f32();
-            }
-            Err(_) => {
-                println!("Cannot get duration");
-            }
-        }
+fn start_trajectories(directory: Option<&Path>, file_size: u64) -> Result<Option<TrajectoryWriter>, String> {
+    let Some(directory) = directory else {
+        return Ok(None);
+    };
+    TrajectoryWriter::create(directory, file_size.saturating_mul(1_000_000))
+        .map(Some)
+        .map_err(|error| format!("Cannot write trajectories to {}: {error}", directory.display()))
+}
+
+/// Close the trajectory files and tell where they are.
+fn finish_trajectories(trajectories: &TrajectoryRecording) {
+    match trajectories.finish() {
+        Ok(files) => match files.as_slice() {
+            [] => println!("No trajectories saved, nothing was updated"),
+            [file] => println!("Saved trajectories to {}", file.display()),
+            [first, ..] => println!("Saved trajectories to {} files like {}", files.len(), first.display()),
+        },
+        Err(error) => eprintln!("Cannot save trajectories: {error}"),
     }
 }
+
+/// Frame pixel under a cursor position in physical window pixels, which differ once the frame
+/// is scaled to a window of another size or scale factor. Clamped to the frame edges.
+fn frame_position(pixels: &Pixels, position: PhysicalPosition<f64>) -> (f32, f32) {
+    let (x, y) = pixels
+        .window_pos_to_pixel((position.x as f32, position.y as f32))
+        .unwrap_or_else(|outside| pixels.clamp_pixel_pos(outside));
+    (x as f32, y as f32)
+}
+
+/// Open the stats window next to the main one.
+#[cfg(not(target_arch = "wasm32"))]
+fn open_stats_window<T>(event_loop: &EventLoopWindowTarget<T>) -> Result<StatsWindow, String> {
+    let (width, height) = StatsWindow::SIZE;
+    let window = WindowBuilder::new()
+        .with_title(StatsWindow::TITLE)
+        .with_inner_size(PhysicalSize::new(width, height))
+        .build(event_loop)
+        .map_err(|error| format!("Cannot open the stats window: {error}"))?;
+    // Without vsync, so presenting it does not wait for a second refresh after the main window
+    let pixels = pollster::block_on(create_pixels(&window, false))
+        .map_err(|error| format!("Cannot set up rendering of the stats window: {error}"))?;
+    Ok(StatsWindow::new(event_loop, window, pixels))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn open_stats_window<T>(_: &EventLoopWindowTarget<T>) -> Result<StatsWindow, String> {
+    Err("The browser has a single window, open Statistics from the Setting menu instead".to_string())
+}
+
+/// Run `task` on a thread of its own, or right away where there are no threads.
+fn in_background(task: impl FnOnce() + Send + 'static) {
+    #[cfg(target_arch = "wasm32")]
+    task();
+    #[cfg(not(target_arch = "wasm32"))]
+    std::thread::spawn(task);
+}
+
+async fn create_pixels(window: &Window, vsync: bool) -> Result<Pixels, pixels::Error> {
+    let window_size = window.inner_size();
+    let surface_texture = SurfaceTexture::new(window_size.width, window_size.height, window);
+    PixelsBuilder::new(window_size.width, window_size.height, surface_texture)
+        .enable_vsync(vsync)
+        .build_async()
+        .await
+}
@@ -0,0 +1,164 @@
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use midir::{MidiInput, MidiInputConnection};
+use serde::{Deserialize, Serialize};
+
+use crate::lfo::Parameter;
+use crate::WorldOption;
+
+/// A control change of a MIDI controller, driving a parameter across its slider range.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MidiBinding {
+    // From 0, shown from 1 like on the devices
+    pub channel: u8,
+    pub controller: u8,
+    pub parameter: Parameter,
+}
+
+/// Control changes from every connected MIDI input, applied to the parameters they are bound to.
+///
+/// In learn mode the last control moved is remembered, and `learn` binds it to a parameter.
+pub struct MidiControls {
+    // Inputs stay open as long as their connections live
+    connections: Vec<MidiInputConnection<()>>,
+    port_names: Vec<String>,
+    sender: Sender<(u8, u8, u8)>,
+    // Channel, controller and value
+    messages: Receiver<(u8, u8, u8)>,
+    bindings: Vec<MidiBinding>,
+    learning: bool,
+    // Channel and controller last moved while learning
+    last_control: Option<(u8, u8)>,
+}
+
+impl MidiControls {
+    const CLIENT_NAME: &'static str = "boids";
+    const CONTROL_CHANGE: u8 = 0xB0;
+    const MAX_VALUE: f32 = 127.0;
+
+    /// Controls with no inputs connected yet.
+    pub fn new() -> Self {
+        let (sender, messages) = channel();
+        Self {
+            connections: vec![],
+            port_names: vec![],
+            sender,
+            messages,
+            bindings: vec![],
+            learning: false,
+            last_control: None,
+        }
+    }
+
+    /// Listen to every MIDI input there is now, replacing the ones connected before.
+    pub fn connect(&mut self) -> Result<(), String> {
+        self.connections.clear();
+        self.port_names.clear();
+        let port_count = MidiInput::new(Self::CLIENT_NAME)
+            .map_err(|error| error.to_string())?
+            .port_count();
+        for index in 0..port_count {
+            // Connecting takes the whole input, so there is one for each port
+            let input = MidiInput::new(Self::CLIENT_NAME).map_err(|error| error.to_string())?;
+            let Some(port) = input.ports().get(index).cloned() else {
+                continue;
+            };
+            let name = input.port_name(&port).unwrap_or_else(|_| format!("Input {}", index + 1));
+            let sender = self.sender.clone();
+            let connection = input
+                .connect(
+                    &port,
+                    Self::CLIENT_NAME,
+                    move |_, message, _| {
+                        if let [status, controller, value] = *message {
+                            if status & 0xF0 == Self::CONTROL_CHANGE {
+                                // Only fails once the controls are gone
+                                let _ = sender.send((status & 0x0F, controller, value));
+                            }
+                        }
+                    },
+                    (),
+                )
+                .map_err(|error| format!("Cannot connect to {name}: {error}"))?;
+            self.connections.push(connection);
+            self.port_names.push(name);
+        }
+        Ok(())
+    }
+
+    /// Names of the connected inputs.
+    pub fn port_names(&self) -> &[String] {
+        &self.port_names
+    }
+
+    pub fn bindings(&self) -> &[MidiBinding] {
+        &self.bindings
+    }
+
+    pub fn set_bindings(&mut self, bindings: Vec<MidiBinding>) {
+        self.bindings = bindings;
+    }
+
+    pub fn binding(&self, parameter: Parameter) -> Option<&MidiBinding> {
+        self.bindings.iter().find(|binding| binding.parameter == parameter)
+    }
+
+    pub fn is_learning(&self) -> bool {
+        self.learning
+    }
+
+    pub fn set_learning(&mut self, learning: bool) {
+        self.learning = learning;
+        self.last_control = None;
+    }
+
+    /// Channel and controller moved last in learn mode.
+    pub fn last_control(&self) -> Option<(u8, u8)> {
+        self.last_control
+    }
+
+    /// Bind the control moved last in learn mode to `parameter`, instead of whatever either was
+    /// bound to. False if no control moved yet.
+    pub fn learn(&mut self, parameter: Parameter) -> bool {
+        let Some((channel, controller)) = self.last_control else {
+            return false;
+        };
+        self.bindings.retain(|binding| {
+            binding.parameter != parameter && (binding.channel, binding.controller) != (channel, controller)
+        });
+        self.bindings.push(MidiBinding {
+            channel,
+            controller,
+            parameter,
+        });
+        true
+    }
+
+    pub fn unbind(&mut self, parameter: Parameter) {
+        self.bindings.retain(|binding| binding.parameter != parameter);
+    }
+
+    /// Apply the control changes received since the last call.
+    pub fn poll(&mut self, option: &mut WorldOption) {
+        while let Ok((channel, controller, value)) = self.messages.try_recv() {
+            if self.learning {
+                self.last_control = Some((channel, controller));
+            }
+            let bound = self
+                .bindings
+                .iter()
+                .filter(|binding| (binding.channel, binding.controller) == (channel, controller));
+            for binding in bound {
+                let range = binding.parameter.range();
+                let value = range.start() + value as f32 / Self::MAX_VALUE * (range.end() - range.start());
+                binding.parameter.set(option, value);
+            }
+        }
+    }
+}
+
+impl Default for MidiControls {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,98 @@
+use crate::{
+    geometry::{blend_pixel, Rectangle},
+    node::{QuadTree, RenderNode},
+    palette::Theme,
+    preset::Species,
+};
+
+/// Boid density overview drawn in the bottom right corner of the screen.
+pub struct Minimap {
+    bounds: Rectangle,
+    world_width: u16,
+    world_height: u16,
+}
+
+impl Minimap {
+    // Fraction of the screen taken by the minimap
+    const SCALE: f32 = 1.0 / 6.0;
+    const MARGIN: f32 = 10.0;
+
+    pub fn new(world_width: u16, world_height: u16) -> Self {
+        let half_width = (world_width as f32 * Self::SCALE / 2.0).round();
+        let half_height = (world_height as f32 * Self::SCALE / 2.0).round();
+        let bounds = Rectangle::new(
+            world_width as f32 - Self::MARGIN - half_width,
+            world_height as f32 - Self::MARGIN - half_height,
+            half_width,
+            half_height,
+        );
+        Self {
+            bounds,
+            world_width,
+            world_height,
+        }
+    }
+
+    /// World position shown at screen position (x, y), if it is on the minimap.
+    pub fn to_world(&self, x: f32, y: f32) -> Option<(f32, f32)> {
+        if !self.bounds.contains_point(x, y) {
+            return None;
+        }
+        let left = self.bounds.center_x - self.bounds.half_width;
+        let top = self.bounds.center_y - self.bounds.half_height;
+        Some((
+            (x - left) / (self.bounds.half_width * 2.0) * self.world_width as f32,
+            (y - top) / (self.bounds.half_height * 2.0) * self.world_height as f32,
+        ))
+    }
+
+    fn to_minimap(&self, x: f32, y: f32) -> (f32, f32) {
+        let left = self.bounds.center_x - self.bounds.half_width;
+        let top = self.bounds.center_y - self.bounds.half_height;
+        (
+            left + x / self.world_width as f32 * self.bounds.half_width * 2.0,
+            top + y / self.world_height as f32 * self.bounds.half_height * 2.0,
+        )
+    }
+
+    pub fn draw(
+        &self,
+        frame: &mut [u8],
+        index: &QuadTree,
+        viewport: &Rectangle,
+        theme: &Theme,
+    ) {
+        let (width, height) = (self.world_width, self.world_height);
+        self.bounds.fill(frame, width, height, theme.background.to_color_array(), 0.7);
+
+        // Each minimap pixel gets brighter with the number of boids it covers
+        let map_width = (self.bounds.half_width * 2.0) as usize;
+        let map_height = (self.bounds.half_height * 2.0) as usize;
+        let mut density = vec![0u16; map_width * map_height];
+        for boid in index.iter_of(Species::Boid) {
+            let x = (boid.vertice.x.max(0) as usize * map_width / width as usize).min(map_width - 1);
+            let y = (boid.vertice.y.max(0) as usize * map_height / height as usize).min(map_height - 1);
+            density[y * map_width + x] += 1;
+        }
+        let boid_color = theme.boid.to_color_array();
+        let predator_color = theme.predator.to_color_array();
+        let left = (self.bounds.center_x - self.bounds.half_width) as i32;
+        let top = (self.bounds.center_y - self.bounds.half_height) as i32;
+        for (index, &count) in density.iter().enumerate() {
+            if count > 0 {
+                let x = left + (index % map_width) as i32;
+                let y = top + (index / map_width) as i32;
+                blend_pixel(frame, x, y, width, height, boid_color, count as f32 / 4.0);
+            }
+        }
+        for predator in index.iter_of(Species::Predator) {
+            let (x, y) = self.to_minimap(predator.vertice.x as f32, predator.vertice.y as f32);
+            blend_pixel(frame, x as i32, y as i32, width, height, predator_color, 1.0);
+        }
+
+        let (center_x, center_y) = self.to_minimap(viewport.center_x, viewport.center_y);
+        let scale = self.bounds.half_width * 2.0 / width as f32;
+        Rectangle::new(center_x, center_y, viewport.half_width * scale, viewport.half_height * scale)
+            .draw(frame, width, height);
+    }
+}
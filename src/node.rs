@@ -1,4 +1,11 @@
-use crate::{boid::Boid, geometry::Rectangle, WorldOption};
+use crate::{
+    boid::Boid,
+    error::{Error, Result},
+    geometry::{Color, Rectangle, Text},
+    preset::{Species, SpeciesSet},
+    WorldOption,
+};
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
 pub trait RenderNode {
@@ -10,7 +17,8 @@ pub trait MovableNode {
     fn update(&mut self, _width: u16, _height: u16) {}
 }
 
-#[derive(Clone, PartialEq)]
+/// Integer point in world coordinates.
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Vertice {
     pub x: i16,
     pub y: i16,
@@ -37,258 +45,631 @@ impl Display for Vertice {
     }
 }
 
+/// How `QuadTree` lays out its nodes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IndexBackend {
+    // Nodes split into four once they hold `capacity` boids
+    QuadTree,
+    // Fixed square cells of `cell_size`, no splitting
+    Grid,
+}
+
+impl IndexBackend {
+    pub const ALL: [IndexBackend; 2] = [IndexBackend::QuadTree, IndexBackend::Grid];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            IndexBackend::QuadTree => "Quad tree",
+            IndexBackend::Grid => "Uniform grid",
+        }
+    }
+}
+
+/// Layout settings of a spatial index.
+#[derive(Clone, Copy, Debug)]
+pub struct IndexConfig {
+    pub backend: IndexBackend,
+    pub capacity: usize,
+    // Nodes this deep keep every boid instead of splitting
+    pub max_depth: u16,
+    pub cell_size: f32,
+}
+
+impl IndexConfig {
+    pub const DEFAULT_MAX_DEPTH: u16 = 16;
+    pub const DEFAULT_CELL_SIZE: f32 = 40.0;
+
+    pub fn quad_tree(capacity: usize) -> Self {
+        Self {
+            backend: IndexBackend::QuadTree,
+            capacity,
+            max_depth: Self::DEFAULT_MAX_DEPTH,
+            cell_size: Self::DEFAULT_CELL_SIZE,
+        }
+    }
+}
+
+/// Shape of an index, shown in the Spatial index window.
+pub struct IndexStats {
+    pub nodes: usize,
+    pub deepest: u16,
+    // Depth of the node holding each boid, averaged over the boids
+    pub average_depth: f32,
+}
+
 #[derive(Clone)]
-pub struct QuadTree {
-    capacity: usize,
+struct QuadTreeNode {
     boundary: Rectangle,
-    boids: Vec<Boid>,
-    top_right: Option<Box<QuadTree>>,
-    top_left: Option<Box<QuadTree>>,
-    bottom_right: Option<Box<QuadTree>>,
-    bottom_left: Option<Box<QuadTree>>,
-    splitted: bool,
+    // One list per species, in `Species::ALL` order
+    boids: [Vec<Boid>; Species::ALL.len()],
+    // Arena indices of the top left, top right, bottom left and bottom right children
+    children: Option<[usize; 4]>,
+    // Species that filled this node up and went on into its children
+    opened: [bool; Species::ALL.len()],
+    depth: u16,
 }
 
-impl QuadTree {
-    pub fn new(capacity: usize, boundary: Rectangle) -> Self {
+impl QuadTreeNode {
+    fn new(boundary: Rectangle, depth: u16) -> Self {
         Self {
-            capacity,
             boundary,
-            boids: vec![],
-            top_right: None,
-            top_left: None,
-            bottom_right: None,
-            bottom_left: None,
-            splitted: false,
+            boids: Default::default(),
+            children: None,
+            opened: [false; Species::ALL.len()],
+            depth,
         }
     }
 
-    pub fn insert(&mut self, boid: &Boid) -> bool {
-        if !self
-            .boundary
-            .contains_point(boid.vertice.x as f32, boid.vertice.y as f32)
-        {
-            return false;
-        }
-        if self.boids.len() < self.capacity {
-            self.boids.push(boid.clone());
-            return true;
-        }
-        if !self.splitted {
-            self.split();
-            self.splitted = true;
+    fn len(&self) -> usize {
+        self.boids.iter().map(Vec::len).sum()
+    }
+}
+
+/// Spatial index of the boids, a quadtree or a uniform grid depending on `IndexBackend`.
+///
+/// Every species fills and splits the nodes on its own, `capacity` is per species, so the
+/// boids of one species are placed, queried and iterated in the same order as in an index
+/// holding them alone.
+#[derive(Clone)]
+pub struct QuadTree {
+    config: IndexConfig,
+    // All nodes live in one arena, the root is always at index 0. A grid keeps its cells
+    // row by row after the root, which then holds no boids.
+    nodes: Vec<QuadTreeNode>,
+    // Arena indices of the nodes each species reached, in the order an index of that
+    // species alone would have made them
+    order: [Vec<usize>; Species::ALL.len()],
+    // Boids held of each species
+    counts: [usize; Species::ALL.len()],
+    columns: usize,
+    rows: usize,
+}
+
+impl QuadTree {
+    const ROOT: usize = 0;
+
+    /// Quadtree over `boundary` splitting nodes holding more than `capacity` boids.
+    pub fn new(capacity: usize, boundary: Rectangle) -> Self {
+        Self::with_config(IndexConfig::quad_tree(capacity), boundary)
+    }
+
+    pub fn with_config(config: IndexConfig, boundary: Rectangle) -> Self {
+        let mut quad_tree = Self {
+            config,
+            nodes: vec![QuadTreeNode::new(boundary, 0)],
+            order: Default::default(),
+            counts: [0; Species::ALL.len()],
+            columns: 0,
+            rows: 0,
+        };
+        if config.backend == IndexBackend::Grid {
+            quad_tree.build_cells();
         }
-        match &mut self.top_left {
-            Some(q_tree) => {
-                if q_tree.insert(boid) {
-                    return true;
-                }
-            }
-            None => {
-                panic!("Top left is not create");
+        quad_tree.reset_order();
+        quad_tree
+    }
+
+    fn build_cells(&mut self) {
+        let boundary = self.nodes[Self::ROOT].boundary.clone();
+        let cell_size = self.config.cell_size.max(1.0);
+        self.columns = (boundary.half_width * 2.0 / cell_size).ceil().max(1.0) as usize;
+        self.rows = (boundary.half_height * 2.0 / cell_size).ceil().max(1.0) as usize;
+        let left = boundary.center_x - boundary.half_width;
+        let top = boundary.center_y - boundary.half_height;
+        let half_cell = cell_size / 2.0;
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let cell = Rectangle::new(
+                    left + column as f32 * cell_size + half_cell,
+                    top + row as f32 * cell_size + half_cell,
+                    half_cell,
+                    half_cell,
+                );
+                self.nodes.push(QuadTreeNode::new(cell, 1));
             }
         }
-        match &mut self.top_right {
-            Some(q_tree) => {
-                if q_tree.insert(boid) {
-                    return true;
-                }
-            }
-            None => {
-                panic!("Top right is not create");
+    }
+
+    // Only the root is reached until a species splits it, a grid never splits
+    fn reset_order(&mut self) {
+        let reached = match self.config.backend {
+            IndexBackend::QuadTree => vec![Self::ROOT],
+            IndexBackend::Grid => (0..self.nodes.len()).collect(),
+        };
+        self.order = Species::ALL.map(|_| reached.clone());
+    }
+
+    pub fn config(&self) -> IndexConfig {
+        self.config
+    }
+
+    /// Remove every boid while keeping the root boundary and the arena allocation.
+    pub fn clear(&mut self) {
+        self.counts = [0; Species::ALL.len()];
+        if self.config.backend == IndexBackend::Grid {
+            for node in &mut self.nodes {
+                node.boids.iter_mut().for_each(Vec::clear);
             }
+            return;
         }
-        match &mut self.bottom_left {
-            Some(q_tree) => {
-                if q_tree.insert(boid) {
-                    return true;
-                }
-            }
-            None => {
-                panic!("Bottom left is not create");
-            }
+        self.nodes.truncate(1);
+        let root = &mut self.nodes[Self::ROOT];
+        root.boids.iter_mut().for_each(Vec::clear);
+        root.children = None;
+        root.opened = [false; Species::ALL.len()];
+        self.reset_order();
+    }
+
+    /// Arena indices of the grid cells overlapping the given area.
+    fn cells_within(&self, min_x: f32, min_y: f32, max_x: f32, max_y: f32) -> impl Iterator<Item = usize> {
+        let boundary = &self.nodes[Self::ROOT].boundary;
+        let left = boundary.center_x - boundary.half_width;
+        let top = boundary.center_y - boundary.half_height;
+        let cell_size = self.config.cell_size.max(1.0);
+        let column = |x: f32| ((x - left) / cell_size).floor().clamp(0.0, self.columns as f32 - 1.0) as usize;
+        let row = |y: f32| ((y - top) / cell_size).floor().clamp(0.0, self.rows as f32 - 1.0) as usize;
+        let (first_column, last_column) = (column(min_x), column(max_x));
+        let (first_row, last_row) = (row(min_y), row(max_y));
+        let columns = self.columns;
+        (first_row..=last_row)
+            .flat_map(move |row| (first_column..=last_column).map(move |column| 1 + row * columns + column))
+    }
+
+    /// Node count and depths, see `IndexStats`.
+    pub fn stats(&self) -> IndexStats {
+        let mut boids = 0;
+        let mut depth_sum = 0;
+        let mut deepest = 0;
+        for node in &self.nodes {
+            if node.children.is_none() {
+                deepest = deepest.max(node.depth);
+            }
+            boids += node.len();
+            depth_sum += node.len() * node.depth as usize;
         }
-        match &mut self.bottom_right {
-            Some(q_tree) => {
-                if q_tree.insert(boid) {
-                    return true;
-                }
-            }
-            None => {
-                panic!("Bottom right is not create");
+        IndexStats {
+            nodes: self.nodes.len(),
+            deepest,
+            average_depth: depth_sum as f32 / boids.max(1) as f32,
+        }
+    }
+
+    /// Number of boids held.
+    pub fn len(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    /// Number of boids of `species` held.
+    pub fn len_of(&self, species: Species) -> usize {
+        self.counts[species.index()]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Add a boid, an error when it lies outside the boundary.
+    pub fn insert(&mut self, boid: &Boid) -> Result<()> {
+        if self.insert_node(Self::ROOT, boid) {
+            self.counts[boid.species().index()] += 1;
+            return Ok(());
+        }
+        Err(Error::OutOfBounds {
+            x: boid.vertice.x as f32,
+            y: boid.vertice.y as f32,
+        })
+    }
+
+    fn insert_node(&mut self, index: usize, boid: &Boid) -> bool {
+        let species = boid.species().index();
+        let node = &mut self.nodes[index];
+        let (x, y) = (boid.vertice.x as f32, boid.vertice.y as f32);
+        if !node.boundary.contains_point(x, y) {
+            return false;
+        }
+        if self.config.backend == IndexBackend::Grid {
+            let cell = self.cells_within(x, y, x, y).next().unwrap_or(Self::ROOT);
+            self.nodes[cell].boids[species].push(boid.clone());
+            return true;
+        }
+        if node.boids[species].len() < self.config.capacity || node.depth >= self.config.max_depth {
+            node.boids[species].push(boid.clone());
+            return true;
+        }
+        let children = match node.children {
+            Some(children) => children,
+            None => self.split(index),
+        };
+        // The children may have been made by another species, this one reaches them now
+        if !self.nodes[index].opened[species] {
+            self.nodes[index].opened[species] = true;
+            self.order[species].extend(children);
+        }
+        for child in children {
+            if self.insert_node(child, boid) {
+                return true;
             }
         }
-        println!("{}", boid.vertice);
         false
     }
 
-    fn split(&mut self) {
-        let tr = Rectangle::new(
-            self.boundary.center_x + self.boundary.half_width / 2.0,
-            self.boundary.center_y + self.boundary.half_height / 2.0,
-            self.boundary.half_width / 2.0,
-            self.boundary.half_height / 2.0,
-        );
-        self.top_right = Some(Box::new(QuadTree::new(self.capacity, tr)));
+    fn split(&mut self, index: usize) -> [usize; 4] {
+        let boundary = self.nodes[index].boundary.clone();
+        let depth = self.nodes[index].depth + 1;
         let tl = Rectangle::new(
-            self.boundary.center_x - self.boundary.half_width / 2.0,
-            self.boundary.center_y + self.boundary.half_height / 2.0,
-            self.boundary.half_width / 2.0,
-            self.boundary.half_height / 2.0,
+            boundary.center_x - boundary.half_width / 2.0,
+            boundary.center_y + boundary.half_height / 2.0,
+            boundary.half_width / 2.0,
+            boundary.half_height / 2.0,
         );
-        self.top_left = Some(Box::new(QuadTree::new(self.capacity, tl)));
-        let br = Rectangle::new(
-            self.boundary.center_x + self.boundary.half_width / 2.0,
-            self.boundary.center_y - self.boundary.half_height / 2.0,
-            self.boundary.half_width / 2.0,
-            self.boundary.half_height / 2.0,
+        let tr = Rectangle::new(
+            boundary.center_x + boundary.half_width / 2.0,
+            boundary.center_y + boundary.half_height / 2.0,
+            boundary.half_width / 2.0,
+            boundary.half_height / 2.0,
         );
-        self.bottom_right = Some(Box::new(QuadTree::new(self.capacity, br)));
         let bl = Rectangle::new(
-            self.boundary.center_x - self.boundary.half_width / 2.0,
-            self.boundary.center_y - self.boundary.half_height / 2.0,
-            self.boundary.half_width / 2.0,
-            self.boundary.half_height / 2.0,
+            boundary.center_x - boundary.half_width / 2.0,
+            boundary.center_y - boundary.half_height / 2.0,
+            boundary.half_width / 2.0,
+            boundary.half_height / 2.0,
+        );
+        let br = Rectangle::new(
+            boundary.center_x + boundary.half_width / 2.0,
+            boundary.center_y - boundary.half_height / 2.0,
+            boundary.half_width / 2.0,
+            boundary.half_height / 2.0,
         );
-        self.bottom_left = Some(Box::new(QuadTree::new(self.capacity, bl)));
+        let first = self.nodes.len();
+        self.nodes.extend([tl, tr, bl, br].map(|boundary| QuadTreeNode::new(boundary, depth)));
+        let children = [first, first + 1, first + 2, first + 3];
+        self.nodes[index].children = Some(children);
+        children
     }
 
+    /// Push the boids around `boid` within about `vision_radius` into `found`.
     pub fn query(&self, found: &mut Vec<Boid>, boid: &Boid, vision_radius: f32) {
-        if !self.boundary.intersects(&boid.vertice, vision_radius) && !self.boundary.contains_point(boid.vertice.x as f32, boid.vertice.y as f32) || vision_radius == 0.0 {
+        self.query_species(found, boid, vision_radius, SpeciesSet::ALL);
+    }
+
+    /// Like `query`, keeping only the boids of `species`. Prey only is `Species::Boid.into()`
+    /// and the species of `boid` alone is `boid.species().into()`.
+    pub fn query_species(&self, found: &mut Vec<Boid>, boid: &Boid, vision_radius: f32, species: SpeciesSet) {
+        if species == SpeciesSet::NONE {
             return;
         }
-        for other_boid in &self.boids {
-            if other_boid != boid {
-                found.push(other_boid.clone());
+        if self.config.backend == IndexBackend::Grid {
+            if vision_radius == 0.0 {
+                return;
+            }
+            let (x, y) = (boid.vertice.x as f32, boid.vertice.y as f32);
+            for cell in self.cells_within(x - vision_radius, y - vision_radius, x + vision_radius, y + vision_radius) {
+                Self::extend_node(&self.nodes[cell], found, boid, species);
             }
+            return;
         }
-        if !self.splitted {
+        self.query_node(Self::ROOT, found, boid, vision_radius, species);
+    }
+
+    fn query_node(&self, index: usize, found: &mut Vec<Boid>, boid: &Boid, vision_radius: f32, species: SpeciesSet) {
+        let node = &self.nodes[index];
+        if vision_radius == 0.0 || !node.boundary.intersects(&boid.vertice, vision_radius) {
             return;
         }
-        match &self.top_left {
-            Some(q_tree) => {
-                q_tree.query(found, boid, vision_radius);
-            }
-            None => {
-                panic!("Top left is not create");
+        Self::extend_node(node, found, boid, species);
+        if let Some(children) = node.children {
+            for child in children {
+                self.query_node(child, found, boid, vision_radius, species);
             }
         }
-        match &self.top_right {
-            Some(q_tree) => {
-                q_tree.query(found, boid, vision_radius);
-            }
-            None => {
-                panic!("Top right is not create");
+    }
+
+    // The boids of `node` of `species` other than `boid`
+    fn extend_node(node: &QuadTreeNode, found: &mut Vec<Boid>, boid: &Boid, species: SpeciesSet) {
+        for (kind, boids) in Species::ALL.into_iter().zip(&node.boids) {
+            if species.contains(kind) {
+                found.extend(boids.iter().filter(|other_boid| *other_boid != boid).cloned());
             }
         }
-        match &self.bottom_left {
-            Some(q_tree) => {
-                q_tree.query(found, boid, vision_radius);
-            }
-            None => {
-                panic!("Bottom left is not create");
-            }
+    }
+
+    /// Move the boids within `radius` of (x, y) into `removed`, only visiting the nodes around it.
+    pub fn remove_within(&mut self, removed: &mut Vec<Boid>, x: f32, y: f32, radius: f32) {
+        let area = Rectangle::new(x, y, radius, radius);
+        let start = removed.len();
+        if self.config.backend == IndexBackend::Grid {
+            for cell in self
+                .cells_within(x - radius, y - radius, x + radius, y + radius)
+                .collect::<Vec<_>>()
+            {
+                self.remove_node(cell, removed, &area, radius);
+            }
+        } else {
+            self.remove_node(Self::ROOT, removed, &area, radius);
         }
-        match &self.bottom_right {
-            Some(q_tree) => {
-                q_tree.query(found, boid, vision_radius);
-            }
-            None => {
-                panic!("Bottom right is not create");
-            }
+        for boid in &removed[start..] {
+            self.counts[boid.species().index()] -= 1;
         }
     }
 
-    pub fn to_vec(&self) -> Vec<Boid> {
-        let mut boids: Vec<Boid> = vec![];
-        for boid in &self.boids {
-            boids.push(boid.clone());
+    fn remove_node(&mut self, index: usize, removed: &mut Vec<Boid>, area: &Rectangle, radius: f32) {
+        let node = &mut self.nodes[index];
+        if !node.boundary.overlaps(area) {
+            return;
         }
-        if !self.splitted {
-            return boids;
+        for boids in &mut node.boids {
+            boids.retain(|boid| {
+                let dx = boid.vertice.x as f32 - area.center_x;
+                let dy = boid.vertice.y as f32 - area.center_y;
+                let outside = (dx * dx + dy * dy).sqrt() > radius;
+                if !outside {
+                    removed.push(boid.clone());
+                }
+                outside
+            });
         }
-        match &self.top_left {
-            Some(q_tree) => {
-                boids.append(&mut q_tree.to_vec());
-            }
-            None => {
-                panic!("Top left is not create");
+        if let Some(children) = node.children {
+            for child in children {
+                self.remove_node(child, removed, area, radius);
             }
         }
-        match &self.top_right {
-            Some(q_tree) => {
-                boids.append(&mut q_tree.to_vec());
+    }
+
+    /// Boids inside `area`, only visiting the nodes overlapping it.
+    pub fn query_rectangle(&self, found: &mut Vec<Boid>, area: &Rectangle) {
+        if self.config.backend == IndexBackend::Grid {
+            let (min_x, min_y) = (area.center_x - area.half_width, area.center_y - area.half_height);
+            let (max_x, max_y) = (area.center_x + area.half_width, area.center_y + area.half_height);
+            for cell in self.cells_within(min_x, min_y, max_x, max_y) {
+                self.query_rectangle_node(cell, found, area);
             }
-            None => {
-                panic!("Top right is not create");
+            return;
+        }
+        self.query_rectangle_node(Self::ROOT, found, area);
+    }
+
+    fn query_rectangle_node(&self, index: usize, found: &mut Vec<Boid>, area: &Rectangle) {
+        let node = &self.nodes[index];
+        if !node.boundary.overlaps(area) {
+            return;
+        }
+        found.extend(
+            node.boids
+                .iter()
+                .flatten()
+                .filter(|boid| area.contains_point(boid.vertice.x as f32, boid.vertice.y as f32))
+                .cloned(),
+        );
+        if let Some(children) = node.children {
+            for child in children {
+                self.query_rectangle_node(child, found, area);
             }
         }
-        match &self.bottom_left {
-            Some(q_tree) => {
-                boids.append(&mut q_tree.to_vec());
+    }
+
+    /// Boundary of every node and how many boids it holds, the root first. A grid gives its
+    /// cells.
+    pub fn boundaries(&self) -> impl Iterator<Item = (&Rectangle, usize)> {
+        let skipped = usize::from(self.config.backend == IndexBackend::Grid);
+        self.nodes.iter().skip(skipped).map(|node| (&node.boundary, node.len()))
+    }
+
+    /// Every boid held, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &Boid> {
+        self.nodes.iter().flat_map(|node| node.boids.iter().flatten())
+    }
+
+    /// Every boid of `species` held, in no particular order.
+    pub fn iter_of(&self, species: Species) -> impl Iterator<Item = &Boid> {
+        let species = species.index();
+        self.order[species]
+            .iter()
+            .flat_map(move |index| self.nodes[*index].boids[species].iter())
+    }
+
+    /// Visit every boid without cloning them.
+    pub fn for_each(&self, mut f: impl FnMut(&Boid)) {
+        for node in &self.nodes {
+            node.boids.iter().flatten().for_each(&mut f);
+        }
+    }
+
+    /// Change boids in place, `f` must not move them or change their species.
+    pub fn for_each_mut(&mut self, mut f: impl FnMut(&mut Boid)) {
+        for node in &mut self.nodes {
+            node.boids.iter_mut().flatten().for_each(&mut f);
+        }
+    }
+
+    /// Keep only the boids for which `keep` returns true.
+    pub fn retain(&mut self, mut keep: impl FnMut(&Boid) -> bool) {
+        for node in &mut self.nodes {
+            for (boids, count) in node.boids.iter_mut().zip(&mut self.counts) {
+                boids.retain(|boid| {
+                    let kept = keep(boid);
+                    *count -= usize::from(!kept);
+                    kept
+                });
             }
-            None => {
-                panic!("Bottom left is not create");
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<Boid> {
+        self.collect(SpeciesSet::ALL)
+    }
+
+    /// Every boid of `species`, in the order `QuadTree::to_vec` gives them.
+    pub fn to_vec_of(&self, species: Species) -> Vec<Boid> {
+        self.collect(species.into())
+    }
+
+    fn collect(&self, species: SpeciesSet) -> Vec<Boid> {
+        let mut boids: Vec<Boid> = vec![];
+        if self.config.backend == IndexBackend::Grid {
+            for node in &self.nodes {
+                Self::collect_boids(node, &mut boids, species);
             }
+            return boids;
         }
-        match &self.bottom_right {
-            Some(q_tree) => {
-                boids.append(&mut q_tree.to_vec());
+        self.collect_node(Self::ROOT, &mut boids, species);
+        boids
+    }
+
+    fn collect_node(&self, index: usize, boids: &mut Vec<Boid>, species: SpeciesSet) {
+        let node = &self.nodes[index];
+        Self::collect_boids(node, boids, species);
+        if let Some(children) = node.children {
+            for child in children {
+                self.collect_node(child, boids, species);
             }
-            None => {
-                panic!("Bottom right is not create");
+        }
+    }
+
+    fn collect_boids(node: &QuadTreeNode, boids: &mut Vec<Boid>, species: SpeciesSet) {
+        for (kind, held) in Species::ALL.into_iter().zip(&node.boids) {
+            if species.contains(kind) {
+                boids.extend(held.iter().cloned());
             }
         }
-        boids
     }
 
     pub fn draw_quad_tree(&self, frame: &mut [u8], width: u16, height: u16) {
-        self.boundary.draw(frame, width, height);
+        self.nodes[Self::ROOT].boundary.draw(frame, width, height);
     }
-}
 
-impl RenderNode for QuadTree {
-    fn draw_with_option(&self, frame: &mut [u8], width: u16, height: u16, _world_option: &WorldOption) {
-        if _world_option.show_quad_tree {
-            self.draw_quad_tree(frame, width, height);
+    /// Draw only the nodes overlapping `viewport`, given in world coordinates. Without `bodies`
+    /// only the debug overlays of the boids are drawn. Species are drawn one after the other,
+    /// predators over boids.
+    pub fn draw_visible(
+        &self,
+        frame: &mut [u8],
+        width: u16,
+        height: u16,
+        viewport: &Rectangle,
+        world_option: &WorldOption,
+        bodies: bool,
+    ) {
+        // Debug overlays reach past the boid itself so grow the viewport to keep them
+        let largest_size = world_option.boid_size.max(world_option.predator_size) as f32
+            * (1.0 + world_option.size_variation);
+        let mut reach = largest_size + world_option.debug_line_width;
+        if world_option.show_safe_radius {
+            reach = reach.max(world_option.safe_radius + largest_size + world_option.debug_line_width);
         }
-        for boid in &self.boids {
-            boid.draw_with_option(frame, width, width, _world_option);
+        if world_option.show_vision_radius {
+            reach = reach.max(world_option.boid_vision_radius + world_option.debug_line_width);
         }
-        if !self.splitted {
-            return;
+        if world_option.show_facing_direction_with_speed {
+            let max_speed = world_option.boid_max_speed.max(world_option.predator_max_speed);
+            reach = reach.max(max_speed as f32 + world_option.debug_line_width);
         }
-        match &self.top_left {
-            Some(q_tree) => {
-                q_tree.draw_with_option(frame, width, width, _world_option);
-            }
-            None => {
-                panic!("Top left is not create");
+        let viewport = Rectangle::new(
+            viewport.center_x,
+            viewport.center_y,
+            viewport.half_width + reach,
+            viewport.half_height + reach,
+        );
+        if self.config.backend == IndexBackend::Grid {
+            let (min_x, min_y) = (viewport.center_x - viewport.half_width, viewport.center_y - viewport.half_height);
+            let (max_x, max_y) = (viewport.center_x + viewport.half_width, viewport.center_y + viewport.half_height);
+            for species in Species::ALL {
+                for cell in self.cells_within(min_x, min_y, max_x, max_y) {
+                    self.draw_node(cell, frame, width, height, &viewport, world_option, bodies, species);
+                }
             }
+            return;
         }
-        match &self.top_right {
-            Some(q_tree) => {
-                q_tree.draw_with_option(frame, width, width, _world_option);
-            }
-            None => {
-                panic!("Top right is not create");
-            }
+        for species in Species::ALL {
+            self.draw_node(Self::ROOT, frame, width, height, &viewport, world_option, bodies, species);
         }
-        match &self.bottom_left {
-            Some(q_tree) => {
-                q_tree.draw_with_option(frame, width, width, _world_option);
-            }
-            None => {
-                panic!("Bottom left is not create");
-            }
+    }
+
+    fn draw_boundary(
+        &self,
+        node: &QuadTreeNode,
+        frame: &mut [u8],
+        width: u16,
+        height: u16,
+        world_option: &WorldOption,
+    ) {
+        let theme = world_option.palette.theme();
+        let color = if world_option.show_quad_tree_occupancy {
+            // Empty to full at capacity
+            let fullness = node.len() as f32 / self.config.capacity.max(1) as f32;
+            Color::gradient(&theme.quad_tree_empty, &theme.quad_tree_full, fullness)
+        } else {
+            theme.quad_tree
+        };
+        node.boundary
+            .draw_stroke(frame, width, height, world_option.debug_line_width, &color, world_option);
+        if world_option.show_quad_tree_counts && node.len() > 0 {
+            let left = node.boundary.center_x - node.boundary.half_width;
+            let top = node.boundary.center_y - node.boundary.half_height;
+            let count = Text::new(left as i32 + 3, top as i32 + 3, 1, node.len().to_string(), color);
+            count.draw(frame, width, height);
         }
-        match &self.bottom_right {
-            Some(q_tree) => {
-                q_tree.draw_with_option(frame, width, width, _world_option);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn draw_node(
+        &self,
+        index: usize,
+        frame: &mut [u8],
+        width: u16,
+        height: u16,
+        viewport: &Rectangle,
+        world_option: &WorldOption,
+        bodies: bool,
+        species: Species,
+    ) {
+        let node = &self.nodes[index];
+        if !node.boundary.overlaps(viewport) {
+            return;
+        }
+        // Under the bodies of every species
+        if world_option.show_quad_tree && species == Species::ALL[0] {
+            self.draw_boundary(node, frame, width, height, world_option);
+        }
+        for boid in &node.boids[species.index()] {
+            if bodies {
+                boid.draw_with_option(frame, width, height, world_option);
+            } else {
+                boid.draw_overlays(frame, width, height, world_option);
             }
-            None => {
-                panic!("Bottom right is not create");
+        }
+        if let Some(children) = node.children {
+            for child in children {
+                self.draw_node(child, frame, width, height, viewport, world_option, bodies, species);
             }
         }
     }
 }
+
+impl RenderNode for QuadTree {
+    fn draw_with_option(&self, frame: &mut [u8], width: u16, height: u16, world_option: &WorldOption) {
+        let half_width = width as f32 / 2.0;
+        let half_height = height as f32 / 2.0;
+        let viewport = Rectangle::new(half_width, half_height, half_width, half_height);
+        self.draw_visible(frame, width, height, &viewport, world_option, true);
+    }
+}
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    geometry::{Circle, Disc},
+    node::RenderNode,
+    WorldOption,
+};
+
+/// A round area boids and predators steer around.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Obstacle {
+    pub x: f32,
+    pub y: f32,
+    pub radius: f32,
+}
+
+impl Obstacle {
+    pub const DEFAULT_RADIUS: f32 = 30.0;
+
+    pub fn new(x: f32, y: f32) -> Self {
+        Self {
+            x,
+            y,
+            radius: Self::DEFAULT_RADIUS,
+        }
+    }
+}
+
+impl RenderNode for Obstacle {
+    fn draw_with_option(&self, frame: &mut [u8], width: u16, height: u16, world_option: &WorldOption) {
+        let color = world_option.palette.theme().obstacle;
+        Disc::new(self.x, self.y, self.radius, color).draw(frame, width, height);
+    }
+}
+
+/// Pulls the boids within `radius` towards its center.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Attractor {
+    pub x: f32,
+    pub y: f32,
+    pub radius: f32,
+    // Fraction of the distance to the center added to the velocity each update
+    pub strength: f32,
+}
+
+impl Attractor {
+    pub const DEFAULT_RADIUS: f32 = 150.0;
+    pub const DEFAULT_STRENGTH: f32 = 0.02;
+
+    pub fn new(x: f32, y: f32) -> Self {
+        Self {
+            x,
+            y,
+            radius: Self::DEFAULT_RADIUS,
+            strength: Self::DEFAULT_STRENGTH,
+        }
+    }
+}
+
+impl RenderNode for Attractor {
+    fn draw_with_option(&self, frame: &mut [u8], width: u16, height: u16, world_option: &WorldOption) {
+        let color = world_option.palette.theme().attractor;
+        Disc::new(self.x, self.y, 4.0, color.clone()).draw(frame, width, height);
+        Circle::new(self.x, self.y, self.radius, 1.0, color).draw_with_option(frame, width, height, world_option);
+    }
+}
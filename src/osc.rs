@@ -0,0 +1,113 @@
+use std::io;
+use std::net::UdpSocket;
+
+use boids::lfo::Parameter;
+use boids::World;
+
+/// Takes OSC messages over UDP, from TouchOSC, Max, Ableton and the like.
+///
+/// `/boids/<parameter>` with a number from 0 to 1 moves that parameter across its slider range,
+/// like a fader. `/boids/restart` and `/boids/pause` act when sent with no value or a value
+/// other than 0, so a button acts on press but not on release.
+pub struct OscReceiver {
+    socket: UdpSocket,
+    buffer: Vec<u8>,
+}
+
+impl OscReceiver {
+    // Largest UDP payload
+    const MAX_PACKET_SIZE: usize = 65_536;
+    const PREFIX: &'static str = "/boids/";
+
+    /// Listen on `port` of every network interface.
+    pub fn bind(port: u16) -> io::Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", port))?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            buffer: vec![0; Self::MAX_PACKET_SIZE],
+        })
+    }
+
+    /// Apply the messages received since the last call.
+    pub fn poll(&mut self, world: &mut World) {
+        let mut messages = vec![];
+        while let Ok(size) = self.socket.recv(&mut self.buffer) {
+            // Malformed packets are dropped, there is no one to tell
+            parse_packet(&self.buffer[..size], &mut messages);
+        }
+        for (address, arguments) in messages {
+            apply(&address, &arguments, world);
+        }
+    }
+}
+
+fn apply(address: &str, arguments: &[f32], world: &mut World) {
+    let Some(name) = address.strip_prefix(OscReceiver::PREFIX) else {
+        return;
+    };
+    // Buttons send 1 when pressed and 0 when released
+    let pressed = arguments.first().is_none_or(|value| *value != 0.0);
+    match name {
+        "restart" if pressed => world.restart(),
+        "pause" if pressed => world.toggle_pause(),
+        _ => {
+            let parameter = Parameter::from_key(name);
+            if let (Some(parameter), Some(value)) = (parameter, arguments.first()) {
+                let range = parameter.range();
+                let value = range.start() + value.clamp(0.0, 1.0) * (range.end() - range.start());
+                parameter.set(&mut world.option, value);
+            }
+        }
+    }
+}
+
+/// Add the messages of an OSC packet to `messages`, with bundles unpacked and only the
+/// numeric arguments kept. Stops at the first malformed part.
+fn parse_packet(packet: &[u8], messages: &mut Vec<(String, Vec<f32>)>) -> Option<()> {
+    if packet.starts_with(b"#bundle\0") {
+        // The time tag after the name is ignored, messages apply as soon as they arrive
+        let mut position = 16;
+        while position < packet.len() {
+            let size = u32::from_be_bytes(read_bytes(packet, &mut position)?) as usize;
+            parse_packet(packet.get(position..position + size)?, messages)?;
+            position += size;
+        }
+        return Some(());
+    }
+    let mut position = 0;
+    let address = read_string(packet, &mut position)?.to_string();
+    // Very old senders leave out the type tags
+    let tags = read_string(packet, &mut position).unwrap_or(",");
+    let mut arguments = vec![];
+    for tag in tags.strip_prefix(',')?.chars() {
+        let value = match tag {
+            'f' => f32::from_be_bytes(read_bytes(packet, &mut position)?),
+            'i' => i32::from_be_bytes(read_bytes(packet, &mut position)?) as f32,
+            'd' => f64::from_be_bytes(read_bytes(packet, &mut position)?) as f32,
+            'h' => i64::from_be_bytes(read_bytes(packet, &mut position)?) as f32,
+            'T' => 1.0,
+            'F' => 0.0,
+            // Strings, blobs and the rest carry no number, the arguments after them are lost
+            _ => break,
+        };
+        arguments.push(value);
+    }
+    messages.push((address, arguments));
+    Some(())
+}
+
+// Null terminated and padded to a multiple of 4 bytes
+fn read_string<'a>(packet: &'a [u8], position: &mut usize) -> Option<&'a str> {
+    let rest = packet.get(*position..)?;
+    let length = rest.iter().position(|byte| *byte == 0)?;
+    let text = std::str::from_utf8(&rest[..length]).ok()?;
+    *position += (length + 4) & !3;
+    Some(text)
+}
+
+fn read_bytes<const N: usize>(packet: &[u8], position: &mut usize) -> Option<[u8; N]> {
+    let bytes = packet.get(*position..*position + N)?.try_into().ok()?;
+    *position += N;
+    Some(bytes)
+}
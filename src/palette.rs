@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+
+use crate::geometry::Color;
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Palette {
+    Classic,
+    Pastel,
+    Neon,
+    ColorblindSafe,
+}
+
+/// Colors of everything drawn into the frame for one palette
+pub struct Theme {
+    pub background: Color,
+    pub grid: Color,
+    pub boid: Color,
+    pub predator: Color,
+    // Ends of the speed gradient in `ColorMode::Speed`
+    pub slow: Color,
+    pub fast: Color,
+    pub highlight: Color,
+    pub text: Color,
+    pub safe_radius: Color,
+    pub vision: Color,
+    pub quad_tree: Color,
+    pub quad_tree_empty: Color,
+    pub quad_tree_full: Color,
+    // Neighbor lines of the selected boid, mixed when a neighbor feeds several rules
+    pub separation: Color,
+    pub alignment: Color,
+    pub cohesion: Color,
+    pub obstacle: Color,
+    pub attractor: Color,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 4] = [
+        Palette::Classic,
+        Palette::Pastel,
+        Palette::Neon,
+        Palette::ColorblindSafe,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Palette::Classic => "Classic",
+            Palette::Pastel => "Pastel",
+            Palette::Neon => "Neon",
+            Palette::ColorblindSafe => "Colorblind safe",
+        }
+    }
+
+    pub fn theme(&self) -> Theme {
+        match self {
+            Palette::Classic => Theme {
+                background: Color::Black,
+                grid: Color::Rgba(40, 40, 40, 255),
+                boid: Color::Green,
+                predator: Color::Red,
+                slow: Color::Red,
+                fast: Color::Green,
+                highlight: Color::Rgba(255, 255, 0, 255),
+                text: Color::White,
+                safe_radius: Color::Red,
+                vision: Color::Blue,
+                quad_tree: Color::White,
+                quad_tree_empty: Color::Green,
+                quad_tree_full: Color::Red,
+                separation: Color::Red,
+                alignment: Color::Blue,
+                cohesion: Color::Green,
+                obstacle: Color::Rgba(110, 110, 110, 255),
+                attractor: Color::Rgba(255, 200, 0, 255),
+            },
+            Palette::Pastel => Theme {
+                background: Color::Rgba(40, 42, 54, 255),
+                grid: Color::Rgba(62, 64, 80, 255),
+                boid: Color::Rgba(170, 230, 180, 255),
+                predator: Color::Rgba(255, 170, 170, 255),
+                slow: Color::Rgba(255, 179, 186, 255),
+                fast: Color::Rgba(186, 255, 201, 255),
+                highlight: Color::Rgba(255, 240, 170, 255),
+                text: Color::Rgba(240, 240, 240, 255),
+                safe_radius: Color::Rgba(255, 170, 170, 255),
+                vision: Color::Rgba(174, 198, 255, 255),
+                quad_tree: Color::Rgba(200, 200, 220, 255),
+                quad_tree_empty: Color::Rgba(186, 255, 201, 255),
+                quad_tree_full: Color::Rgba(255, 179, 186, 255),
+                separation: Color::Rgba(255, 140, 140, 255),
+                alignment: Color::Rgba(140, 140, 255, 255),
+                cohesion: Color::Rgba(140, 255, 140, 255),
+                obstacle: Color::Rgba(120, 120, 140, 255),
+                attractor: Color::Rgba(255, 220, 150, 255),
+            },
+            Palette::Neon => Theme {
+                background: Color::Rgba(5, 0, 20, 255),
+                grid: Color::Rgba(40, 0, 70, 255),
+                boid: Color::Rgba(57, 255, 20, 255),
+                predator: Color::Rgba(255, 0, 144, 255),
+                slow: Color::Rgba(255, 0, 144, 255),
+                fast: Color::Rgba(0, 255, 255, 255),
+                highlight: Color::Rgba(255, 255, 0, 255),
+                text: Color::Rgba(0, 255, 255, 255),
+                safe_radius: Color::Rgba(255, 0, 144, 255),
+                vision: Color::Rgba(0, 170, 255, 255),
+                quad_tree: Color::Rgba(140, 0, 255, 255),
+                quad_tree_empty: Color::Rgba(0, 255, 255, 255),
+                quad_tree_full: Color::Rgba(255, 0, 144, 255),
+                separation: Color::Rgba(255, 0, 144, 255),
+                alignment: Color::Rgba(0, 0, 255, 255),
+                cohesion: Color::Rgba(0, 255, 0, 255),
+                obstacle: Color::Rgba(60, 60, 90, 255),
+                attractor: Color::Rgba(255, 255, 0, 255),
+            },
+            // Okabe-Ito colors, told apart with any kind of color vision deficiency
+            Palette::ColorblindSafe => Theme {
+                background: Color::Black,
+                grid: Color::Rgba(40, 40, 40, 255),
+                boid: Color::Rgba(86, 180, 233, 255),
+                predator: Color::Rgba(230, 159, 0, 255),
+                slow: Color::Rgba(213, 94, 0, 255),
+                fast: Color::Rgba(86, 180, 233, 255),
+                highlight: Color::Rgba(240, 228, 66, 255),
+                text: Color::White,
+                safe_radius: Color::Rgba(213, 94, 0, 255),
+                vision: Color::Rgba(0, 114, 178, 255),
+                quad_tree: Color::White,
+                quad_tree_empty: Color::Rgba(0, 114, 178, 255),
+                quad_tree_full: Color::Rgba(230, 159, 0, 255),
+                separation: Color::Rgba(213, 94, 0, 255),
+                alignment: Color::Rgba(0, 114, 178, 255),
+                cohesion: Color::Rgba(0, 158, 115, 255),
+                obstacle: Color::Rgba(120, 120, 120, 255),
+                attractor: Color::Rgba(240, 228, 66, 255),
+            },
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use rand::Rng;
+
+use crate::{
+    geometry::{blend_pixel, Color},
+    node::RenderNode,
+};
+
+#[derive(Clone)]
+struct Particle {
+    x: f32,
+    y: f32,
+    velocity_x: f32,
+    velocity_y: f32,
+    // Remaining and starting lifetime in updates
+    life: u16,
+    max_life: u16,
+    color: [u8; 4],
+}
+
+/// Short-lived fading pixels, e.g. the burst left behind by a catch.
+#[derive(Clone, Default)]
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+}
+
+impl ParticleSystem {
+    const BURST_SIZE: std::ops::RangeInclusive<usize> = 8..=16;
+    const LIFE: std::ops::RangeInclusive<u16> = 15..=30;
+    const SPEED: std::ops::RangeInclusive<f32> = 0.5..=2.5;
+    // Slows the particles down a little every update
+    const DRAG: f32 = 0.92;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+
+    /// Emit a burst of particles flying out of (x, y).
+    pub fn burst(&mut self, x: f32, y: f32, color: &Color) {
+        // Only for looks, kept off the world's random numbers so hiding particles does not change a seeded run
+        let mut rng = rand::thread_rng();
+        let color = color.to_color_array();
+        for _ in 0..rng.gen_range(Self::BURST_SIZE) {
+            let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+            let speed = rng.gen_range(Self::SPEED);
+            let life = rng.gen_range(Self::LIFE);
+            self.particles.push(Particle {
+                x,
+                y,
+                velocity_x: angle.cos() * speed,
+                velocity_y: angle.sin() * speed,
+                life,
+                max_life: life,
+                color,
+            });
+        }
+    }
+
+    /// Move every particle by `dx`, `dy` at once.
+    pub fn shift(&mut self, dx: f32, dy: f32) {
+        for particle in &mut self.particles {
+            particle.x += dx;
+            particle.y += dy;
+        }
+    }
+
+    /// Move every particle and drop the ones that faded out.
+    pub fn update(&mut self) {
+        for particle in &mut self.particles {
+            particle.x += particle.velocity_x;
+            particle.y += particle.velocity_y;
+            particle.velocity_x *= Self::DRAG;
+            particle.velocity_y *= Self::DRAG;
+            particle.life -= 1;
+        }
+        self.particles.retain(|particle| particle.life > 0);
+    }
+}
+
+impl RenderNode for ParticleSystem {
+    fn draw(&self, frame: &mut [u8], width: u16, height: u16) {
+        for particle in &self.particles {
+            let coverage = particle.life as f32 / particle.max_life as f32;
+            blend_pixel(
+                frame,
+                particle.x.round() as i32,
+                particle.y.round() as i32,
+                width,
+                height,
+                particle.color,
+                coverage,
+            );
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::thread::{self, JoinHandle};
+
+use crate::{RunState, World};
+
+/// Runs `World::advance` on a worker thread so the main thread can draw and
+/// present the previous frame in the meantime. Without threads, like in the
+/// browser, updates run on the main thread instead.
+pub struct UpdatePipeline {
+    sender: Sender<World>,
+    receiver: Receiver<World>,
+    busy: bool,
+    // No worker could be started or it stopped, updates run on the main thread
+    inline: bool,
+    _worker: Option<JoinHandle<()>>,
+}
+
+impl UpdatePipeline {
+    pub fn new() -> Self {
+        let (snapshot_sender, snapshot_receiver) = channel::<World>();
+        let (result_sender, result_receiver) = channel::<World>();
+        let worker = thread::Builder::new()
+            .name("world-update".to_string())
+            .spawn(move || {
+                for mut world in snapshot_receiver {
+                    world.advance();
+                    if result_sender.send(world).is_err() {
+                        break;
+                    }
+                }
+            })
+            // The receiver goes with the closure, so `step` finds the worker gone
+            .ok();
+
+        Self {
+            sender: snapshot_sender,
+            receiver: result_receiver,
+            busy: false,
+            inline: worker.is_none(),
+            _worker: worker,
+        }
+    }
+
+    /// Merge the last finished update into `world` and hand the worker a new
+    /// snapshot. Does nothing while the worker is still busy or the world is paused.
+    pub fn step(&mut self, world: &mut World) {
+        if self.busy {
+            match self.receiver.try_recv() {
+                Ok(updated) => {
+                    world.apply_update(updated);
+                    self.busy = false;
+                }
+                Err(TryRecvError::Empty) => return,
+                // The snapshot it had is lost, this frame is updated here instead
+                Err(TryRecvError::Disconnected) => self.stop_worker(),
+            }
+        }
+        if world.run_state() == RunState::Paused {
+            return;
+        }
+        world.flush_inputs();
+        if self.inline {
+            world.advance();
+            return;
+        }
+        // The snapshot keeps a pending single step while `world` consumes it
+        let snapshot = world.update_copy();
+        world.begin_tick();
+        match self.sender.send(snapshot) {
+            Ok(()) => self.busy = true,
+            Err(error) => {
+                self.stop_worker();
+                let mut snapshot = error.0;
+                snapshot.advance();
+                world.apply_update(snapshot);
+            }
+        }
+    }
+
+    fn stop_worker(&mut self) {
+        eprintln!("Update thread stopped, updating on the main thread");
+        self.busy = false;
+        self.inline = true;
+    }
+}
+
+impl Default for UpdatePipeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,338 @@
+use std::{fs, ops::BitOr, path::Path};
+
+use rand::Rng;
+use ron::ser::PrettyConfig;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::WorldOption;
+
+/// Write every option, spawn counts included, as RON to `path`.
+pub fn save_preset(option: &WorldOption, path: &Path) -> Result<()> {
+    write_ron(option, path)
+}
+
+/// Read options saved by `save_preset`, missing fields keep their default.
+pub fn load_preset(path: &Path) -> Result<WorldOption> {
+    read_ron(path)
+}
+
+/// Write `value` as pretty RON to `path`.
+pub fn write_ron<T: Serialize>(value: &T, path: &Path) -> Result<()> {
+    let text = ron::ser::to_string_pretty(value, PrettyConfig::default())
+        .map_err(|error| Error::Format(error.to_string()))?;
+    fs::write(path, text)?;
+    Ok(())
+}
+
+pub fn read_ron<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let text = fs::read_to_string(path)?;
+    ron::from_str(&text).map_err(|error| Error::Format(error.to_string()))
+}
+
+/// Curated flocking behaviours, picked from the menu bar.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Scenario {
+    ClassicFlock,
+    PanickedSchool,
+    LazySwarm,
+    PredatorFrenzy,
+}
+
+impl Scenario {
+    pub const ALL: [Scenario; 4] = [
+        Scenario::ClassicFlock,
+        Scenario::PanickedSchool,
+        Scenario::LazySwarm,
+        Scenario::PredatorFrenzy,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Scenario::ClassicFlock => "Classic flock",
+            Scenario::PanickedSchool => "Panicked school",
+            Scenario::LazySwarm => "Lazy swarm",
+            Scenario::PredatorFrenzy => "Predator frenzy",
+        }
+    }
+
+    fn behaviour(&self) -> WorldOption {
+        let defaults = WorldOption::new();
+        match self {
+            Scenario::ClassicFlock => defaults,
+            Scenario::PanickedSchool => WorldOption {
+                safe_radius: 6.0,
+                matching_factor: 0.7,
+                centering_factor: 0.1,
+                boid_max_speed: 14,
+                boid_min_speed: 8,
+                noise: true,
+                boid_count: 2500,
+                fear_radius: 80.0,
+                predator_max_speed: 10,
+                predator_min_speed: 6,
+                predator_count: 6,
+                ..defaults
+            },
+            Scenario::LazySwarm => WorldOption {
+                avoid_factor: 0.1,
+                matching_factor: 0.2,
+                centering_factor: 0.02,
+                boid_vision_radius: 50.0,
+                boid_max_speed: 4,
+                boid_min_speed: 1,
+                boid_view_angle: 300.0,
+                noise: true,
+                boid_count: 1500,
+                predator_count: 0,
+                ..defaults
+            },
+            Scenario::PredatorFrenzy => WorldOption {
+                boid_count: 3000,
+                fear_radius: 40.0,
+                predator_vision_radius: 80.0,
+                predator_max_speed: 12,
+                predator_min_speed: 6,
+                predators_catch: true,
+                catch_radius: 8.0,
+                predator_count: 12,
+                ..defaults
+            },
+        }
+    }
+
+    /// Overwrite the boid and predator settings and the spawn counts. Appearance,
+    /// performance and debug settings are kept.
+    pub fn apply(&self, option: &mut WorldOption) {
+        let behaviour = self.behaviour();
+        // Boid
+        option.avoid_factor = behaviour.avoid_factor;
+        option.matching_factor = behaviour.matching_factor;
+        option.centering_factor = behaviour.centering_factor;
+        option.safe_radius = behaviour.safe_radius;
+        option.boid_vision_radius = behaviour.boid_vision_radius;
+        option.boid_max_speed = behaviour.boid_max_speed;
+        option.boid_min_speed = behaviour.boid_min_speed;
+        option.margin = behaviour.margin;
+        option.turn_factor = behaviour.turn_factor;
+        option.boid_view_angle = behaviour.boid_view_angle;
+        option.noise = behaviour.noise;
+        option.boid_size = behaviour.boid_size;
+        option.size_variation = behaviour.size_variation;
+        option.boid_count = behaviour.boid_count;
+        // Predator
+        option.fear_factor = behaviour.fear_factor;
+        option.fear_radius = behaviour.fear_radius;
+        option.predator_vision_radius = behaviour.predator_vision_radius;
+        option.predator_max_speed = behaviour.predator_max_speed;
+        option.predator_min_speed = behaviour.predator_min_speed;
+        option.predator_view_angle = behaviour.predator_view_angle;
+        option.predator_size = behaviour.predator_size;
+        option.predators_catch = behaviour.predators_catch;
+        option.catch_radius = behaviour.catch_radius;
+        option.predator_count = behaviour.predator_count;
+    }
+}
+
+/// Rule weights and radii shuffled by "Surprise me".
+#[derive(Clone, Copy)]
+pub struct Rules {
+    avoid_factor: f32,
+    matching_factor: f32,
+    centering_factor: f32,
+    safe_radius: f32,
+    boid_vision_radius: f32,
+    fear_factor: f32,
+    fear_radius: f32,
+    predator_vision_radius: f32,
+}
+
+impl Rules {
+    pub fn of(option: &WorldOption) -> Self {
+        Self {
+            avoid_factor: option.avoid_factor,
+            matching_factor: option.matching_factor,
+            centering_factor: option.centering_factor,
+            safe_radius: option.safe_radius,
+            boid_vision_radius: option.boid_vision_radius,
+            fear_factor: option.fear_factor,
+            fear_radius: option.fear_radius,
+            predator_vision_radius: option.predator_vision_radius,
+        }
+    }
+
+    /// Random rules, bounded so the boids still form some kind of flock.
+    pub fn random(rng: &mut impl Rng) -> Self {
+        let boid_vision_radius = rng.gen_range(15.0..=80.0);
+        Self {
+            avoid_factor: rng.gen_range(0.05..=0.6),
+            matching_factor: rng.gen_range(0.1..=0.9),
+            centering_factor: rng.gen_range(0.005..=0.15),
+            // Boids seeing nothing but their personal space never flock
+            safe_radius: rng.gen_range(3.0..=boid_vision_radius * 0.6),
+            boid_vision_radius,
+            fear_factor: rng.gen_range(0.2..=1.0),
+            fear_radius: rng.gen_range(15.0..=100.0),
+            predator_vision_radius: rng.gen_range(20.0..=120.0),
+        }
+    }
+
+    fn lerp(&self, other: &Rules, t: f32) -> Self {
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        Self {
+            avoid_factor: mix(self.avoid_factor, other.avoid_factor),
+            matching_factor: mix(self.matching_factor, other.matching_factor),
+            centering_factor: mix(self.centering_factor, other.centering_factor),
+            safe_radius: mix(self.safe_radius, other.safe_radius),
+            boid_vision_radius: mix(self.boid_vision_radius, other.boid_vision_radius),
+            fear_factor: mix(self.fear_factor, other.fear_factor),
+            fear_radius: mix(self.fear_radius, other.fear_radius),
+            predator_vision_radius: mix(self.predator_vision_radius, other.predator_vision_radius),
+        }
+    }
+
+    pub fn apply(&self, option: &mut WorldOption) {
+        option.avoid_factor = self.avoid_factor;
+        option.matching_factor = self.matching_factor;
+        option.centering_factor = self.centering_factor;
+        option.safe_radius = self.safe_radius;
+        option.boid_vision_radius = self.boid_vision_radius;
+        option.fear_factor = self.fear_factor;
+        option.fear_radius = self.fear_radius;
+        option.predator_vision_radius = self.predator_vision_radius;
+    }
+}
+
+/// Eases the rules from one set to another over `DURATION` seconds.
+pub struct RuleTransition {
+    from: Rules,
+    to: Rules,
+    start_time: f64,
+}
+
+impl RuleTransition {
+    pub const DURATION: f64 = 2.0;
+
+    pub fn new(from: Rules, to: Rules, start_time: f64) -> Self {
+        Self { from, to, start_time }
+    }
+
+    /// Apply the rules reached at `time`, returns false once the target is reached.
+    pub fn apply_at(&self, option: &mut WorldOption, time: f64) -> bool {
+        let progress = ((time - self.start_time) / Self::DURATION).clamp(0.0, 1.0) as f32;
+        // Smoothstep, so the flock eases in and out of the change
+        let eased = progress * progress * (3.0 - 2.0 * progress);
+        self.from.lerp(&self.to, eased).apply(option);
+        progress < 1.0
+    }
+}
+
+/// Kinds of flock simulated side by side.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug, Serialize, Deserialize)]
+pub enum Species {
+    #[default]
+    Boid,
+    Predator,
+}
+
+impl Species {
+    pub const ALL: [Species; 2] = [Species::Boid, Species::Predator];
+
+    /// Position in `Species::ALL`.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Species::Boid => "Boids",
+            Species::Predator => "Predators",
+        }
+    }
+}
+
+/// Species a query of the spatial index keeps, one bit each, like `Species::Boid |
+/// Species::Predator`. See `QuadTree::query_species`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SpeciesSet(u8);
+
+impl SpeciesSet {
+    pub const NONE: SpeciesSet = SpeciesSet(0);
+    pub const ALL: SpeciesSet = SpeciesSet((1 << Species::ALL.len()) - 1);
+
+    pub fn contains(self, species: Species) -> bool {
+        self.0 & SpeciesSet::from(species).0 != 0
+    }
+}
+
+impl From<Species> for SpeciesSet {
+    fn from(species: Species) -> Self {
+        SpeciesSet(1 << species.index())
+    }
+}
+
+impl<T: Into<SpeciesSet>> BitOr<T> for SpeciesSet {
+    type Output = SpeciesSet;
+
+    fn bitor(self, other: T) -> SpeciesSet {
+        SpeciesSet(self.0 | other.into().0)
+    }
+}
+
+impl BitOr for Species {
+    type Output = SpeciesSet;
+
+    fn bitor(self, other: Species) -> SpeciesSet {
+        SpeciesSet::from(self) | other
+    }
+}
+
+/// Parameters every species has, copied from one to another in the Flocks window.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Movement {
+    max_speed: i16,
+    min_speed: i16,
+    vision_radius: f32,
+    view_angle: f32,
+    size: i16,
+}
+
+impl Movement {
+    pub fn of(option: &WorldOption, species: Species) -> Self {
+        match species {
+            Species::Boid => Self {
+                max_speed: option.boid_max_speed,
+                min_speed: option.boid_min_speed,
+                vision_radius: option.boid_vision_radius,
+                view_angle: option.boid_view_angle,
+                size: option.boid_size,
+            },
+            Species::Predator => Self {
+                max_speed: option.predator_max_speed,
+                min_speed: option.predator_min_speed,
+                vision_radius: option.predator_vision_radius,
+                view_angle: option.predator_view_angle,
+                size: option.predator_size,
+            },
+        }
+    }
+
+    pub fn apply(&self, option: &mut WorldOption, species: Species) {
+        match species {
+            Species::Boid => {
+                option.boid_max_speed = self.max_speed;
+                option.boid_min_speed = self.min_speed;
+                option.boid_vision_radius = self.vision_radius;
+                option.boid_view_angle = self.view_angle;
+                option.boid_size = self.size;
+            }
+            Species::Predator => {
+                option.predator_max_speed = self.max_speed;
+                option.predator_min_speed = self.min_speed;
+                option.predator_vision_radius = self.vision_radius;
+                option.predator_view_angle = self.view_angle;
+                option.predator_size = self.size;
+            }
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+use crate::WorldOption;
+
+/// Settings one boid follows instead of those of the world, set from the inspector to watch how
+/// a single deviant boid, faster, blind or fearless, moves the flock around it. Every setting
+/// left at `None` is the one of the world.
+#[derive(Clone, Copy, Default, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub avoid_factor: Option<f32>,
+    pub matching_factor: Option<f32>,
+    pub centering_factor: Option<f32>,
+    /// 0 is blind to other boids, predators are seen within the fear radius
+    pub vision_radius: Option<f32>,
+    pub max_speed: Option<i16>,
+    pub min_speed: Option<i16>,
+    /// 0 is fearless
+    pub fear_factor: Option<f32>,
+}
+
+impl Profile {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// `option` with the settings of this profile in place of its own.
+    pub fn apply(&self, option: &WorldOption) -> WorldOption {
+        let mut option = option.clone();
+        let set = |value: &mut f32, profile: Option<f32>| {
+            if let Some(profile) = profile {
+                *value = profile;
+            }
+        };
+        set(&mut option.avoid_factor, self.avoid_factor);
+        set(&mut option.matching_factor, self.matching_factor);
+        set(&mut option.centering_factor, self.centering_factor);
+        set(&mut option.boid_vision_radius, self.vision_radius);
+        set(&mut option.fear_factor, self.fear_factor);
+        if let Some(max_speed) = self.max_speed {
+            option.boid_max_speed = max_speed;
+        }
+        if let Some(min_speed) = self.min_speed {
+            option.boid_min_speed = min_speed;
+        }
+        option
+    }
+}
@@ -0,0 +1,72 @@
+use instant::Instant;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    TreeBuild,
+    Rules,
+    Rasterize,
+    Gui,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 4] = [Stage::TreeBuild, Stage::Rules, Stage::Rasterize, Stage::Gui];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::TreeBuild => "Tree build",
+            Stage::Rules => "Rule evaluation",
+            Stage::Rasterize => "Rasterization",
+            Stage::Gui => "GUI",
+        }
+    }
+
+    /// Whether the stage runs as part of `World::update`
+    pub fn is_update(&self) -> bool {
+        matches!(self, Stage::TreeBuild | Stage::Rules)
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// Smoothed time spent in each stage of a frame, in milliseconds.
+#[derive(Clone, Default)]
+pub struct Profiler {
+    milliseconds: [f32; Stage::ALL.len()],
+}
+
+impl Profiler {
+    // Weight of the newest sample in the moving average
+    const SMOOTHING: f32 = 0.1;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the time since `start_time` for `stage` and return it in milliseconds.
+    pub fn record(&mut self, stage: Stage, start_time: Instant) -> f32 {
+        let elapsed = start_time.elapsed().as_secs_f32() * 1000.0;
+        let average = &mut self.milliseconds[stage.index()];
+        *average += (elapsed - *average) * Self::SMOOTHING;
+        elapsed
+    }
+
+    pub fn time<T>(&mut self, stage: Stage, f: impl FnOnce() -> T) -> T {
+        let start_time = Instant::now();
+        let result = f();
+        self.record(stage, start_time);
+        result
+    }
+
+    pub fn milliseconds(&self, stage: Stage) -> f32 {
+        self.milliseconds[stage.index()]
+    }
+
+    /// Copy the update stages measured by another profiler, e.g. on the update thread.
+    pub fn merge_update(&mut self, other: &Profiler) {
+        for stage in Stage::ALL.iter().filter(|stage| stage.is_update()) {
+            self.milliseconds[stage.index()] = other.milliseconds[stage.index()];
+        }
+    }
+}
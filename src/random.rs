@@ -0,0 +1,40 @@
+use std::any::Any;
+
+use rand::RngCore;
+
+/// Where a `World` takes every random choice from, see `World::set_rng`. Any generator that
+/// can be cloned and sent to the update thread is one, like a stub counting its calls in tests.
+///
+/// ```
+/// use boids::World;
+/// use rand::rngs::mock::StepRng;
+///
+/// let mut world = World::with_size(320, 240);
+/// world.option.boid_count = 10;
+/// world.set_rng(StepRng::new(0, 0));
+/// world.restart();
+/// // A generator stuck on zero spawns everything in the top left corner
+/// assert!(world.boids().all(|boid| boid.position() == (0.0, 0.0)));
+/// ```
+pub trait Random: RngCore + Send + Sync {
+    /// Copy at the same point of the sequence, for the copies of the world.
+    fn box_clone(&self) -> Box<dyn Random>;
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<R: RngCore + Clone + Send + Sync + 'static> Random for R {
+    fn box_clone(&self) -> Box<dyn Random> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Clone for Box<dyn Random> {
+    fn clone(&self) -> Self {
+        self.as_ref().box_clone()
+    }
+}
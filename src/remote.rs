@@ -0,0 +1,210 @@
+use std::io::{self, ErrorKind};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+use boids::{World, WorldOption};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tungstenite::Message;
+
+use crate::config::check_values;
+
+/// A JSON command from a client, like `{"command": "set", "field": "centering_factor", "value": 0.1}`.
+#[derive(Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case", deny_unknown_fields)]
+enum Request {
+    /// One option by its name in presets, or all of them without a field
+    Get { field: Option<String> },
+    Set { field: String, value: Value },
+    /// Boids and predators at random positions
+    Spawn {
+        #[serde(default)]
+        boids: u16,
+        #[serde(default)]
+        predators: u16,
+    },
+    Clear,
+    Restart,
+    Pause,
+    Resume,
+    /// Statistics after every frame that ran updates
+    Subscribe,
+    Unsubscribe,
+}
+
+// A message from a client, answered on `reply`
+struct Incoming {
+    client: u64,
+    text: String,
+    reply: Sender<String>,
+}
+
+/// WebSocket server for controlling the world with JSON commands, from dashboards or scripts.
+///
+/// Clients are served on threads of their own, their commands run on the main thread between
+/// frames. Every command is answered with `{"ok": true}`, with a `value` for `get`, or
+/// `{"ok": false, "error": ...}`.
+pub struct RemoteServer {
+    incoming: Receiver<Incoming>,
+    subscribers: Vec<(u64, Sender<String>)>,
+    // Tick of the last statistics sent
+    published_tick: u64,
+}
+
+impl RemoteServer {
+    // How long a client waits for a message before sending what is queued for it
+    const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+    /// Listen on `address`, like `127.0.0.1:9001`.
+    pub fn start(address: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        let (sender, incoming) = channel();
+        thread::Builder::new()
+            .name("remote-listener".to_string())
+            .spawn(move || {
+                for (client, stream) in (0..).zip(listener.incoming()) {
+                    let Ok(stream) = stream else {
+                        continue;
+                    };
+                    let sender = sender.clone();
+                    let spawned = thread::Builder::new()
+                        .name("remote-client".to_string())
+                        .spawn(move || serve(stream, client, sender));
+                    if let Err(error) = spawned {
+                        eprintln!("Cannot serve remote client: {error}");
+                    }
+                }
+            })?;
+        Ok(Self {
+            incoming,
+            subscribers: vec![],
+            published_tick: 0,
+        })
+    }
+
+    /// Run the commands received since the last call.
+    pub fn poll(&mut self, world: &mut World) {
+        while let Ok(Incoming { client, text, reply }) = self.incoming.try_recv() {
+            let result = match serde_json::from_str(&text) {
+                Ok(Request::Subscribe) => {
+                    self.subscribers.push((client, reply.clone()));
+                    Ok(None)
+                }
+                Ok(Request::Unsubscribe) => {
+                    self.subscribers.retain(|(subscriber, _)| *subscriber != client);
+                    Ok(None)
+                }
+                Ok(request) => run(request, world),
+                Err(error) => Err(format!("Invalid command: {error}")),
+            };
+            let response = match result {
+                Ok(None) => json!({ "ok": true }),
+                Ok(Some(value)) => json!({ "ok": true, "value": value }),
+                Err(error) => json!({ "ok": false, "error": error }),
+            };
+            // Gone already if this fails, nobody is left to answer
+            let _ = reply.send(response.to_string());
+        }
+    }
+
+    /// Send the statistics of `world` to the subscribers, if it updated since the last call.
+    pub fn publish(&mut self, world: &World) {
+        if self.subscribers.is_empty() || world.tick() == self.published_tick {
+            return;
+        }
+        self.published_tick = world.tick();
+        let statistics = json!({
+            "event": "statistics",
+            "tick": world.tick(),
+            "boids": world.boids_count(),
+            "predators": world.predators_count(),
+            "catches": world.catches(),
+            "update_fps": world.update_fps,
+        })
+        .to_string();
+        // Clients that went away dropped their end
+        self.subscribers
+            .retain(|(_, subscriber)| subscriber.send(statistics.clone()).is_ok());
+    }
+}
+
+fn run(request: Request, world: &mut World) -> Result<Option<Value>, String> {
+    match request {
+        Request::Get { field } => return get_option(&world.option, field.as_deref()).map(Some),
+        Request::Set { field, value } => world.option = set_option(&world.option, &field, value)?,
+        Request::Spawn { boids, predators } => world.spawn_random_boids(boids, predators),
+        Request::Clear => world.clear_all(),
+        Request::Restart => world.restart(),
+        Request::Pause => {
+            if !world.is_paused() {
+                world.toggle_pause();
+            }
+        }
+        Request::Resume => {
+            if world.is_paused() {
+                world.toggle_pause();
+            }
+        }
+        Request::Subscribe | Request::Unsubscribe => (),
+    }
+    Ok(None)
+}
+
+fn get_option(option: &WorldOption, field: Option<&str>) -> Result<Value, String> {
+    let options = serde_json::to_value(option).map_err(|error| error.to_string())?;
+    match field {
+        Some(field) => options
+            .get(field)
+            .cloned()
+            .ok_or_else(|| format!("Unknown option `{field}`")),
+        None => Ok(options),
+    }
+}
+
+// Checked like the [simulation] table of `boids.toml`
+fn set_option(option: &WorldOption, field: &str, value: Value) -> Result<WorldOption, String> {
+    let mut options = serde_json::to_value(option).map_err(|error| error.to_string())?;
+    let slot = options
+        .get_mut(field)
+        .ok_or_else(|| format!("Unknown option `{field}`"))?;
+    *slot = value;
+    let option = serde_json::from_value(options).map_err(|error| format!("Invalid `{field}`: {error}"))?;
+    check_values(&option)?;
+    Ok(option)
+}
+
+fn serve(stream: TcpStream, client: u64, server: Sender<Incoming>) {
+    let Ok(mut socket) = tungstenite::accept(stream) else {
+        return;
+    };
+    // Short reads so replies and statistics go out without waiting for the client to talk
+    if socket.get_ref().set_read_timeout(Some(RemoteServer::POLL_INTERVAL)).is_err() {
+        return;
+    }
+    let (reply, replies) = channel();
+    loop {
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                let message = Incoming {
+                    client,
+                    text,
+                    reply: reply.clone(),
+                };
+                if server.send(message).is_err() {
+                    return;
+                }
+            }
+            // Pings and closing are answered by tungstenite itself
+            Ok(_) => (),
+            Err(tungstenite::Error::Io(error)) if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => (),
+            Err(_) => return,
+        }
+        for text in replies.try_iter() {
+            if socket.send(Message::Text(text)).is_err() {
+                return;
+            }
+        }
+    }
+}
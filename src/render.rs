@@ -0,0 +1,15 @@
+use std::io;
+
+use crate::World;
+
+/// An output the flock can be shown on besides the window.
+///
+/// The window draws with `World::draw` into its pixel buffer, other outputs implement this so
+/// the same world can be updated and shown on them frame after frame.
+pub trait Renderer {
+    /// World size in pixels filling the output, the world is resized to it before rendering.
+    fn size(&self) -> io::Result<(u16, u16)>;
+
+    /// Show the current state of `world`.
+    fn render(&mut self, world: &mut World) -> io::Result<()>;
+}
@@ -0,0 +1,214 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::Result;
+use crate::geometry::{Color, Rectangle};
+use crate::obstacle::{Attractor, Obstacle};
+use crate::preset::{read_ron, write_ron};
+use crate::profile::Profile;
+use crate::scenario::ScenarioFile;
+use crate::snapshot::Snapshot;
+use crate::WorldOption;
+
+/// Something done to the world from outside of `update`, replayed by calling the same method.
+#[derive(Clone, Serialize, Deserialize)]
+pub enum Input {
+    Options(Box<WorldOption>),
+    // Oscillators moved to their value at this time
+    Animate(f64),
+    SpawnBoid { x: i16, y: i16 },
+    SpawnPredator { x: i16, y: i16 },
+    SpawnRandom { boids: u16, predators: u16 },
+    AddObstacle { x: f32, y: f32 },
+    AddAttractor { x: f32, y: f32 },
+    EditScenery { obstacles: Vec<Obstacle>, attractors: Vec<Attractor> },
+    Erase { x: f32, y: f32, radius: f32 },
+    SelectIn(Rectangle),
+    ClearSelection,
+    DeleteSelection,
+    RecolorSelection(Color),
+    FreezeSelection(bool),
+    ConvertSelection,
+    SetProfile { id: Uuid, profile: Profile },
+    Restart,
+    Clear,
+    Resize { width: u16, height: u16 },
+    SetSeed(u64),
+    Restore(Box<Snapshot>),
+    LoadScenario(Box<ScenarioFile>),
+    Commit,
+    Undo,
+    Redo,
+}
+
+/// A recorded run: the world it started from and every input made since, so it plays out again
+/// the same way. Settings following the frame rate, like auto population, only replay roughly.
+#[derive(Serialize, Deserialize)]
+pub struct Replay {
+    pub(crate) start: Snapshot,
+    // Each input with the number of updates run before it
+    pub(crate) inputs: Vec<(u64, Input)>,
+    pub(crate) ticks: u64,
+}
+
+impl Replay {
+    /// Updates run while recording.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub fn inputs_count(&self) -> usize {
+        self.inputs.len()
+    }
+
+    /// Window size the replay has to run at.
+    pub fn size(&self) -> (u16, u16) {
+        (self.start.width, self.start.height)
+    }
+
+    /// Write the replay as RON to `path`.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        write_ron(self, path)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        read_ron(path)
+    }
+}
+
+/// Gathers the inputs of a world being recorded.
+pub(crate) struct Recorder {
+    start: Snapshot,
+    inputs: Vec<(u64, Input)>,
+    // Made since the world was last handed to an update, they count from the next one
+    pending: Vec<Input>,
+    // Options as last recorded, compared against to notice slider changes
+    option: WorldOption,
+    ticks: u64,
+    // World tick when `ticks` was last brought up to date
+    last_tick: u64,
+}
+
+impl Recorder {
+    pub fn new(start: Snapshot) -> Self {
+        Self {
+            option: start.option.clone(),
+            last_tick: start.tick,
+            start,
+            inputs: vec![],
+            pending: vec![],
+            ticks: 0,
+        }
+    }
+
+    pub fn record(&mut self, option: &WorldOption, input: Input) {
+        self.note_option(option);
+        self.pending.push(input);
+    }
+
+    fn note_option(&mut self, option: &WorldOption) {
+        if *option != self.option {
+            self.option = option.clone();
+            self.pending.push(Input::Options(Box::new(option.clone())));
+        }
+    }
+
+    /// Make the same change to the recorded options, it is replayed by an input of its own.
+    pub fn follow(&mut self, change: impl FnOnce(&mut WorldOption)) {
+        change(&mut self.option);
+    }
+
+    /// Give the pending inputs their place, the world is at `tick` and about to be updated.
+    pub fn stamp(&mut self, tick: u64, option: &WorldOption) {
+        self.note_option(option);
+        self.ticks += tick.saturating_sub(self.last_tick);
+        self.last_tick = tick;
+        let ticks = self.ticks;
+        self.inputs.extend(self.pending.drain(..).map(|input| (ticks, input)));
+    }
+
+    /// The world tick started over from `tick` without running updates.
+    pub fn rebase(&mut self, tick: u64) {
+        self.last_tick = tick;
+    }
+
+    pub fn inputs_count(&self) -> usize {
+        self.inputs.len() + self.pending.len()
+    }
+
+    pub fn finish(mut self, tick: u64, option: &WorldOption) -> Replay {
+        self.stamp(tick, option);
+        Replay {
+            start: self.start,
+            inputs: self.inputs,
+            ticks: self.ticks,
+        }
+    }
+}
+
+/// Feeds the inputs of a replay back to the world as it updates.
+pub(crate) struct Player {
+    inputs: Vec<(u64, Input)>,
+    next: usize,
+    ticks: u64,
+    // Updates run since the replay started
+    played: u64,
+}
+
+impl Player {
+    pub fn new(inputs: Vec<(u64, Input)>, ticks: u64) -> Self {
+        Self {
+            inputs,
+            next: 0,
+            ticks,
+            played: 0,
+        }
+    }
+
+    /// Next input due before the coming update.
+    pub fn next_input(&mut self) -> Option<Input> {
+        let (ticks, input) = self.inputs.get(self.next)?;
+        if *ticks > self.played {
+            return None;
+        }
+        self.next += 1;
+        Some(input.clone())
+    }
+
+    pub fn count_update(&mut self) {
+        self.played += 1;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.played >= self.ticks && self.next == self.inputs.len()
+    }
+
+    /// Updates played and in the whole replay.
+    pub fn progress(&self) -> (u64, u64) {
+        (self.played, self.ticks)
+    }
+}
+
+/// Whether the world is being recorded or replayed.
+#[derive(Default)]
+pub(crate) enum Tape {
+    #[default]
+    Idle,
+    Recording(Box<Recorder>),
+    Replaying(Player),
+}
+
+impl Tape {
+    pub fn is_replaying(&self) -> bool {
+        matches!(self, Tape::Replaying(_))
+    }
+}
+
+// Inputs are recorded and replayed on the main thread, so update thread clones start idle
+impl Clone for Tape {
+    fn clone(&self) -> Self {
+        Tape::Idle
+    }
+}
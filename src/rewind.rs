@@ -0,0 +1,87 @@
+use std::collections::VecDeque;
+
+use crate::snapshot::Snapshot;
+use crate::World;
+
+/// Snapshots of the last seconds of a world, to scrub back through them and run on from any.
+pub struct Rewind {
+    // Oldest first
+    frames: VecDeque<Snapshot>,
+    /// Seconds of history kept
+    pub length: f64,
+    interval: f64,
+    last_time: f64,
+    // Frame shown while scrubbing, None while the world runs on
+    position: Option<usize>,
+}
+
+impl Rewind {
+    pub const MAX_LENGTH: f64 = 30.0;
+
+    /// History of `length` seconds, a snapshot taken every `interval` seconds.
+    pub fn new(length: f64, interval: f64) -> Self {
+        Self {
+            frames: VecDeque::new(),
+            length,
+            interval,
+            last_time: f64::NEG_INFINITY,
+            position: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Frame shown by the last `seek`, None once the world ran on.
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+
+    /// Tick of frame `index`.
+    pub fn tick_at(&self, index: usize) -> Option<u64> {
+        self.frames.get(index).map(Snapshot::tick)
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.position = None;
+    }
+
+    /// Take a snapshot of `world` if it moved on and an interval passed since the last one.
+    pub fn record(&mut self, time: f64, world: &World) {
+        if let Some(position) = self.position {
+            if self.frames[position].tick() == world.tick() {
+                return;
+            }
+            // Run on from an earlier frame, the later ones are not its future anymore
+            self.frames.truncate(position + 1);
+            self.position = None;
+        }
+        if self.frames.back().is_some_and(|last| last.tick() == world.tick()) || time - self.last_time < self.interval {
+            return;
+        }
+        self.last_time = time;
+        self.frames.push_back(world.snapshot());
+        let capacity = (self.length / self.interval).ceil().max(1.0) as usize;
+        while self.frames.len() > capacity {
+            self.frames.pop_front();
+        }
+    }
+
+    /// Put `world` back to frame `index`, paused so it can be looked at.
+    pub fn seek(&mut self, index: usize, world: &mut World) {
+        let Some(snapshot) = self.frames.get(index) else {
+            return;
+        };
+        if !world.is_paused() {
+            world.toggle_pause();
+        }
+        world.restore(snapshot.clone());
+        self.position = Some(index);
+    }
+}
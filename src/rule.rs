@@ -0,0 +1,209 @@
+use std::sync::Arc;
+
+use rand::RngCore;
+
+use crate::boid::Boid;
+use crate::geometry::Rectangle;
+use crate::obstacle::{Attractor, Obstacle};
+use crate::WorldOption;
+
+/// What a steering rule can look at besides the boid it steers.
+pub struct Surroundings<'a> {
+    /// Boids within the vision radius, the steered boid included
+    pub boids: &'a [Boid],
+    /// Predators within the fear radius
+    pub predators: &'a [Boid],
+    pub obstacles: &'a [Obstacle],
+    /// Areas to keep out of, see `World::set_avoided_areas`
+    pub avoided_areas: &'a [Rectangle],
+    pub attractors: &'a [Attractor],
+    pub width: u16,
+    pub height: u16,
+    /// Random numbers of the world, so seeded runs stay the same
+    pub rng: &'a mut dyn RngCore,
+}
+
+/// One step of how a boid changes its velocity each tick. `World::update` runs its rules in
+/// order on every boid, then moves it.
+///
+/// ```
+/// use std::sync::Arc;
+/// use boids::rule::{SteeringRule, Surroundings};
+/// use boids::{Boid, World, WorldOption};
+///
+/// /// Blows every boid to the right.
+/// struct Wind;
+///
+/// impl SteeringRule for Wind {
+///     fn name(&self) -> &str {
+///         "Wind"
+///     }
+///
+///     fn apply(&self, boid: &mut Boid, _: &mut Surroundings, _: &WorldOption) {
+///         let (x, y) = boid.velocity();
+///         boid.set_velocity(x + 0.1, y);
+///     }
+/// }
+///
+/// let mut world = World::with_size(320, 240);
+/// world.rules_mut().push(Arc::new(Wind));
+/// ```
+pub trait SteeringRule: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn apply(&self, boid: &mut Boid, surroundings: &mut Surroundings, option: &WorldOption);
+}
+
+/// Steer away from boids inside the safe radius.
+pub struct Separation;
+
+impl SteeringRule for Separation {
+    fn name(&self) -> &str {
+        "Separation"
+    }
+
+    fn apply(&self, boid: &mut Boid, surroundings: &mut Surroundings, option: &WorldOption) {
+        boid.separate(
+            surroundings.boids,
+            option.avoid_factor,
+            option.safe_radius,
+            option.boid_view_angle,
+            None,
+        );
+    }
+}
+
+/// Match the velocity of the boids in sight.
+pub struct Alignment;
+
+impl SteeringRule for Alignment {
+    fn name(&self) -> &str {
+        "Alignment"
+    }
+
+    fn apply(&self, boid: &mut Boid, surroundings: &mut Surroundings, option: &WorldOption) {
+        boid.align(
+            surroundings.boids,
+            option.matching_factor,
+            option.boid_vision_radius,
+            option.boid_view_angle,
+            None,
+        );
+    }
+}
+
+/// Move towards the center of the boids in sight.
+pub struct Cohesion;
+
+impl SteeringRule for Cohesion {
+    fn name(&self) -> &str {
+        "Cohesion"
+    }
+
+    fn apply(&self, boid: &mut Boid, surroundings: &mut Surroundings, option: &WorldOption) {
+        boid.cohesion(
+            surroundings.boids,
+            option.centering_factor,
+            option.boid_vision_radius,
+            option.boid_view_angle,
+            None,
+        );
+    }
+}
+
+/// Flee the predators within the fear radius.
+pub struct Fear;
+
+impl SteeringRule for Fear {
+    fn name(&self) -> &str {
+        "Fear"
+    }
+
+    fn apply(&self, boid: &mut Boid, surroundings: &mut Surroundings, option: &WorldOption) {
+        boid.fear(surroundings.predators, option.fear_factor, option.fear_radius);
+    }
+}
+
+/// Get pulled towards the attractors in reach.
+pub struct Attraction;
+
+impl SteeringRule for Attraction {
+    fn name(&self) -> &str {
+        "Attractors"
+    }
+
+    fn apply(&self, boid: &mut Boid, surroundings: &mut Surroundings, _: &WorldOption) {
+        boid.seek_attractors(surroundings.attractors);
+    }
+}
+
+/// Steer around the obstacles and the avoided areas.
+pub struct ObstacleAvoidance;
+
+impl SteeringRule for ObstacleAvoidance {
+    fn name(&self) -> &str {
+        "Obstacles"
+    }
+
+    fn apply(&self, boid: &mut Boid, surroundings: &mut Surroundings, option: &WorldOption) {
+        boid.avoid_obstacles(surroundings.obstacles, option.turn_factor, option.margin);
+        boid.avoid_areas(surroundings.avoided_areas, option.turn_factor, option.margin);
+    }
+}
+
+/// Random jitter, when turned on in the settings.
+pub struct Noise;
+
+impl SteeringRule for Noise {
+    fn name(&self) -> &str {
+        "Noise"
+    }
+
+    fn apply(&self, boid: &mut Boid, surroundings: &mut Surroundings, option: &WorldOption) {
+        boid.noise(option.noise, surroundings.rng);
+    }
+}
+
+/// Keep the speed between the minimum and maximum.
+pub struct SpeedLimit;
+
+impl SteeringRule for SpeedLimit {
+    fn name(&self) -> &str {
+        "Speed limit"
+    }
+
+    fn apply(&self, boid: &mut Boid, surroundings: &mut Surroundings, option: &WorldOption) {
+        boid.speed_limit(option.boid_max_speed, option.boid_min_speed, surroundings.rng);
+    }
+}
+
+/// Turn back inside the margin along the world edges, unless the world is unbounded.
+pub struct Border;
+
+impl SteeringRule for Border {
+    fn name(&self) -> &str {
+        "Border"
+    }
+
+    fn apply(&self, boid: &mut Boid, surroundings: &mut Surroundings, option: &WorldOption) {
+        if option.unbounded {
+            return;
+        }
+        boid.avoid_border(option.turn_factor, option.margin, surroundings.width, surroundings.height);
+    }
+}
+
+/// The rules every world starts with, in the order they run.
+pub fn default_rules() -> Vec<Arc<dyn SteeringRule>> {
+    vec![
+        Arc::new(Separation),
+        Arc::new(Alignment),
+        Arc::new(Cohesion),
+        Arc::new(Fear),
+        Arc::new(Attraction),
+        Arc::new(ObstacleAvoidance),
+        Arc::new(Noise),
+        Arc::new(SpeedLimit),
+        Arc::new(Border),
+    ]
+}
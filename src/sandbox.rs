@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::lfo::Parameter;
+use crate::obstacle::{Attractor, Obstacle};
+use crate::spectate_wire::MAX_PACKET_SIZE;
+use crate::World;
+
+// Start of a change packet and of a state packet, with a version in the last byte
+const CHANGE_MAGIC: [u8; 4] = *b"BDC1";
+const STATE_MAGIC: [u8; 4] = *b"BDT1";
+
+/// What a spectator of a shared sandbox can do to the world of the server.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Change {
+    SpawnBoid { x: f32, y: f32 },
+    SpawnPredator { x: f32, y: f32 },
+    AddObstacle { x: f32, y: f32 },
+    AddAttractor { x: f32, y: f32 },
+    Set { parameter: Parameter, value: f32 },
+}
+
+impl Change {
+    pub fn encode(&self) -> Vec<u8> {
+        let (kind, first, second) = match *self {
+            Change::SpawnBoid { x, y } => (0, x, y),
+            Change::SpawnPredator { x, y } => (1, x, y),
+            Change::AddObstacle { x, y } => (2, x, y),
+            Change::AddAttractor { x, y } => (3, x, y),
+            Change::Set { parameter, value } => (4, parameter_index(parameter) as f32, value),
+        };
+        let mut packet = Vec::with_capacity(13);
+        packet.extend(CHANGE_MAGIC);
+        packet.push(kind);
+        packet.extend(first.to_le_bytes());
+        packet.extend(second.to_le_bytes());
+        packet
+    }
+
+    /// The change in `packet`, or `None` if it is not one.
+    pub fn decode(packet: &[u8]) -> Option<Self> {
+        if packet.len() != 13 || packet[..4] != CHANGE_MAGIC {
+            return None;
+        }
+        let first = f32::from_le_bytes(packet[5..9].try_into().ok()?);
+        let second = f32::from_le_bytes(packet[9..13].try_into().ok()?);
+        if !first.is_finite() || !second.is_finite() {
+            return None;
+        }
+        let (x, y) = (first, second);
+        match packet[4] {
+            0 => Some(Change::SpawnBoid { x, y }),
+            1 => Some(Change::SpawnPredator { x, y }),
+            2 => Some(Change::AddObstacle { x, y }),
+            3 => Some(Change::AddAttractor { x, y }),
+            4 => Some(Change::Set {
+                parameter: *Parameter::ALL.get(first as usize)?,
+                value: second,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Make the change in `world`, settings kept within their slider range.
+    pub fn apply(&self, world: &mut World) {
+        match *self {
+            Change::SpawnBoid { x, y } => world.spawn_boids(x as i16, y as i16),
+            Change::SpawnPredator { x, y } => world.spawn_predators(x as i16, y as i16),
+            Change::AddObstacle { x, y } => world.add_obstacle(x, y),
+            Change::AddAttractor { x, y } => world.add_attractor(x, y),
+            Change::Set { parameter, value } => {
+                let range = parameter.range();
+                parameter.set(&mut world.option, value.clamp(*range.start(), *range.end()));
+            }
+        }
+    }
+}
+
+fn parameter_index(parameter: Parameter) -> usize {
+    Parameter::ALL.iter().position(|other| *other == parameter).unwrap_or(0)
+}
+
+/// Decides which changes of the clients of a shared sandbox go through, in the order they
+/// arrive.
+///
+/// A client moving a setting holds it for `LOCK` seconds, changes of the same setting from
+/// others meanwhile are dropped so two people dragging one slider do not fight over it. Each
+/// client spawns and places up to `BUDGET` things at once, refilled by `BUDGET` a second, so
+/// no one fills the world alone.
+pub struct Referee<C> {
+    // Holder of each setting and when it last moved it
+    locks: HashMap<Parameter, (C, f64)>,
+    // Things each client can still spawn or place, and when that was counted
+    budgets: HashMap<C, (f64, f64)>,
+}
+
+impl<C: Clone + Eq + Hash> Referee<C> {
+    pub const LOCK: f64 = 1.0;
+    pub const BUDGET: f64 = 100.0;
+
+    pub fn new() -> Self {
+        Self {
+            locks: HashMap::new(),
+            budgets: HashMap::new(),
+        }
+    }
+
+    /// Make `change` from `client` in `world` if it goes through at `time`, in seconds.
+    /// Returns whether it did.
+    pub fn apply(&mut self, client: C, change: Change, world: &mut World, time: f64) -> bool {
+        let allowed = match change {
+            Change::Set { parameter, .. } => match self.locks.get(&parameter) {
+                Some((holder, since)) if *holder != client && time - since < Self::LOCK => false,
+                _ => {
+                    self.locks.insert(parameter, (client, time));
+                    true
+                }
+            },
+            _ => {
+                let (left, counted) = self.budgets.entry(client).or_insert((Self::BUDGET, time));
+                *left = (*left + (time - *counted) * Self::BUDGET).min(Self::BUDGET);
+                *counted = time;
+                let allowed = *left >= 1.0;
+                if allowed {
+                    *left -= 1.0;
+                }
+                allowed
+            }
+        };
+        if allowed {
+            change.apply(world);
+        }
+        allowed
+    }
+
+    /// Forget what `client` held, for when it leaves.
+    pub fn forget(&mut self, client: &C) {
+        self.locks.retain(|_, (holder, _)| holder != client);
+        self.budgets.remove(client);
+    }
+}
+
+impl<C: Clone + Eq + Hash> Default for Referee<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The settings and scenery of the server's world, sent to spectators along with the frames
+/// since those only carry the flock.
+#[derive(Clone, PartialEq, Debug)]
+pub struct State {
+    // Every setting, in the order of `Parameter::ALL`
+    pub settings: Vec<(Parameter, f32)>,
+    // Position and radius
+    pub obstacles: Vec<(f32, f32, f32)>,
+    // Position, radius and strength
+    pub attractors: Vec<(f32, f32, f32, f32)>,
+}
+
+impl State {
+    pub fn of(world: &World) -> Self {
+        Self {
+            settings: Parameter::ALL
+                .into_iter()
+                .map(|parameter| (parameter, parameter.get(&world.option)))
+                .collect(),
+            obstacles: world
+                .obstacles()
+                .iter()
+                .map(|obstacle| (obstacle.x, obstacle.y, obstacle.radius))
+                .collect(),
+            attractors: world
+                .attractors()
+                .iter()
+                .map(|attractor| (attractor.x, attractor.y, attractor.radius, attractor.strength))
+                .collect(),
+        }
+    }
+
+    /// One packet of at most `MAX_PACKET_SIZE` bytes, scenery that does not fit is left out.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut packet = Vec::with_capacity(MAX_PACKET_SIZE);
+        packet.extend(STATE_MAGIC);
+        for (_, value) in &self.settings {
+            packet.extend(value.to_le_bytes());
+        }
+        let room = MAX_PACKET_SIZE - packet.len() - 4;
+        let obstacles = self.obstacles.len().min(room / 12);
+        let attractors = self.attractors.len().min((room - obstacles * 12) / 16);
+        packet.extend((obstacles as u16).to_le_bytes());
+        packet.extend((attractors as u16).to_le_bytes());
+        for (x, y, radius) in &self.obstacles[..obstacles] {
+            [x, y, radius]
+                .into_iter()
+                .for_each(|value| packet.extend(value.to_le_bytes()));
+        }
+        for (x, y, radius, strength) in &self.attractors[..attractors] {
+            [x, y, radius, strength]
+                .into_iter()
+                .for_each(|value| packet.extend(value.to_le_bytes()));
+        }
+        packet
+    }
+
+    /// The state in `packet`, or `None` if it is not one.
+    pub fn decode(packet: &[u8]) -> Option<Self> {
+        if packet.len() < 4 || packet[..4] != STATE_MAGIC {
+            return None;
+        }
+        let settings_end = 4 + Parameter::ALL.len() * 4;
+        if packet.len() < settings_end + 4 {
+            return None;
+        }
+        let u16_at = |at: usize| u16::from_le_bytes([packet[at], packet[at + 1]]) as usize;
+        let (obstacles, attractors) = (u16_at(settings_end), u16_at(settings_end + 2));
+        let scenery = settings_end + 4;
+        let attractors_start = scenery + obstacles * 12;
+        if packet.len() != scenery + obstacles * 12 + attractors * 16 {
+            return None;
+        }
+        let f32_at = |at: usize| f32::from_le_bytes([packet[at], packet[at + 1], packet[at + 2], packet[at + 3]]);
+        let settings = Parameter::ALL
+            .into_iter()
+            .enumerate()
+            .map(|(index, parameter)| (parameter, f32_at(4 + index * 4)))
+            .collect();
+        let obstacles = (0..obstacles)
+            .map(|index| scenery + index * 12)
+            .map(|at| (f32_at(at), f32_at(at + 4), f32_at(at + 8)))
+            .collect();
+        let attractors = (0..attractors)
+            .map(|index| attractors_start + index * 16)
+            .map(|at| (f32_at(at), f32_at(at + 4), f32_at(at + 8), f32_at(at + 12)))
+            .collect();
+        Some(Self {
+            settings,
+            obstacles,
+            attractors,
+        })
+    }
+
+    /// Show the scenery in `world` and take the settings, but for those `keep` says to leave
+    /// alone.
+    pub fn apply(&self, world: &mut World, keep: impl Fn(Parameter) -> bool) {
+        for (parameter, value) in &self.settings {
+            if !keep(*parameter) {
+                parameter.set(&mut world.option, *value);
+            }
+        }
+        let obstacles = self
+            .obstacles
+            .iter()
+            .map(|(x, y, radius)| Obstacle {
+                x: *x,
+                y: *y,
+                radius: *radius,
+            })
+            .collect();
+        let attractors = self
+            .attractors
+            .iter()
+            .map(|(x, y, radius, strength)| Attractor {
+                x: *x,
+                y: *y,
+                radius: *radius,
+                strength: *strength,
+            })
+            .collect();
+        world.replace_scenery(obstacles, attractors);
+    }
+}
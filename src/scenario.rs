@@ -0,0 +1,78 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::obstacle::{Attractor, Obstacle};
+use crate::preset::{read_ron, write_ron, Movement, Species};
+use crate::{World, WorldOption};
+
+/// A whole demo in one file to share, like a maze or a predator arena: the settings, how each
+/// species moves, the scenery and groups of boids and predators placed by hand. Loaded with
+/// `World::load_scenario`.
+///
+/// Any field can be left out of a hand written file and keeps its default.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScenarioFile {
+    pub name: String,
+    pub description: String,
+    pub option: WorldOption,
+    /// Speeds, vision and size of a species, over the ones in `option`
+    pub species: Vec<(Species, Movement)>,
+    pub obstacles: Vec<Obstacle>,
+    pub attractors: Vec<Attractor>,
+    /// Placed on top of the `boid_count` and `predator_count` of `option`, spread at random
+    pub spawns: Vec<Spawn>,
+    /// World size the positions are laid out for, stretched to the size of the world loading
+    /// them. Taken as they are when left out.
+    pub size: Option<(u16, u16)>,
+}
+
+/// A group of boids or predators scattered around a point.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Spawn {
+    pub species: Species,
+    pub count: u16,
+    pub x: f32,
+    pub y: f32,
+    /// Radius the group is scattered in, 0 puts every one on the point
+    #[serde(default)]
+    pub spread: f32,
+}
+
+impl ScenarioFile {
+    /// The settings and scenery of `world` at its size, without spawn groups.
+    pub fn of(world: &World) -> Self {
+        Self {
+            option: world.option.clone(),
+            obstacles: world.obstacles().to_vec(),
+            attractors: world.attractors().to_vec(),
+            size: Some((world.width(), world.height())),
+            ..Self::default()
+        }
+    }
+
+    /// The settings with the movement of every species applied.
+    pub fn option(&self) -> WorldOption {
+        let mut option = self.option.clone();
+        for (species, movement) in &self.species {
+            movement.apply(&mut option, *species);
+        }
+        option
+    }
+
+    /// Bodies spawned by the groups.
+    pub fn spawn_count(&self) -> usize {
+        self.spawns.iter().map(|spawn| spawn.count as usize).sum()
+    }
+
+    /// Write the scenario as RON to `path`.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        write_ron(self, path)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        read_ron(path)
+    }
+}
@@ -0,0 +1,199 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use rhai::{Array, Dynamic, Engine, Map, Scope, AST, FLOAT};
+
+use crate::boid::Boid;
+use crate::rule::{SteeringRule, Surroundings};
+use crate::{World, WorldOption};
+
+/// A steering rule written in a Rhai script.
+///
+/// The script defines `steer(boid, neighbors)`, called for every boid each tick. `boid` and
+/// each of `neighbors` are maps with `x`, `y`, `vx`, `vy` and `size`, the returned map gives
+/// the new `vx` and `vy`:
+///
+/// ```text
+/// fn steer(boid, neighbors) {
+///     // Swirl around the center of the world
+///     boid.vx += (360.0 - boid.y) * 0.001;
+///     boid.vy += (boid.x - 640.0) * 0.001;
+///     boid
+/// }
+/// ```
+///
+/// Scripts can only compute, they have no access to files or the network, and each call is
+/// cut short past a number of operations.
+pub struct ScriptRule {
+    name: String,
+    engine: Engine,
+    ast: AST,
+    // Last failure of a call, shown instead of stopping the simulation
+    error: Mutex<Option<String>>,
+}
+
+impl ScriptRule {
+    // Enough for a loop over a few hundred neighbors
+    const MAX_OPERATIONS: u64 = 100_000;
+
+    /// Compile the script at `path`.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = fs::read_to_string(path).map_err(|error| format!("Cannot read {}: {error}", path.display()))?;
+        let mut engine = Engine::new();
+        engine.set_max_operations(Self::MAX_OPERATIONS);
+        let ast = engine
+            .compile(source)
+            .map_err(|error| format!("{}: {error}", path.display()))?;
+        let name = path
+            .file_stem()
+            .map_or_else(|| "Script".to_string(), |stem| stem.to_string_lossy().into_owned());
+        Ok(Self {
+            name,
+            engine,
+            ast,
+            error: Mutex::new(None),
+        })
+    }
+
+    /// Why the last call failed, if it did.
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().ok()?.clone()
+    }
+
+    fn fail(&self, error: String) {
+        if let Ok(mut last) = self.error.lock() {
+            *last = Some(error);
+        }
+    }
+
+    fn to_map(boid: &Boid) -> Map {
+        let (x, y) = boid.position();
+        let (vx, vy) = boid.velocity();
+        let mut map = Map::new();
+        map.insert("x".into(), Dynamic::from(x as FLOAT));
+        map.insert("y".into(), Dynamic::from(y as FLOAT));
+        map.insert("vx".into(), Dynamic::from(vx as FLOAT));
+        map.insert("vy".into(), Dynamic::from(vy as FLOAT));
+        map.insert("size".into(), Dynamic::from(boid.size() as FLOAT));
+        map
+    }
+
+    fn steer(&self, boid: &Boid, neighbors: &[Boid]) -> Result<(f32, f32), String> {
+        let neighbors: Array = neighbors
+            .iter()
+            .filter(|other| other.id() != boid.id())
+            .map(|other| Dynamic::from_map(Self::to_map(other)))
+            .collect();
+        let result: Dynamic = self
+            .engine
+            .call_fn(&mut Scope::new(), &self.ast, "steer", (Self::to_map(boid), neighbors))
+            .map_err(|error| error.to_string())?;
+        let map = result
+            .try_cast::<Map>()
+            .ok_or_else(|| "steer has to return the boid map".to_string())?;
+        let velocity = |key: &str| {
+            map.get(key)
+                .and_then(|value| value.as_float().ok())
+                .map(|value| value as f32)
+                .ok_or_else(|| format!("The returned boid has no number `{key}`"))
+        };
+        Ok((velocity("vx")?, velocity("vy")?))
+    }
+}
+
+impl SteeringRule for ScriptRule {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn apply(&self, boid: &mut Boid, surroundings: &mut Surroundings, _: &WorldOption) {
+        match self.steer(boid, surroundings.boids) {
+            Ok((x, y)) if x.is_finite() && y.is_finite() => boid.set_velocity(x, y),
+            Ok(_) => self.fail("steer returned a velocity that is not a finite number".to_string()),
+            Err(error) => self.fail(error),
+        }
+    }
+}
+
+/// Keeps the rule of a script file in a world, reloaded whenever the file changes.
+pub struct ScriptWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    rule: Option<Arc<ScriptRule>>,
+    // Why the script could not be loaded
+    load_error: Option<String>,
+}
+
+impl ScriptWatcher {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            modified: None,
+            rule: None,
+            load_error: None,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Why the script could not be loaded or last failed to run.
+    pub fn error(&self) -> Option<String> {
+        self.load_error
+            .clone()
+            .or_else(|| self.rule.as_ref().and_then(|rule| rule.error()))
+    }
+
+    /// Load the script again if the file changed since the last call, replacing its rule in
+    /// `world`. A new rule runs before the speed limit so scripts cannot make boids too fast.
+    pub fn poll(&mut self, world: &mut World) {
+        let modified = fs::metadata(&self.path).and_then(|metadata| metadata.modified()).ok();
+        if modified.is_none() && self.rule.is_none() {
+            self.load_error = Some(format!("Cannot find {}", self.path.display()));
+            return;
+        }
+        // Gone for a moment while an editor saves, the loaded version keeps running
+        if modified.is_none() || modified == self.modified {
+            return;
+        }
+        self.modified = modified;
+        let rule = match ScriptRule::load(&self.path) {
+            Ok(rule) => Arc::new(rule),
+            Err(error) => {
+                // The previous version keeps running until the script is fixed
+                self.load_error = Some(error);
+                return;
+            }
+        };
+        self.load_error = None;
+        let rules = world.rules_mut();
+        let index = self.rule.take().and_then(|old| rules.iter().position(|rule| is_same(rule, &old)));
+        match index {
+            Some(index) => rules[index] = rule.clone(),
+            None => {
+                let index = rules
+                    .iter()
+                    .position(|rule| rule.name() == "Speed limit")
+                    .unwrap_or(rules.len());
+                rules.insert(index, rule.clone());
+            }
+        }
+        self.rule = Some(rule);
+    }
+
+    /// Take the script rule out of `world`.
+    pub fn unload(&mut self, world: &mut World) {
+        if let Some(old) = self.rule.take() {
+            world.rules_mut().retain(|rule| !is_same(rule, &old));
+        }
+        self.modified = None;
+        self.load_error = None;
+    }
+}
+
+fn is_same(rule: &Arc<dyn SteeringRule>, script: &Arc<ScriptRule>) -> bool {
+    std::ptr::addr_eq(Arc::as_ptr(rule), Arc::as_ptr(script))
+}
@@ -0,0 +1,102 @@
+use winit::event::{ModifiersState, VirtualKeyCode};
+
+/// Something a key press does in the simulation window.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    TogglePause,
+    Restart,
+    Clear,
+    ToggleGui,
+    ToggleDebugOverlays,
+    SpeedUp,
+    SlowDown,
+    Fullscreen,
+    Screenshot,
+    SaveInstantReplay,
+    Undo,
+    Redo,
+    CommandPalette,
+}
+
+// Every key bound to an action and whether it needs control held, several keys can share one
+const SHORTCUTS: [(VirtualKeyCode, bool, Action); 16] = [
+    (VirtualKeyCode::Space, false, Action::TogglePause),
+    (VirtualKeyCode::R, false, Action::Restart),
+    (VirtualKeyCode::C, false, Action::Clear),
+    (VirtualKeyCode::H, false, Action::ToggleGui),
+    (VirtualKeyCode::D, false, Action::ToggleDebugOverlays),
+    (VirtualKeyCode::Plus, false, Action::SpeedUp),
+    (VirtualKeyCode::Equals, false, Action::SpeedUp),
+    (VirtualKeyCode::NumpadAdd, false, Action::SpeedUp),
+    (VirtualKeyCode::Minus, false, Action::SlowDown),
+    (VirtualKeyCode::NumpadSubtract, false, Action::SlowDown),
+    (VirtualKeyCode::F11, false, Action::Fullscreen),
+    (VirtualKeyCode::F12, false, Action::Screenshot),
+    (VirtualKeyCode::F10, false, Action::SaveInstantReplay),
+    (VirtualKeyCode::Z, true, Action::Undo),
+    (VirtualKeyCode::Y, true, Action::Redo),
+    (VirtualKeyCode::P, true, Action::CommandPalette),
+];
+
+impl Action {
+    pub const ALL: [Action; 13] = [
+        Action::TogglePause,
+        Action::Restart,
+        Action::Clear,
+        Action::ToggleGui,
+        Action::ToggleDebugOverlays,
+        Action::SpeedUp,
+        Action::SlowDown,
+        Action::Fullscreen,
+        Action::Screenshot,
+        Action::SaveInstantReplay,
+        Action::Undo,
+        Action::Redo,
+        Action::CommandPalette,
+    ];
+
+    /// Action bound to `key` with the held `modifiers`, if any.
+    pub fn for_key(key: VirtualKeyCode, modifiers: ModifiersState) -> Option<Action> {
+        SHORTCUTS
+            .iter()
+            .find(|(bound_key, control, _)| *bound_key == key && *control == modifiers.ctrl())
+            .map(|(_, _, action)| *action)
+    }
+
+    /// Keys as shown in the Help window.
+    pub fn keys(&self) -> &'static str {
+        match self {
+            Action::TogglePause => "Space",
+            Action::Restart => "R",
+            Action::Clear => "C",
+            Action::ToggleGui => "H",
+            Action::ToggleDebugOverlays => "D",
+            Action::SpeedUp => "+",
+            Action::SlowDown => "-",
+            Action::Fullscreen => "F11",
+            Action::Screenshot => "F12",
+            Action::SaveInstantReplay => "F10",
+            Action::Undo => "Ctrl+Z",
+            Action::Redo => "Ctrl+Y",
+            Action::CommandPalette => "Ctrl+P",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::TogglePause => "Pause or resume",
+            Action::Restart => "Restart",
+            Action::Clear => "Clear all boids",
+            Action::ToggleGui => "Hide or show the GUI",
+            Action::ToggleDebugOverlays => "Hide or show the debug overlays",
+            Action::SpeedUp => "Speed up the simulation",
+            Action::SlowDown => "Slow down the simulation",
+            Action::Fullscreen => "Toggle fullscreen",
+            Action::Screenshot => "Save a screenshot",
+            Action::SaveInstantReplay => "Save the last seconds as a GIF",
+            Action::Undo => "Undo",
+            Action::Redo => "Redo",
+            Action::CommandPalette => "Search every command",
+        }
+    }
+}
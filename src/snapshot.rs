@@ -0,0 +1,47 @@
+use std::path::Path;
+
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::boid::Boid;
+use crate::error::Result;
+use crate::obstacle::{Attractor, Obstacle};
+use crate::preset::{read_ron, write_ron};
+use crate::WorldOption;
+
+/// The whole state of a world, taken with `World::snapshot` and resumed with `World::restore`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub(crate) option: WorldOption,
+    pub(crate) width: u16,
+    pub(crate) height: u16,
+    pub(crate) boids: Vec<Boid>,
+    pub(crate) predators: Vec<Boid>,
+    pub(crate) obstacles: Vec<Obstacle>,
+    pub(crate) attractors: Vec<Attractor>,
+    pub(crate) catches: u64,
+    pub(crate) tick: u64,
+    pub(crate) seed: u64,
+    // Where the random numbers were, so a seeded run goes on as if it was never saved
+    pub(crate) rng: ChaCha12Rng,
+}
+
+impl Snapshot {
+    /// Updates run before the snapshot was taken.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn boids_count(&self) -> usize {
+        self.boids.len()
+    }
+
+    /// Write the snapshot as RON to `path`.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        write_ron(self, path)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        read_ron(path)
+    }
+}
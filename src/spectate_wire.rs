@@ -0,0 +1,196 @@
+use crate::boid::Boid;
+use crate::geometry::Color;
+use crate::node::Vertice;
+use crate::{BoidView, World};
+
+/// The flock of one tick as sent to spectators: where each boid and predator is, where it
+/// heads, its size and its color. Nothing else of the world goes over the network.
+///
+/// ```
+/// use boids::spectate_wire::{encode, FrameAssembler};
+/// use boids::World;
+///
+/// let world = World::builder().size(320, 240).boids(500).predators(2).build();
+/// let mut assembler = FrameAssembler::new();
+/// let frame = encode(&world, 0).iter().find_map(|packet| assembler.push(packet)).unwrap();
+/// assert_eq!((frame.boids.len(), frame.predators.len()), (500, 2));
+/// ```
+#[derive(Clone)]
+pub struct Frame {
+    // Counts the frames sent, which unlike the tick never goes back on a restart
+    pub sequence: u64,
+    pub width: u16,
+    pub height: u16,
+    pub boids: Vec<Boid>,
+    pub predators: Vec<Boid>,
+}
+
+impl Frame {
+    /// Show the received flock in `world`, resized to the world it comes from.
+    pub fn apply(self, world: &mut World) {
+        world.resize(self.width, self.height);
+        world.replace_flock(self.boids, self.predators);
+    }
+}
+
+// Start of every frame packet, with a version in the last byte
+const MAGIC: [u8; 4] = *b"BDS1";
+const HEADER_SIZE: usize = 24;
+// x, y, velocity x and y, size and RGBA
+const BODY_SIZE: usize = 11;
+// Velocities are sent in quarter pixels per tick, up to 32 in any direction
+const VELOCITY_SCALE: f32 = 4.0;
+
+/// Largest packet `encode` makes, small enough to travel unfragmented over Ethernet so a
+/// lost packet only costs the boids it held.
+pub const MAX_PACKET_SIZE: usize = 1400;
+
+/// The flock of `world` as the packets of frame number `sequence`, predators first. Each
+/// packet can be decoded on its own, see `FrameAssembler`.
+pub fn encode(world: &World, sequence: u64) -> Vec<Vec<u8>> {
+    let per_packet = (MAX_PACKET_SIZE - HEADER_SIZE) / BODY_SIZE;
+    let bodies: Vec<(bool, BoidView)> = world
+        .predators()
+        .map(|predator| (true, predator))
+        .chain(world.boids().map(|boid| (false, boid)))
+        .collect();
+    let parts = bodies.len().div_ceil(per_packet).max(1);
+    let mut packets = Vec::with_capacity(parts);
+    for part in 0..parts {
+        let slice = &bodies
+            [(part * per_packet).min(bodies.len())..((part + 1) * per_packet).min(bodies.len())];
+        let predators = slice.iter().filter(|(predator, _)| *predator).count();
+        let mut packet = Vec::with_capacity(HEADER_SIZE + slice.len() * BODY_SIZE);
+        packet.extend(MAGIC);
+        packet.extend(sequence.to_le_bytes());
+        packet.extend(world.width().to_le_bytes());
+        packet.extend(world.height().to_le_bytes());
+        packet.extend((part as u16).to_le_bytes());
+        packet.extend((parts as u16).to_le_bytes());
+        packet.extend((predators as u16).to_le_bytes());
+        packet.extend(((slice.len() - predators) as u16).to_le_bytes());
+        for (_, body) in slice {
+            let (x, y) = body.position();
+            let (velocity_x, velocity_y) = body.velocity();
+            let velocity = |value: f32| {
+                (value * VELOCITY_SCALE)
+                    .round()
+                    .clamp(i8::MIN as f32, i8::MAX as f32) as i8
+            };
+            packet.extend((x.round() as i16).to_le_bytes());
+            packet.extend((y.round() as i16).to_le_bytes());
+            packet.push(velocity(velocity_x) as u8);
+            packet.push(velocity(velocity_y) as u8);
+            packet.push(body.size().clamp(0, u8::MAX as i16) as u8);
+            packet.extend(body.color().to_color_array());
+        }
+        packets.push(packet);
+    }
+    packets
+}
+
+/// Puts the frames back together from packets arriving in any order, some maybe lost.
+pub struct FrameAssembler {
+    frame: Option<Frame>,
+    // Parts of `frame` still missing
+    missing: Vec<bool>,
+}
+
+impl FrameAssembler {
+    pub fn new() -> Self {
+        Self {
+            frame: None,
+            missing: vec![],
+        }
+    }
+
+    /// Take a packet. Returns a frame once all its parts arrived, or what arrived of it when a
+    /// later frame starts first. Packets of earlier frames and anything else are ignored, a
+    /// new sender has to start over from a frame number past the last one.
+    pub fn push(&mut self, packet: &[u8]) -> Option<Frame> {
+        let header = Header::read(packet)?;
+        let bodies = &packet[HEADER_SIZE..];
+        if bodies.len() != (header.predators + header.boids) * BODY_SIZE
+            || header.part >= header.parts
+        {
+            return None;
+        }
+        let mut finished = None;
+        match &self.frame {
+            Some(frame) if header.sequence < frame.sequence => return None,
+            Some(frame) if header.sequence == frame.sequence => {
+                if !self.missing[header.part] {
+                    return None;
+                }
+            }
+            _ => {
+                finished = self.frame.take();
+                self.frame = Some(Frame {
+                    sequence: header.sequence,
+                    width: header.width,
+                    height: header.height,
+                    boids: vec![],
+                    predators: vec![],
+                });
+                self.missing = vec![true; header.parts];
+            }
+        }
+        let frame = self.frame.as_mut()?;
+        self.missing[header.part] = false;
+        let mut bodies = bodies.chunks_exact(BODY_SIZE).map(decode_body);
+        frame
+            .predators
+            .extend(bodies.by_ref().take(header.predators));
+        frame.boids.extend(bodies);
+        if self.missing.iter().all(|missing| !missing) {
+            return self.frame.take();
+        }
+        finished
+    }
+}
+
+impl Default for FrameAssembler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct Header {
+    sequence: u64,
+    width: u16,
+    height: u16,
+    part: usize,
+    parts: usize,
+    predators: usize,
+    boids: usize,
+}
+
+impl Header {
+    fn read(packet: &[u8]) -> Option<Self> {
+        if packet.len() < HEADER_SIZE || packet[..4] != MAGIC {
+            return None;
+        }
+        let u16_at = |at: usize| u16::from_le_bytes([packet[at], packet[at + 1]]);
+        Some(Self {
+            sequence: u64::from_le_bytes(packet[4..12].try_into().ok()?),
+            width: u16_at(12),
+            height: u16_at(14),
+            part: u16_at(16) as usize,
+            parts: u16_at(18) as usize,
+            predators: u16_at(20) as usize,
+            boids: u16_at(22) as usize,
+        })
+    }
+}
+
+fn decode_body(bytes: &[u8]) -> Boid {
+    let x = i16::from_le_bytes([bytes[0], bytes[1]]);
+    let y = i16::from_le_bytes([bytes[2], bytes[3]]);
+    let color = Color::Rgba(bytes[7], bytes[8], bytes[9], bytes[10]);
+    let mut boid = Boid::new(Vertice { x, y }, bytes[6] as i16, 0, 0, color);
+    boid.set_velocity(
+        bytes[4] as i8 as f32 / VELOCITY_SCALE,
+        bytes[5] as i8 as f32 / VELOCITY_SCALE,
+    );
+    boid
+}
@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, SystemTime};
+
+use boids::lfo::Parameter;
+use boids::sandbox::{Change, Referee, State};
+use boids::spectate_wire::{encode, FrameAssembler, MAX_PACKET_SIZE};
+use boids::World;
+use instant::Instant;
+
+// Sent by spectators to start or keep receiving frames
+const JOIN: &[u8] = b"BDSJ";
+// Spectators not heard from for this long are dropped
+const TIMEOUT: Duration = Duration::from_secs(5);
+// How often a spectator says it is still watching, well within the timeout
+const JOIN_INTERVAL: Duration = Duration::from_secs(1);
+// How long a setting moved by a spectator is kept from the state sent back, which may not
+// have caught up with it yet
+const SETTLE: Duration = Duration::from_millis(500);
+
+/// Sends the flock over UDP to every spectator, one frame per tick, with the settings and
+/// scenery. Spectators join by sending a join packet and keep sending it while they watch.
+///
+/// In a shared sandbox spectators also spawn, place obstacles and attractors and move
+/// settings, as far as the `Referee` lets them.
+pub struct SpectatorServer {
+    socket: UdpSocket,
+    spectators: HashMap<SocketAddr, Instant>,
+    // Set when spectators can change the world
+    referee: Option<Referee<SocketAddr>>,
+    start: Instant,
+    // Tick of the last frame sent, the same tick is not sent twice
+    tick: Option<u64>,
+    sequence: u64,
+}
+
+impl SpectatorServer {
+    /// Listen for spectators on `address`, like 0.0.0.0:9002, letting them change the world if
+    /// `sandbox` is set.
+    pub fn bind(address: &str, sandbox: bool) -> io::Result<Self> {
+        let socket = UdpSocket::bind(address)?;
+        socket.set_nonblocking(true)?;
+        // Frames are numbered on from the time, so a restarted server is not taken for an old one
+        let sequence = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis() as u64);
+        Ok(Self {
+            socket,
+            spectators: HashMap::new(),
+            referee: sandbox.then(Referee::new),
+            start: Instant::now(),
+            tick: None,
+            sequence,
+        })
+    }
+
+    /// Take the packets received since the last call, making the changes of spectators that go
+    /// through, and forget silent spectators.
+    pub fn poll(&mut self, world: &mut World) {
+        let mut buffer = [0; 64];
+        let now = Instant::now();
+        let time = now.duration_since(self.start).as_secs_f64();
+        while let Ok((size, from)) = self.socket.recv_from(&mut buffer) {
+            let packet = &buffer[..size];
+            if packet == JOIN {
+                self.spectators.insert(from, now);
+            } else if let (Some(change), Some(referee)) = (Change::decode(packet), &mut self.referee) {
+                // Changes that do not go through are undone on the spectator by the next state
+                referee.apply(from, change, world, time);
+            }
+        }
+        let referee = &mut self.referee;
+        self.spectators.retain(|spectator, seen| {
+            let watching = now.duration_since(*seen) < TIMEOUT;
+            if let (false, Some(referee)) = (watching, referee.as_mut()) {
+                referee.forget(spectator);
+            }
+            watching
+        });
+    }
+
+    /// Send the settings and scenery to the spectators, and the flock if it moved since the last
+    /// frame.
+    pub fn broadcast(&mut self, world: &World) {
+        if self.spectators.is_empty() {
+            return;
+        }
+        let mut packets = vec![State::of(world).encode()];
+        if self.tick != Some(world.tick()) {
+            self.tick = Some(world.tick());
+            self.sequence += 1;
+            packets.extend(encode(world, self.sequence));
+        }
+        for packet in packets {
+            for spectator in self.spectators.keys() {
+                // A full send buffer drops the packet like the network would
+                let _ = self.socket.send_to(&packet, spectator);
+            }
+        }
+    }
+}
+
+/// Watches the flock of a `SpectatorServer`, which keeps simulating on its own. Changes made
+/// here are sent to it, a shared sandbox makes those that go through.
+pub struct SpectatorClient {
+    socket: UdpSocket,
+    server: SocketAddr,
+    assembler: FrameAssembler,
+    last_join: Option<Instant>,
+    buffer: Vec<u8>,
+    // Settings as last received or sent, to tell which were moved here
+    settings: Vec<(Parameter, f32)>,
+    // Settings moved here lately
+    moved: HashMap<Parameter, Instant>,
+}
+
+impl SpectatorClient {
+    /// Join the server on `address`, like 192.168.1.20:9002.
+    pub fn connect(address: &str) -> io::Result<Self> {
+        let server = address
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no address found"))?;
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            server,
+            assembler: FrameAssembler::new(),
+            last_join: None,
+            buffer: vec![0; MAX_PACKET_SIZE],
+            settings: vec![],
+            moved: HashMap::new(),
+        })
+    }
+
+    /// Show in `world` what the server sent since the last call, the latest frame of the flock
+    /// and the settings and scenery. Also tells the server this spectator is still there.
+    pub fn poll(&mut self, world: &mut World) {
+        let now = Instant::now();
+        if self
+            .last_join
+            .is_none_or(|last| now.duration_since(last) >= JOIN_INTERVAL)
+        {
+            self.last_join = Some(now);
+            // Sent again in a second if the server is not up yet
+            let _ = self.socket.send_to(JOIN, self.server);
+        }
+        let (mut frame, mut state) = (None, None);
+        while let Ok((size, from)) = self.socket.recv_from(&mut self.buffer) {
+            if from != self.server {
+                continue;
+            }
+            let packet = &self.buffer[..size];
+            if let Some(received) = State::decode(packet) {
+                state = Some(received);
+            } else if let Some(received) = self.assembler.push(packet) {
+                frame = Some(received);
+            }
+        }
+        if let Some(frame) = frame {
+            frame.apply(world);
+        }
+        if let Some(state) = state {
+            self.moved.retain(|_, moved| now.duration_since(*moved) < SETTLE);
+            state.apply(world, |parameter| self.moved.contains_key(&parameter));
+            self.settings = State::of(world).settings;
+        }
+    }
+
+    /// Send `change` to the server.
+    pub fn send(&self, change: Change) {
+        // Lost like any other packet, the state sent back shows what happened
+        let _ = self.socket.send_to(&change.encode(), self.server);
+    }
+
+    /// Send the settings of `world` moved here since the last call.
+    pub fn send_settings(&mut self, world: &World) {
+        let now = Instant::now();
+        let mut changes = vec![];
+        for (parameter, value) in &mut self.settings {
+            let current = parameter.get(&world.option);
+            if current != *value {
+                *value = current;
+                self.moved.insert(*parameter, now);
+                changes.push(Change::Set {
+                    parameter: *parameter,
+                    value: current,
+                });
+            }
+        }
+        for change in changes {
+            self.send(change);
+        }
+    }
+}
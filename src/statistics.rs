@@ -0,0 +1,282 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use uuid::Uuid;
+
+use crate::{boid::Boid, capture::timestamped_path, preset::Species, World, WorldOption};
+
+#[derive(Clone, Copy)]
+pub struct StatisticsSample {
+    /// Seconds since the recording started
+    pub time: f64,
+    pub boids: usize,
+    pub predators: usize,
+    /// Cumulative catches
+    pub catches: u64,
+    /// Mean and smallest distance from a boid to its nearest neighbor, see `NeighborDistances`
+    pub nearest_mean: Option<f32>,
+    pub nearest_min: Option<f32>,
+}
+
+/// Population and catch counts sampled at a fixed interval.
+pub struct Statistics {
+    samples: Vec<StatisticsSample>,
+    interval: f64,
+}
+
+impl Statistics {
+    pub fn new(interval: f64) -> Self {
+        Self {
+            samples: Vec::new(),
+            interval,
+        }
+    }
+
+    pub fn samples(&self) -> &[StatisticsSample] {
+        &self.samples
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Take a sample of `world` if at least one interval passed since the last one.
+    pub fn record(&mut self, time: f64, world: &World) {
+        if self
+            .samples
+            .last()
+            .is_some_and(|sample| time - sample.time < self.interval)
+        {
+            return;
+        }
+        let nearest = world.neighbor_distances();
+        self.samples.push(StatisticsSample {
+            time,
+            boids: world.boids_count(),
+            predators: world.predators_count(),
+            catches: world.catches(),
+            nearest_mean: nearest.map(|nearest| nearest.mean),
+            nearest_min: nearest.map(|nearest| nearest.min),
+        });
+    }
+
+    /// Write every sample to `statistics-<unix milliseconds>.csv` in `directory`.
+    pub fn export_csv(&self, directory: &Path) -> io::Result<PathBuf> {
+        let path = timestamped_path(directory, "statistics", "csv");
+        let mut writer = BufWriter::new(File::create(&path)?);
+        writeln!(writer, "time,boids,predators,catches,nearest_mean,nearest_min")?;
+        // Left empty while no boid sees another
+        let distance = |value: Option<f32>| value.map_or_else(String::new, |value| format!("{value:.2}"));
+        for sample in &self.samples {
+            writeln!(
+                writer,
+                "{:.2},{},{},{},{},{}",
+                sample.time,
+                sample.boids,
+                sample.predators,
+                sample.catches,
+                distance(sample.nearest_mean),
+                distance(sample.nearest_min)
+            )?;
+        }
+        writer.flush()?;
+        Ok(path)
+    }
+}
+
+/// Distance from each boid to its nearest neighbor within vision radius, the usual measure of
+/// crowding, taken from the neighbors found by `World::update`. Boids seeing no other boid are
+/// left out.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct NeighborDistances {
+    pub mean: f32,
+    pub min: f32,
+    /// Boids with a neighbor within vision radius
+    pub counted: usize,
+}
+
+impl NeighborDistances {
+    /// From the nearest neighbor distance of every boid that has one, `None` without any.
+    pub fn of(nearest: &[f32]) -> Option<Self> {
+        if nearest.is_empty() {
+            return None;
+        }
+        Some(Self {
+            mean: nearest.iter().sum::<f32>() / nearest.len() as f32,
+            min: nearest.iter().copied().fold(f32::INFINITY, f32::min),
+            counted: nearest.len(),
+        })
+    }
+
+    /// Whether boids sit within half the safe radius of their nearest neighbor on average, deep
+    /// in the space separation should keep clear, so it is losing to cohesion.
+    pub fn is_crowded(&self, option: &WorldOption) -> bool {
+        self.mean < option.safe_radius / 2.0
+    }
+}
+
+/// Distance from `boid` to the nearest of `neighbors` within `radius`, itself left out.
+pub(crate) fn nearest_distance(boid: &Boid, neighbors: &[Boid], radius: f32) -> Option<f32> {
+    let (x, y) = boid.position();
+    neighbors
+        .iter()
+        .filter(|other| other.id() != boid.id())
+        .map(|other| {
+            let (other_x, other_y) = other.position();
+            (other_x - x).hypot(other_y - y)
+        })
+        .filter(|distance| *distance <= radius)
+        .min_by(f32::total_cmp)
+}
+
+/// Pairs closer than the safe radius per boid above which separation is too weak to keep the
+/// flock apart, see `World::overlaps`. About 2.5 at the default settings.
+pub const MAX_OVERLAPS_PER_BOID: f32 = 4.0;
+
+/// Boids among `neighbors` whose body comes within `safe_radius` of the body of `boid`, the
+/// overlaps separation is there to prevent. Only those with a greater id are counted, so summed
+/// over every boid each pair counts once.
+pub(crate) fn overlaps(boid: &Boid, neighbors: &[Boid], safe_radius: f32) -> usize {
+    let (x, y) = boid.position();
+    neighbors
+        .iter()
+        .filter(|other| other.id() > boid.id())
+        .filter(|other| {
+            let (other_x, other_y) = other.position();
+            let gap = (other_x - x).hypot(other_y - y) - (boid.size() + other.size()) as f32 / 2.0;
+            gap <= safe_radius
+        })
+        .count()
+}
+
+/// How ordered the boids fly, the usual measures of collective motion. Predators are left out.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct OrderParameters {
+    /// Length of the mean heading, 1 when every boid flies the same way and near 0 when they go
+    /// every way
+    pub polarization: f32,
+    /// Mean turn of the headings around the center of the boids, 1 when they all circle it the
+    /// same way as in a mill
+    pub milling: f32,
+    /// Groups of boids linked by neighbors within vision radius of each other
+    pub flocks: usize,
+}
+
+impl OrderParameters {
+    pub fn of(world: &World) -> Self {
+        let boids = indexed_boids(world);
+        if boids.is_empty() {
+            return Self {
+                polarization: 0.0,
+                milling: 0.0,
+                flocks: 0,
+            };
+        }
+        let count = boids.len() as f32;
+        let headings: Vec<(f32, f32)> = boids
+            .iter()
+            .map(|boid| {
+                let (x, y) = boid.velocity();
+                let speed = x.hypot(y);
+                // Standing still does not point anywhere
+                if speed > 0.0 {
+                    (x / speed, y / speed)
+                } else {
+                    (0.0, 0.0)
+                }
+            })
+            .collect();
+        let (heading_x, heading_y) = headings.iter().fold((0.0, 0.0), |(sx, sy), (x, y)| (sx + x, sy + y));
+        let polarization = (heading_x / count).hypot(heading_y / count);
+
+        let (center_x, center_y) = boids.iter().fold((0.0, 0.0), |(sx, sy), boid| {
+            let (x, y) = boid.position();
+            (sx + x / count, sy + y / count)
+        });
+        let turn: f32 = boids
+            .iter()
+            .zip(&headings)
+            .map(|(boid, (heading_x, heading_y))| {
+                let (x, y) = boid.position();
+                let (offset_x, offset_y) = (x - center_x, y - center_y);
+                let distance = offset_x.hypot(offset_y);
+                if distance > 0.0 {
+                    (offset_x * heading_y - offset_y * heading_x) / distance
+                } else {
+                    0.0
+                }
+            })
+            .sum();
+
+        Self {
+            polarization,
+            milling: (turn / count).abs(),
+            flocks: flock_count(world, &boids),
+        }
+    }
+}
+
+/// Boids split into flocks, two boids in the same flock when a chain of boids each within
+/// vision radius of the next links them.
+pub fn flocks(world: &World) -> Vec<Vec<&Boid>> {
+    let boids = indexed_boids(world);
+    let roots = flock_roots(world, &boids);
+    let mut flocks: HashMap<usize, Vec<&Boid>> = HashMap::new();
+    for (boid, root) in boids.into_iter().zip(roots) {
+        flocks.entry(root).or_default().push(boid);
+    }
+    flocks.into_values().collect()
+}
+
+// The boids in the index of `world`, predators left out
+fn indexed_boids(world: &World) -> Vec<&Boid> {
+    world.index().iter_of(Species::Boid).collect()
+}
+
+fn flock_count(world: &World, boids: &[&Boid]) -> usize {
+    let roots = flock_roots(world, boids);
+    roots.iter().enumerate().filter(|(index, root)| index == *root).count()
+}
+
+// Index of the boid standing for the flock of each of `boids`, found by merging each boid with
+// the neighbors it sees
+fn flock_roots(world: &World, boids: &[&Boid]) -> Vec<usize> {
+    let indexes: HashMap<Uuid, usize> = boids
+        .iter()
+        .enumerate()
+        .map(|(index, boid)| (boid.id(), index))
+        .collect();
+    // Each boid points to another of its group, up to the one standing for the group
+    let mut parents: Vec<usize> = (0..boids.len()).collect();
+    let radius = world.option.boid_vision_radius;
+    let mut found = vec![];
+    for (index, boid) in boids.iter().enumerate() {
+        found.clear();
+        world.index().query_species(&mut found, boid, radius, Species::Boid.into());
+        let (x, y) = boid.position();
+        for other in &found {
+            let (other_x, other_y) = other.position();
+            let Some(&other_index) = indexes.get(&other.id()) else {
+                continue;
+            };
+            if (other_x - x).hypot(other_y - y) <= radius {
+                let (first, second) = (root(&mut parents, index), root(&mut parents, other_index));
+                parents[first] = second;
+            }
+        }
+    }
+    (0..boids.len()).map(|index| root(&mut parents, index)).collect()
+}
+
+fn root(parents: &mut [usize], mut index: usize) -> usize {
+    while parents[index] != index {
+        // Halving the path keeps later lookups short
+        parents[index] = parents[parents[index]];
+        index = parents[index];
+    }
+    index
+}
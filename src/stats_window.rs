@@ -0,0 +1,118 @@
+use egui::{ClippedPrimitive, Context, TexturesDelta};
+use egui_wgpu::renderer::{Renderer, ScreenDescriptor};
+use pixels::Pixels;
+use winit::event::WindowEvent;
+use winit::event_loop::EventLoopWindowTarget;
+use winit::window::{Window, WindowId};
+
+use crate::gui::{inspector_ui, render_egui, statistics_ui};
+
+use boids::statistics::Statistics;
+use boids::World;
+
+/// Second OS window with the statistics and the inspector, for a second monitor while the
+/// main window only shows the simulation.
+pub struct StatsWindow {
+    // Dropped before the window its surface draws to
+    pixels: Pixels,
+    window: Window,
+    egui_ctx: Context,
+    egui_state: egui_winit::State,
+    screen_descriptor: ScreenDescriptor,
+    renderer: Renderer,
+    paint_jobs: Vec<ClippedPrimitive>,
+    textures: TexturesDelta,
+    // Path of the last CSV export or why it failed
+    export: Option<Result<String, String>>,
+}
+
+impl StatsWindow {
+    pub const TITLE: &'static str = "Boids statistics";
+    pub const SIZE: (u32, u32) = (420, 560);
+
+    /// Draw the GUI in `window`, over the surface of `pixels`.
+    pub fn new<T>(event_loop: &EventLoopWindowTarget<T>, window: Window, pixels: Pixels) -> Self {
+        let max_texture_size = pixels.device().limits().max_texture_dimension_2d as usize;
+        let scale_factor = window.scale_factor() as f32;
+        let size = window.inner_size();
+
+        let mut egui_state = egui_winit::State::new(event_loop);
+        egui_state.set_max_texture_side(max_texture_size);
+        egui_state.set_pixels_per_point(scale_factor);
+        let renderer = Renderer::new(pixels.device(), pixels.render_texture_format(), None, 1);
+
+        Self {
+            pixels,
+            window,
+            egui_ctx: Context::default(),
+            egui_state,
+            screen_descriptor: ScreenDescriptor {
+                size_in_pixels: [size.width, size.height],
+                pixels_per_point: scale_factor,
+            },
+            renderer,
+            paint_jobs: Vec::new(),
+            textures: TexturesDelta::default(),
+            export: None,
+        }
+    }
+
+    pub fn id(&self) -> WindowId {
+        self.window.id()
+    }
+
+    /// Handle input events sent to this window, resizing the surface along with it.
+    pub fn handle_event(&mut self, event: &WindowEvent) {
+        self.egui_state.on_event(&self.egui_ctx, event);
+        match event {
+            WindowEvent::Resized(size) if size.width > 0 && size.height > 0 => {
+                // Nothing but the GUI is drawn, a failed resize only stretches it until the next one
+                let _ = self.pixels.resize_surface(size.width, size.height);
+                self.screen_descriptor.size_in_pixels = [size.width, size.height];
+            }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                self.screen_descriptor.pixels_per_point = *scale_factor as f32;
+            }
+            _ => (),
+        }
+    }
+
+    /// Run the egui frame of the statistics and the inspector, then ask for a redraw.
+    pub fn prepare(&mut self, statistics: &mut Statistics, world: &mut World) {
+        let raw_input = self.egui_state.take_egui_input(&self.window);
+        let output = self.egui_ctx.run(raw_input, |egui_ctx| {
+            egui::CentralPanel::default().show(egui_ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    ui.heading("Statistics");
+                    statistics_ui(ui, statistics, &mut self.export, world);
+                    ui.separator();
+                    ui.heading("Selected boid");
+                    inspector_ui(ui, world);
+                });
+            });
+        });
+
+        self.textures.append(output.textures_delta);
+        self.egui_state
+            .handle_platform_output(&self.window, &self.egui_ctx, output.platform_output);
+        self.paint_jobs = self.egui_ctx.tessellate(output.shapes);
+        self.window.request_redraw();
+    }
+
+    pub fn render(&mut self) -> Result<(), pixels::Error> {
+        self.pixels.render_with(|encoder, render_target, context| {
+            // Clears the surface, the panel covers all of it
+            context.scaling_renderer.render(encoder, render_target);
+            render_egui(
+                &mut self.renderer,
+                &mut self.textures,
+                &self.paint_jobs,
+                &self.screen_descriptor,
+                encoder,
+                render_target,
+                context,
+            );
+            Ok(())
+        })
+    }
+}
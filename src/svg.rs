@@ -0,0 +1,152 @@
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::capture::timestamped_path;
+use crate::geometry::Color;
+use crate::node::QuadTree;
+use crate::World;
+
+/// Save the world as `figure-<unix milliseconds>.svg` in `directory`, see `World::to_svg`.
+pub fn save_svg(world: &World, directory: &Path) -> io::Result<PathBuf> {
+    let path = timestamped_path(directory, "figure", "svg");
+    fs::write(&path, world.to_svg())?;
+    Ok(path)
+}
+
+// The SVG document of `world`, whose boids and predators are indexed in `index`
+pub(crate) fn document(world: &World, index: &QuadTree) -> String {
+    let option = &world.option;
+    let theme = option.palette.theme();
+    let overlays = option.show_debug_overlays;
+    let line_width = option.debug_line_width;
+    let (width, height) = (world.width(), world.height());
+
+    let mut svg = String::new();
+    // Writing to a String cannot fail
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    );
+    let _ = writeln!(
+        svg,
+        r#"<rect width="{width}" height="{height}" {}/>"#,
+        paint("fill", &theme.background)
+    );
+    for obstacle in world.obstacles() {
+        let _ = writeln!(
+            svg,
+            r#"<circle cx="{:.2}" cy="{:.2}" r="{:.2}" {}/>"#,
+            obstacle.x,
+            obstacle.y,
+            obstacle.radius,
+            paint("fill", &theme.obstacle)
+        );
+    }
+    for attractor in world.attractors() {
+        let _ = writeln!(
+            svg,
+            r#"<circle cx="{:.2}" cy="{:.2}" r="{:.2}" fill="none" stroke-width="1" {}/>"#,
+            attractor.x,
+            attractor.y,
+            attractor.radius,
+            paint("stroke", &theme.attractor)
+        );
+    }
+    if overlays && option.show_quad_tree {
+        let _ = writeln!(svg, r#"<g fill="none" stroke-width="{line_width:.2}">"#);
+        let capacity = index.config().capacity.max(1);
+        for (boundary, count) in index.boundaries() {
+            let color = if option.show_quad_tree_occupancy {
+                // Empty to full at capacity
+                Color::gradient(
+                    &theme.quad_tree_empty,
+                    &theme.quad_tree_full,
+                    count as f32 / capacity as f32,
+                )
+            } else {
+                theme.quad_tree.clone()
+            };
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" {}/>"#,
+                boundary.center_x - boundary.half_width,
+                boundary.center_y - boundary.half_height,
+                boundary.half_width * 2.0,
+                boundary.half_height * 2.0,
+                paint("stroke", &color)
+            );
+        }
+        let _ = writeln!(svg, "</g>");
+    }
+
+    let bodies: Vec<_> = world.boids().chain(world.predators()).collect();
+    // Shown or not, color, radius and how much of the body size it adds
+    let radii = [
+        (option.show_safe_radius, &theme.safe_radius, option.safe_radius, 0.5),
+        (option.show_vision_radius, &theme.vision, option.boid_vision_radius, 0.0),
+    ];
+    for (shown, color, radius, of_size) in radii {
+        if !overlays || !shown {
+            continue;
+        }
+        let _ = writeln!(
+            svg,
+            r#"<g fill="none" stroke-width="{line_width:.2}" {}>"#,
+            paint("stroke", color)
+        );
+        for body in &bodies {
+            let (x, y) = center(body.position(), body.size());
+            let radius = radius + body.size() as f32 * of_size;
+            let _ = writeln!(svg, r#"<circle cx="{x:.2}" cy="{y:.2}" r="{radius:.2}"/>"#);
+        }
+        let _ = writeln!(svg, "</g>");
+    }
+
+    for body in &bodies {
+        let size = body.size().max(1) as f32;
+        let (x, y) = center(body.position(), body.size());
+        // Pointing where it flies, as drawn in the window
+        let (forward_x, forward_y) = (body.heading().cos(), body.heading().sin());
+        let (side_x, side_y) = (-forward_y, forward_x);
+        let corners = [
+            (x + forward_x * size, y + forward_y * size),
+            (
+                x - forward_x * size / 2.0 + side_x * size / 2.0,
+                y - forward_y * size / 2.0 + side_y * size / 2.0,
+            ),
+            (
+                x - forward_x * size / 2.0 - side_x * size / 2.0,
+                y - forward_y * size / 2.0 - side_y * size / 2.0,
+            ),
+        ];
+        let points: Vec<String> = corners.iter().map(|(x, y)| format!("{x:.2},{y:.2}")).collect();
+        let _ = writeln!(
+            svg,
+            r#"<polygon points="{}" {}/>"#,
+            points.join(" "),
+            paint("fill", body.color())
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// Bodies are drawn from their position to the right and down
+fn center((x, y): (f32, f32), size: i16) -> (f32, f32) {
+    (x + size as f32 / 2.0, y + size as f32 / 2.0)
+}
+
+// `fill` or `stroke` attributes painting in `color`, with its opacity
+fn paint(attribute: &str, color: &Color) -> String {
+    let [r, g, b, a] = color.to_color_array();
+    if a == u8::MAX {
+        format!(r##"{attribute}="#{r:02x}{g:02x}{b:02x}""##)
+    } else {
+        format!(
+            r##"{attribute}="#{r:02x}{g:02x}{b:02x}" {attribute}-opacity="{:.3}""##,
+            a as f32 / 255.0
+        )
+    }
+}
@@ -0,0 +1,132 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::capture::timestamped_path;
+use crate::lfo::Parameter;
+use crate::statistics::OrderParameters;
+use crate::{World, WorldOption};
+
+/// An experiment running one setting over evenly spaced values, each with several seeds, for a
+/// number of ticks without drawing.
+#[derive(Clone)]
+pub struct Sweep {
+    pub parameter: Parameter,
+    pub from: f32,
+    pub to: f32,
+    /// Values run, `from` and `to` included
+    pub steps: u32,
+    /// Seeds run for each value, counted up from `first_seed`
+    pub seeds: u32,
+    pub first_seed: u64,
+    pub ticks: u32,
+    /// Settings of every run but the swept one
+    pub option: WorldOption,
+    pub size: (u16, u16),
+}
+
+/// Where one run of a `Sweep` ended.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SweepResult {
+    pub value: f32,
+    pub seed: u64,
+    pub boids: usize,
+    pub predators: usize,
+    pub catches: u64,
+    pub order: OrderParameters,
+}
+
+impl Sweep {
+    /// The values run, in order.
+    pub fn values(&self) -> Vec<f32> {
+        match self.steps {
+            0 => vec![],
+            1 => vec![self.from],
+            steps => (0..steps)
+                .map(|step| self.from + (self.to - self.from) * step as f32 / (steps - 1) as f32)
+                .collect(),
+        }
+    }
+
+    /// Run every value with every seed, spread over the cores, calling `done` as each run ends.
+    /// Results are in the order of the values then the seeds, whatever order they ran in.
+    pub fn run(&self, done: impl Fn(&SweepResult) + Sync) -> Vec<SweepResult> {
+        let runs: Vec<(f32, u64)> = self
+            .values()
+            .into_iter()
+            .flat_map(|value| (0..self.seeds as u64).map(move |seed| (value, seed)))
+            .map(|(value, seed)| (value, self.first_seed + seed))
+            .collect();
+        let results = Mutex::new(vec![None; runs.len()]);
+        let next = AtomicUsize::new(0);
+        let threads = thread::available_parallelism()
+            .map_or(1, |count| count.get())
+            .min(runs.len());
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(&(value, seed)) = runs.get(index) else {
+                        break;
+                    };
+                    let result = self.run_one(value, seed);
+                    done(&result);
+                    if let Ok(mut results) = results.lock() {
+                        results[index] = Some(result);
+                    }
+                });
+            }
+        });
+        results.into_inner().unwrap_or_default().into_iter().flatten().collect()
+    }
+
+    /// Run `value` with `seed` alone.
+    pub fn run_one(&self, value: f32, seed: u64) -> SweepResult {
+        let mut option = self.option.clone();
+        self.parameter.set(&mut option, value);
+        let (width, height) = self.size;
+        let mut world = World::builder().size(width, height).option(option).seed(seed).build();
+        for _ in 0..self.ticks {
+            world.update();
+        }
+        SweepResult {
+            value,
+            seed,
+            boids: world.boids_count(),
+            predators: world.predators_count(),
+            catches: world.catches(),
+            order: OrderParameters::of(&world),
+        }
+    }
+}
+
+/// Write `results` of a sweep of `parameter` to `sweep-<unix milliseconds>.csv` in
+/// `directory`, one row per run.
+pub fn export_csv(parameter: Parameter, results: &[SweepResult], directory: &Path) -> io::Result<PathBuf> {
+    let path = timestamped_path(directory, "sweep", "csv");
+    let mut writer = BufWriter::new(File::create(&path)?);
+    writeln!(
+        writer,
+        "{},seed,boids,predators,catches,polarization,milling,flocks",
+        parameter.key()
+    )?;
+    for result in results {
+        writeln!(
+            writer,
+            "{},{},{},{},{},{:.4},{:.4},{}",
+            result.value,
+            result.seed,
+            result.boids,
+            result.predators,
+            result.catches,
+            result.order.polarization,
+            result.order.milling,
+            result.order.flocks
+        )?;
+    }
+    writer.flush()?;
+    Ok(path)
+}
@@ -0,0 +1,159 @@
+use std::f32::consts::FRAC_PI_4;
+use std::io::{self, Stdout};
+
+use crossterm::execute;
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::backend::CrosstermBackend;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color as TerminalColor, Modifier, Style};
+use ratatui::widgets::{Paragraph, Widget};
+use ratatui::Terminal;
+
+use crate::boid::BoidView;
+use crate::geometry::Color;
+use crate::render::Renderer;
+use crate::World;
+
+/// Draws the flock as characters in the terminal, each boid an arrow pointing where it flies.
+///
+/// Takes over the terminal while it lives: alternate screen, raw mode and no cursor.
+pub struct TerminalRenderer {
+    terminal: Terminal<CrosstermBackend<Stdout>>,
+}
+
+impl TerminalRenderer {
+    /// World pixels in one character cell, cells are about twice as tall as wide
+    pub const CELL_WIDTH: u16 = 8;
+    pub const CELL_HEIGHT: u16 = 16;
+    // Headings from the right, turning clockwise as y grows downwards
+    const ARROWS: [char; 8] = ['→', '↘', '↓', '↙', '←', '↖', '↑', '↗'];
+    const PREDATOR: char = '◆';
+    const OBSTACLE: char = '█';
+    const ATTRACTOR: char = '✚';
+
+    pub fn new() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen)?;
+        let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+        terminal.hide_cursor()?;
+        Ok(Self { terminal })
+    }
+}
+
+impl Drop for TerminalRenderer {
+    fn drop(&mut self) {
+        // Nothing left to report failures to, the shell gets the terminal back as well as possible
+        let _ = terminal::disable_raw_mode();
+        let _ = execute!(io::stdout(), LeaveAlternateScreen);
+        let _ = self.terminal.show_cursor();
+    }
+}
+
+impl Renderer for TerminalRenderer {
+    fn size(&self) -> io::Result<(u16, u16)> {
+        let area = self.terminal.size()?;
+        // The last row is the status line
+        let rows = area.height.saturating_sub(1).max(1);
+        Ok((
+            area.width.max(1).saturating_mul(Self::CELL_WIDTH),
+            rows.saturating_mul(Self::CELL_HEIGHT),
+        ))
+    }
+
+    fn render(&mut self, world: &mut World) -> io::Result<()> {
+        self.terminal.draw(|frame| {
+            let area = frame.size();
+            let status_height = area.height.min(1);
+            let flock = Rect::new(area.x, area.y, area.width, area.height - status_height);
+            let status = Rect::new(area.x, flock.bottom(), area.width, status_height);
+            frame.render_widget(Flock { world }, flock);
+            frame.render_widget(status_line(world), status);
+        })?;
+        Ok(())
+    }
+}
+
+/// The world scaled to the cells of an area.
+struct Flock<'a> {
+    world: &'a World,
+}
+
+impl Flock<'_> {
+    fn cell(&self, area: Rect, x: f32, y: f32) -> Option<(u16, u16)> {
+        let column = x * area.width as f32 / self.world.width() as f32;
+        let row = y * area.height as f32 / self.world.height() as f32;
+        if column < 0.0 || row < 0.0 || column >= area.width as f32 || row >= area.height as f32 {
+            return None;
+        }
+        Some((area.x + column as u16, area.y + row as u16))
+    }
+
+    fn put(&self, buffer: &mut Buffer, area: Rect, x: f32, y: f32, symbol: char, style: Style) {
+        if let Some((column, row)) = self.cell(area, x, y) {
+            buffer.get_mut(column, row).set_char(symbol).set_style(style);
+        }
+    }
+}
+
+impl Widget for Flock<'_> {
+    fn render(self, area: Rect, buffer: &mut Buffer) {
+        if area.is_empty() {
+            return;
+        }
+        let theme = self.world.option.palette.theme();
+        let cell_width = self.world.width() as f32 / area.width as f32;
+        let cell_height = self.world.height() as f32 / area.height as f32;
+        let obstacle_style = Style::new().fg(terminal_color(&theme.obstacle));
+        for row in area.top()..area.bottom() {
+            for column in area.left()..area.right() {
+                let x = (column - area.x) as f32 * cell_width + cell_width / 2.0;
+                let y = (row - area.y) as f32 * cell_height + cell_height / 2.0;
+                let covered = self.world.obstacles().iter().any(|obstacle| {
+                    (x - obstacle.x).powi(2) + (y - obstacle.y).powi(2) <= obstacle.radius.powi(2)
+                });
+                if covered {
+                    buffer.get_mut(column, row).set_char(TerminalRenderer::OBSTACLE).set_style(obstacle_style);
+                }
+            }
+        }
+        let attractor_style = Style::new().fg(terminal_color(&theme.attractor));
+        for center in self.world.attractors() {
+            self.put(buffer, area, center.x, center.y, TerminalRenderer::ATTRACTOR, attractor_style);
+        }
+        for boid in self.world.boids() {
+            let (x, y) = boid.position();
+            let style = Style::new().fg(terminal_color(boid.color()));
+            self.put(buffer, area, x, y, heading_arrow(boid), style);
+        }
+        // Last so they stay visible inside a flock
+        for predator in self.world.predators() {
+            let (x, y) = predator.position();
+            let style = Style::new()
+                .fg(terminal_color(predator.color()))
+                .add_modifier(Modifier::BOLD);
+            self.put(buffer, area, x, y, TerminalRenderer::PREDATOR, style);
+        }
+    }
+}
+
+fn heading_arrow(boid: BoidView) -> char {
+    let octant = (boid.heading() / FRAC_PI_4).round() as i32;
+    TerminalRenderer::ARROWS[octant.rem_euclid(8) as usize]
+}
+
+fn status_line(world: &World) -> Paragraph<'static> {
+    let state = if world.is_paused() { "  Paused" } else { "" };
+    let text = format!(
+        " Boids {}  Predators {}  Tick {}{state}   Space pause  R restart  +/- speed  Q quit",
+        world.boids_count(),
+        world.predators_count(),
+        world.tick()
+    );
+    Paragraph::new(text).style(Style::new().add_modifier(Modifier::REVERSED))
+}
+
+fn terminal_color(color: &Color) -> TerminalColor {
+    let [r, g, b, _] = color.to_color_array();
+    TerminalColor::Rgb(r, g, b)
+}
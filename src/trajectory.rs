@@ -0,0 +1,202 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use arrow_array::{ArrayRef, FixedSizeBinaryArray, Float32Array, Int64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use parquet::arrow::ArrowWriter;
+
+use crate::capture::timestamped_path;
+use crate::World;
+
+/// Streams where every boid and predator is at every tick to Parquet files, one row per body
+/// and tick, for analysis in pandas, polars, R or DuckDB.
+///
+/// The columns are `tick`, `id` (the 16 bytes of a UUID), `x`, `y`, `vx`, `vy` and `species`
+/// (`boid` or `predator`), written by `parquet`'s `ArrowWriter` without compression. Rows are written in row groups as they
+/// fill up, and once a file passes the size limit it is closed and the next one started, so a
+/// long run never makes a file too big to load.
+pub struct TrajectoryWriter {
+    // Path of the files without the part number and extension
+    stem: PathBuf,
+    max_file_size: u64,
+    row_group_rows: usize,
+    schema: SchemaRef,
+    rows: Rows,
+    file: Option<ArrowWriter<BufWriter<File>>>,
+    files: Vec<PathBuf>,
+}
+
+impl TrajectoryWriter {
+    pub const DEFAULT_ROW_GROUP_ROWS: usize = 1 << 17;
+
+    /// Write `trajectories-<unix milliseconds>-<part>.parquet` files to `directory`, created
+    /// if needed, starting a new one past `max_file_size` bytes.
+    pub fn create(directory: &Path, max_file_size: u64) -> io::Result<Self> {
+        fs::create_dir_all(directory)?;
+        Ok(Self {
+            stem: timestamped_path(directory, "trajectories", "parquet").with_extension(""),
+            max_file_size,
+            row_group_rows: Self::DEFAULT_ROW_GROUP_ROWS,
+            schema: Arc::new(Schema::new(vec![
+                Field::new("tick", DataType::Int64, false),
+                Field::new("id", DataType::FixedSizeBinary(16), false),
+                Field::new("x", DataType::Float32, false),
+                Field::new("y", DataType::Float32, false),
+                Field::new("vx", DataType::Float32, false),
+                Field::new("vy", DataType::Float32, false),
+                Field::new("species", DataType::Utf8, false),
+            ])),
+            rows: Rows::default(),
+            file: None,
+            files: vec![],
+        })
+    }
+
+    /// Rows kept in memory before they are written as a row group.
+    pub fn row_group_rows(mut self, rows: usize) -> Self {
+        self.row_group_rows = rows.max(1);
+        self
+    }
+
+    /// Add a row for every boid and predator of `world` at its current tick.
+    pub fn record(&mut self, world: &World) -> io::Result<()> {
+        let tick = world.tick() as i64;
+        let boids = world.boids().map(|boid| (boid, false));
+        for (body, predator) in boids.chain(world.predators().map(|predator| (predator, true))) {
+            let (x, y) = body.position();
+            let (vx, vy) = body.velocity();
+            self.rows.push(tick, *body.id().as_bytes(), [x, y, vx, vy], predator);
+        }
+        if self.rows.len() >= self.row_group_rows {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Files written so far, the last one may still be open.
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Write the rows left and close the last file, giving every file written.
+    pub fn finish(mut self) -> io::Result<Vec<PathBuf>> {
+        self.flush()?;
+        if let Some(file) = self.file.take() {
+            file.close()?;
+        }
+        Ok(self.files)
+    }
+
+    /// Record every tick `world` completes from now on, see `TrajectoryRecording`. Updates run
+    /// on a copy are recorded once `World::apply_update` takes them, those it drops are not.
+    pub fn record_ticks(self, world: &mut World) -> TrajectoryRecording {
+        let recording = TrajectoryRecording(Arc::new(Mutex::new(Ok(Some(self)))));
+        let state = recording.0.clone();
+        world.on_tick_complete(move |world| {
+            let Ok(mut state) = state.lock() else {
+                return;
+            };
+            if let Ok(Some(writer)) = &mut *state {
+                if let Err(error) = writer.record(world) {
+                    *state = Err(error);
+                }
+            }
+        });
+        recording
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.rows.len() == 0 {
+            return Ok(());
+        }
+        if self.file.is_none() {
+            let part = self.files.len();
+            let path = PathBuf::from(format!("{}-{part:03}.parquet", self.stem.display()));
+            let file = BufWriter::new(File::create(&path)?);
+            self.file = Some(ArrowWriter::try_new(file, self.schema.clone(), None)?);
+            self.files.push(path);
+        }
+        let batch = std::mem::take(&mut self.rows).batch(self.schema.clone())?;
+        if let Some(file) = &mut self.file {
+            // One row group per flush, the writer would otherwise hold rows until it has enough
+            file.write(&batch)?;
+            file.flush()?;
+            if file.bytes_written() as u64 >= self.max_file_size {
+                self.file.take().map_or(Ok(()), |file| file.close().map(drop))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A `TrajectoryWriter` fed by `World::on_tick_complete`, which stops writing at the first
+/// error and keeps it for `finish`.
+#[derive(Clone)]
+pub struct TrajectoryRecording(Arc<Mutex<io::Result<Option<TrajectoryWriter>>>>);
+
+impl TrajectoryRecording {
+    /// Stop recording and close the files, giving every file written. Ticks completed later
+    /// are not recorded.
+    pub fn finish(&self) -> io::Result<Vec<PathBuf>> {
+        let mut state = self
+            .0
+            .lock()
+            .map_err(|_| io::Error::other("the trajectory writer panicked"))?;
+        match std::mem::replace(&mut *state, Ok(None)) {
+            Ok(Some(writer)) => writer.finish(),
+            Ok(None) => Ok(vec![]),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+// Buffered rows, column by column
+#[derive(Default)]
+struct Rows {
+    ticks: Vec<i64>,
+    ids: Vec<[u8; 16]>,
+    // Position and velocity
+    motions: Vec<[f32; 4]>,
+    predators: Vec<bool>,
+}
+
+impl Rows {
+    fn push(&mut self, tick: i64, id: [u8; 16], motion: [f32; 4], predator: bool) {
+        self.ticks.push(tick);
+        self.ids.push(id);
+        self.motions.push(motion);
+        self.predators.push(predator);
+    }
+
+    fn len(&self) -> usize {
+        self.ticks.len()
+    }
+
+    // The columns of `schema`, in its order
+    fn batch(self, schema: SchemaRef) -> io::Result<RecordBatch> {
+        let motion = |index: usize| -> ArrayRef {
+            Arc::new(
+                self.motions
+                    .iter()
+                    .map(|motion| motion[index])
+                    .collect::<Float32Array>(),
+            )
+        };
+        let species = self
+            .predators
+            .iter()
+            .map(|predator| if *predator { "predator" } else { "boid" });
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from(self.ticks)),
+            Arc::new(FixedSizeBinaryArray::try_from_iter(self.ids.iter()).map_err(io::Error::other)?),
+            motion(0),
+            motion(1),
+            motion(2),
+            motion(3),
+            Arc::new(StringArray::from_iter_values(species)),
+        ];
+        RecordBatch::try_new(schema, columns).map_err(io::Error::other)
+    }
+}
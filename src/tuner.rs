@@ -0,0 +1,103 @@
+/// Hill climbs the quadtree node capacity towards the lowest measured cost per query.
+#[derive(Clone)]
+pub struct CapacityTuner {
+    capacity: usize,
+    direction: isize,
+    last_cost: Option<f32>,
+    window_milliseconds: f32,
+    window_queries: usize,
+    window_ticks: u16,
+}
+
+impl CapacityTuner {
+    pub const MIN_CAPACITY: usize = 1;
+    pub const MAX_CAPACITY: usize = 64;
+    // Ticks averaged together before each adjustment
+    const WINDOW: u16 = 30;
+
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            direction: 1,
+            last_cost: None,
+            window_milliseconds: 0.0,
+            window_queries: 0,
+            window_ticks: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Restart tuning from the given capacity.
+    pub fn reset(&mut self, capacity: usize) {
+        *self = Self::new(capacity);
+    }
+
+    /// Add the time spent on `queries` quadtree queries during one tick.
+    pub fn record(&mut self, milliseconds: f32, queries: usize) {
+        self.window_milliseconds += milliseconds;
+        self.window_queries += queries;
+        self.window_ticks += 1;
+        if self.window_ticks < Self::WINDOW {
+            return;
+        }
+
+        let cost = self.window_milliseconds / self.window_queries.max(1) as f32;
+        if let Some(last_cost) = self.last_cost {
+            if cost > last_cost {
+                self.direction = -self.direction;
+            }
+        }
+        self.last_cost = Some(cost);
+        self.capacity = (self.capacity as isize + self.direction)
+            .clamp(Self::MIN_CAPACITY as isize, Self::MAX_CAPACITY as isize) as usize;
+
+        self.window_milliseconds = 0.0;
+        self.window_queries = 0;
+        self.window_ticks = 0;
+    }
+}
+
+/// Raises the avoid factor a step at a time until few enough boids overlap, see
+/// `World::overlaps`. Overlaps jump around from tick to tick, so they are averaged over a window
+/// before each step.
+#[derive(Clone, Default)]
+pub struct AvoidTuner {
+    last_tick: Option<u64>,
+    window_overlaps: f32,
+    window_ticks: u16,
+}
+
+impl AvoidTuner {
+    pub const STEP: f32 = 0.02;
+    pub const MAX_AVOID_FACTOR: f32 = 1.0;
+    // Ticks averaged together before each step
+    const WINDOW: u16 = 30;
+
+    /// Add the overlaps per boid of the update at `tick`, raising `avoid_factor` after a window
+    /// averaging above `target`. Ticks already added are skipped, so it can be called every
+    /// frame. False once a window averages at or below `target` or the avoid factor is at its
+    /// max, tuning is then over.
+    pub fn record(&mut self, tick: u64, overlaps_per_boid: f32, target: f32, avoid_factor: &mut f32) -> bool {
+        if self.last_tick == Some(tick) {
+            return true;
+        }
+        self.last_tick = Some(tick);
+        self.window_overlaps += overlaps_per_boid;
+        self.window_ticks += 1;
+        if self.window_ticks < Self::WINDOW {
+            return true;
+        }
+
+        let mean = self.window_overlaps / self.window_ticks as f32;
+        self.window_overlaps = 0.0;
+        self.window_ticks = 0;
+        if mean <= target || *avoid_factor >= Self::MAX_AVOID_FACTOR {
+            return false;
+        }
+        *avoid_factor = (*avoid_factor + Self::STEP).min(Self::MAX_AVOID_FACTOR);
+        true
+    }
+}
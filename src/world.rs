@@ -0,0 +1,2298 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::Path;
+use std::sync::Arc;
+
+use instant::Instant;
+
+use crate::background::{Background, BackgroundStyle};
+use crate::boid::{Boid, BoidView};
+use crate::builder::WorldBuilder;
+use crate::camera::Camera;
+use crate::chunk::Chunks;
+use crate::error::{Error, Result};
+use crate::geometry::{
+    convex_hull, draw_colored_line, draw_colored_line_anti_aliased, Circle, Color, Polygon, Polyline, Rectangle, Text,
+};
+use crate::history::{Edit, History};
+use crate::hook::Hooks;
+use crate::hunt::HuntStats;
+use crate::lfo::Lfo;
+use crate::minimap::Minimap;
+use crate::node::{IndexBackend, IndexConfig, IndexStats, MovableNode, QuadTree, RenderNode, Vertice};
+use crate::obstacle::{Attractor, Obstacle};
+use crate::palette::Palette;
+use crate::preset::Species;
+use crate::profile::Profile;
+use crate::particle::ParticleSystem;
+use crate::profiler::{Profiler, Stage};
+use crate::random::Random;
+use crate::rule::{default_rules, SteeringRule, Surroundings};
+use crate::replay::{Input, Player, Recorder, Replay, Tape};
+use crate::scenario::{ScenarioFile, Spawn};
+use crate::snapshot::Snapshot;
+use crate::statistics::{self, NeighborDistances};
+use crate::svg;
+use crate::tuner::CapacityTuner;
+use crate::{HEIGHT, NUMBER_OF_BOIDS, NUMBER_OF_PREDATOR, QUAD_TREE_CAPACITY, SIZE, WIDTH};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorMode {
+    Plain,
+    Speed,
+    Heading,
+}
+
+impl ColorMode {
+    pub const ALL: [ColorMode; 3] = [ColorMode::Plain, ColorMode::Speed, ColorMode::Heading];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorMode::Plain => "Plain",
+            ColorMode::Speed => "Speed",
+            ColorMode::Heading => "Heading",
+        }
+    }
+}
+
+/// Boids whose rule forces are drawn as arrows, see `WorldOption::force_arrows`.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ForceArrows {
+    Off,
+    Selected,
+    All,
+}
+
+impl ForceArrows {
+    pub const ALL: [ForceArrows; 3] = [ForceArrows::Off, ForceArrows::Selected, ForceArrows::All];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ForceArrows::Off => "Off",
+            ForceArrows::Selected => "Selected boid",
+            ForceArrows::All => "Every boid",
+        }
+    }
+}
+
+/// Whether `World::update` advances the simulation
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RunState {
+    Running,
+    Paused,
+    // Advance one tick, then pause
+    Step,
+}
+
+/// Every setting of the simulation, edited live from the GUI and saved in presets.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+// Presets saved by older versions miss the newer options
+#[serde(default)]
+pub struct WorldOption {
+    // Boid
+    pub avoid_factor: f32,
+    pub matching_factor: f32,
+    pub centering_factor: f32,
+    pub safe_radius: f32,
+    pub boid_vision_radius: f32,
+    pub boid_max_speed: i16,
+    pub boid_min_speed: i16,
+    pub margin: u16,
+    pub turn_factor: i16,
+    // Steer around the areas given with `World::set_avoided_areas`, like GUI windows
+    pub avoid_gui: bool,
+    // No edges to wrap around or turn back at, the frame follows the flock, see `Chunks`
+    pub unbounded: bool,
+    pub boid_view_angle: f32,
+    pub noise: bool,
+    pub boid_size: i16,
+    // Random spread of each boid size, 0.25 is up to 25% smaller or larger
+    pub size_variation: f32,
+    // Spawned by `restart`, or approached while running with `live_population`
+    pub boid_count: u16,
+    pub live_population: bool,
+    // Predator
+    pub fear_factor: f32,
+    pub fear_radius: f32,
+    pub predator_vision_radius: f32,
+    pub predator_max_speed: i16,
+    pub predator_min_speed: i16,
+    pub predator_view_angle: f32,
+    pub predator_size: i16,
+    pub predators_catch: bool,
+    pub catch_radius: f32,
+    pub predator_count: u16,
+    // Performance
+    pub auto_population: bool,
+    pub target_fps: u16,
+    pub quad_tree_capacity: usize,
+    pub auto_quad_tree_capacity: bool,
+    pub index_backend: IndexBackend,
+    pub quad_tree_max_depth: u16,
+    pub grid_cell_size: f32,
+    pub limit_fps: bool,
+    pub max_fps: u16,
+    pub vsync: bool,
+    // No updates or redraws while the window is unfocused or minimized
+    pub pause_in_background: bool,
+    // Boid and predator bodies drawn as GPU instances instead of into the frame
+    pub gpu_boids: bool,
+    // Updates run per frame, up to `MAX_SIMULATION_SPEED`
+    pub simulation_speed: u8,
+    // Appearance
+    pub show_trails: bool,
+    pub trail_decay: f32,
+    pub high_quality_rendering: bool,
+    pub color_mode: ColorMode,
+    // Share of the turn towards the velocity a drawn body leaves for the next ticks, 0 points
+    // it along the velocity however much that swings
+    pub heading_smoothing: f32,
+    pub background_style: BackgroundStyle,
+    pub gradient_top: [u8; 3],
+    pub gradient_bottom: [u8; 3],
+    pub grid_spacing: u16,
+    pub show_catch_particles: bool,
+    pub show_minimap: bool,
+    pub palette: Palette,
+    // DEBUG
+    // Hides every overlay below without forgetting which ones are on
+    pub show_debug_overlays: bool,
+    pub show_quad_tree: bool,
+    pub show_quad_tree_occupancy: bool,
+    pub show_quad_tree_counts: bool,
+    pub show_safe_radius: bool,
+    pub show_vision_radius: bool,
+    pub show_facing_direction_with_speed: bool,
+    pub debug_line_width: f32,
+    pub show_timing_overlay: bool,
+    pub trajectory_length: usize,
+    pub show_selected_neighbors: bool,
+    // Outline of every flock of at least three boids, redrawn every few frames
+    pub show_flock_hulls: bool,
+    // What the separation, alignment, cohesion and fear rules changed in the velocity, drawn
+    // from the boid in their palette colors and lengthened by the scale
+    pub force_arrows: ForceArrows,
+    pub force_arrow_scale: f32,
+    // Animation
+    pub lfos: Vec<Lfo>,
+}
+
+impl WorldOption {
+    pub fn new() -> Self {
+        Self {
+            // Boid
+            avoid_factor: 0.27,
+            matching_factor: 0.55,
+            centering_factor: 0.06,
+            safe_radius: 10.0,
+            boid_vision_radius: 30.0,
+            boid_max_speed: 10,
+            boid_min_speed: 5,
+            margin: 20,
+            turn_factor: 30,
+            avoid_gui: false,
+            unbounded: false,
+            boid_view_angle: 120.0,
+            noise: false,
+            boid_size: SIZE,
+            size_variation: 0.0,
+            boid_count: NUMBER_OF_BOIDS,
+            live_population: false,
+            // Predator
+            fear_factor: 1.0,
+            fear_radius: 30.0,
+            predator_vision_radius: 40.0,
+            predator_max_speed: 8,
+            predator_min_speed: 3,
+            predator_view_angle: 90.0,
+            predator_size: SIZE * 2,
+            predators_catch: false,
+            catch_radius: 6.0,
+            predator_count: NUMBER_OF_PREDATOR,
+            // Performance
+            auto_population: false,
+            target_fps: 60,
+            quad_tree_capacity: QUAD_TREE_CAPACITY,
+            auto_quad_tree_capacity: false,
+            index_backend: IndexBackend::QuadTree,
+            quad_tree_max_depth: IndexConfig::DEFAULT_MAX_DEPTH,
+            grid_cell_size: IndexConfig::DEFAULT_CELL_SIZE,
+            limit_fps: false,
+            max_fps: 60,
+            vsync: true,
+            pause_in_background: false,
+            gpu_boids: false,
+            simulation_speed: 1,
+            // Appearance
+            show_trails: false,
+            trail_decay: 0.85,
+            high_quality_rendering: false,
+            color_mode: ColorMode::Plain,
+            heading_smoothing: 0.6,
+            background_style: BackgroundStyle::Solid,
+            gradient_top: [10, 20, 60],
+            gradient_bottom: [0, 0, 0],
+            grid_spacing: 40,
+            show_catch_particles: true,
+            show_minimap: true,
+            palette: Palette::Classic,
+            // DEBUG
+            show_debug_overlays: true,
+            show_quad_tree: false,
+            show_quad_tree_occupancy: false,
+            show_quad_tree_counts: false,
+            show_safe_radius: false,
+            show_vision_radius: false,
+            show_facing_direction_with_speed: false,
+            debug_line_width: 1.0,
+            show_timing_overlay: false,
+            trajectory_length: 100,
+            show_selected_neighbors: true,
+            show_flock_hulls: false,
+            force_arrows: ForceArrows::Off,
+            force_arrow_scale: 5.0,
+            // Animation
+            lfos: vec![],
+        }
+    }
+}
+
+impl WorldOption {
+    pub const MAX_SIMULATION_SPEED: u8 = 8;
+
+    /// Copy with every debug overlay turned off.
+    fn without_debug_overlays(&self) -> Self {
+        Self {
+            show_quad_tree: false,
+            show_safe_radius: false,
+            show_vision_radius: false,
+            show_facing_direction_with_speed: false,
+            show_timing_overlay: false,
+            show_selected_neighbors: false,
+            show_flock_hulls: false,
+            force_arrows: ForceArrows::Off,
+            ..self.clone()
+        }
+    }
+}
+
+impl Default for WorldOption {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// World sized frame drawn into while zoomed in, kept between frames for the trails.
+///
+/// The update thread never draws, so clones start empty instead of copying the pixels.
+#[derive(Default)]
+struct SceneBuffer(Vec<u8>);
+
+impl Clone for SceneBuffer {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+/// The simulation: boids and predators in their spatial indexes, the scenery and the camera.
+///
+/// `update` moves everything one tick and `draw` renders into an RGBA frame of
+/// `width` × `height` pixels.
+#[derive(Clone)]
+pub struct World {
+    background: Background,
+    width: u16,
+    height: u16,
+    // Area indexed and simulated, the frame unless the world is unbounded
+    boundary: Rectangle,
+    // Stragglers of an unbounded world and where the frame is on the plane
+    chunks: Chunks,
+    // How far the frame moved to follow the flock last update
+    scroll: (f32, f32),
+    // Every boid and predator, told apart by their species
+    quad_tree: QuadTree,
+    particles: ParticleSystem,
+    // Placed by the user, kept across restarts
+    obstacles: Vec<Obstacle>,
+    // Covered by something drawn on top of the world, not saved with it
+    avoided_areas: Vec<Rectangle>,
+    attractors: Vec<Attractor>,
+    selected: Option<Uuid>,
+    // Boids and predators picked with a selection box for bulk actions
+    box_selection: HashSet<Uuid>,
+    // Selection box being dragged, in world coordinates
+    selection_box: Option<Rectangle>,
+    // Recent positions of the selected boid, oldest first
+    trajectory: VecDeque<Vertice>,
+    // Neighbors the selected boid reacted to last update, colored by rule
+    neighbors: Vec<(Vertice, Color)>,
+    // Force arrows of last update, from the boid to the tip, colored by rule
+    forces: Vec<(Vertice, Vertice, Color)>,
+    // Boids of each flock with its color, frames drawn since they were found and their outlines
+    flocks: Vec<(Vec<Uuid>, Color)>,
+    flock_hulls_age: u32,
+    flock_hulls: Vec<(Vec<(f32, f32)>, Color)>,
+    camera: Camera,
+    scene: SceneBuffer,
+    // Boids caught by predators since the world was created
+    catches: u64,
+    // Of every predator, forgotten once it is gone
+    hunts: HashMap<Uuid, HuntStats>,
+    run_state: RunState,
+    pub update_fps: f32,
+    pub draw_fps: f32,
+    pub option: WorldOption,
+    pub profiler: Profiler,
+    capacity_tuner: CapacityTuner,
+    // Boids returned per neighbor query and rules time per query in microseconds, last update
+    query_candidates: f32,
+    query_cost: f32,
+    // Nearest neighbor distances of last update
+    neighbor_distances: Option<NeighborDistances>,
+    // Boid pairs closer than the safe radius, last update
+    overlaps: usize,
+    // Bumped whenever the whole flock is replaced outside of `update`
+    generation: u64,
+    // Boids and predators edited here since `update_copy`, and whether each one was there
+    // when the copy was taken
+    edited: Option<HashMap<Uuid, bool>>,
+    history: History,
+    // Every random choice of the simulation comes from here, so a seed replays a run.
+    // A `ChaCha12Rng` unless set with `set_rng`, the same generator as `StdRng` but one
+    // snapshots can save
+    rng: Box<dyn Random>,
+    seed: u64,
+    // Updates run since the last restart
+    tick: u64,
+    tape: Tape,
+    // Run in order on every boid by `update`
+    rules: Vec<Arc<dyn SteeringRule>>,
+    // Position of the last boid left out of the spatial index, until taken by `take_error`
+    lost_boid: Option<(f32, f32)>,
+    hooks: Hooks,
+}
+
+impl World {
+    /// Set up a world with its flock, starting from the default size and options.
+    pub fn builder() -> WorldBuilder {
+        WorldBuilder::new()
+    }
+
+    /// Empty world, call `restart` to spawn the flock.
+    pub fn with_size(width: u16, height: u16) -> Self {
+        let boundary = Self::boundary_for(width, height);
+        let seed = rand::random();
+        Self {
+            background: Background::new(Color::Black),
+            width,
+            height,
+            quad_tree: QuadTree::new(QUAD_TREE_CAPACITY, boundary.clone()),
+            particles: ParticleSystem::new(),
+            obstacles: vec![],
+            avoided_areas: vec![],
+            attractors: vec![],
+            selected: None,
+            box_selection: HashSet::new(),
+            selection_box: None,
+            trajectory: VecDeque::new(),
+            neighbors: vec![],
+            forces: vec![],
+            flocks: vec![],
+            flock_hulls_age: 0,
+            flock_hulls: vec![],
+            camera: Camera::new(width, height),
+            scene: SceneBuffer::default(),
+            catches: 0,
+            hunts: HashMap::new(),
+            run_state: RunState::Running,
+            boundary,
+            chunks: Chunks::default(),
+            scroll: (0.0, 0.0),
+            update_fps: 0.0,
+            draw_fps: 0.0,
+            option: WorldOption::new(),
+            profiler: Profiler::new(),
+            capacity_tuner: CapacityTuner::new(QUAD_TREE_CAPACITY),
+            query_candidates: 0.0,
+            query_cost: 0.0,
+            neighbor_distances: None,
+            overlaps: 0,
+            generation: 0,
+            edited: None,
+            history: History::new(WorldOption::new()),
+            rng: Box::new(ChaCha12Rng::seed_from_u64(seed)),
+            seed,
+            tick: 0,
+            tape: Tape::Idle,
+            rules: default_rules(),
+            lost_boid: None,
+            hooks: Hooks::default(),
+        }
+    }
+
+    fn boundary_for(width: u16, height: u16) -> Rectangle {
+        Rectangle::new(
+            width as f32 / 2.0,
+            height as f32 / 2.0,
+            width as f32 / 2.0,
+            height as f32 / 2.0,
+        )
+    }
+
+    /// Width in pixels.
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    /// Height in pixels.
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Change the world size, moving every boid to the same relative position.
+    pub fn resize(&mut self, width: u16, height: u16) {
+        if width == 0 || height == 0 || (width == self.width && height == self.height) {
+            return;
+        }
+        self.record(Input::Resize { width, height });
+        self.generation = self.generation.wrapping_add(1);
+        let scale_x = width as f32 / self.width as f32;
+        let scale_y = height as f32 / self.height as f32;
+        self.width = width;
+        self.height = height;
+        self.boundary = Self::boundary_for(width, height);
+        let boids: Vec<Boid> = self
+            .quad_tree
+            .to_vec()
+            .into_iter()
+            .map(|mut boid| {
+                let (x, y) = boid.position();
+                boid.set_position(x * scale_x, y * scale_y);
+                boid
+            })
+            .collect();
+        self.quad_tree = QuadTree::with_config(self.index_config(), self.boundary.clone());
+        let parking = self.option.unbounded.then_some(&mut self.chunks);
+        let lost = insert_all(&mut self.quad_tree, &boids, parking);
+        self.report(lost);
+        for obstacle in &mut self.obstacles {
+            obstacle.x *= scale_x;
+            obstacle.y *= scale_y;
+        }
+        for attractor in &mut self.attractors {
+            attractor.x *= scale_x;
+            attractor.y *= scale_y;
+        }
+        self.trajectory.clear();
+        self.neighbors.clear();
+        self.forces.clear();
+        self.camera = Camera::new(width, height);
+    }
+
+    pub fn zoom(&self) -> f32 {
+        self.camera.zoom()
+    }
+
+    /// Part of the world shown on screen, in world coordinates.
+    pub fn viewport(&self) -> Rectangle {
+        self.camera.viewport(self.width, self.height)
+    }
+
+    /// World position under the screen position (x, y).
+    pub fn screen_to_world(&self, x: f32, y: f32) -> (f32, f32) {
+        self.camera.screen_to_world(x, y, self.width, self.height)
+    }
+
+    /// Zoom by `factor` around the screen position (x, y).
+    pub fn zoom_at(&mut self, factor: f32, x: f32, y: f32) {
+        self.camera.zoom_at(factor, x, y, self.width, self.height);
+    }
+
+    /// Move the camera by a drag of (dx, dy) screen pixels.
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        self.camera.pan(dx, dy, self.width, self.height);
+    }
+
+    pub fn reset_camera(&mut self) {
+        self.camera = Camera::new(self.width, self.height);
+    }
+
+    /// Center the camera on the minimap position under (x, y), returns false when (x, y) is
+    /// not on a visible minimap.
+    pub fn jump_to_minimap(&mut self, x: f32, y: f32) -> bool {
+        if !self.camera.is_zoomed() || !self.option.show_minimap {
+            return false;
+        }
+        match Minimap::new(self.width, self.height).to_world(x, y) {
+            Some((world_x, world_y)) => {
+                self.camera.look_at(world_x, world_y, self.width, self.height);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Show the selection box being dragged, or hide it with `None`.
+    pub fn set_selection_box(&mut self, area: Option<Rectangle>) {
+        self.selection_box = area;
+    }
+
+    /// Replace the box selection with the boids and predators inside `area`.
+    pub fn select_in(&mut self, area: &Rectangle) {
+        self.record(Input::SelectIn(area.clone()));
+        let mut found: Vec<Boid> = vec![];
+        self.quad_tree.query_rectangle(&mut found, area);
+        self.box_selection = found.iter().map(Boid::id).collect();
+    }
+
+    /// Number of boxed boids and predators still alive.
+    pub fn box_selection_count(&self) -> usize {
+        self.quad_tree
+            .iter()
+            .filter(|boid| self.box_selection.contains(&boid.id()))
+            .count()
+    }
+
+    pub fn clear_box_selection(&mut self) {
+        self.record(Input::ClearSelection);
+        self.box_selection.clear();
+    }
+
+    pub fn delete_box_selection(&mut self) {
+        self.record(Input::DeleteSelection);
+        let selection = std::mem::take(&mut self.box_selection);
+        self.touch(selection.iter().copied(), true);
+        let history = &mut self.history;
+        let hooks = &mut self.hooks;
+        self.quad_tree.retain(|boid| {
+            if selection.contains(&boid.id()) {
+                history.record_removed(boid);
+                hooks.despawned(boid, boid.species());
+                return false;
+            }
+            true
+        });
+    }
+
+    pub fn recolor_box_selection(&mut self, color: Color) {
+        self.record(Input::RecolorSelection(color.clone()));
+        self.modify_box_selection(|boid| boid.set_fixed_color(color.clone()));
+    }
+
+    pub fn freeze_box_selection(&mut self, frozen: bool) {
+        self.record(Input::FreezeSelection(frozen));
+        self.modify_box_selection(|boid| boid.set_frozen(frozen));
+    }
+
+    /// Turn the boxed boids into predators, keeping their position and speed.
+    pub fn convert_box_selection_to_predators(&mut self) {
+        self.record(Input::ConvertSelection);
+        self.touch(self.box_selection.clone(), true);
+        let predator_color = self.option.palette.theme().predator;
+        // Species split the index on their own so converted boids are placed again
+        for mut boid in self.quad_tree.to_vec_of(Species::Boid) {
+            if self.box_selection.contains(&boid.id()) {
+                self.history.record_removed(&boid);
+                boid.set_species(Species::Predator);
+                boid.set_base_size(self.option.predator_size);
+                boid.set_color(predator_color.clone());
+                self.history.record_added(&boid);
+                let result = self.quad_tree.insert(&boid);
+                self.report(result.err());
+            }
+        }
+        let selection = &self.box_selection;
+        self.quad_tree
+            .retain(|boid| boid.species() != Species::Boid || !selection.contains(&boid.id()));
+    }
+
+    /// Have the boid `id` follow `profile` over the settings of the world, an empty profile
+    /// puts it back on them. Predators keep to the predator settings.
+    pub fn set_profile(&mut self, id: Uuid, profile: Profile) {
+        self.record(Input::SetProfile { id, profile });
+        self.touch([id], true);
+        let history = &mut self.history;
+        self.quad_tree.for_each_mut(|boid| {
+            if boid.id() == id && boid.species() == Species::Boid {
+                history.record_removed(boid);
+                boid.set_profile(profile);
+                history.record_added(boid);
+            }
+        });
+    }
+
+    fn modify_box_selection(&mut self, mut f: impl FnMut(&mut Boid)) {
+        // Changes made here must not be overwritten by an update in flight
+        self.touch(self.box_selection.clone(), true);
+        let selection = &self.box_selection;
+        let history = &mut self.history;
+        self.quad_tree.for_each_mut(|boid| {
+            if selection.contains(&boid.id()) {
+                history.record_removed(boid);
+                f(boid);
+                history.record_added(boid);
+            }
+        });
+    }
+
+    /// Select the boid or predator closest to (x, y), or clear the selection if none is near.
+    pub fn select_at(&mut self, x: i16, y: i16) {
+        const SELECT_RADIUS: f32 = 15.0;
+        let distance = |boid: &Boid| {
+            let dx = (boid.vertice.x - x) as f32;
+            let dy = (boid.vertice.y - y) as f32;
+            (dx * dx + dy * dy).sqrt()
+        };
+        self.selected = self
+            .quad_tree
+            .iter()
+            .map(|boid| (distance(boid), boid))
+            .filter(|(distance, _)| *distance <= SELECT_RADIUS)
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, boid)| boid.id());
+        self.trajectory.clear();
+        self.neighbors.clear();
+        self.forces.clear();
+    }
+
+    pub fn selected(&self) -> Option<Uuid> {
+        self.selected
+    }
+
+    fn record_trajectory(&mut self, position: Vertice) {
+        // Start over when the boid wraps around an edge instead of drawing across the screen
+        if let Some(last) = self.trajectory.back() {
+            if (last.x - position.x).unsigned_abs() > self.width / 2
+                || (last.y - position.y).unsigned_abs() > self.height / 2
+            {
+                self.trajectory.clear();
+            }
+        }
+        self.trajectory.push_back(position);
+        while self.trajectory.len() > self.option.trajectory_length {
+            self.trajectory.pop_front();
+        }
+    }
+
+    /// Color each neighbor by the palette color of its rules, mixed when it counts for more than one.
+    fn record_neighbors(&mut self, position: &Vertice, accepted: &[Vec<Uuid>; 3], boids: &[Boid]) {
+        let theme = self.option.palette.theme();
+        let rule_colors = [theme.separation, theme.alignment, theme.cohesion];
+        self.neighbors = boids
+            .iter()
+            .filter(|boid| accepted.iter().any(|ids| ids.contains(&boid.id())))
+            // Neighbors that wrapped around an edge would be joined across the screen
+            .filter(|boid| {
+                (boid.vertice.x - position.x).unsigned_abs() <= self.width / 2
+                    && (boid.vertice.y - position.y).unsigned_abs() <= self.height / 2
+            })
+            .map(|boid| {
+                let mut color = [0, 0, 0, 255];
+                for (ids, rule_color) in accepted.iter().zip(&rule_colors) {
+                    if ids.contains(&boid.id()) {
+                        for (channel, rule_channel) in color.iter_mut().zip(rule_color.to_color_array()).take(3) {
+                            *channel = (*channel).max(rule_channel);
+                        }
+                    }
+                }
+                let [r, g, b, a] = color;
+                (boid.vertice.clone(), Color::Rgba(r, g, b, a))
+            })
+            .collect();
+    }
+
+    pub fn run_state(&self) -> RunState {
+        self.run_state
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.run_state != RunState::Running
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.run_state = match self.run_state {
+            RunState::Running => RunState::Paused,
+            RunState::Paused | RunState::Step => RunState::Running,
+        };
+    }
+
+    /// Advance one tick on the next update and pause again.
+    pub fn step_once(&mut self) {
+        self.run_state = RunState::Step;
+    }
+
+    /// Whether this tick should run, consuming a pending single step.
+    pub(crate) fn begin_tick(&mut self) -> bool {
+        match self.run_state {
+            RunState::Running => true,
+            RunState::Step => {
+                self.run_state = RunState::Paused;
+                true
+            }
+            RunState::Paused => false,
+        }
+    }
+
+    pub fn load_background_image(&mut self, path: &Path) -> Result<()> {
+        self.background.load_image(path)
+    }
+
+    fn random_position(&mut self) -> (i16, i16) {
+        let size = self.option.boid_size.max(0) as u16;
+        let x = self.rng.gen_range(0..self.width.saturating_sub(size).max(1)) as i16;
+        let y = self.rng.gen_range(0..self.height.saturating_sub(size).max(1)) as i16;
+        (x, y)
+    }
+
+    /// Seed the random numbers were last started from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Start the random numbers over from `seed`, a `restart` right after replays the same run.
+    /// A generator set with `set_rng` is replaced by the default one.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.record(Input::SetSeed(seed));
+        self.seed = seed;
+        self.rng = Box::new(ChaCha12Rng::seed_from_u64(seed));
+    }
+
+    /// Take every random choice from `rng` from now on, until `set_seed` or `restore`.
+    /// Snapshots of the world then resume from the seed instead of where `rng` was, and
+    /// recorded inputs only replay the same run with the same generator.
+    pub fn set_rng(&mut self, rng: impl Random + 'static) {
+        self.set_boxed_rng(Box::new(rng));
+    }
+
+    pub(crate) fn set_boxed_rng(&mut self, rng: Box<dyn Random>) {
+        self.rng = rng;
+    }
+
+    /// Spawn boids and predators at random positions.
+    pub fn spawn_random_boids(&mut self, boids_numbers: u16, predators_numbers: u16) {
+        self.record(Input::SpawnRandom {
+            boids: boids_numbers,
+            predators: predators_numbers,
+        });
+        self.quietly(|world| {
+            for _ in 0..boids_numbers {
+                let (x, y) = world.random_position();
+                world.spawn_boids(x, y);
+            }
+            for _ in 0..predators_numbers {
+                let (x, y) = world.random_position();
+                world.spawn_predators(x, y);
+            }
+        });
+    }
+
+    /// Spawn one boid at `x`, `y` in world coordinates.
+    pub fn spawn_boids(&mut self, x: i16, y: i16) {
+        self.record(Input::SpawnBoid { x, y });
+        let boid = self.new_boid(x, y);
+        self.touch([boid.id()], false);
+        self.history.record_added(&boid);
+        self.hooks.spawned(&boid, Species::Boid);
+        let result = self.quad_tree.insert(&boid);
+        self.report(result.err());
+    }
+
+    /// Spawn one predator at `x`, `y` in world coordinates.
+    pub fn spawn_predators(&mut self, x: i16, y: i16) {
+        self.record(Input::SpawnPredator { x, y });
+        let predator = self.new_predator(x, y);
+        self.touch([predator.id()], false);
+        self.history.record_added(&predator);
+        self.hooks.spawned(&predator, Species::Predator);
+        let result = self.quad_tree.insert(&predator);
+        self.report(result.err());
+    }
+
+    pub fn obstacles(&self) -> &[Obstacle] {
+        &self.obstacles
+    }
+
+    /// Areas in world coordinates the flock steers out of while `avoid_gui` is on, kept until
+    /// set again. The window passes the panels drawn over the world every frame.
+    pub fn set_avoided_areas(&mut self, areas: Vec<Rectangle>) {
+        self.avoided_areas = areas;
+    }
+
+    pub fn attractors(&self) -> &[Attractor] {
+        &self.attractors
+    }
+
+    /// Place an obstacle the boids steer around.
+    pub fn add_obstacle(&mut self, x: f32, y: f32) {
+        self.record(Input::AddObstacle { x, y });
+        self.history.record_scenery(&self.obstacles, &self.attractors);
+        self.obstacles.push(Obstacle::new(x, y));
+    }
+
+    /// Place an attractor the boids are pulled towards.
+    pub fn add_attractor(&mut self, x: f32, y: f32) {
+        self.record(Input::AddAttractor { x, y });
+        self.history.record_scenery(&self.obstacles, &self.attractors);
+        self.attractors.push(Attractor::new(x, y));
+    }
+
+    /// Put `obstacles` and `attractors` in place of the scenery as one undoable change, like
+    /// the obstacle editor does.
+    pub fn edit_scenery(&mut self, obstacles: Vec<Obstacle>, attractors: Vec<Attractor>) {
+        self.record(Input::EditScenery {
+            obstacles: obstacles.clone(),
+            attractors: attractors.clone(),
+        });
+        self.history.record_scenery(&self.obstacles, &self.attractors);
+        self.obstacles = obstacles;
+        self.attractors = attractors;
+    }
+
+    /// Remove the boids, predators, obstacles and attractors within `radius` of (x, y).
+    pub fn erase_at(&mut self, x: f32, y: f32, radius: f32) {
+        let outside = |center_x: f32, center_y: f32| {
+            let dx = center_x - x;
+            let dy = center_y - y;
+            (dx * dx + dy * dy).sqrt() > radius
+        };
+        let scenery_hit = self.obstacles.iter().any(|obstacle| !outside(obstacle.x, obstacle.y))
+            || self.attractors.iter().any(|attractor| !outside(attractor.x, attractor.y));
+        if scenery_hit {
+            self.history.record_scenery(&self.obstacles, &self.attractors);
+            self.obstacles.retain(|obstacle| outside(obstacle.x, obstacle.y));
+            self.attractors.retain(|attractor| outside(attractor.x, attractor.y));
+        }
+        let mut removed: Vec<Boid> = vec![];
+        self.quad_tree.remove_within(&mut removed, x, y, radius);
+        if scenery_hit || !removed.is_empty() {
+            // Erasing follows the cursor, only the strokes that hit something are worth replaying
+            self.record(Input::Erase { x, y, radius });
+        }
+        self.touch(removed.iter().map(Boid::id), true);
+        for boid in &removed {
+            self.history.record_removed(boid);
+            self.hooks.despawned(boid, boid.species());
+        }
+    }
+
+    fn new_boid(&mut self, x: i16, y: i16) -> Boid {
+        let velocity_x = self.rng.gen_range(-self.option.boid_min_speed..=self.option.boid_min_speed);
+        let velocity_y = self.rng.gen_range(-self.option.boid_min_speed..=self.option.boid_min_speed);
+        let mut vertice = Vertice::new();
+        vertice.x = x;
+        vertice.y = y;
+        let mut boid = Boid::new(
+            vertice,
+            self.option.boid_size,
+            velocity_x,
+            velocity_y,
+            self.option.palette.theme().boid,
+        );
+        boid.vary_size(self.option.size_variation, &mut self.rng);
+        boid
+    }
+
+    fn new_predator(&mut self, x: i16, y: i16) -> Boid {
+        let velocity_x = self.rng.gen_range(-self.option.predator_min_speed..=self.option.predator_min_speed);
+        let velocity_y = self.rng.gen_range(-self.option.predator_min_speed..=self.option.predator_min_speed);
+        let mut vertice = Vertice::new();
+        vertice.x = x;
+        vertice.y = y;
+        let mut predator = Boid::new(
+            vertice,
+            self.option.predator_size,
+            velocity_x,
+            velocity_y,
+            self.option.palette.theme().predator,
+        );
+        predator.set_species(Species::Predator);
+        predator.vary_size(self.option.size_variation, &mut self.rng);
+        predator
+    }
+
+    /// Node capacity used for the quadtree built by `update`.
+    pub fn quad_tree_capacity(&self) -> usize {
+        if self.option.auto_quad_tree_capacity {
+            self.capacity_tuner.capacity()
+        } else {
+            self.option.quad_tree_capacity
+        }
+    }
+
+    /// Spatial index layout used by `update`.
+    pub fn index_config(&self) -> IndexConfig {
+        IndexConfig {
+            backend: self.option.index_backend,
+            capacity: self.quad_tree_capacity(),
+            max_depth: self.option.quad_tree_max_depth,
+            cell_size: self.option.grid_cell_size,
+        }
+    }
+
+    /// Every boid, in no particular order.
+    pub fn boids(&self) -> impl Iterator<Item = BoidView<'_>> {
+        self.of_species(Species::Boid)
+    }
+
+    /// Every predator, in no particular order.
+    pub fn predators(&self) -> impl Iterator<Item = BoidView<'_>> {
+        self.of_species(Species::Predator)
+    }
+
+    // Copies of the indexed bodies of `species`
+    fn indexed(&self, species: Species) -> Vec<Boid> {
+        self.quad_tree.to_vec_of(species)
+    }
+
+    fn of_species(&self, species: Species) -> impl Iterator<Item = BoidView<'_>> {
+        self.quad_tree.iter_of(species).map(BoidView::new)
+    }
+
+    /// Index holding every boid and predator, for neighbor and area queries. Query it with
+    /// `QuadTree::query_species` to find one species alone.
+    pub fn index(&self) -> &QuadTree {
+        &self.quad_tree
+    }
+
+    /// Shape of the index.
+    pub fn index_stats(&self) -> IndexStats {
+        self.quad_tree.stats()
+    }
+
+    /// Average number of boids returned by each neighbor query during the last update.
+    pub fn query_candidates(&self) -> f32 {
+        self.query_candidates
+    }
+
+    /// Rules time of the last update divided by its neighbor queries, in microseconds.
+    pub fn query_cost(&self) -> f32 {
+        self.query_cost
+    }
+
+    pub fn predators_count(&self) -> usize {
+        self.quad_tree.len_of(Species::Predator)
+    }
+
+    /// Why a boid went missing since the last call: one was outside the spatial index and
+    /// left out of it.
+    pub fn take_error(&mut self) -> Option<Error> {
+        self.lost_boid.take().map(|(x, y)| Error::OutOfBounds { x, y })
+    }
+
+    // Remember a boid the spatial index could not take, the simulation goes on without it
+    fn report(&mut self, error: Option<Error>) {
+        if let Some(Error::OutOfBounds { x, y }) = error {
+            self.lost_boid = Some((x, y));
+        }
+    }
+
+    /// Boids caught by predators since the world was created.
+    pub fn catches(&self) -> u64 {
+        self.catches
+    }
+
+    /// How close boids flew to their nearest neighbor last update, `None` before the first update
+    /// or when no boid saw another.
+    pub fn neighbor_distances(&self) -> Option<NeighborDistances> {
+        self.neighbor_distances
+    }
+
+    /// Pairs of boids whose bodies came closer than the safe radius last update, a sign the
+    /// avoid factor is too weak when it stays high.
+    pub fn overlaps(&self) -> usize {
+        self.overlaps
+    }
+
+    /// How the predator `id` hunted since it spawned or the world was loaded, `None` until
+    /// its first update.
+    pub fn hunt(&self, id: Uuid) -> Option<&HuntStats> {
+        self.hunts.get(&id)
+    }
+
+    pub fn boids_count(&self) -> usize {
+        self.quad_tree.len_of(Species::Boid)
+    }
+
+    // Number of boids to add (positive) or remove (negative) this tick to
+    // bring the update rate back towards the target, or the population towards `boid_count`
+    fn population_change(&self) -> i32 {
+        if !self.option.auto_population {
+            if self.option.live_population {
+                return Self::step_towards(self.boids_count(), self.option.boid_count);
+            }
+            return 0;
+        }
+        if self.update_fps == 0.0 {
+            return 0;
+        }
+        let population = self.boids_count() as i32;
+        let step = (population / 100).max(1);
+        let target_fps = self.option.target_fps as f32;
+        if self.update_fps < target_fps * 0.9 {
+            return -step.min(population);
+        }
+        if self.update_fps > target_fps * 1.1 {
+            return step;
+        }
+        0
+    }
+
+    fn predator_change(&self) -> i32 {
+        if self.option.live_population {
+            return Self::step_towards(self.predators_count(), self.option.predator_count);
+        }
+        0
+    }
+
+    // Close 5% of the gap per tick so large changes spread over a few frames
+    fn step_towards(count: usize, target: u16) -> i32 {
+        let difference = target as i32 - count as i32;
+        let step = (difference.abs() / 20).max(1);
+        difference.clamp(-step, step)
+    }
+
+    /// Replace every boid and predator with `boid_count` and `predator_count` new ones.
+    pub fn restart(&mut self) {
+        self.record_now(Input::Restart);
+        self.quietly(|world| {
+            world.clear_all();
+            world.tick = 0;
+            world.spawn_random_boids(world.option.boid_count, world.option.predator_count);
+        });
+        self.rebase_recording();
+    }
+
+    /// Put `boids` and `predators` in place of the flock without undo, like a flock simulated
+    /// elsewhere and only shown here.
+    pub fn replace_flock(&mut self, boids: Vec<Boid>, mut predators: Vec<Boid>) {
+        mark_predators(&mut predators);
+        self.generation = self.generation.wrapping_add(1);
+        self.quad_tree = QuadTree::with_config(self.index_config(), self.boundary.clone());
+        let lost = insert_all(&mut self.quad_tree, &[boids, predators].concat(), None);
+        self.report(lost);
+        self.chunks = Chunks::default();
+    }
+
+    /// Put `obstacles` and `attractors` in place of the scenery without undo.
+    pub fn replace_scenery(&mut self, obstacles: Vec<Obstacle>, attractors: Vec<Attractor>) {
+        self.obstacles = obstacles;
+        self.attractors = attractors;
+    }
+
+    /// Remove every boid and predator.
+    pub fn clear_all(&mut self) {
+        self.record(Input::Clear);
+        self.generation = self.generation.wrapping_add(1);
+        let (history, hooks) = (&mut self.history, &mut self.hooks);
+        self.quad_tree.for_each(|boid| {
+            history.record_removed(boid);
+            hooks.despawned(boid, boid.species());
+        });
+        let (parked_boids, parked_predators) = self.chunks.unpark_all();
+        for boid in parked_boids.iter().chain(&parked_predators) {
+            self.history.record_removed(boid);
+            self.hooks.despawned(boid, boid.species());
+        }
+        self.quad_tree.clear();
+        self.particles.clear();
+        self.selected = None;
+        self.box_selection.clear();
+        self.trajectory.clear();
+        self.neighbors.clear();
+        self.forces.clear();
+    }
+
+    /// Move every parameter driven by an oscillator to its value at `time` seconds.
+    pub fn animate(&mut self, time: f64) {
+        // A replay moves them as they moved while recording
+        if self.option.lfos.is_empty() || self.tape.is_replaying() {
+            return;
+        }
+        self.record(Input::Animate(time));
+        let lfos = self.option.lfos.clone();
+        let apply = |option: &mut WorldOption| {
+            for lfo in &lfos {
+                lfo.apply(option, time);
+            }
+        };
+        apply(&mut self.option);
+        // Oscillating is not an edit to undo
+        self.history.follow(apply);
+        if let Tape::Recording(recorder) = &mut self.tape {
+            recorder.follow(apply);
+        }
+    }
+
+    /// Close the changes made since the last call into one undoable edit.
+    pub fn commit_edit(&mut self) {
+        // Edits are closed where they were while recording
+        if self.tape.is_replaying() {
+            return;
+        }
+        if self.history.commit(&self.option) {
+            self.record(Input::Commit);
+        }
+    }
+
+    /// Updates run since the last restart.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Everything needed to resume the world later, see `restore`.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            option: self.option.clone(),
+            width: self.width,
+            height: self.height,
+            boids: [self.indexed(Species::Boid), self.chunks.parked(Species::Boid)].concat(),
+            predators: [self.indexed(Species::Predator), self.chunks.parked(Species::Predator)].concat(),
+            obstacles: self.obstacles.clone(),
+            attractors: self.attractors.clone(),
+            catches: self.catches,
+            tick: self.tick,
+            seed: self.seed,
+            rng: self.saved_rng(),
+        }
+    }
+
+    // Where the default generator is, or a fresh one from the seed for others
+    fn saved_rng(&self) -> ChaCha12Rng {
+        match self.rng.as_ref().as_any().downcast_ref::<ChaCha12Rng>() {
+            Some(rng) => rng.clone(),
+            None => ChaCha12Rng::seed_from_u64(self.seed),
+        }
+    }
+
+    /// Resume a saved world, stretched to the current size. Box selection and undo start over.
+    pub fn restore(&mut self, snapshot: Snapshot) {
+        let (width, height) = (self.width, self.height);
+        self.record_now(Input::Restore(Box::new(snapshot.clone())));
+        self.quietly(|world| {
+            world.load_snapshot(snapshot);
+            world.resize(width, height);
+        });
+        self.rebase_recording();
+    }
+
+    /// Start over from `scenario`: its settings and scenery, the `boid_count` and
+    /// `predator_count` of its settings at random and its spawn groups. Undo starts over.
+    pub fn load_scenario(&mut self, scenario: &ScenarioFile) {
+        let (width, height) = (self.width, self.height);
+        self.record_now(Input::LoadScenario(Box::new(scenario.clone())));
+        self.quietly(|world| {
+            // Laid out at its own size, then stretched like a resized world
+            if let Some((width, height)) = scenario.size {
+                world.resize(width, height);
+            }
+            world.option = scenario.option();
+            world.obstacles = scenario.obstacles.clone();
+            world.attractors = scenario.attractors.clone();
+            world.restart();
+            for spawn in &scenario.spawns {
+                for _ in 0..spawn.count {
+                    let (x, y) = world.spawn_position(spawn);
+                    match spawn.species {
+                        Species::Boid => world.spawn_boids(x, y),
+                        Species::Predator => world.spawn_predators(x, y),
+                    }
+                }
+            }
+            world.resize(width, height);
+            world.forget_history();
+        });
+        self.rebase_recording();
+    }
+
+    // Somewhere within the spread of `spawn`, kept in the world unless it is unbounded
+    fn spawn_position(&mut self, spawn: &Spawn) -> (i16, i16) {
+        let angle = self.rng.gen_range(0.0..std::f32::consts::TAU);
+        // Square root so the group is as dense at its edge as in its middle
+        let distance = spawn.spread.max(0.0) * self.rng.gen::<f32>().sqrt();
+        let mut x = spawn.x + distance * angle.cos();
+        let mut y = spawn.y + distance * angle.sin();
+        if !self.option.unbounded {
+            x = x.clamp(0.0, self.width.saturating_sub(1) as f32);
+            y = y.clamp(0.0, self.height.saturating_sub(1) as f32);
+        }
+        (x.round() as i16, y.round() as i16)
+    }
+
+    // Restore at the size of the snapshot
+    fn load_snapshot(&mut self, mut snapshot: Snapshot) {
+        // Snapshots saved before boids knew their species have predators as boids
+        mark_predators(&mut snapshot.predators);
+        // Followed on if it is in the snapshot, like when rewinding
+        let selected = self
+            .selected
+            .filter(|id| snapshot.boids.iter().chain(&snapshot.predators).any(|boid| boid.id() == *id));
+        self.generation = self.generation.wrapping_add(1);
+        self.option = snapshot.option;
+        self.width = snapshot.width;
+        self.height = snapshot.height;
+        self.boundary = Self::boundary_for(self.width, self.height);
+        self.quad_tree = QuadTree::with_config(self.index_config(), self.boundary.clone());
+        self.chunks = Chunks::default();
+        let parking = self.option.unbounded.then_some(&mut self.chunks);
+        let lost = insert_all(&mut self.quad_tree, &[snapshot.boids, snapshot.predators].concat(), parking);
+        self.report(lost);
+        self.obstacles = snapshot.obstacles;
+        self.attractors = snapshot.attractors;
+        self.catches = snapshot.catches;
+        self.tick = snapshot.tick;
+        self.seed = snapshot.seed;
+        self.rng = Box::new(snapshot.rng);
+        self.selected = selected;
+        self.box_selection.clear();
+        self.selection_box = None;
+        self.trajectory.clear();
+        self.neighbors.clear();
+        self.forces.clear();
+        self.particles.clear();
+        self.forget_history();
+    }
+
+    /// Start recording every input from now on, see `stop_recording`.
+    pub fn start_recording(&mut self) {
+        // An update in flight may have used options changed since, it is dropped so the
+        // recording starts from the world as it is now
+        self.generation = self.generation.wrapping_add(1);
+        self.tape = Tape::Recording(Box::new(Recorder::new(self.snapshot())));
+    }
+
+    /// Stop recording, giving the replay of everything since `start_recording`.
+    pub fn stop_recording(&mut self) -> Option<Replay> {
+        match std::mem::take(&mut self.tape) {
+            Tape::Recording(recorder) => Some(recorder.finish(self.tick, &self.option)),
+            tape => {
+                self.tape = tape;
+                None
+            }
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        matches!(self.tape, Tape::Recording(_))
+    }
+
+    /// Inputs recorded so far, None when not recording.
+    pub fn recorded_inputs(&self) -> Option<usize> {
+        match &self.tape {
+            Tape::Recording(recorder) => Some(recorder.inputs_count()),
+            _ => None,
+        }
+    }
+
+    /// Go back to where `replay` started and play its inputs again as the world updates.
+    /// The world takes the size it was recorded at and pauses once the replay is over.
+    pub fn start_replay(&mut self, replay: Replay) {
+        self.load_snapshot(replay.start);
+        self.camera = Camera::new(self.width, self.height);
+        self.run_state = RunState::Running;
+        self.tape = Tape::Replaying(Player::new(replay.inputs, replay.ticks));
+    }
+
+    pub fn stop_replay(&mut self) {
+        if self.tape.is_replaying() {
+            self.tape = Tape::Idle;
+        }
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.tape.is_replaying()
+    }
+
+    /// Updates played and in the whole replay, None when not replaying.
+    pub fn replay_progress(&self) -> Option<(u64, u64)> {
+        match &self.tape {
+            Tape::Replaying(player) => Some(player.progress()),
+            _ => None,
+        }
+    }
+
+    fn record(&mut self, input: Input) {
+        if let Tape::Recording(recorder) = &mut self.tape {
+            recorder.record(&self.option, input);
+        }
+    }
+
+    // For inputs dropping any update in flight, they count from the current tick
+    fn record_now(&mut self, input: Input) {
+        self.record(input);
+        self.flush_inputs();
+    }
+
+    // After the tick started over
+    fn rebase_recording(&mut self) {
+        if let Tape::Recording(recorder) = &mut self.tape {
+            recorder.rebase(self.tick);
+        }
+    }
+
+    /// Recorded inputs so far count from the tick the world is at, called before it is updated.
+    pub(crate) fn flush_inputs(&mut self) {
+        if let Tape::Recording(recorder) = &mut self.tape {
+            recorder.stamp(self.tick, &self.option);
+        }
+    }
+
+    // Run `f` without recording, for inputs made of other inputs
+    fn quietly(&mut self, f: impl FnOnce(&mut Self)) {
+        let tape = std::mem::take(&mut self.tape);
+        f(self);
+        self.tape = tape;
+    }
+
+    // Apply the replay inputs due before this update, returns true once the replay is over
+    fn play_inputs(&mut self) -> bool {
+        let Tape::Replaying(mut player) = std::mem::take(&mut self.tape) else {
+            return false;
+        };
+        while let Some(input) = player.next_input() {
+            self.apply_input(input);
+        }
+        if player.is_finished() {
+            self.run_state = RunState::Paused;
+            return true;
+        }
+        self.tape = Tape::Replaying(player);
+        false
+    }
+
+    fn apply_input(&mut self, input: Input) {
+        match input {
+            Input::Options(option) => self.option = *option,
+            Input::Animate(time) => self.animate(time),
+            Input::SpawnBoid { x, y } => self.spawn_boids(x, y),
+            Input::SpawnPredator { x, y } => self.spawn_predators(x, y),
+            Input::SpawnRandom { boids, predators } => self.spawn_random_boids(boids, predators),
+            Input::AddObstacle { x, y } => self.add_obstacle(x, y),
+            Input::AddAttractor { x, y } => self.add_attractor(x, y),
+            Input::EditScenery { obstacles, attractors } => self.edit_scenery(obstacles, attractors),
+            Input::Erase { x, y, radius } => self.erase_at(x, y, radius),
+            Input::SelectIn(area) => self.select_in(&area),
+            Input::ClearSelection => self.clear_box_selection(),
+            Input::DeleteSelection => self.delete_box_selection(),
+            Input::RecolorSelection(color) => self.recolor_box_selection(color),
+            Input::FreezeSelection(frozen) => self.freeze_box_selection(frozen),
+            Input::ConvertSelection => self.convert_box_selection_to_predators(),
+            Input::SetProfile { id, profile } => self.set_profile(id, profile),
+            Input::Restart => self.restart(),
+            Input::Clear => self.clear_all(),
+            Input::Resize { width, height } => self.resize(width, height),
+            Input::SetSeed(seed) => self.set_seed(seed),
+            Input::Restore(snapshot) => self.restore(*snapshot),
+            Input::LoadScenario(scenario) => self.load_scenario(&scenario),
+            Input::Commit => self.commit_edit(),
+            Input::Undo => self.undo(),
+            Input::Redo => self.redo(),
+        }
+    }
+
+    /// Drop every edit, the current state can no longer be undone.
+    pub fn forget_history(&mut self) {
+        self.history.clear(self.option.clone());
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+
+    pub fn undo(&mut self) {
+        self.commit_edit();
+        self.record(Input::Undo);
+        if let Some(edit) = self.history.pop_undo() {
+            let redo = self.apply_edit(edit);
+            self.history.push_redo(redo);
+        }
+    }
+
+    pub fn redo(&mut self) {
+        self.commit_edit();
+        self.record(Input::Redo);
+        if let Some(edit) = self.history.pop_redo() {
+            let undo = self.apply_edit(edit);
+            self.history.push_undo(undo);
+        }
+    }
+
+    // Returns the edit reverting this one
+    fn apply_edit(&mut self, edit: Edit) -> Edit {
+        let mut reverse = Edit::default();
+        if let Some(option) = edit.option {
+            reverse.option = Some(Box::new(std::mem::replace(&mut self.option, *option)));
+            self.history.set_committed_option(&self.option);
+        }
+        let ids: HashSet<Uuid> = edit.take.iter().map(Boid::id).collect();
+        let taken = &mut reverse.put;
+        self.quad_tree.retain(|boid| {
+            if ids.contains(&boid.id()) {
+                taken.push(boid.clone());
+                return false;
+            }
+            true
+        });
+        self.touch(reverse.put.iter().map(Boid::id), true);
+        self.touch(edit.put.iter().map(Boid::id), false);
+        let parking = self.option.unbounded.then_some(&mut self.chunks);
+        let lost = insert_all(&mut self.quad_tree, &edit.put, parking);
+        self.report(lost);
+        reverse.take = edit.put;
+        if let Some((obstacles, attractors)) = edit.scenery {
+            let obstacles = std::mem::replace(&mut self.obstacles, obstacles);
+            let attractors = std::mem::replace(&mut self.attractors, attractors);
+            reverse.scenery = Some((obstacles, attractors));
+        }
+        reverse
+    }
+
+    /// The whole world as an SVG document, for figures that stay sharp at any size. Boids and
+    /// predators are triangles pointing where they fly, over the obstacles and attractors, and
+    /// the quad tree and radii turned on in the debug overlays come along as rectangles and
+    /// circles.
+    pub fn to_svg(&self) -> String {
+        svg::document(self, &self.quad_tree)
+    }
+
+    /// Render the world into `frame`, RGBA pixels row by row.
+    pub fn draw(&mut self, frame: &mut [u8]) {
+        self.draw_frame(frame, true);
+    }
+
+    /// Render everything but the boid and predator bodies, for when they are drawn on the GPU.
+    /// Their debug overlays are still drawn.
+    pub fn draw_scenery(&mut self, frame: &mut [u8]) {
+        self.draw_frame(frame, false);
+    }
+
+    fn draw_frame(&mut self, frame: &mut [u8], bodies: bool) {
+        let start_time = Instant::now();
+        let hidden_overlays = if self.option.show_debug_overlays {
+            None
+        } else {
+            let option = self.option.without_debug_overlays();
+            Some(std::mem::replace(&mut self.option, option))
+        };
+        if self.option.show_flock_hulls {
+            self.find_flock_hulls();
+        } else {
+            // Found again right away once turned back on
+            self.flock_hulls_age = 0;
+        }
+        let viewport = self.viewport();
+        if self.camera.is_zoomed() {
+            let mut scene = std::mem::take(&mut self.scene.0);
+            scene.resize(frame.len(), 0);
+            self.draw_scene(&mut scene, &viewport, bodies);
+            self.camera.blit(&scene, frame, self.width, self.height);
+            self.scene.0 = scene;
+            if self.option.show_minimap {
+                let minimap = Minimap::new(self.width, self.height);
+                minimap.draw(frame, &self.quad_tree, &viewport, &self.option.palette.theme());
+            }
+        } else {
+            self.draw_scene(frame, &viewport, bodies);
+        }
+        if self.option.show_timing_overlay {
+            self.draw_timing_overlay(frame);
+        }
+        if let Some(option) = hidden_overlays {
+            self.option = option;
+        }
+        let end_time = Instant::now();
+        self.profiler.record(Stage::Rasterize, start_time);
+        Self::update_fps_count(&mut self.draw_fps, start_time, end_time);
+    }
+
+    // Everything in world coordinates, only the nodes inside `viewport` are rasterized
+    fn draw_scene(&self, frame: &mut [u8], viewport: &Rectangle, bodies: bool) {
+        self.background.draw_with_option(frame, self.width, self.height, &self.option);
+        for obstacle in &self.obstacles {
+            obstacle.draw_with_option(frame, self.width, self.height, &self.option);
+        }
+        for attractor in &self.attractors {
+            attractor.draw_with_option(frame, self.width, self.height, &self.option);
+        }
+        self.quad_tree
+            .draw_visible(frame, self.width, self.height, viewport, &self.option, bodies);
+        self.particles.draw(frame, self.width, self.height);
+        if self.option.show_flock_hulls {
+            for (hull, color) in &self.flock_hulls {
+                let outline = Polygon::new(hull.clone(), color.clone(), 0.15, self.option.debug_line_width);
+                outline.draw_with_option(frame, self.width, self.height, &self.option);
+            }
+        }
+        let highlight = self.option.palette.theme().highlight;
+        if !self.box_selection.is_empty() {
+            self.quad_tree.for_each(|boid| {
+                if self.box_selection.contains(&boid.id()) {
+                    let half_size = boid.size() as f32 / 2.0;
+                    let (x, y) = boid.position();
+                    Rectangle::new(x + half_size, y + half_size, half_size + 2.0, half_size + 2.0)
+                        .draw_stroke(frame, self.width, self.height, 1.0, &highlight, &self.option);
+                }
+            });
+        }
+        if let Some(area) = &self.selection_box {
+            area.draw_stroke(frame, self.width, self.height, 1.0, &highlight, &self.option);
+        }
+        for (start, end, color) in &self.forces {
+            self.draw_arrow(frame, start, end, color);
+        }
+        if let Some(position) = self.trajectory.back() {
+            if self.option.show_selected_neighbors {
+                for (neighbor, color) in &self.neighbors {
+                    let color = color.to_color_array();
+                    if self.option.high_quality_rendering {
+                        draw_colored_line_anti_aliased(position, neighbor, frame, self.width, self.height, color, self.option.debug_line_width);
+                    } else {
+                        draw_colored_line(position, neighbor, frame, self.width, self.height, color, self.option.debug_line_width);
+                    }
+                }
+            }
+            let ribbon = Polyline::new(self.trajectory.iter().cloned().collect(), 1.0, highlight.clone());
+            ribbon.draw_with_option(frame, self.width, self.height, &self.option);
+            let marker = Circle::new(position.x as f32, position.y as f32, 8.0, 2.0, highlight);
+            marker.draw_with_option(frame, self.width, self.height, &self.option);
+        }
+    }
+
+    // Flocks split and merge slower than they move, so they are found again every few frames
+    // while their outlines follow the boids every frame
+    fn find_flock_hulls(&mut self) {
+        const INTERVAL: u32 = 10;
+        // Pushed out from the middle so the outline wraps around the bodies
+        const PADDING: f32 = 8.0;
+        if self.flock_hulls_age == 0 || self.flock_hulls_age >= INTERVAL {
+            self.flock_hulls_age = 0;
+            self.flocks = statistics::flocks(self)
+                .into_iter()
+                .filter(|flock| flock.len() >= 3)
+                .map(|flock| {
+                    let ids: Vec<Uuid> = flock.iter().map(|boid| boid.id()).collect();
+                    // Colored after the smallest id among its boids, so it keeps its color as it moves
+                    let hue = (ids.iter().min().copied().unwrap_or_default().as_u128() % 360) as f32;
+                    (ids, Color::from_hsv(hue, 0.8, 1.0))
+                })
+                .collect();
+        }
+        self.flock_hulls_age += 1;
+        let positions: HashMap<Uuid, (f32, f32)> =
+            self.boids().map(|boid| (boid.id(), boid.position())).collect();
+        self.flock_hulls = self
+            .flocks
+            .iter()
+            .filter_map(|(ids, color)| {
+                let points: Vec<(f32, f32)> = ids.iter().filter_map(|id| positions.get(id).copied()).collect();
+                if points.len() < 3 {
+                    return None;
+                }
+                let count = points.len() as f32;
+                let (center_x, center_y) =
+                    points.iter().fold((0.0, 0.0), |(sx, sy), (x, y)| (sx + x / count, sy + y / count));
+                let hull = convex_hull(&points)
+                    .into_iter()
+                    .map(|(x, y)| {
+                        let distance = (x - center_x).hypot(y - center_y).max(f32::EPSILON);
+                        let scale = (distance + PADDING) / distance;
+                        (center_x + (x - center_x) * scale, center_y + (y - center_y) * scale)
+                    })
+                    .collect();
+                Some((hull, color.clone()))
+            })
+            .collect();
+    }
+
+    // A line from `start` to `end` with a head at `end`
+    fn draw_arrow(&self, frame: &mut [u8], start: &Vertice, end: &Vertice, color: &Color) {
+        let (dx, dy) = ((end.x - start.x) as f32, (end.y - start.y) as f32);
+        let length = dx.hypot(dy);
+        if length < 1.0 {
+            return;
+        }
+        let head = (length / 3.0).min(6.0);
+        let (back_x, back_y) = (-dx / length * head, -dy / length * head);
+        let mut lines = vec![(start.clone(), end.clone())];
+        // Both sides of the head, 45 degrees off pointing back
+        for side in [-1.0, 1.0] {
+            let mut tip = end.clone();
+            tip.x += (back_x * 0.7 - side * back_y * 0.7).round() as i16;
+            tip.y += (back_y * 0.7 + side * back_x * 0.7).round() as i16;
+            lines.push((end.clone(), tip));
+        }
+        let color = color.to_color_array();
+        let line_width = self.option.debug_line_width;
+        for (from, to) in &lines {
+            if self.option.high_quality_rendering {
+                draw_colored_line_anti_aliased(from, to, frame, self.width, self.height, color, line_width);
+            } else {
+                draw_colored_line(from, to, frame, self.width, self.height, color, line_width);
+            }
+        }
+    }
+
+    /// Remove the boids touching `predator`, leaving a particle burst behind.
+    fn catch_boids(&mut self, predator: &Boid, caught: &mut HashSet<Uuid>) {
+        let mut found_boids: Vec<Boid> = vec![];
+        self.quad_tree
+            .query_species(&mut found_boids, predator, self.option.catch_radius, Species::Boid.into());
+        for boid in found_boids {
+            let dx = (boid.vertice.x - predator.vertice.x) as f32;
+            let dy = (boid.vertice.y - predator.vertice.y) as f32;
+            if (dx * dx + dy * dy).sqrt() > self.option.catch_radius || !caught.insert(boid.id()) {
+                continue;
+            }
+            self.catches += 1;
+            self.hunts.entry(predator.id()).or_default().catches += 1;
+            self.hooks.caught(predator, &boid);
+            if self.option.show_catch_particles {
+                self.particles.burst(boid.vertice.x as f32, boid.vertice.y as f32, boid.color());
+            }
+        }
+    }
+
+    // Drawn into the frame itself so it also shows up in screenshots and recordings
+    fn draw_timing_overlay(&self, frame: &mut [u8]) {
+        const SCALE: i32 = 2;
+        const PADDING: i32 = 6;
+        let update_milliseconds = self.profiler.milliseconds(Stage::TreeBuild) + self.profiler.milliseconds(Stage::Rules);
+        let lines = [
+            format!("UPDATE {:.2} MS", update_milliseconds),
+            format!("DRAW {:.2} MS", self.profiler.milliseconds(Stage::Rasterize)),
+            format!("BOIDS {}", self.boids_count()),
+        ];
+        let theme = self.option.palette.theme();
+        let (_, line_height) = Text::size("", SCALE);
+        let line_step = line_height + SCALE * 2;
+        let text_width = lines
+            .iter()
+            .map(|line| Text::size(line, SCALE).0)
+            .max()
+            .unwrap_or_default();
+        let box_width = text_width + PADDING * 2;
+        let box_height = line_step * lines.len() as i32 - SCALE * 2 + PADDING * 2;
+        // Bottom left keeps it clear of the menu bar
+        let top = self.height as i32 - box_height - PADDING;
+        Rectangle::new(
+            (PADDING + box_width / 2) as f32,
+            (top + box_height / 2) as f32,
+            box_width as f32 / 2.0,
+            box_height as f32 / 2.0,
+        )
+        .fill(frame, self.width, self.height, theme.background.to_color_array(), 0.6);
+        for (index, line) in lines.into_iter().enumerate() {
+            let y = top + PADDING + index as i32 * line_step;
+            Text::new(PADDING * 2, y, SCALE, line, theme.text.clone()).draw(frame, self.width, self.height);
+        }
+    }
+
+    /// Run `simulation_speed` updates, a single step still runs only once.
+    pub fn advance(&mut self) {
+        for _ in 0..self.option.simulation_speed.max(1) {
+            self.update();
+        }
+    }
+
+    /// Run one more update per frame.
+    pub fn speed_up(&mut self) {
+        self.option.simulation_speed = (self.option.simulation_speed + 1).min(WorldOption::MAX_SIMULATION_SPEED);
+    }
+
+    /// Run one update less per frame.
+    pub fn slow_down(&mut self) {
+        self.option.simulation_speed = self.option.simulation_speed.saturating_sub(1).max(1);
+    }
+
+    /// Move the simulation one tick forward, unless paused.
+    pub fn update(&mut self) {
+        if !self.begin_tick() {
+            return;
+        }
+        // Tick hooks held back for the update before see the world as that one left it
+        if self.hooks.needs_world() {
+            let world = self.clone();
+            self.hooks.keep_world(world);
+        }
+        self.flush_inputs();
+        if self.play_inputs() {
+            return;
+        }
+        self.tick += 1;
+        if let Tape::Replaying(player) = &mut self.tape {
+            player.count_update();
+        }
+        let start_time = Instant::now();
+        let population_change = self.population_change();
+        let predator_change = self.predator_change();
+
+        let rules_start_time = Instant::now();
+        self.particles.update();
+        let theme = self.option.palette.theme();
+        let mut caught: HashSet<Uuid> = HashSet::new();
+        // Separation, alignment and cohesion neighbors of the selected boid
+        let mut accepted: [Vec<Uuid>; 3] = Default::default();
+        // Rules drawn as force arrows, by name so a replaced built-in rule still shows
+        let force_colors = [
+            ("Separation", &theme.separation),
+            ("Alignment", &theme.alignment),
+            ("Cohesion", &theme.cohesion),
+            ("Fear", &theme.predator),
+        ];
+        let mut forces = vec![];
+        let mut new_predators: Vec<Boid> = vec![];
+        let mut queries = 0;
+        let mut candidates = 0;
+        let mut predators = self.indexed(Species::Predator);
+        let culled_predators: Vec<Boid> = predators.drain(..((-predator_change).max(0) as usize).min(predators.len())).collect();
+        for predator in &culled_predators {
+            self.hooks.despawned(predator, Species::Predator);
+        }
+        for predator in predators {
+            let mut new_predator = predator.clone();
+            new_predator.set_base_size(self.option.predator_size);
+            if !new_predator.has_fixed_color() {
+                new_predator.set_color(theme.predator.clone());
+            }
+            if new_predator.is_frozen() {
+                new_predators.push(new_predator);
+                continue;
+            }
+            let is_selected = self.selected == Some(predator.id());
+            let mut found_boids: Vec<Boid> = vec![];
+            self.quad_tree
+                .query_species(&mut found_boids, &predator, self.option.fear_radius, Species::Boid.into());
+            queries += 1;
+            candidates += found_boids.len();
+            let mut seen = vec![];
+            new_predator.cohesion(
+                &found_boids,
+                1.0,
+                self.option.predator_vision_radius,
+                self.option.predator_view_angle,
+                Some(&mut seen),
+            );
+            if is_selected {
+                accepted[2].extend_from_slice(&seen);
+            }
+            new_predator.avoid_obstacles(&self.obstacles, self.option.turn_factor, self.option.margin);
+            if self.option.avoid_gui {
+                new_predator.avoid_areas(&self.avoided_areas, self.option.turn_factor, self.option.margin);
+            }
+            new_predator.speed_limit(self.option.predator_max_speed, self.option.predator_min_speed, &mut self.rng);
+            let (velocity_x, velocity_y) = new_predator.velocity();
+            let hunt = self.hunts.entry(predator.id()).or_default();
+            hunt.record(self.tick, !seen.is_empty(), velocity_x.hypot(velocity_y));
+            if self.option.unbounded {
+                new_predator.advance();
+            } else {
+                new_predator.update(self.width, self.height);
+            }
+            new_predator.smooth_heading(self.option.heading_smoothing);
+            if self.option.predators_catch {
+                self.catch_boids(&new_predator, &mut caught);
+            }
+            new_predators.push(new_predator);
+        }
+        let mut boids = self.indexed(Species::Boid);
+        let culled: Vec<Boid> = boids.drain(..((-population_change).max(0) as usize).min(boids.len())).collect();
+        for boid in &culled {
+            self.hooks.despawned(boid, Species::Boid);
+        }
+        let mut new_boids: Vec<Boid> = vec![];
+        let mut nearest = vec![];
+        let mut overlaps = 0;
+        for boid in boids {
+            if caught.contains(&boid.id()) {
+                self.hooks.despawned(&boid, Species::Boid);
+                continue;
+            }
+            let mut new_boid = boid.clone();
+            new_boid.set_base_size(self.option.boid_size);
+            if new_boid.is_frozen() {
+                new_boids.push(new_boid);
+                continue;
+            }
+            let is_selected = self.selected == Some(boid.id());
+            let profiled = boid.profile().map(|profile| profile.apply(&self.option));
+            let option = profiled.as_ref().unwrap_or(&self.option);
+            let mut found_boids: Vec<Boid> = vec![];
+            let mut found_predators: Vec<Boid> = vec![];
+            self.quad_tree
+                .query_species(&mut found_boids, &boid, option.boid_vision_radius, Species::Boid.into());
+            self.quad_tree
+                .query_species(&mut found_predators, &boid, option.fear_radius, Species::Predator.into());
+            queries += 2;
+            candidates += found_boids.len() + found_predators.len();
+            nearest.extend(statistics::nearest_distance(&boid, &found_boids, self.option.boid_vision_radius));
+            overlaps += statistics::overlaps(&boid, &found_boids, self.option.safe_radius);
+            if is_selected {
+                Self::accepted_neighbors(&boid, &found_boids, option, &mut accepted);
+            }
+            let mut surroundings = Surroundings {
+                boids: &found_boids,
+                predators: &found_predators,
+                obstacles: &self.obstacles,
+                avoided_areas: if self.option.avoid_gui { &self.avoided_areas } else { &[] },
+                attractors: &self.attractors,
+                width: self.width,
+                height: self.height,
+                rng: &mut self.rng,
+            };
+            let traced = match self.option.force_arrows {
+                ForceArrows::Off => false,
+                ForceArrows::Selected => is_selected,
+                ForceArrows::All => true,
+            };
+            let mut boid_forces = vec![];
+            for rule in &self.rules {
+                let (before_x, before_y) = new_boid.velocity();
+                rule.apply(&mut new_boid, &mut surroundings, option);
+                let color = force_colors.iter().find(|(name, _)| *name == rule.name());
+                if let (true, Some((_, color))) = (traced, color) {
+                    let (x, y) = new_boid.velocity();
+                    boid_forces.push((x - before_x, y - before_y, *color));
+                }
+            }
+            if self.option.unbounded {
+                new_boid.advance();
+            } else {
+                new_boid.update(self.width, self.height);
+            }
+            new_boid.smooth_heading(self.option.heading_smoothing);
+            for (x, y, color) in boid_forces {
+                if x == 0.0 && y == 0.0 {
+                    continue;
+                }
+                let start = new_boid.vertice.clone();
+                let mut end = start.clone();
+                end.x += (x * self.option.force_arrow_scale).round() as i16;
+                end.y += (y * self.option.force_arrow_scale).round() as i16;
+                forces.push((start, end, color.clone()));
+            }
+            if !new_boid.has_fixed_color() {
+                match self.option.color_mode {
+                    ColorMode::Plain => new_boid.set_color(theme.boid.clone()),
+                    ColorMode::Speed => new_boid.update_color(
+                        self.option.boid_max_speed,
+                        self.option.boid_min_speed,
+                        &theme.slow,
+                        &theme.fast,
+                    ),
+                    ColorMode::Heading => new_boid.update_heading_color(),
+                }
+            }
+            new_boids.push(new_boid);
+        }
+        if population_change > 0 {
+            for _ in 0..population_change {
+                let (x, y) = self.random_position();
+                let boid = self.new_boid(x, y);
+                self.hooks.spawned(&boid, Species::Boid);
+                new_boids.push(boid);
+            }
+        }
+        if predator_change > 0 {
+            for _ in 0..predator_change {
+                let (x, y) = self.random_position();
+                let predator = self.new_predator(x, y);
+                self.hooks.spawned(&predator, Species::Predator);
+                new_predators.push(predator);
+            }
+        }
+        if let Some(id) = self.selected {
+            let position = new_boids
+                .iter()
+                .chain(&new_predators)
+                .find(|boid| boid.id() == id)
+                .map(|boid| boid.vertice.clone());
+            match position {
+                Some(position) => {
+                    self.record_neighbors(&position, &accepted, &new_boids);
+                    self.record_trajectory(position);
+                }
+                None => {
+                    self.selected = None;
+                    self.trajectory.clear();
+                    self.neighbors.clear();
+                }
+            }
+        }
+        self.forces = forces;
+        let predator_ids: HashSet<Uuid> = new_predators.iter().map(|predator| predator.id()).collect();
+        self.hunts.retain(|id, _| predator_ids.contains(id));
+        let rules_milliseconds = self.profiler.record(Stage::Rules, rules_start_time);
+        self.query_candidates = candidates as f32 / queries.max(1) as f32;
+        self.query_cost = rules_milliseconds * 1000.0 / queries.max(1) as f32;
+        self.neighbor_distances = NeighborDistances::of(&nearest);
+        self.overlaps = overlaps;
+        if self.option.auto_quad_tree_capacity {
+            self.capacity_tuner
+                .record(rules_milliseconds, new_predators.len() + new_boids.len());
+        } else {
+            self.capacity_tuner.reset(self.option.quad_tree_capacity);
+        }
+
+        if self.option.unbounded {
+            self.roam(&mut new_boids, &mut new_predators);
+        } else {
+            self.settle(&mut new_boids, &mut new_predators);
+        }
+
+        let tree_start_time = Instant::now();
+        let mut new_quad_tree = QuadTree::with_config(self.index_config(), self.boundary.clone());
+        let parking = self.option.unbounded.then_some(&mut self.chunks);
+        let lost = insert_all(&mut new_quad_tree, &[new_predators, new_boids].concat(), parking);
+        self.report(lost);
+        self.quad_tree = new_quad_tree;
+        self.profiler.record(Stage::TreeBuild, tree_start_time);
+
+        let end_time = Instant::now();
+        Self::update_fps_count(&mut self.update_fps, start_time, end_time);
+        if !self.hooks.defer_tick() {
+            self.hooks.tick_completed(self);
+        }
+    }
+
+    // Move the frame of an unbounded world along with the middle of the flock, then load
+    // the chunks around the frame and the bulk of the flock. Boids outside are parked when
+    // indexed, those parked inside are taken back.
+    fn roam(&mut self, boids: &mut Vec<Boid>, predators: &mut Vec<Boid>) {
+        let (mut xs, mut ys): (Vec<f64>, Vec<f64>) = boids
+            .iter()
+            .map(|boid| boid.position())
+            .map(|(x, y)| (x as f64, y as f64))
+            .chain(self.chunks.positions(Species::Boid))
+            .unzip();
+        if xs.is_empty() {
+            (xs, ys) = predators
+                .iter()
+                .map(|predator| predator.position())
+                .map(|(x, y)| (x as f64, y as f64))
+                .chain(self.chunks.positions(Species::Predator))
+                .unzip();
+        }
+        let frame = Self::boundary_for(self.width, self.height);
+        let Some(((low_x, middle_x, high_x), (low_y, middle_y, high_y))) = spread(&mut xs).zip(spread(&mut ys)) else {
+            self.scroll = (0.0, 0.0);
+            self.boundary = self.chunks.snap(&frame);
+            return;
+        };
+        let dx = (middle_x - frame.center_x as f64).round();
+        let dy = (middle_y - frame.center_y as f64).round();
+        self.chunks.shift(dx as i64, dy as i64);
+        let (dx, dy) = (dx as f32, dy as f32);
+        self.scroll = (dx, dy);
+        for boid in boids.iter_mut().chain(predators.iter_mut()) {
+            let (x, y) = boid.position();
+            boid.set_position(x - dx, y - dy);
+        }
+        self.shift_scenery(dx, dy);
+        self.particles.shift(-dx, -dy);
+        let forces = self.forces.iter_mut().flat_map(|(start, end, _)| [start, end]);
+        let neighbors = self.neighbors.iter_mut().map(|(vertice, _)| vertice);
+        for vertice in self.trajectory.iter_mut().chain(neighbors).chain(forces) {
+            vertice.x = (vertice.x as f32 - dx) as i16;
+            vertice.y = (vertice.y as f32 - dy) as i16;
+        }
+        // Stragglers out of the bulk of the flock do not keep chunks loaded, those leaving it
+        // have a chunk to come back before they are parked
+        let reach = Chunks::REACH as f32;
+        let clamp = |value: f64, center: f32| (value as f32).clamp(center - reach, center + reach);
+        let margin = Chunks::SIZE as f32;
+        let area = Rectangle::from_corners(
+            clamp(low_x - dx as f64, frame.center_x).min(0.0) - margin,
+            clamp(low_y - dy as f64, frame.center_y).min(0.0) - margin,
+            clamp(high_x - dx as f64, frame.center_x).max(self.width as f32) + margin,
+            clamp(high_y - dy as f64, frame.center_y).max(self.height as f32) + margin,
+        );
+        self.boundary = self.chunks.snap(&area);
+        let (parked_boids, parked_predators) = self.chunks.unpark_within(&self.boundary);
+        boids.extend(parked_boids);
+        predators.extend(parked_predators);
+    }
+
+    // Back to a bounded world, everything wrapped into the frame
+    fn settle(&mut self, boids: &mut Vec<Boid>, predators: &mut Vec<Boid>) {
+        let frame = Self::boundary_for(self.width, self.height);
+        self.scroll = (0.0, 0.0);
+        if self.boundary == frame && self.chunks.is_empty() {
+            return;
+        }
+        let (parked_boids, parked_predators) = self.chunks.unpark_all();
+        boids.extend(parked_boids);
+        predators.extend(parked_predators);
+        let (width, height) = (self.width as f32, self.height as f32);
+        for boid in boids.iter_mut().chain(predators.iter_mut()) {
+            let (x, y) = boid.position();
+            boid.set_position(x.rem_euclid(width), y.rem_euclid(height));
+        }
+        self.chunks = Chunks::default();
+        self.boundary = frame;
+    }
+
+    // Move the obstacles and attractors back by the distance the frame moved
+    fn shift_scenery(&mut self, dx: f32, dy: f32) {
+        for obstacle in &mut self.obstacles {
+            obstacle.x -= dx;
+            obstacle.y -= dy;
+        }
+        for attractor in &mut self.attractors {
+            attractor.x -= dx;
+            attractor.y -= dy;
+        }
+    }
+
+    /// Position on the unbounded plane of the top left corner of the frame, see `WorldOption::unbounded`.
+    pub fn origin(&self) -> (i64, i64) {
+        self.chunks.origin()
+    }
+
+    /// Stragglers of an unbounded world parked far from the flock, not counted in
+    /// `boids_count` and `predators_count` nor simulated until the flock comes back.
+    pub fn parked_count(&self) -> usize {
+        self.chunks.count(Species::Boid) + self.chunks.count(Species::Predator)
+    }
+
+    /// Chunks of an unbounded world loaded around the frame and the flock, and chunks with
+    /// parked boids.
+    pub fn chunk_counts(&self) -> (usize, usize) {
+        let side = Chunks::SIZE as f32;
+        let loaded = if self.option.unbounded {
+            ((self.boundary.half_width * 2.0 / side).round() * (self.boundary.half_height * 2.0 / side).round()) as usize
+        } else {
+            0
+        };
+        (loaded, self.chunks.len())
+    }
+
+    // Neighbors the separation, alignment and cohesion rules count for `boid`, tried on a
+    // copy since the rules in use may be others
+    fn accepted_neighbors(boid: &Boid, found_boids: &[Boid], option: &WorldOption, accepted: &mut [Vec<Uuid>; 3]) {
+        let mut boid = boid.clone();
+        let [separated, aligned, centered] = accepted;
+        boid.separate(
+            found_boids,
+            option.avoid_factor,
+            option.safe_radius,
+            option.boid_view_angle,
+            Some(separated),
+        );
+        boid.align(
+            found_boids,
+            option.matching_factor,
+            option.boid_vision_radius,
+            option.boid_view_angle,
+            Some(aligned),
+        );
+        boid.cohesion(
+            found_boids,
+            option.centering_factor,
+            option.boid_vision_radius,
+            option.boid_view_angle,
+            Some(centered),
+        );
+    }
+
+    /// Steering rules run on every boid each update, in order.
+    pub fn rules(&self) -> &[Arc<dyn SteeringRule>] {
+        &self.rules
+    }
+
+    /// Add, remove or reorder the steering rules, see `rule::default_rules` for the built-in ones.
+    pub fn rules_mut(&mut self) -> &mut Vec<Arc<dyn SteeringRule>> {
+        &mut self.rules
+    }
+
+    /// Call `hook` with every boid or predator added, by hand, by a restart or by the
+    /// population settings. Boids brought back by undo or a snapshot are not spawned.
+    ///
+    /// Hooks go with the copies from `update_copy` but are not called there: the calls are
+    /// held back and made by `apply_update` on the thread it runs on, in order, once the
+    /// update is taken. An update `apply_update` drops calls none of them.
+    pub fn on_spawn(&mut self, hook: impl Fn(&Boid, Species) + Send + Sync + 'static) {
+        self.hooks.add_spawn(Arc::new(hook));
+    }
+
+    /// Call `hook` with every boid or predator removed, caught, erased, deleted, cleared or
+    /// culled by the population settings.
+    pub fn on_despawn(&mut self, hook: impl Fn(&Boid, Species) + Send + Sync + 'static) {
+        self.hooks.add_despawn(Arc::new(hook));
+    }
+
+    /// Call `hook` with the predator and the boid it caught, before the boid is despawned.
+    pub fn on_catch(&mut self, hook: impl Fn(&Boid, &Boid) + Send + Sync + 'static) {
+        self.hooks.add_catch(Arc::new(hook));
+    }
+
+    /// Call `hook` with the world at the end of every update. For an update run on a copy
+    /// it is the world once the update is taken, or the copy for the earlier updates of a
+    /// simulation speed above one.
+    pub fn on_tick_complete(&mut self, hook: impl Fn(&World) + Send + Sync + 'static) {
+        self.hooks.add_tick_complete(Arc::new(hook));
+    }
+
+    /// Drop every hook registered with `on_spawn`, `on_despawn`, `on_catch` and `on_tick_complete`.
+    pub fn clear_hooks(&mut self) {
+        self.hooks = Hooks::default();
+    }
+
+    /// A copy to `advance` elsewhere and give back to `apply_update`. The undo history, the
+    /// recorded inputs and what only drawing uses stay out of it, so it costs the same
+    /// however long the world has been edited or recorded.
+    pub fn update_copy(&mut self) -> World {
+        self.edited = None;
+        let history = std::mem::replace(&mut self.history, History::new(WorldOption::new()));
+        // A replay goes along, its inputs are played by the update
+        let tape = match self.tape {
+            Tape::Recording(_) => std::mem::take(&mut self.tape),
+            Tape::Idle | Tape::Replaying(_) => Tape::Idle,
+        };
+        let box_selection = std::mem::take(&mut self.box_selection);
+        let flocks = std::mem::take(&mut self.flocks);
+        let flock_hulls = std::mem::take(&mut self.flock_hulls);
+        let mut copy = self.clone();
+        copy.hooks.defer();
+        self.history = history;
+        if let Tape::Recording(_) = tape {
+            self.tape = tape;
+        }
+        self.box_selection = box_selection;
+        self.flocks = flocks;
+        self.flock_hulls = flock_hulls;
+        self.edited = Some(HashMap::new());
+        copy
+    }
+
+    // Note the boids and predators `ids` edited while a copy is updated elsewhere, `existed`
+    // if they were there before this edit
+    fn touch(&mut self, ids: impl IntoIterator<Item = Uuid>, existed: bool) {
+        if let Some(edited) = &mut self.edited {
+            for id in ids {
+                edited.entry(id).or_insert(existed);
+            }
+        }
+    }
+
+    /// Take the boids and timing of a world that was updated elsewhere. Boids and predators
+    /// spawned, removed or changed here since it was copied stay so, those that were in the
+    /// copy move on with the update. Nothing is taken if the flock was cleared, loaded or
+    /// resized here meanwhile.
+    pub fn apply_update(&mut self, mut updated: World) {
+        let edited = self.edited.take().unwrap_or_default();
+        let calls = updated.hooks.take_deferred();
+        if updated.generation != self.generation {
+            return;
+        }
+        let mut kept = vec![];
+        let mut moved = HashMap::new();
+        if !edited.is_empty() {
+            let is_edited = |boid: &Boid| edited.contains_key(&boid.id());
+            self.quad_tree.for_each(|boid| {
+                if is_edited(boid) {
+                    kept.push(boid.clone());
+                }
+            });
+            kept.extend(self.chunks.take(is_edited));
+            updated.quad_tree.retain(|boid| {
+                if is_edited(boid) {
+                    moved.insert(boid.id(), boid.clone());
+                    return false;
+                }
+                true
+            });
+            moved.extend(updated.chunks.take(is_edited).into_iter().map(|boid| (boid.id(), boid)));
+        }
+        self.quad_tree = updated.quad_tree;
+        self.boundary = updated.boundary;
+        self.chunks = updated.chunks;
+        // Scenery may have been edited here meanwhile, so it follows the frame on its own
+        self.shift_scenery(updated.scroll.0, updated.scroll.1);
+        self.particles = updated.particles;
+        self.catches = updated.catches;
+        self.hunts = updated.hunts;
+        self.rng = updated.rng;
+        self.tick = updated.tick;
+        // The selection may have changed while the update was running
+        if updated.selected == self.selected {
+            self.trajectory = updated.trajectory;
+            self.neighbors = updated.neighbors;
+        }
+        self.forces = updated.forces;
+        self.update_fps = updated.update_fps;
+        self.profiler.merge_update(&updated.profiler);
+        self.capacity_tuner = updated.capacity_tuner;
+        self.query_candidates = updated.query_candidates;
+        self.query_cost = updated.query_cost;
+        self.neighbor_distances = updated.neighbor_distances;
+        self.overlaps = updated.overlaps;
+        self.lost_boid = updated.lost_boid.or(self.lost_boid);
+        let (dx, dy) = updated.scroll;
+        let kept: Vec<Boid> = kept
+            .into_iter()
+            .filter_map(|mut boid| {
+                if edited[&boid.id()] {
+                    // Gone if the update caught or culled it
+                    boid.move_like(moved.get(&boid.id())?);
+                } else {
+                    let (x, y) = boid.position();
+                    boid.set_position(x - dx, y - dy);
+                }
+                Some(boid)
+            })
+            .collect();
+        let parking = self.option.unbounded.then_some(&mut self.chunks);
+        let lost = insert_all(&mut self.quad_tree, &kept, parking);
+        self.report(lost);
+        self.hooks.replay(calls, self);
+    }
+
+    fn update_fps_count(fps: &mut f32, start_time: Instant, end_time: Instant) {
+        *fps = 1.0 / end_time.duration_since(start_time).as_secs_f32();
+    }
+}
+
+impl Default for World {
+    /// Empty world of the default `WIDTH` × `HEIGHT` size.
+    fn default() -> Self {
+        Self::with_size(WIDTH, HEIGHT)
+    }
+}
+
+// Put `boids` in `tree`. Those it cannot take are parked when given chunks, otherwise the
+// last of them is returned
+fn insert_all(tree: &mut QuadTree, boids: &[Boid], mut parking: Option<&mut Chunks>) -> Option<Error> {
+    let mut lost = None;
+    for boid in boids {
+        if let Err(error) = tree.insert(boid) {
+            match &mut parking {
+                Some(chunks) => chunks.park(boid.clone(), boid.species()),
+                None => lost = Some(error),
+            }
+        }
+    }
+    lost
+}
+
+// Tell the predators from boids in `predators`, received without their species
+fn mark_predators(predators: &mut [Boid]) {
+    for predator in predators {
+        predator.set_species(Species::Predator);
+    }
+}
+
+// Low, middle and high of `values`, leaving out the farthest tenth
+fn spread(values: &mut [f64]) -> Option<(f64, f64, f64)> {
+    if values.is_empty() {
+        return None;
+    }
+    let last = values.len() - 1;
+    let mut at = |index: usize| *values.select_nth_unstable_by(index, f64::total_cmp).1;
+    Some((at(last / 20), at(last / 2), at(last - last / 20)))
+}
@@ -0,0 +1,76 @@
+//! GIF capture: recordings and instant replays, read back with a GIF decoder.
+
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use boids::capture::{GifRecorder, GifSettings, InstantReplay};
+use image::codecs::gif::GifDecoder;
+use image::{AnimationDecoder, Frame};
+
+fn directory(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("boids-capture-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+// An RGBA frame buffer of one color
+fn frame(width: u32, height: u32, rgb: [u8; 3]) -> Vec<u8> {
+    [rgb[0], rgb[1], rgb[2], 255].repeat((width * height) as usize)
+}
+
+fn decode(path: &Path) -> Vec<Frame> {
+    assert_eq!(path.extension().unwrap(), "gif");
+    let decoder = GifDecoder::new(File::open(path).unwrap()).unwrap();
+    decoder.into_frames().collect_frames().unwrap()
+}
+
+#[test]
+fn recordings_keep_every_sampled_frame() {
+    let directory = directory("recording");
+    let settings = GifSettings {
+        scale: 2,
+        frame_step: 2,
+    };
+    let mut recorder = GifRecorder::new(41, 30, settings);
+    for index in 0..6 {
+        let color = if index % 4 == 0 { [255, 0, 0] } else { [0, 0, 255] };
+        recorder.capture(&frame(41, 30, color), 41, 30);
+    }
+    // Frames of another size are left out
+    recorder.capture(&frame(20, 20, [0, 255, 0]), 20, 20);
+    let frames = decode(&recorder.finish(&directory).unwrap());
+    assert_eq!(frames.len(), 3);
+    for frame in &frames {
+        assert_eq!(frame.buffer().dimensions(), (21, 15));
+    }
+    assert_eq!(frames[0].buffer().get_pixel(10, 7).0, [255, 0, 0, 255]);
+    assert_eq!(frames[1].buffer().get_pixel(10, 7).0, [0, 0, 255, 255]);
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn recordings_without_frames_are_not_saved() {
+    let directory = directory("empty");
+    let recorder = GifRecorder::new(40, 30, GifSettings::default());
+    assert!(recorder.finish(&directory).is_err());
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn instant_replays_save_the_frames_kept() {
+    let directory = directory("replay");
+    let mut replay = InstantReplay::new(60.0);
+    for _ in 0..4 {
+        replay.capture(&frame(30, 24, [255, 255, 255]), 30, 24);
+    }
+    let clip = replay.clip();
+    assert!(!clip.is_empty());
+    let frames = decode(&clip.save(&directory).unwrap());
+    assert_eq!(frames.len(), 2);
+    for frame in &frames {
+        assert_eq!(frame.buffer().dimensions(), (10, 8));
+        assert_eq!(frame.buffer().get_pixel(0, 0).0, [255, 255, 255, 255]);
+    }
+    fs::remove_dir_all(&directory).unwrap();
+}
@@ -0,0 +1,63 @@
+//! A/B comparison: the compared world starts from the main one and keeps up with it tick for tick.
+
+use boids::compare::Comparison;
+use boids::{World, WorldOption};
+
+// Nothing timed, so both worlds only depend on the options and random numbers they share
+fn world() -> World {
+    let mut world = World::with_size(200, 150);
+    let mut option = WorldOption::new();
+    option.boid_count = 60;
+    option.predator_count = 2;
+    option.auto_population = false;
+    option.auto_quad_tree_capacity = false;
+    world.option = option;
+    world.set_seed(7);
+    world.restart();
+    world
+}
+
+fn positions(world: &World) -> Vec<(f32, f32)> {
+    world.boids().chain(world.predators()).map(|boid| boid.position()).collect()
+}
+
+fn run(main: &mut World, comparison: &mut Comparison, ticks: u32) {
+    for _ in 0..ticks {
+        main.update();
+        comparison.follow(main);
+    }
+}
+
+#[test]
+fn same_options_stay_in_step() {
+    let mut main = world();
+    let mut comparison = Comparison::new(&main);
+    run(&mut main, &mut comparison, 50);
+    assert_eq!(comparison.world.tick(), main.tick());
+    assert_eq!(positions(&comparison.world), positions(&main));
+}
+
+#[test]
+fn other_options_move_differently() {
+    let mut main = world();
+    let mut comparison = Comparison::new(&main);
+    comparison.world.option.centering_factor = 0.0;
+    comparison.world.option.matching_factor = 0.0;
+    run(&mut main, &mut comparison, 50);
+    assert_eq!(comparison.world.tick(), main.tick());
+    assert_ne!(positions(&comparison.world), positions(&main));
+}
+
+#[test]
+fn a_restart_starts_both_over_keeping_the_compared_options() {
+    let mut main = world();
+    let mut comparison = Comparison::new(&main);
+    comparison.world.option.centering_factor = 0.0;
+    run(&mut main, &mut comparison, 20);
+    main.restart();
+    main.update();
+    comparison.follow(&main);
+    assert_eq!(comparison.world.tick(), 1);
+    assert_eq!(comparison.world.boids_count(), main.boids_count());
+    assert_eq!(comparison.world.option.centering_factor, 0.0);
+}
@@ -0,0 +1,89 @@
+//! Rectangle tests the spatial index relies on to find neighbors, and the flock outlines.
+
+use boids::geometry::{convex_hull, Polygon};
+use boids::node::RenderNode;
+use boids::{Color, Rectangle, Vertice};
+
+// 40 × 20 around 50, 50
+fn rectangle() -> Rectangle {
+    Rectangle::new(50.0, 50.0, 20.0, 10.0)
+}
+
+#[test]
+fn contains_points_inside_and_on_the_edges() {
+    let rectangle = rectangle();
+    assert!(rectangle.contains_point(50.0, 50.0));
+    assert!(rectangle.contains_point(30.0, 40.0));
+    assert!(rectangle.contains_point(70.0, 60.0));
+    assert!(rectangle.contains_point(69.9, 41.0));
+}
+
+#[test]
+fn does_not_contain_points_outside() {
+    let rectangle = rectangle();
+    for (x, y) in [(29.9, 50.0), (70.1, 50.0), (50.0, 39.9), (50.0, 60.1), (0.0, 0.0)] {
+        assert!(!rectangle.contains_point(x, y), "{x}, {y} is outside");
+    }
+}
+
+#[test]
+fn corners_can_be_given_in_any_order() {
+    let rectangle = Rectangle::from_corners(70.0, 60.0, 30.0, 40.0);
+    assert!(rectangle.contains_point(30.0, 40.0));
+    assert!(rectangle.contains_point(70.0, 60.0));
+    assert!(!rectangle.contains_point(71.0, 60.0));
+}
+
+#[test]
+fn overlapping_rectangles() {
+    let rectangle = rectangle();
+    assert!(rectangle.overlaps(&Rectangle::new(80.0, 50.0, 10.0, 10.0)));
+    assert!(rectangle.overlaps(&Rectangle::new(50.0, 50.0, 1.0, 1.0)));
+    assert!(!rectangle.overlaps(&Rectangle::new(90.0, 50.0, 10.0, 5.0)));
+}
+
+#[test]
+fn intersects_a_circle_around_its_center() {
+    let rectangle = rectangle();
+    assert!(rectangle.intersects(&Vertice { x: 50, y: 50 }, 1.0));
+    assert!(rectangle.intersects(&Vertice { x: 55, y: 45 }, 10.0));
+}
+
+#[test]
+fn does_not_intersect_far_circles() {
+    let rectangle = rectangle();
+    assert!(!rectangle.intersects(&Vertice { x: 200, y: 200 }, 10.0));
+    assert!(!rectangle.intersects(&Vertice { x: 50, y: 100 }, 5.0));
+}
+
+#[test]
+fn intersects_circles_reaching_only_an_edge_or_corner() {
+    let rectangle = rectangle();
+    assert!(rectangle.intersects(&Vertice { x: 75, y: 50 }, 5.0));
+    assert!(rectangle.intersects(&Vertice { x: 73, y: 64 }, 5.0));
+    assert!(!rectangle.intersects(&Vertice { x: 74, y: 64 }, 5.0));
+}
+
+#[test]
+fn convex_hull_leaves_out_points_inside_and_on_edges() {
+    let points = [(0.0, 0.0), (10.0, 0.0), (5.0, 5.0), (10.0, 10.0), (0.0, 10.0), (5.0, 0.0), (0.0, 0.0)];
+    let mut hull = convex_hull(&points);
+    assert_eq!(hull.len(), 4);
+    hull.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+    assert_eq!(hull, [(0.0, 0.0), (0.0, 10.0), (10.0, 0.0), (10.0, 10.0)]);
+
+    assert_eq!(convex_hull(&[(0.0, 0.0), (1.0, 1.0), (2.0, 2.0)]), [(0.0, 0.0), (2.0, 2.0)]);
+    assert!(convex_hull(&[]).is_empty());
+}
+
+#[test]
+fn polygon_fills_its_inside_only() {
+    let (width, height) = (20u16, 20u16);
+    let mut frame = vec![0; width as usize * height as usize * 4];
+    let triangle = Polygon::new(vec![(2.0, 2.0), (18.0, 2.0), (2.0, 18.0)], Color::White, 1.0, 1.0);
+    triangle.draw(&mut frame, width, height);
+    let pixel = |x: usize, y: usize| frame[(y * width as usize + x) * 4];
+    assert_eq!(pixel(5, 5), 255);
+    assert_eq!(pixel(16, 16), 0);
+    assert_eq!(pixel(0, 10), 0);
+}
@@ -0,0 +1,90 @@
+//! Golden runs: a small seeded world advanced a fixed number of ticks, hashed down to every
+//! position and velocity and compared with the hash recorded when the behavior was last
+//! known good.
+//!
+//! A mismatch means the simulation now moves differently. If the change is intended, run
+//! `cargo test --test golden` and copy the new hashes from the failure messages.
+
+use boids::node::IndexBackend;
+use boids::{BoidView, World, WorldOption};
+
+const TICKS: u32 = 120;
+const SEED: u64 = 2024;
+
+// FNV-1a, stable across Rust versions unlike `DefaultHasher`
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+fn mix(hash: &mut u64, bytes: &[u8]) {
+    for byte in bytes {
+        *hash ^= *byte as u64;
+        *hash = hash.wrapping_mul(FNV_PRIME);
+    }
+}
+
+fn mix_boid(hash: &mut u64, boid: BoidView) {
+    let (x, y) = boid.position();
+    let (velocity_x, velocity_y) = boid.velocity();
+    for value in [x, y, velocity_x, velocity_y] {
+        mix(hash, &value.to_bits().to_le_bytes());
+    }
+}
+
+// Nothing timed (auto population, capacity tuning) so runs only depend on the seed
+fn options() -> WorldOption {
+    let mut option = WorldOption::new();
+    option.boid_count = 150;
+    option.predator_count = 4;
+    option.predators_catch = true;
+    option.auto_population = false;
+    option.auto_quad_tree_capacity = false;
+    option
+}
+
+fn run(seed: u64, setup: impl FnOnce(&mut World)) -> u64 {
+    let mut world = World::with_size(400, 300);
+    world.option = options();
+    setup(&mut world);
+    world.set_seed(seed);
+    world.restart();
+    for _ in 0..TICKS {
+        world.update();
+    }
+    let mut hash = FNV_OFFSET;
+    for boid in world.boids().chain(world.predators()) {
+        mix_boid(&mut hash, boid);
+    }
+    mix(&mut hash, &world.catches().to_le_bytes());
+    hash
+}
+
+fn assert_golden(name: &str, hash: u64, golden: u64) {
+    assert_eq!(hash, golden, "{name} changed, new golden hash is {hash:#018x}");
+}
+
+#[test]
+fn same_seed_replays_same_run() {
+    assert_eq!(run(SEED, |_| ()), run(SEED, |_| ()));
+    assert_ne!(run(SEED, |_| ()), run(SEED + 1, |_| ()));
+}
+
+#[test]
+fn default_rules() {
+    assert_golden("default_rules", run(SEED, |_| ()), 0x1520_496e_fa35_e2eb);
+}
+
+#[test]
+fn grid_index() {
+    let hash = run(SEED, |world| world.option.index_backend = IndexBackend::Grid);
+    assert_golden("grid_index", hash, 0x02ee_e13c_8701_b5cb);
+}
+
+#[test]
+fn noise_and_scenery() {
+    let hash = run(SEED, |world| {
+        world.option.noise = true;
+        world.add_obstacle(100.0, 150.0);
+        world.add_attractor(300.0, 150.0);
+    });
+    assert_golden("noise_and_scenery", hash, 0x07c4_517e_a563_4953);
+}
@@ -0,0 +1,153 @@
+//! Event hooks see every spawn, despawn, catch and update of the world they are registered on.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use boids::preset::Species;
+use boids::{UpdatePipeline, World, WorldOption};
+
+fn world() -> World {
+    let mut world = World::with_size(200, 150);
+    let mut option = WorldOption::new();
+    option.boid_count = 80;
+    option.predator_count = 3;
+    option.auto_population = false;
+    option.auto_quad_tree_capacity = false;
+    world.option = option;
+    world.set_seed(11);
+    world
+}
+
+fn counter() -> Arc<AtomicUsize> {
+    Arc::new(AtomicUsize::new(0))
+}
+
+fn count(counter: &Arc<AtomicUsize>) -> usize {
+    counter.load(Ordering::Relaxed)
+}
+
+#[test]
+fn spawns_and_despawns_are_counted_by_species() {
+    let mut world = world();
+    let (boids, predators, despawned) = (counter(), counter(), counter());
+    let (boids_hook, predators_hook, despawned_hook) = (boids.clone(), predators.clone(), despawned.clone());
+    world.on_spawn(move |_, species| {
+        let counter = if species == Species::Boid { &boids_hook } else { &predators_hook };
+        counter.fetch_add(1, Ordering::Relaxed);
+    });
+    world.on_despawn(move |_, _| {
+        despawned_hook.fetch_add(1, Ordering::Relaxed);
+    });
+    world.restart();
+    assert_eq!((count(&boids), count(&predators)), (80, 3));
+    world.spawn_boids(50, 50);
+    world.clear_all();
+    assert_eq!(count(&despawned), 84);
+}
+
+#[test]
+fn every_catch_despawns_its_boid() {
+    let mut world = world();
+    world.option.predator_count = 20;
+    world.option.predators_catch = true;
+    world.option.catch_radius = 20.0;
+    let (catches, despawned) = (counter(), counter());
+    let (catches_hook, despawned_hook) = (catches.clone(), despawned.clone());
+    world.on_catch(move |predator, boid| {
+        assert_ne!(predator.id(), boid.id());
+        catches_hook.fetch_add(1, Ordering::Relaxed);
+    });
+    world.on_despawn(move |_, species| {
+        assert!(species == Species::Boid, "predators are never caught");
+        despawned_hook.fetch_add(1, Ordering::Relaxed);
+    });
+    world.restart();
+    for _ in 0..100 {
+        world.update();
+    }
+    assert!(count(&catches) > 0, "nothing was caught");
+    assert_eq!(count(&catches) as u64, world.catches());
+    assert_eq!(count(&despawned), count(&catches));
+}
+
+#[test]
+fn tick_complete_runs_after_every_update() {
+    let mut world = world();
+    let ticks = counter();
+    let ticks_hook = ticks.clone();
+    world.on_tick_complete(move |world| {
+        assert_eq!(world.tick() as usize, ticks_hook.fetch_add(1, Ordering::Relaxed) + 1);
+    });
+    world.restart();
+    for _ in 0..10 {
+        world.update();
+    }
+    assert_eq!(count(&ticks), 10);
+    world.clear_hooks();
+    world.update();
+    assert_eq!(count(&ticks), 10);
+}
+
+#[test]
+fn updates_run_elsewhere_call_the_hooks_only_once_taken() {
+    let mut world = world();
+    world.option.predator_count = 20;
+    world.option.predators_catch = true;
+    world.option.catch_radius = 20.0;
+    let (spawned, catches, despawned, ticks) = (counter(), counter(), counter(), counter());
+    let (spawned_hook, catches_hook, despawned_hook, ticks_hook) =
+        (spawned.clone(), catches.clone(), despawned.clone(), ticks.clone());
+    world.on_spawn(move |_, _| {
+        spawned_hook.fetch_add(1, Ordering::Relaxed);
+    });
+    world.on_catch(move |_, _| {
+        catches_hook.fetch_add(1, Ordering::Relaxed);
+    });
+    world.on_despawn(move |_, _| {
+        despawned_hook.fetch_add(1, Ordering::Relaxed);
+    });
+    world.on_tick_complete(move |world| {
+        assert_eq!(world.tick() as usize, ticks_hook.fetch_add(1, Ordering::Relaxed) + 1);
+    });
+    world.restart();
+
+    let mut pipeline = UpdatePipeline::new();
+    let start = Instant::now();
+    let mut spawns = 100;
+    while world.tick() < 60 {
+        assert!(start.elapsed() < Duration::from_secs(10), "the worker never answered");
+        world.spawn_boids(100, 75);
+        spawns += 1;
+        if world.tick() >= 20 {
+            // Drops the update in flight, along with its calls
+            world.resize(220, 165);
+        }
+        pipeline.step(&mut world);
+        thread::sleep(Duration::from_millis(1));
+    }
+    assert!(count(&catches) > 0, "nothing was caught");
+    assert_eq!(count(&ticks) as u64, world.tick());
+    assert_eq!(count(&catches) as u64, world.catches());
+    assert_eq!(count(&despawned), count(&catches));
+    assert_eq!(count(&spawned), spawns);
+    assert_eq!(world.boids_count() + world.predators_count() + count(&despawned), spawns);
+}
+
+#[test]
+fn ticks_run_on_a_copy_are_completed_in_order_once_taken() {
+    let mut world = world();
+    let ticks = counter();
+    let ticks_hook = ticks.clone();
+    world.on_tick_complete(move |world| {
+        assert_eq!(world.tick() as usize, ticks_hook.fetch_add(1, Ordering::Relaxed) + 1);
+    });
+    world.restart();
+    world.option.simulation_speed = 3;
+    let mut copy = world.update_copy();
+    copy.advance();
+    assert_eq!(count(&ticks), 0);
+    world.apply_update(copy);
+    assert_eq!(count(&ticks), 3);
+}
@@ -0,0 +1,44 @@
+//! Hunting statistics: chases, catches and distance of every predator.
+
+use boids::hunt::HuntStats;
+use boids::World;
+
+#[test]
+fn a_chase_lasts_while_boids_are_in_sight() {
+    let mut hunt = HuntStats::default();
+    hunt.record(1, false, 2.0);
+    hunt.record(2, true, 2.0);
+    hunt.record(3, true, 2.0);
+    assert!(hunt.is_chasing());
+    assert_eq!(hunt.mean_chase_ticks(), None);
+    hunt.record(5, false, 2.0);
+    hunt.record(6, true, 2.0);
+    hunt.record(7, false, 2.0);
+    assert_eq!(hunt.chases, 2);
+    assert_eq!(hunt.mean_chase_ticks(), Some(2.0));
+    assert_eq!(hunt.distance, 12.0);
+}
+
+#[test]
+fn every_predator_keeps_its_own_count() {
+    let mut world = World::builder().size(200, 150).boids(150).predators(3).seed(4).build();
+    world.option.auto_population = false;
+    for _ in 0..200 {
+        world.update();
+    }
+    let hunts: Vec<HuntStats> = world
+        .predators()
+        .filter_map(|predator| world.hunt(predator.id()).copied())
+        .collect();
+    assert_eq!(hunts.len(), 3);
+    assert!(hunts.iter().all(|hunt| hunt.distance > 0.0));
+    assert!(hunts.iter().any(|hunt| hunt.chases > 0));
+    let catches: u32 = hunts.iter().map(|hunt| hunt.catches).sum();
+    assert_eq!(catches as u64, world.catches());
+
+    // Gone predators are forgotten
+    let id = world.predators().next().unwrap().id();
+    world.clear_all();
+    world.update();
+    assert!(world.hunt(id).is_none());
+}
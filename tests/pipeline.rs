@@ -0,0 +1,105 @@
+//! Update pipeline: the world advanced on a worker thread and merged back.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use boids::profile::Profile;
+use boids::sandbox::Change;
+use boids::{UpdatePipeline, World};
+
+fn world() -> World {
+    World::builder().size(320, 240).boids(40).predators(2).seed(7).build()
+}
+
+#[test]
+fn updates_made_by_the_worker_reach_the_world() {
+    let mut world = world();
+    let mut pipeline = UpdatePipeline::new();
+    let start = Instant::now();
+    while world.tick() < 3 {
+        assert!(start.elapsed() < Duration::from_secs(10), "the worker never answered");
+        pipeline.step(&mut world);
+        thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!((world.boids_count(), world.predators_count()), (40, 2));
+}
+
+#[test]
+fn copies_for_the_worker_leave_the_history_and_recording_behind() {
+    let mut world = world();
+    world.spawn_boids(100, 100);
+    world.commit_edit();
+    world.start_recording();
+    let mut copy = world.update_copy();
+    assert!(world.can_undo() && world.is_recording());
+    assert!(!copy.can_undo() && !copy.is_recording());
+    assert_eq!(copy.boids_count(), world.boids_count());
+
+    copy.advance();
+    world.apply_update(copy);
+    assert_eq!(world.tick(), 1);
+    assert!(world.can_undo() && world.is_recording());
+}
+
+#[test]
+fn edits_made_during_an_update_stay_on_top_of_it() {
+    let mut world = World::builder().size(320, 240).boids(40).predators(0).seed(7).build();
+    let ids: Vec<_> = world.boids().map(|boid| boid.id()).take(2).collect();
+    let (edited, erased) = (ids[0], ids[1]);
+    let mut copy = world.update_copy();
+
+    let fast = Profile {
+        max_speed: Some(30),
+        ..Profile::default()
+    };
+    world.set_profile(edited, fast);
+    let (x, y) = world.boids().find(|boid| boid.id() == erased).unwrap().position();
+    world.erase_at(x, y, 0.01);
+    world.spawn_boids(100, 100);
+    copy.advance();
+    let moved = copy.boids().find(|boid| boid.id() == edited).unwrap().position();
+    world.apply_update(copy);
+
+    assert_eq!(world.tick(), 1);
+    assert_eq!(world.boids_count(), 40);
+    let boid = world.boids().find(|boid| boid.id() == edited).unwrap();
+    assert!(boid.profile() == Some(&fast) && boid.position() == moved);
+    assert!(world.boids().all(|boid| boid.id() != erased));
+    assert!(world.boids().any(|boid| boid.position() == (100.0, 100.0)));
+}
+
+#[test]
+fn spawning_on_every_frame_does_not_hold_the_updates_back() {
+    let mut world = world();
+    let mut pipeline = UpdatePipeline::new();
+    let start = Instant::now();
+    let mut spawned = 0;
+    while world.tick() < 10 {
+        assert!(start.elapsed() < Duration::from_secs(10), "the updates stopped while spawning");
+        world.spawn_boids(160, 120);
+        spawned += 1;
+        pipeline.step(&mut world);
+        thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(world.boids_count() + world.catches() as usize, 40 + spawned);
+}
+
+#[test]
+fn a_spray_at_full_rate_keeps_the_flock_moving() {
+    let mut world = world();
+    let mut pipeline = UpdatePipeline::new();
+    let start = Instant::now();
+    let mut sprayed = 0;
+    while world.tick() < 20 {
+        assert!(start.elapsed() < Duration::from_secs(10), "the flock stopped under the spray");
+        // 500 boids a second at 60 frames a second, along a stroke
+        for step in 0..9 {
+            let x = 40.0 + sprayed as f32 % 240.0 + step as f32;
+            Change::SpawnBoid { x, y: 120.0 }.apply(&mut world);
+        }
+        sprayed += 9;
+        pipeline.step(&mut world);
+        thread::sleep(Duration::from_millis(16));
+    }
+    assert_eq!(world.boids_count() + world.catches() as usize, 40 + sprayed);
+}
@@ -0,0 +1,65 @@
+//! Settings one boid follows over those of the world, set from the inspector.
+
+use boids::profile::Profile;
+use boids::{World, WorldOption};
+
+#[test]
+fn a_profile_only_replaces_the_settings_it_has() {
+    let option = WorldOption::new();
+    let profile = Profile {
+        vision_radius: Some(0.0),
+        max_speed: Some(40),
+        ..Profile::default()
+    };
+    let applied = profile.apply(&option);
+    assert_eq!(applied.boid_vision_radius, 0.0);
+    assert_eq!(applied.boid_max_speed, 40);
+    assert!(applied.avoid_factor == option.avoid_factor && applied.boid_min_speed == option.boid_min_speed);
+    assert!(Profile::default().apply(&option) == option);
+}
+
+#[test]
+fn one_fast_boid_outruns_the_flock_until_undone() {
+    let mut world = World::builder().size(400, 300).boids(30).predators(0).seed(3).build();
+    world.option.auto_population = false;
+    let id = world.boids().next().unwrap().id();
+    let fast = Profile {
+        max_speed: Some(30),
+        min_speed: Some(25),
+        ..Profile::default()
+    };
+    world.set_profile(id, fast);
+    world.commit_edit();
+    for _ in 0..5 {
+        world.update();
+    }
+    let speed = |world: &World| {
+        let boid = world.boids().find(|boid| boid.id() == id).unwrap();
+        let (velocity_x, velocity_y) = boid.velocity();
+        velocity_x.hypot(velocity_y)
+    };
+    assert!(speed(&world) >= 25.0 - 0.01);
+    assert!(world
+        .boids()
+        .filter(|boid| boid.id() != id)
+        .all(|boid| boid.velocity().0.hypot(boid.velocity().1) <= world.option.boid_max_speed as f32 + 0.01));
+
+    world.undo();
+    assert_eq!(world.boids().find(|boid| boid.id() == id).unwrap().profile(), None);
+    world.update();
+    assert!(speed(&world) <= world.option.boid_max_speed as f32 + 0.01);
+}
+
+#[test]
+fn predators_keep_the_predator_settings() {
+    let mut world = World::builder().size(400, 300).boids(0).predators(1).seed(3).build();
+    let id = world.predators().next().unwrap().id();
+    world.set_profile(
+        id,
+        Profile {
+            fear_factor: Some(0.0),
+            ..Profile::default()
+        },
+    );
+    assert_eq!(world.predators().next().unwrap().profile(), None);
+}
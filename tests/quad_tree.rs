@@ -0,0 +1,199 @@
+//! Invariants of the spatial index over random flocks, for both backends: nothing inserted
+//! gets lost, no query misses a boid it should find and sharing the index with another
+//! species changes nothing for a species.
+
+use std::collections::HashSet;
+
+use boids::node::{IndexBackend, IndexConfig};
+use boids::preset::{Species, SpeciesSet};
+use boids::{Boid, Color, QuadTree, Rectangle, Vertice, World};
+use proptest::prelude::*;
+use uuid::Uuid;
+
+const WIDTH: i16 = 640;
+const HEIGHT: i16 = 480;
+
+fn boundary() -> Rectangle {
+    let (half_width, half_height) = (WIDTH as f32 / 2.0, HEIGHT as f32 / 2.0);
+    Rectangle::new(half_width, half_height, half_width, half_height)
+}
+
+fn boid((x, y): (i16, i16)) -> Boid {
+    Boid::new(Vertice { x, y }, 4, 0, 0, Color::White)
+}
+
+fn distance(a: &Boid, b: &Boid) -> f32 {
+    let dx = (a.vertice.x - b.vertice.x) as f32;
+    let dy = (a.vertice.y - b.vertice.y) as f32;
+    (dx * dx + dy * dy).sqrt()
+}
+
+fn ids(boids: &[Boid]) -> HashSet<Uuid> {
+    boids.iter().map(Boid::id).collect()
+}
+
+fn points() -> impl Strategy<Value = Vec<(i16, i16)>> {
+    prop::collection::vec((0..=WIDTH, 0..=HEIGHT), 0..300)
+}
+
+// Small capacities and depths so the trees get deep and the grid cells vary in size
+fn config() -> impl Strategy<Value = IndexConfig> {
+    (prop_oneof![Just(IndexBackend::QuadTree), Just(IndexBackend::Grid)], 1..8usize, 1..12u16, 5.0..120.0f32).prop_map(
+        |(backend, capacity, max_depth, cell_size)| IndexConfig {
+            backend,
+            capacity,
+            max_depth,
+            cell_size,
+        },
+    )
+}
+
+fn build(config: IndexConfig, boids: &[Boid]) -> QuadTree {
+    let mut quad_tree = QuadTree::with_config(config, boundary());
+    for boid in boids {
+        quad_tree.insert(boid).expect("every point lies inside the boundary");
+    }
+    quad_tree
+}
+
+proptest! {
+    #[test]
+    fn every_inserted_boid_is_kept(config in config(), points in points()) {
+        let boids: Vec<Boid> = points.into_iter().map(boid).collect();
+        let quad_tree = build(config, &boids);
+        let kept = quad_tree.to_vec();
+        prop_assert_eq!(kept.len(), boids.len());
+        prop_assert_eq!(ids(&kept), ids(&boids));
+    }
+
+    #[test]
+    fn queries_find_every_neighbor_within_the_radius(
+        config in config(),
+        points in points(),
+        center in (0..=WIDTH, 0..=HEIGHT),
+        radius in 1.0..200.0f32,
+    ) {
+        let boids: Vec<Boid> = points.into_iter().map(boid).collect();
+        let quad_tree = build(config, &boids);
+        let center = boid(center);
+        let mut found = vec![];
+        quad_tree.query(&mut found, &center, radius);
+        // Whole nodes come back, so more than the neighbors at times but never twice the same
+        prop_assert_eq!(ids(&found).len(), found.len());
+        let found = ids(&found);
+        for other in boids.iter().filter(|other| distance(&center, other) <= radius) {
+            prop_assert!(
+                found.contains(&other.id()),
+                "missed the boid at {}, {}", other.vertice.x, other.vertice.y
+            );
+        }
+    }
+
+    #[test]
+    fn species_queries_keep_only_the_species_asked_for(
+        config in config(),
+        points in prop::collection::vec(((0..=WIDTH, 0..=HEIGHT), any::<bool>()), 0..300),
+        center in (0..=WIDTH, 0..=HEIGHT),
+        radius in 1.0..200.0f32,
+        species in prop_oneof![
+            Just(SpeciesSet::NONE),
+            Just(SpeciesSet::from(Species::Boid)),
+            Just(SpeciesSet::from(Species::Predator)),
+            Just(Species::Boid | Species::Predator),
+        ],
+    ) {
+        let boids: Vec<Boid> = points
+            .into_iter()
+            .map(|(point, is_predator)| {
+                let mut boid = boid(point);
+                if is_predator {
+                    boid.set_species(Species::Predator);
+                }
+                boid
+            })
+            .collect();
+        let quad_tree = build(config, &boids);
+        let center = boid(center);
+        let mut all = vec![];
+        quad_tree.query(&mut all, &center, radius);
+        let mut found = vec![];
+        quad_tree.query_species(&mut found, &center, radius, species);
+        let kept: Vec<Boid> = all.into_iter().filter(|boid| species.contains(boid.species())).collect();
+        prop_assert_eq!(ids(&found), ids(&kept));
+        prop_assert_eq!(found.len(), kept.len());
+    }
+
+    #[test]
+    fn every_species_is_indexed_as_if_alone(
+        config in config(),
+        points in prop::collection::vec(((0..=WIDTH, 0..=HEIGHT), any::<bool>()), 0..300),
+        center in (0..=WIDTH, 0..=HEIGHT),
+        radius in 1.0..200.0f32,
+        erased in ((0..=WIDTH, 0..=HEIGHT), 0.0..100.0f32),
+    ) {
+        let boids: Vec<Boid> = points
+            .into_iter()
+            .map(|(point, is_predator)| {
+                let mut boid = boid(point);
+                if is_predator {
+                    boid.set_species(Species::Predator);
+                }
+                boid
+            })
+            .collect();
+        let mut quad_tree = build(config, &boids);
+        let ((x, y), erase_radius) = erased;
+        let mut removed = vec![];
+        quad_tree.remove_within(&mut removed, x as f32, y as f32, erase_radius);
+        let center = boid(center);
+        for species in Species::ALL {
+            let of_species: Vec<Boid> = boids.iter().filter(|boid| boid.species() == species).cloned().collect();
+            let mut alone = build(config, &of_species);
+            alone.remove_within(&mut vec![], x as f32, y as f32, erase_radius);
+            let order = |boids: Vec<Boid>| boids.iter().map(Boid::id).collect::<Vec<_>>();
+            prop_assert_eq!(quad_tree.len_of(species), alone.len());
+            prop_assert_eq!(order(quad_tree.to_vec_of(species)), order(alone.to_vec()));
+            let iterated: Vec<Boid> = quad_tree.iter_of(species).cloned().collect();
+            prop_assert_eq!(order(iterated), order(alone.iter().cloned().collect()));
+            let (mut found, mut found_alone) = (vec![], vec![]);
+            quad_tree.query_species(&mut found, &center, radius, species.into());
+            alone.query(&mut found_alone, &center, radius);
+            prop_assert_eq!(order(found), order(found_alone));
+        }
+        prop_assert_eq!(quad_tree.len(), boids.len() - removed.len());
+    }
+
+    #[test]
+    fn rectangle_queries_match_brute_force(
+        config in config(),
+        points in points(),
+        corners in ((0..=WIDTH, 0..=HEIGHT), (0..=WIDTH, 0..=HEIGHT)),
+    ) {
+        let boids: Vec<Boid> = points.into_iter().map(boid).collect();
+        let quad_tree = build(config, &boids);
+        let ((x1, y1), (x2, y2)) = corners;
+        let area = Rectangle::from_corners(x1 as f32, y1 as f32, x2 as f32, y2 as f32);
+        let mut found = vec![];
+        quad_tree.query_rectangle(&mut found, &area);
+        let inside: Vec<Boid> = boids
+            .iter()
+            .filter(|boid| area.contains_point(boid.vertice.x as f32, boid.vertice.y as f32))
+            .cloned()
+            .collect();
+        prop_assert_eq!(found.len(), inside.len());
+        prop_assert_eq!(ids(&found), ids(&inside));
+    }
+}
+
+#[test]
+fn bodies_of_a_world_know_their_species() {
+    let mut world = World::builder().size(200, 150).boids(20).predators(3).seed(2).build();
+    world.update();
+    assert!(world.boids().all(|boid| boid.species() == Species::Boid));
+    assert!(world.predators().all(|predator| predator.species() == Species::Predator));
+
+    // Flocks received from elsewhere are told apart by the list they come in
+    world.replace_flock(vec![boid((10, 10))], vec![boid((20, 20)), boid((30, 30))]);
+    assert_eq!(world.predators_count(), 2);
+    assert!(world.predators().all(|predator| predator.species() == Species::Predator));
+}
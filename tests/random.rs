@@ -0,0 +1,79 @@
+//! Worlds take every random choice from the generator they are given.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use boids::World;
+use rand::rngs::mock::StepRng;
+use rand::RngCore;
+
+// Counts the numbers drawn from it, the copies of a world share the count
+#[derive(Clone)]
+struct CountingRng {
+    inner: StepRng,
+    draws: Arc<AtomicUsize>,
+}
+
+impl RngCore for CountingRng {
+    fn next_u32(&mut self) -> u32 {
+        self.draws.fetch_add(1, Ordering::Relaxed);
+        self.inner.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.draws.fetch_add(1, Ordering::Relaxed);
+        self.inner.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.draws.fetch_add(1, Ordering::Relaxed);
+        self.inner.fill_bytes(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+fn counting_rng() -> (CountingRng, Arc<AtomicUsize>) {
+    let draws = Arc::new(AtomicUsize::new(0));
+    let rng = CountingRng {
+        inner: StepRng::new(1, 7919),
+        draws: draws.clone(),
+    };
+    (rng, draws)
+}
+
+#[test]
+fn spawns_and_updates_draw_from_the_given_generator() {
+    let (rng, draws) = counting_rng();
+    let mut world = World::builder().size(200, 150).boids(20).predators(1).rng(rng).build();
+    let spawned = draws.load(Ordering::Relaxed);
+    assert!(spawned >= 21 * 4, "only {spawned} draws for 21 spawns");
+    world.option.noise = true;
+    world.update();
+    assert!(draws.load(Ordering::Relaxed) > spawned);
+}
+
+#[test]
+fn the_same_generator_makes_the_same_run() {
+    let run = || {
+        let mut world = World::builder().size(200, 150).boids(30).rng(StepRng::new(3, 104_729)).build();
+        for _ in 0..20 {
+            world.update();
+        }
+        world.boids().map(|boid| boid.position()).collect::<Vec<_>>()
+    };
+    assert_eq!(run(), run());
+}
+
+#[test]
+fn a_seed_goes_back_to_the_default_generator() {
+    let (rng, draws) = counting_rng();
+    let mut world = World::with_size(200, 150);
+    world.set_rng(rng);
+    world.set_seed(5);
+    world.restart();
+    assert_eq!(draws.load(Ordering::Relaxed), 0);
+}
@@ -0,0 +1,95 @@
+//! Shared sandbox: changes from several clients to one world, and the state sent back.
+
+use boids::lfo::Parameter;
+use boids::sandbox::{Change, Referee, State};
+use boids::World;
+
+fn world() -> World {
+    World::builder().size(320, 240).boids(0).predators(0).seed(1).build()
+}
+
+#[test]
+fn changes_survive_the_network() {
+    let changes = [
+        Change::SpawnBoid { x: 10.0, y: 20.0 },
+        Change::SpawnPredator { x: 30.5, y: 40.0 },
+        Change::AddObstacle { x: 50.0, y: 60.0 },
+        Change::AddAttractor { x: 70.0, y: 80.0 },
+        Change::Set {
+            parameter: Parameter::TrailDecay,
+            value: 0.25,
+        },
+    ];
+    for change in changes {
+        assert_eq!(Change::decode(&change.encode()), Some(change));
+    }
+    assert_eq!(Change::decode(b"BDSJ"), None);
+    let mut unknown = changes[0].encode();
+    unknown[4] = 9;
+    assert_eq!(Change::decode(&unknown), None);
+}
+
+#[test]
+fn a_moved_setting_is_held_for_a_while() {
+    let mut world = world();
+    let mut referee = Referee::new();
+    let cohesion = |value| Change::Set {
+        parameter: Parameter::CenteringFactor,
+        value,
+    };
+    assert!(referee.apply("alice", cohesion(0.01), &mut world, 0.0));
+    assert!(referee.apply("alice", cohesion(0.02), &mut world, 0.5));
+    assert!(!referee.apply("bob", cohesion(0.03), &mut world, 1.0));
+    assert_eq!(world.option.centering_factor, 0.02);
+    // Other settings stay free
+    let separation = Change::Set {
+        parameter: Parameter::AvoidFactor,
+        value: 0.1,
+    };
+    assert!(referee.apply("bob", separation, &mut world, 1.0));
+
+    assert!(referee.apply("bob", cohesion(0.03), &mut world, 1.6));
+    assert_eq!(world.option.centering_factor, 0.03);
+    referee.forget(&"bob");
+    assert!(referee.apply("alice", cohesion(0.04), &mut world, 1.7));
+}
+
+#[test]
+fn no_one_fills_the_world_alone() {
+    let mut world = world();
+    let mut referee = Referee::new();
+    let spawn = Change::SpawnBoid { x: 100.0, y: 100.0 };
+    let spawned = (0..150).filter(|_| referee.apply(1, spawn, &mut world, 0.0)).count();
+    assert_eq!(spawned, Referee::<u8>::BUDGET as usize);
+    // Others still can, and the budget refills over time
+    assert!(referee.apply(2, spawn, &mut world, 0.0));
+    assert!(!referee.apply(1, spawn, &mut world, 0.005));
+    assert!(referee.apply(1, spawn, &mut world, 0.1));
+    assert_eq!(world.boids_count(), spawned + 2);
+}
+
+#[test]
+fn the_state_shows_the_settings_and_scenery() {
+    let mut server = world();
+    server.option.boid_max_speed = 9;
+    server.add_obstacle(10.0, 20.0);
+    server.add_attractor(30.0, 40.0);
+    let state = State::decode(&State::of(&server).encode()).unwrap();
+    assert!(State::of(&server) == state);
+
+    let mut spectator = world();
+    spectator.option.trail_decay = 0.5;
+    state.apply(&mut spectator, |parameter| parameter == Parameter::TrailDecay);
+    assert_eq!(spectator.option.boid_max_speed, 9);
+    assert_eq!(spectator.option.trail_decay, 0.5);
+    assert_eq!((spectator.obstacles().len(), spectator.attractors().len()), (1, 1));
+    assert_eq!((spectator.obstacles()[0].x, spectator.obstacles()[0].y), (10.0, 20.0));
+
+    // Scenery that does not fit in one packet is left out
+    for _ in 0..500 {
+        server.add_obstacle(0.0, 0.0);
+    }
+    let state = State::decode(&State::of(&server).encode()).unwrap();
+    assert!(state.obstacles.len() < 501 && state.obstacles.len() > 100);
+    assert!(state.attractors.is_empty());
+}
@@ -0,0 +1,95 @@
+//! Scenario files: settings, scenery and spawn groups loaded as one demo.
+
+use std::fs;
+use std::path::Path;
+
+use boids::obstacle::Obstacle;
+use boids::preset::Species;
+use boids::scenario::{ScenarioFile, Spawn};
+use boids::World;
+
+fn group(species: Species, count: u16, x: f32, y: f32, spread: f32) -> Spawn {
+    Spawn {
+        species,
+        count,
+        x,
+        y,
+        spread,
+    }
+}
+
+#[test]
+fn groups_are_spawned_within_their_spread() {
+    let mut scenario = ScenarioFile::default();
+    scenario.option.boid_count = 5;
+    scenario.option.predator_count = 0;
+    scenario.spawns = vec![
+        group(Species::Boid, 30, 100.0, 80.0, 20.0),
+        group(Species::Predator, 2, 300.0, 200.0, 0.0),
+    ];
+    let mut world = World::builder().size(400, 300).seed(1).build();
+    world.load_scenario(&scenario);
+    assert_eq!(world.boids_count(), 35);
+    assert_eq!(world.predators_count(), 2);
+    let grouped = world.boids().filter(|boid| {
+        let (x, y) = boid.position();
+        (x - 100.0).hypot(y - 80.0) <= 21.0
+    });
+    assert!(grouped.count() >= 30);
+    assert!(world.predators().all(|predator| predator.position() == (300.0, 200.0)));
+    assert!(!world.can_undo());
+}
+
+#[test]
+fn positions_are_stretched_to_the_world() {
+    let mut scenario = ScenarioFile::default();
+    scenario.option.boid_count = 0;
+    scenario.option.predator_count = 0;
+    scenario.size = Some((200, 100));
+    scenario.obstacles = vec![Obstacle::new(20.0, 10.0)];
+    scenario.spawns = vec![group(Species::Boid, 1, 100.0, 50.0, 0.0)];
+    let mut world = World::builder().size(400, 300).seed(1).build();
+    world.load_scenario(&scenario);
+    assert_eq!(world.boids().next().map(|boid| boid.position()), Some((200.0, 150.0)));
+    assert_eq!((world.obstacles()[0].x, world.obstacles()[0].y), (40.0, 30.0));
+    assert_eq!((world.width(), world.height()), (400, 300));
+}
+
+#[test]
+fn a_saved_scenario_loads_the_same_settings_and_scenery() {
+    let mut world = World::builder().size(400, 300).seed(1).build();
+    world.option.boid_vision_radius = 55.0;
+    world.add_obstacle(120.0, 80.0);
+    let path = std::env::temp_dir().join(format!("boids-scenario-{}.ron", std::process::id()));
+    ScenarioFile::of(&world).save(&path).unwrap();
+    let scenario = ScenarioFile::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let mut other = World::builder().size(400, 300).seed(2).build();
+    other.load_scenario(&scenario);
+    assert!(other.option == world.option);
+    assert_eq!((other.obstacles()[0].x, other.obstacles()[0].y), (120.0, 80.0));
+    assert_eq!(other.boids_count(), world.option.boid_count as usize);
+}
+
+#[test]
+fn shipped_scenarios_load() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenarios");
+    let mut loaded = 0;
+    for entry in fs::read_dir(directory).unwrap() {
+        let path = entry.unwrap().path();
+        let scenario = ScenarioFile::load(&path).unwrap_or_else(|error| panic!("{}: {error}", path.display()));
+        assert!(!scenario.name.is_empty(), "{} has no name", path.display());
+        let mut world = World::builder().size(1280, 720).build();
+        world.load_scenario(&scenario);
+        assert_eq!(
+            world.boids_count() + world.predators_count(),
+            scenario.spawn_count(),
+            "{}",
+            path.display()
+        );
+        world.update();
+        loaded += 1;
+    }
+    assert!(loaded > 0);
+}
@@ -0,0 +1,59 @@
+//! Editing obstacles and attractors by numbers, as the obstacle editor does.
+
+use boids::obstacle::{Attractor, Obstacle};
+use boids::World;
+
+// Obstacles as (x, y, radius) and attractors as (x, y, radius, strength)
+type Scenery = (Vec<(f32, f32, f32)>, Vec<(f32, f32, f32, f32)>);
+
+fn positions(world: &World) -> Scenery {
+    (
+        world
+            .obstacles()
+            .iter()
+            .map(|obstacle| (obstacle.x, obstacle.y, obstacle.radius))
+            .collect(),
+        world
+            .attractors()
+            .iter()
+            .map(|attractor| (attractor.x, attractor.y, attractor.radius, attractor.strength))
+            .collect(),
+    )
+}
+
+#[test]
+fn an_edit_of_the_scenery_is_undone_at_once() {
+    let mut world = World::builder().size(200, 150).boids(0).predators(0).build();
+    world.add_obstacle(10.0, 20.0);
+    world.commit_edit();
+    let before = positions(&world);
+
+    let mut obstacles = world.obstacles().to_vec();
+    obstacles[0].radius = 12.5;
+    obstacles.push(Obstacle {
+        x: 50.0,
+        ..obstacles[0].clone()
+    });
+    let attractors = vec![Attractor {
+        strength: 0.1,
+        ..Attractor::new(80.0, 90.0)
+    }];
+    world.edit_scenery(obstacles, attractors);
+    // Dragging a value edits again every frame until the pointer is released
+    let mut attractors = world.attractors().to_vec();
+    attractors[0].x = 85.0;
+    world.edit_scenery(world.obstacles().to_vec(), attractors);
+    let edited = positions(&world);
+    assert_eq!(
+        edited,
+        (
+            vec![(10.0, 20.0, 12.5), (50.0, 20.0, 12.5)],
+            vec![(85.0, 90.0, 150.0, 0.1)]
+        )
+    );
+
+    world.undo();
+    assert_eq!(positions(&world), before);
+    world.redo();
+    assert_eq!(positions(&world), edited);
+}
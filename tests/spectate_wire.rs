@@ -0,0 +1,98 @@
+//! Spectator frames: the flock sent over UDP in packets and put back together.
+
+use boids::spectate_wire::{encode, FrameAssembler, MAX_PACKET_SIZE};
+use boids::World;
+
+fn world() -> World {
+    World::builder()
+        .size(640, 480)
+        .boids(3000)
+        .predators(3)
+        .seed(7)
+        .build()
+}
+
+#[test]
+fn a_large_flock_arrives_whole_in_any_order() {
+    let world = world();
+    let mut packets = encode(&world, 1);
+    assert!(packets.len() > 1);
+    assert!(packets.iter().all(|packet| packet.len() <= MAX_PACKET_SIZE));
+    packets.reverse();
+
+    let mut assembler = FrameAssembler::new();
+    let (last, rest) = packets.split_last().unwrap();
+    assert!(rest.iter().all(|packet| assembler.push(packet).is_none()));
+    let frame = assembler.push(last).unwrap();
+    assert_eq!((frame.width, frame.height), (640, 480));
+    assert_eq!((frame.boids.len(), frame.predators.len()), (3000, 3));
+
+    // Positions are rounded to the pixel
+    let mut sent: Vec<(i16, i16)> = world
+        .boids()
+        .map(|boid| boid.position())
+        .map(|(x, y)| (x.round() as i16, y.round() as i16))
+        .collect();
+    let mut received: Vec<(i16, i16)> = frame
+        .boids
+        .iter()
+        .map(|boid| boid.position())
+        .map(|(x, y)| (x as i16, y as i16))
+        .collect();
+    sent.sort();
+    received.sort();
+    assert_eq!(sent, received);
+
+    let mut spectator = World::with_size(320, 240);
+    frame.apply(&mut spectator);
+    assert_eq!((spectator.width(), spectator.height()), (640, 480));
+    assert_eq!(
+        (spectator.boids_count(), spectator.predators_count()),
+        (3000, 3)
+    );
+}
+
+#[test]
+fn lost_packets_only_cost_their_boids() {
+    let world = world();
+    let packets = encode(&world, 1);
+    let mut assembler = FrameAssembler::new();
+    for packet in packets.iter().skip(1) {
+        assert!(assembler.push(packet).is_none());
+    }
+    // The next frame starting hands over what arrived of this one
+    let next = encode(&world, 2);
+    let partial = assembler.push(&next[0]).unwrap();
+    assert_eq!(partial.sequence, 1);
+    assert!(partial.boids.len() < 3000 && !partial.boids.is_empty());
+    assert!(partial.predators.is_empty());
+
+    // Late packets of earlier frames are dropped
+    assert!(assembler.push(&packets[0]).is_none());
+    let frame = next[1..]
+        .iter()
+        .find_map(|packet| assembler.push(packet))
+        .unwrap();
+    assert_eq!((frame.sequence, frame.boids.len()), (2, 3000));
+}
+
+#[test]
+fn garbage_is_ignored() {
+    let world = world();
+    let mut assembler = FrameAssembler::new();
+    let mut truncated = encode(&world, 1).remove(0);
+    truncated.pop();
+    assert!(assembler.push(&truncated).is_none());
+    assert!(assembler.push(b"BDSJ").is_none());
+    assert!(assembler.push(&[0; 600]).is_none());
+    let empty = World::builder()
+        .size(320, 240)
+        .boids(0)
+        .predators(0)
+        .build();
+    let frame = encode(&empty, 3)
+        .iter()
+        .find_map(|packet| assembler.push(packet))
+        .unwrap();
+    assert_eq!((frame.boids.len(), frame.predators.len()), (0, 0));
+}
@@ -0,0 +1,73 @@
+//! Crowding measured from the neighbors each update finds.
+
+use boids::statistics::{NeighborDistances, Statistics};
+use boids::tuner::AvoidTuner;
+use boids::{World, WorldOption};
+
+#[test]
+fn nearest_neighbor_distances_are_summed_over_the_boids_that_see_one() {
+    assert_eq!(NeighborDistances::of(&[]), None);
+    let distances = NeighborDistances::of(&[4.0, 10.0, 7.0]).unwrap();
+    assert_eq!((distances.mean, distances.min, distances.counted), (7.0, 4.0, 3));
+
+    let mut option = WorldOption::new();
+    option.safe_radius = 14.0;
+    assert!(!distances.is_crowded(&option));
+    option.safe_radius = 15.0;
+    assert!(distances.is_crowded(&option));
+}
+
+#[test]
+fn each_update_measures_the_flock() {
+    let mut world = World::builder().size(200, 150).boids(80).predators(0).seed(5).build();
+    assert_eq!(world.neighbor_distances(), None);
+    world.update();
+    let distances = world.neighbor_distances().unwrap();
+    assert!(distances.counted > 0 && distances.counted <= 80);
+    assert!(distances.min <= distances.mean && distances.mean <= world.option.boid_vision_radius);
+
+    let mut statistics = Statistics::new(1.0);
+    statistics.record(0.0, &world);
+    assert_eq!(statistics.samples()[0].nearest_mean, Some(distances.mean));
+
+    // Blind boids have no neighbor to measure
+    world.option.boid_vision_radius = 0.0;
+    world.update();
+    assert_eq!(world.neighbor_distances(), None);
+}
+
+#[test]
+fn every_close_pair_is_counted_once() {
+    let mut world = World::builder().size(200, 150).boids(0).predators(0).seed(5).build();
+    world.option.auto_population = false;
+    world.spawn_boids(50, 50);
+    world.spawn_boids(56, 50);
+    world.spawn_boids(150, 100);
+    world.update();
+    assert_eq!(world.overlaps(), 1);
+}
+
+#[test]
+fn tuning_raises_the_avoid_factor_until_overlaps_are_few_enough() {
+    let mut tuner = AvoidTuner::default();
+    let mut avoid_factor = 0.1;
+    let mut tick = 0;
+    let mut window = |tuner: &mut AvoidTuner, overlaps: f32, avoid_factor: &mut f32| {
+        let mut tuning = true;
+        for _ in 0..30 {
+            tick += 1;
+            // A frame without an update adds nothing
+            tuning &= tuner.record(tick, overlaps, 4.0, avoid_factor);
+            tuning &= tuner.record(tick, overlaps, 4.0, avoid_factor);
+        }
+        tuning
+    };
+    assert!(window(&mut tuner, 6.0, &mut avoid_factor));
+    assert!(window(&mut tuner, 5.0, &mut avoid_factor));
+    assert_eq!(avoid_factor, 0.1 + 2.0 * AvoidTuner::STEP);
+    assert!(!window(&mut tuner, 3.0, &mut avoid_factor));
+    assert_eq!(avoid_factor, 0.1 + 2.0 * AvoidTuner::STEP);
+
+    let mut avoid_factor = AvoidTuner::MAX_AVOID_FACTOR;
+    assert!(!window(&mut AvoidTuner::default(), 6.0, &mut avoid_factor));
+}
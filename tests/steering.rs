@@ -0,0 +1,211 @@
+//! Steering math of a single boid against hand placed neighbors, so the rules can be
+//! reworked without changing what they do by accident.
+
+use boids::{Boid, Color, Rectangle, Vertice};
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+use std::f32::consts::FRAC_PI_2;
+
+const SIZE: i16 = 2;
+// Wide enough for every neighbor below, narrow enough to leave out the ones behind
+const VIEW_ANGLE: f32 = 120.0;
+const EPSILON: f32 = 1e-4;
+
+fn at(x: i16, y: i16) -> Vertice {
+    Vertice { x, y }
+}
+
+fn boid(x: i16, y: i16, velocity_x: i16, velocity_y: i16) -> Boid {
+    Boid::new(at(x, y), SIZE, velocity_x, velocity_y, Color::White)
+}
+
+fn assert_close(actual: (f32, f32), expected: (f32, f32)) {
+    assert!(
+        (actual.0 - expected.0).abs() < EPSILON && (actual.1 - expected.1).abs() < EPSILON,
+        "{actual:?} is not {expected:?}"
+    );
+}
+
+#[test]
+fn angle_grows_from_the_right_towards_positive_y() {
+    let origin = at(100, 100);
+    let cases = [
+        ((110, 100), 0.0),
+        ((110, 110), 45.0),
+        ((100, 110), 90.0),
+        ((90, 100), 180.0),
+        ((100, 90), 270.0),
+        ((110, 90), 315.0),
+    ];
+    for ((x, y), expected) in cases {
+        let angle = Boid::angle(&origin, &at(x, y));
+        assert!((angle - expected).abs() < EPSILON, "angle to {x}, {y} is {angle}, not {expected}");
+    }
+}
+
+#[test]
+fn sight_is_a_cone_around_the_facing_angle() {
+    assert!(Boid::is_within_sight(90.0, 120.0, 90.0));
+    assert!(Boid::is_within_sight(90.0, 120.0, 150.0));
+    assert!(Boid::is_within_sight(90.0, 120.0, 30.0));
+    assert!(!Boid::is_within_sight(90.0, 120.0, 151.0));
+    assert!(!Boid::is_within_sight(90.0, 120.0, 270.0));
+}
+
+#[test]
+fn sight_wraps_around_zero() {
+    assert!(Boid::is_within_sight(350.0, 40.0, 5.0));
+    assert!(Boid::is_within_sight(10.0, 40.0, 355.0));
+    assert!(!Boid::is_within_sight(350.0, 40.0, 20.0));
+}
+
+#[test]
+fn full_circle_sees_everything() {
+    for object_angle in [0.0, 90.0, 180.0, 270.0, 359.0] {
+        assert!(Boid::is_within_sight(45.0, 360.0, object_angle));
+    }
+}
+
+#[test]
+fn speed_limit_slows_fast_boids_down_keeping_their_heading() {
+    let mut rng = ChaCha12Rng::seed_from_u64(0);
+    let mut boid = boid(100, 100, 30, 40);
+    boid.speed_limit(10, 2, &mut rng);
+    assert_close(boid.velocity(), (6.0, 8.0));
+}
+
+#[test]
+fn speed_limit_speeds_slow_boids_up_keeping_their_heading() {
+    let mut rng = ChaCha12Rng::seed_from_u64(0);
+    let mut boid = boid(100, 100, 0, 0);
+    boid.set_velocity(0.6, 0.8);
+    boid.speed_limit(10, 2, &mut rng);
+    assert_close(boid.velocity(), (1.2, 1.6));
+}
+
+#[test]
+fn speed_limit_leaves_speeds_in_range_alone() {
+    let mut rng = ChaCha12Rng::seed_from_u64(0);
+    let mut boid = boid(100, 100, 3, 4);
+    boid.speed_limit(10, 2, &mut rng);
+    assert_close(boid.velocity(), (3.0, 4.0));
+}
+
+#[test]
+fn speed_limit_sets_stopped_boids_moving() {
+    for seed in 0..20 {
+        let mut rng = ChaCha12Rng::seed_from_u64(seed);
+        let mut boid = boid(100, 100, 0, 0);
+        boid.speed_limit(10, 3, &mut rng);
+        // Picked in whole steps, so a little under the minimum at times
+        assert!(boid.speed() > 2.0 && boid.speed() <= 3.0, "speed {} with seed {seed}", boid.speed());
+    }
+}
+
+#[test]
+fn separate_steers_away_from_close_boids_in_sight() {
+    let mut boid = boid(100, 100, 2, 0);
+    let ahead = self::boid(103, 100, 0, 0);
+    let mut accepted = vec![];
+    boid.separate(std::slice::from_ref(&ahead), 0.5, 5.0, VIEW_ANGLE, Some(&mut accepted));
+    assert_close(boid.velocity(), (0.5, 0.0));
+    assert_eq!(accepted, [ahead.id()]);
+}
+
+#[test]
+fn separate_ignores_far_boids_itself_and_boids_behind() {
+    let mut boid = boid(100, 100, 2, 0);
+    let neighbors = [boid.clone(), self::boid(120, 100, 0, 0), self::boid(97, 100, 0, 0)];
+    boid.separate(&neighbors, 0.5, 5.0, VIEW_ANGLE, None);
+    assert_close(boid.velocity(), (2.0, 0.0));
+}
+
+#[test]
+fn separate_sees_boids_behind_with_a_full_view() {
+    let mut boid = boid(100, 100, 2, 0);
+    boid.separate(&[self::boid(97, 100, 0, 0)], 0.5, 5.0, 360.0, None);
+    assert_close(boid.velocity(), (3.5, 0.0));
+}
+
+#[test]
+fn align_steers_towards_the_average_velocity() {
+    let mut boid = boid(100, 100, 2, 0);
+    let neighbors = [self::boid(105, 100, 0, 2), self::boid(105, 102, 0, 4)];
+    let mut accepted = vec![];
+    boid.align(&neighbors, 0.5, 20.0, VIEW_ANGLE, Some(&mut accepted));
+    assert_close(boid.velocity(), (2.0, 1.5));
+    assert_eq!(accepted, neighbors.map(|neighbor| neighbor.id()));
+}
+
+#[test]
+fn align_without_neighbors_changes_nothing() {
+    let mut boid = boid(100, 100, 2, 0);
+    let far = self::boid(150, 100, 0, 5);
+    boid.align(&[boid.clone(), far], 0.5, 20.0, VIEW_ANGLE, None);
+    assert_close(boid.velocity(), (2.0, 0.0));
+}
+
+#[test]
+fn cohesion_steers_towards_the_center_of_neighbors() {
+    let mut boid = boid(100, 100, 2, 0);
+    let neighbors = [self::boid(110, 100, 0, 0), self::boid(110, 110, 0, 0)];
+    boid.cohesion(&neighbors, 0.1, 20.0, VIEW_ANGLE, None);
+    assert_close(boid.velocity(), (3.0, 0.5));
+}
+
+#[test]
+fn cohesion_ignores_boids_out_of_sight() {
+    let mut boid = boid(100, 100, 2, 0);
+    let neighbors = [self::boid(90, 100, 0, 0), self::boid(130, 100, 0, 0)];
+    boid.cohesion(&neighbors, 0.1, 20.0, VIEW_ANGLE, None);
+    assert_close(boid.velocity(), (2.0, 0.0));
+}
+
+#[test]
+fn avoid_areas_pushes_towards_the_closest_edge() {
+    // 40 × 20 around 50, 50, entered from the right
+    let area = Rectangle::new(50.0, 50.0, 20.0, 10.0);
+    let mut boid = boid(75, 52, -2, 0);
+    boid.avoid_areas(std::slice::from_ref(&area), 3, 10);
+    assert_close(boid.velocity(), (1.0, 0.0));
+    let mut boid = self::boid(52, 38, 0, 2);
+    boid.avoid_areas(&[area], 3, 10);
+    assert_close(boid.velocity(), (0.0, -1.0));
+}
+
+#[test]
+fn avoid_areas_ignores_boids_beyond_the_margin() {
+    let area = Rectangle::new(50.0, 50.0, 20.0, 10.0);
+    let mut boid = boid(81, 50, -2, 0);
+    boid.avoid_areas(&[area], 3, 10);
+    assert_close(boid.velocity(), (-2.0, 0.0));
+}
+
+#[test]
+fn smoothed_heading_steadies_a_swinging_velocity() {
+    let mut boid = boid(100, 100, 1, 0);
+    for tick in 0..20 {
+        let velocity_y = if tick % 2 == 0 { 1.0 } else { -1.0 };
+        boid.set_velocity(1.0, velocity_y);
+        boid.smooth_heading(0.8);
+    }
+    assert!(boid.heading().abs() < 0.1, "{} swings", boid.heading());
+
+    // Standing still keeps the heading, no smoothing follows the velocity at once
+    boid.set_velocity(0.0, 0.0);
+    let heading = boid.heading();
+    boid.smooth_heading(0.0);
+    assert_eq!(boid.heading(), heading);
+    boid.set_velocity(0.0, 2.0);
+    boid.smooth_heading(0.0);
+    assert!((boid.heading() - FRAC_PI_2).abs() < EPSILON);
+}
+
+#[test]
+fn smoothed_heading_turns_the_short_way_around() {
+    let mut boid = boid(100, 100, -1, 0);
+    boid.set_velocity(-1.0, -0.01);
+    boid.smooth_heading(0.5);
+    // From PI towards just under -PI, not back through 0
+    assert!(boid.heading().abs() > 3.1, "{} turned the long way", boid.heading());
+}
@@ -0,0 +1,37 @@
+//! SVG export: the world as a resolution independent figure.
+
+use boids::World;
+
+fn count(svg: &str, element: &str) -> usize {
+    svg.matches(&format!("<{element} ")).count()
+}
+
+#[test]
+fn every_body_is_a_triangle() {
+    let mut world = World::builder().size(320, 240).boids(40).predators(2).seed(5).build();
+    world.add_obstacle(100.0, 100.0);
+    world.update();
+    let svg = world.to_svg();
+    assert!(svg.starts_with("<svg ") && svg.trim_end().ends_with("</svg>"));
+    assert!(svg.contains(r#"viewBox="0 0 320 240""#));
+    assert_eq!(count(&svg, "polygon"), 42);
+    assert_eq!(count(&svg, "circle"), 1);
+    // The background
+    assert_eq!(count(&svg, "rect"), 1);
+}
+
+#[test]
+fn debug_overlays_come_along() {
+    let mut world = World::builder().size(320, 240).boids(40).predators(2).seed(5).build();
+    world.option.show_quad_tree = true;
+    world.option.show_safe_radius = true;
+    world.option.show_vision_radius = true;
+    let svg = world.to_svg();
+    // Radii of every body, and at least the roots of both quad trees over the background
+    assert_eq!(count(&svg, "circle"), 84);
+    assert!(count(&svg, "rect") >= 3);
+
+    world.option.show_debug_overlays = false;
+    let svg = world.to_svg();
+    assert_eq!((count(&svg, "circle"), count(&svg, "rect")), (0, 1));
+}
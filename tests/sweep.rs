@@ -0,0 +1,75 @@
+//! Parameter sweeps: runs over a range of values and the order parameters they end with.
+
+use boids::lfo::Parameter;
+use boids::statistics::{flocks, OrderParameters};
+use boids::sweep::Sweep;
+use boids::{World, WorldOption};
+
+fn sweep(steps: u32) -> Sweep {
+    let mut option = WorldOption::new();
+    option.boid_count = 40;
+    option.predator_count = 0;
+    Sweep {
+        parameter: Parameter::CenteringFactor,
+        from: 0.0,
+        to: 0.01,
+        steps,
+        seeds: 2,
+        first_seed: 7,
+        ticks: 20,
+        option,
+        size: (320, 240),
+    }
+}
+
+#[test]
+fn values_are_evenly_spaced_from_end_to_end() {
+    let mut sweep = sweep(5);
+    sweep.to = 1.0;
+    assert_eq!(sweep.values(), [0.0, 0.25, 0.5, 0.75, 1.0]);
+    sweep.steps = 1;
+    assert_eq!(sweep.values(), [0.0]);
+    sweep.steps = 0;
+    assert!(sweep.values().is_empty());
+}
+
+#[test]
+fn every_value_runs_with_every_seed() {
+    let sweep = sweep(2);
+    let results = sweep.run(|_| ());
+    let runs: Vec<(f32, u64)> = results.iter().map(|result| (result.value, result.seed)).collect();
+    assert_eq!(runs, [(0.0, 7), (0.0, 8), (0.01, 7), (0.01, 8)]);
+    // Runs are seeded, so they end the same run alone
+    assert_eq!(results[3], sweep.run_one(0.01, 8));
+    assert!(results.iter().all(|result| result.boids == 40));
+}
+
+#[test]
+fn order_parameters_stay_in_range() {
+    let mut world = World::builder().size(200, 150).boids(60).predators(0).seed(2).build();
+    world.update();
+    let order = OrderParameters::of(&world);
+    assert!((0.0..=1.0).contains(&order.polarization));
+    assert!((0.0..=1.0).contains(&order.milling));
+    assert!((1..=60).contains(&order.flocks));
+
+    // Boids seeing the whole world make one flock, blind boids one each
+    world.option.boid_vision_radius = 300.0;
+    assert_eq!(OrderParameters::of(&world).flocks, 1);
+    world.option.boid_vision_radius = 0.0;
+    assert_eq!(OrderParameters::of(&world).flocks, 60);
+
+    let empty = World::builder().size(200, 150).boids(0).predators(0).build();
+    assert_eq!(OrderParameters::of(&empty).flocks, 0);
+}
+
+#[test]
+fn every_boid_is_in_one_flock() {
+    let world = World::builder().size(200, 150).boids(60).predators(0).seed(3).build();
+    let found = flocks(&world);
+    assert_eq!(found.len(), OrderParameters::of(&world).flocks);
+    let mut ids: Vec<_> = found.iter().flatten().map(|boid| boid.id()).collect();
+    ids.sort();
+    ids.dedup();
+    assert_eq!(ids.len(), 60);
+}
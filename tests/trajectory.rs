@@ -0,0 +1,135 @@
+//! Trajectory export: every body at every tick, as Parquet files.
+
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use boids::trajectory::TrajectoryWriter;
+use boids::{UpdatePipeline, World};
+use parquet::basic::Type;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::record::{Row, RowAccessor};
+
+fn directory(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("boids-trajectories-{}-{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    directory
+}
+
+fn world() -> World {
+    World::builder().size(320, 240).boids(40).predators(2).seed(3).build()
+}
+
+// The rows of a Parquet file, read back after checking the schema
+fn rows(path: &Path) -> Vec<Row> {
+    assert_eq!(path.extension().unwrap(), "parquet");
+    let reader = SerializedFileReader::new(File::open(path).unwrap()).unwrap();
+    let metadata = reader.metadata().file_metadata();
+    let columns: Vec<_> = metadata
+        .schema_descr()
+        .columns()
+        .iter()
+        .map(|column| (column.name().to_string(), column.physical_type()))
+        .collect();
+    let expected = [
+        ("tick", Type::INT64),
+        ("id", Type::FIXED_LEN_BYTE_ARRAY),
+        ("x", Type::FLOAT),
+        ("y", Type::FLOAT),
+        ("vx", Type::FLOAT),
+        ("vy", Type::FLOAT),
+        ("species", Type::BYTE_ARRAY),
+    ];
+    assert_eq!(columns, expected.map(|(name, kind)| (name.to_string(), kind)));
+    let rows: Vec<Row> = reader.get_row_iter(None).unwrap().map(Result::unwrap).collect();
+    assert_eq!(rows.len() as i64, metadata.num_rows());
+    rows
+}
+
+fn species(rows: &[Row], species: &str) -> usize {
+    rows.iter().filter(|row| row.get_string(6).unwrap() == species).count()
+}
+
+#[test]
+fn every_body_at_every_tick_is_a_row() {
+    let directory = directory("rows");
+    let mut world = world();
+    let recording = TrajectoryWriter::create(&directory, u64::MAX)
+        .unwrap()
+        .record_ticks(&mut world);
+    for _ in 0..10 {
+        world.update();
+    }
+    let files = recording.finish().unwrap();
+    assert_eq!(files.len(), 1);
+    let rows = rows(&files[0]);
+    assert_eq!(rows.len(), 420);
+    assert_eq!(species(&rows, "boid"), 400);
+    assert_eq!(species(&rows, "predator"), 20);
+    // The last tick is where the world left every body
+    let last: Vec<_> = rows
+        .iter()
+        .filter(|row| row.get_long(0).unwrap() == world.tick() as i64)
+        .collect();
+    assert_eq!(last.len(), 42);
+    for body in world.boids().chain(world.predators()) {
+        let row = last
+            .iter()
+            .find(|row| row.get_bytes(1).unwrap().data() == body.id().as_bytes())
+            .unwrap();
+        let ((x, _), (vx, _)) = (body.position(), body.velocity());
+        assert_eq!((row.get_float(2).unwrap(), row.get_float(4).unwrap()), (x, vx));
+    }
+
+    // Nothing is written once finished
+    world.update();
+    assert!(recording.finish().unwrap().is_empty());
+    assert_eq!(fs::read_dir(&directory).unwrap().count(), 1);
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn files_are_started_past_the_size_limit() {
+    let directory = directory("rotation");
+    let mut world = world();
+    let mut writer = TrajectoryWriter::create(&directory, 1).unwrap().row_group_rows(42);
+    for _ in 0..5 {
+        world.update();
+        writer.record(&world).unwrap();
+    }
+    let files = writer.finish().unwrap();
+    assert_eq!(files.len(), 5);
+    for file in &files {
+        let rows = rows(file);
+        assert_eq!((rows.len(), species(&rows, "predator")), (42, 2));
+    }
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn only_ticks_taken_from_the_update_thread_are_written() {
+    let directory = directory("pipeline");
+    let mut world = world();
+    let recording = TrajectoryWriter::create(&directory, u64::MAX)
+        .unwrap()
+        .record_ticks(&mut world);
+    let mut pipeline = UpdatePipeline::new();
+    let start = Instant::now();
+    while world.tick() < 30 {
+        assert!(start.elapsed() < Duration::from_secs(10), "the worker never answered");
+        // Spawning keeps the update in flight, resizing drops it
+        world.spawn_boids(160, 120);
+        if world.tick() % 10 == 5 {
+            world.resize(320 + world.tick() as u16, 240);
+        }
+        pipeline.step(&mut world);
+        thread::sleep(Duration::from_millis(1));
+    }
+    let files = recording.finish().unwrap();
+    let mut ticks: Vec<i64> = rows(&files[0]).iter().map(|row| row.get_long(0).unwrap()).collect();
+    ticks.dedup();
+    assert!(ticks.windows(2).all(|pair| pair[0] < pair[1]), "ticks written twice: {ticks:?}");
+    assert_eq!(ticks, (1..=world.tick() as i64).collect::<Vec<_>>());
+    fs::remove_dir_all(&directory).unwrap();
+}
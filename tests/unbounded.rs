@@ -0,0 +1,81 @@
+//! Unbounded world: the frame follows a migrating flock and stragglers are parked in chunks.
+
+use std::sync::Arc;
+
+use boids::geometry::Rectangle;
+use boids::rule::{SteeringRule, Surroundings};
+use boids::{Boid, World, WorldOption};
+
+/// Sends every boid to the right at the same speed.
+struct Migrate;
+
+impl SteeringRule for Migrate {
+    fn name(&self) -> &str {
+        "Migrate"
+    }
+
+    fn apply(&self, boid: &mut Boid, _: &mut Surroundings, _: &WorldOption) {
+        boid.set_velocity(40.0, 0.0);
+    }
+}
+
+fn world() -> World {
+    let mut option = WorldOption::new();
+    option.boid_count = 50;
+    option.predator_count = 0;
+    option.auto_population = false;
+    option.auto_quad_tree_capacity = false;
+    option.unbounded = true;
+    let mut world = World::builder().size(320, 240).seed(3).option(option).build();
+    world.rules_mut().clear();
+    world.rules_mut().push(Arc::new(Migrate));
+    world
+}
+
+fn in_frame(world: &World) -> bool {
+    let (width, height) = (world.width() as f32, world.height() as f32);
+    world
+        .boids()
+        .all(|boid| (0.0..=width).contains(&boid.position().0) && (0.0..=height).contains(&boid.position().1))
+}
+
+#[test]
+fn the_frame_follows_a_long_migration() {
+    let mut world = world();
+    for _ in 0..1000 {
+        world.update();
+    }
+    // Farther than positions rounded to i16 could go, give or take where the flock started
+    assert!((world.origin().0 - 40_000).abs() < 320);
+    assert!(world.origin().1.abs() < 240);
+    assert_eq!(world.boids_count(), 50);
+    assert_eq!(world.parked_count(), 0);
+    assert!(world.take_error().is_none());
+}
+
+#[test]
+fn stragglers_are_parked_and_kept() {
+    let mut world = world();
+    // A single boid left behind, too few to count as part of the flock
+    let (x, y) = world.boids().next().unwrap().position();
+    world.select_in(&Rectangle::from_corners(x - 0.5, y - 0.5, x + 0.5, y + 0.5));
+    let frozen = world.box_selection_count();
+    assert_eq!(frozen, 1);
+    world.freeze_box_selection(true);
+    world.clear_box_selection();
+    for _ in 0..200 {
+        world.update();
+    }
+    assert_eq!(world.parked_count(), frozen);
+    assert_eq!(world.boids_count(), 50 - frozen);
+    let mut restored = World::with_size(320, 240);
+    restored.restore(world.snapshot());
+    assert_eq!(restored.boids_count() + restored.parked_count(), 50);
+
+    // Back to a bounded world, the parked boids come back into the frame
+    world.option.unbounded = false;
+    world.update();
+    assert_eq!(world.parked_count(), 0);
+    assert_eq!(world.boids_count(), 50);
+    assert!(in_frame(&world));
+}